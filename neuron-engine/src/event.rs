@@ -0,0 +1,458 @@
+//! Events delivered from the platform to the application
+
+use crate::gamepad::{GamepadAxis, GamepadButton, GamepadId};
+use crate::os::LockState;
+use crate::os::window::{DragEffect, InteractionState, WindowId};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Something that happened to a window (or the application) which the application might want to react to.
+///
+/// # Threading
+/// Events are produced on the thread that processes OS events: by [`Engine::process_events`](crate::Engine::process_events), and on Windows also by the window procedure, which the OS can call from inside other calls (e.g. while a window is being created or resized).
+/// They are only queued at that point, so nothing the application does runs inside the platform callback. Drain them with [`Engine::drain_events`](crate::Engine::drain_events) on the same thread, after processing events.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// The user asked to close the window (e.g. with its close button or Alt+F4).
+    ///
+    /// The window manager already started closing the window, see [`WindowManager::window_status`](crate::os::window::WindowManager::window_status). It is gone once the application drops its references to it.
+    #[allow(missing_docs)]
+    CloseRequested { window: WindowId },
+
+    /// The window finished closing and was destroyed, its id is dead from now on (release anything kept for it, like a renderer surface).
+    ///
+    /// Windows still open when the engine shuts down are destroyed too, [`Engine::run`](crate::Engine::run) passes these events to the handler before it returns.
+    /// An engine driven without `run` destroys its remaining windows when dropped, without reporting them.
+    #[allow(missing_docs)]
+    WindowDestroyed { window: WindowId },
+
+    /// The window gained or lost the keyboard focus.
+    #[allow(missing_docs)]
+    Focused { window: WindowId, focused: bool },
+
+    /// The cursor entered the client area of a window.
+    #[allow(missing_docs)]
+    CursorEntered { window: WindowId },
+
+    /// The cursor left the client area of a window.
+    #[allow(missing_docs)]
+    CursorLeft { window: WindowId },
+
+    /// The cursor moved over the client area of a window.
+    ///
+    /// `position` is in physical pixels relative to the top-left of the client area, on every platform.
+    /// Every [`Event::CursorEntered`] is followed by a move to where the cursor entered. While a mouse button is held the window keeps getting moves after [`Event::CursorLeft`], with positions outside the client area (negative left of and above it).
+    #[allow(missing_docs)]
+    CursorMoved {
+        window: WindowId,
+        position: (f64, f64),
+    },
+
+    /// The mouse moved while the cursor is locked to the window (see [`CursorGrabMode::Locked`](crate::os::window::CursorGrabMode::Locked)), by `delta` physical pixels.
+    ///
+    /// The cursor itself stays where it is, so no [`Event::CursorMoved`] is reported.
+    #[allow(missing_docs)]
+    MouseMotion { window: WindowId, delta: (f64, f64) },
+
+    /// A mouse button was pressed or released over the window.
+    ///
+    /// A button pressed over the window also reports its release when the cursor left the window in between.
+    #[allow(missing_docs)]
+    MouseInput {
+        window: WindowId,
+        button: MouseButton,
+        state: ElementState,
+    },
+
+    /// The mouse wheel (or a touchpad scrolling like one) was turned over the window.
+    #[allow(missing_docs)]
+    MouseWheel {
+        window: WindowId,
+        delta: ScrollDelta,
+    },
+
+    /// The window's scale factor changed, usually because it moved to a monitor with a different DPI (see [`Window::scale_factor`](crate::os::window::Window::scale_factor)).
+    ///
+    /// Only produced on Windows, which also resizes the window to keep its logical size (reported as [`Event::SurfaceResized`]).
+    #[allow(missing_docs)]
+    ScaleFactorChanged { window: WindowId, scale_factor: f64 },
+
+    /// The client area (the surface a renderer draws to) changed size, in physical pixels.
+    ///
+    /// `live_resize` is set while the user is still dragging the window border, so a renderer can favour latency (e.g. a mailbox present mode) until the drag ends.
+    #[allow(missing_docs)]
+    SurfaceResized {
+        window: WindowId,
+        size: (u32, u32),
+        live_resize: bool,
+    },
+
+    /// The window should be drawn again, because [`Window::request_redraw`](crate::os::window::Window::request_redraw) was called or the OS lost (part of) its contents (e.g. it was uncovered).
+    ///
+    /// Requests made before the event is reported are combined into one event.
+    #[allow(missing_docs)]
+    RedrawRequested { window: WindowId },
+
+    /// The window stopped being visible (minimized, hidden or fully covered by other windows) or became visible again.
+    ///
+    /// Whether covered windows are reported depends on the platform: compositing window managers usually keep every window "visible".
+    #[allow(missing_docs)]
+    Occluded { window: WindowId, occluded: bool },
+
+    /// An X11 `ClientMessage` with a type registered through [`X11Platform::subscribe_client_message`](crate::os::x11::X11Platform::subscribe_client_message) was sent to the window.
+    ///
+    /// `data` holds the message's 20 data bytes as five longs (the layout of format 32 messages, which is what almost every protocol uses). Never produced on other platforms.
+    #[allow(missing_docs)]
+    RawClientMessage {
+        window: WindowId,
+        message_type: u64,
+        data: [i64; 5],
+    },
+
+    /// The window's decorations (title bar and border drawn by the OS or window manager) were turned on or off, e.g. by a desktop keybinding.
+    ///
+    /// Apps that draw their own title bar can use this to show or hide it.
+    #[allow(missing_docs)]
+    DecorationsChanged { window: WindowId, decorated: bool },
+
+    /// The user started resizing or moving the window, see [`Window::interaction_state`](crate::os::window::Window::interaction_state).
+    ///
+    /// Renderers can use this to throttle or switch present modes until [`Event::InteractionEnded`].
+    #[allow(missing_docs)]
+    InteractionBegan {
+        window: WindowId,
+        state: InteractionState,
+    },
+
+    #[allow(missing_docs)]
+    InteractionEnded { window: WindowId },
+
+    /// A finger touched the window.
+    ///
+    /// `id` identifies the touch point until the matching [`Event::TouchEnd`], so simultaneous touches can be told apart. Ids may be reused afterward.
+    /// `position` is in physical pixels relative to the top-left of the client area.
+    #[allow(missing_docs)]
+    TouchBegin {
+        window: WindowId,
+        id: u64,
+        position: (f64, f64),
+    },
+
+    /// A touch point moved.
+    #[allow(missing_docs)]
+    TouchMove {
+        window: WindowId,
+        id: u64,
+        position: (f64, f64),
+    },
+
+    /// A finger was lifted.
+    #[allow(missing_docs)]
+    TouchEnd {
+        window: WindowId,
+        id: u64,
+        position: (f64, f64),
+    },
+
+    /// A key was pressed or released while the window had keyboard focus.
+    #[allow(missing_docs)]
+    KeyboardInput { window: WindowId, event: KeyEvent },
+
+    /// The modifier keys held changed, reported to the focused window before the key event that changed them.
+    ///
+    /// Losing the focus releases all modifiers, keys still held are picked up again with the next key (or, on X11, pointer) event.
+    #[allow(missing_docs)]
+    ModifiersChanged {
+        window: WindowId,
+        modifiers: Modifiers,
+    },
+
+    /// The window received a typed character, after the keyboard layout, dead keys and input methods were applied.
+    ///
+    /// Control characters are reported as well (e.g. `'\r'` for Enter or `'\u{8}'` for Backspace), and auto-repeats follow [`Window::set_key_repeat`](crate::os::window::Window::set_key_repeat).
+    /// A dead key followed by a letter is a single event for the composed character, as is a character outside the BMP (which Windows delivers in two halves).
+    ///
+    /// ```no_run
+    /// # use neuron_engine::Engine;
+    /// # use neuron_engine::event::Event;
+    /// # use neuron_engine::os::window::WindowAttributes;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let engine = Engine::new()?;
+    /// let (id, _window) = engine.create_window(WindowAttributes::default())?;
+    /// engine.process_all_pending();
+    ///
+    /// #[cfg(windows)]
+    /// unsafe {
+    ///     use windows::Win32::UI::Input::KeyboardAndMouse::*;
+    ///     use windows::core::w;
+    ///
+    ///     // US-International, where ' is a dead key and ' then e types é
+    ///     LoadKeyboardLayoutW(w!("00020409"), KLF_ACTIVATE)?;
+    ///
+    ///     let key = |vk: VIRTUAL_KEY, flags| INPUT {
+    ///         r#type: INPUT_KEYBOARD,
+    ///         Anonymous: INPUT_0 {
+    ///             ki: KEYBDINPUT { wVk: vk, dwFlags: flags, ..Default::default() },
+    ///         },
+    ///     };
+    ///     let inputs = [
+    ///         key(VK_OEM_7, KEYBD_EVENT_FLAGS(0)),
+    ///         key(VK_OEM_7, KEYEVENTF_KEYUP),
+    ///         key(VK_E, KEYBD_EVENT_FLAGS(0)),
+    ///         key(VK_E, KEYEVENTF_KEYUP),
+    ///     ];
+    ///     SendInput(&inputs, size_of::<INPUT>() as i32);
+    /// }
+    ///
+    /// engine.process_all_pending();
+    /// let typed: Vec<char> = engine
+    ///     .drain_events()
+    ///     .into_iter()
+    ///     .filter_map(|event| match event {
+    ///         Event::ReceivedCharacter { window, ch } if window == id => Some(ch),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// # #[cfg(windows)]
+    /// assert_eq!(typed, ['é']);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(missing_docs)]
+    ReceivedCharacter { window: WindowId, ch: char },
+
+    /// The pen tip touched the tablet.
+    ///
+    /// `position` is in physical pixels relative to the top-left of the client area.
+    #[allow(missing_docs)]
+    PenDown {
+        window: WindowId,
+        position: (f64, f64),
+        pen: PenState,
+    },
+
+    /// The pen moved, either in contact with the tablet or hovering above it (pressure is `0.0` then).
+    #[allow(missing_docs)]
+    PenMove {
+        window: WindowId,
+        position: (f64, f64),
+        pen: PenState,
+    },
+
+    /// The pen tip was lifted from the tablet.
+    #[allow(missing_docs)]
+    PenUp {
+        window: WindowId,
+        position: (f64, f64),
+        pen: PenState,
+    },
+
+    /// A key combination registered with [`WindowManager::register_accelerator`](crate::os::window::WindowManager::register_accelerator) was pressed in the window.
+    ///
+    /// The key press itself isn't reported as [`Event::KeyboardInput`].
+    #[allow(missing_docs)]
+    AcceleratorTriggered { window: WindowId, id: u16 },
+
+    /// A drag started with [`Window::start_drag`](crate::os::window::Window::start_drag) is over. `effect` is what the drop target did with the data, `None` if the drag was cancelled or nothing accepted the drop.
+    #[allow(missing_docs)]
+    DragEnded {
+        window: WindowId,
+        effect: Option<DragEffect>,
+    },
+
+    /// A button of the window's taskbar thumbnail toolbar was clicked, `id` is the [`ThumbButton::id`](crate::os::window::ThumbButton::id) it was given.
+    ///
+    /// Only produced on Windows, see [`Window::set_thumbnail_toolbar`](crate::os::window::Window::set_thumbnail_toolbar).
+    #[allow(missing_docs)]
+    ThumbButtonClicked { window: WindowId, id: u32 },
+
+    /// Monitors were connected, disconnected or reconfigured (resolution, arrangement, color depth or HDR mode).
+    MonitorsChanged,
+
+    /// Caps Lock, Num Lock or Scroll Lock was toggled, e.g. to warn about Caps Lock in a password field.
+    ///
+    /// On Windows, keys toggled while another application has the focus are only reported once one of our windows gets it back.
+    #[allow(missing_docs)]
+    LockStateChanged { state: LockState },
+
+    /// A gamepad was connected (gamepads already connected at startup are reported on the first poll).
+    #[allow(missing_docs)]
+    GamepadConnected { gamepad: GamepadId },
+
+    #[allow(missing_docs)]
+    GamepadDisconnected { gamepad: GamepadId },
+
+    /// A gamepad button was pressed or released.
+    #[allow(missing_docs)]
+    GamepadButton {
+        gamepad: GamepadId,
+        button: GamepadButton,
+        pressed: bool,
+    },
+
+    /// A gamepad axis moved, see [`GamepadAxis`] for the value ranges.
+    #[allow(missing_docs)]
+    GamepadAxis {
+        gamepad: GamepadId,
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+impl Event {
+    /// The kind of device that caused a pointer event, or `None` for events that don't come from a pointer.
+    pub fn pointer_kind(&self) -> Option<PointerKind> {
+        match self {
+            Event::CursorEntered { .. }
+            | Event::CursorLeft { .. }
+            | Event::CursorMoved { .. }
+            | Event::MouseMotion { .. }
+            | Event::MouseInput { .. }
+            | Event::MouseWheel { .. } => Some(PointerKind::Mouse),
+            Event::TouchBegin { .. } | Event::TouchMove { .. } | Event::TouchEnd { .. } => {
+                Some(PointerKind::Touch)
+            }
+            Event::PenDown { .. } | Event::PenMove { .. } | Event::PenUp { .. } => {
+                Some(PointerKind::Pen)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A key press or release.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyEvent {
+    /// Platform specific code of the physical key (the X11 keycode, also on Wayland, or the Windows scan code with `0xe000` added for extended keys).
+    pub scancode: u32,
+    pub state: KeyState,
+    /// Whether this press was generated by the OS auto-repeating a held key (always `false` for releases).
+    ///
+    /// Repeats can be turned off per window with [`Window::set_key_repeat`](crate::os::window::Window::set_key_repeat).
+    pub repeat: bool,
+    /// Modifier keys held once this event happened (so pressing Shift already includes [`Modifiers::LSHIFT`] or [`Modifiers::RSHIFT`]).
+    pub modifiers: Modifiers,
+}
+
+bitflags::bitflags! {
+    /// Modifier keys held, see [`Event::ModifiersChanged`].
+    ///
+    /// The left and right keys have separate flags, [`Modifiers::shift`] and the like check for either one.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct Modifiers: u8 {
+        const LSHIFT = 1 << 0;
+        const RSHIFT = 1 << 1;
+        const LCTRL = 1 << 2;
+        const RCTRL = 1 << 3;
+        const LALT = 1 << 4;
+        const RALT = 1 << 5;
+        /// The left Windows (or Super) key.
+        const LSUPER = 1 << 6;
+        /// The right Windows (or Super) key.
+        const RSUPER = 1 << 7;
+    }
+}
+
+impl Modifiers {
+    /// Either Shift key is held.
+    pub fn shift(&self) -> bool {
+        self.intersects(Self::LSHIFT | Self::RSHIFT)
+    }
+
+    /// Either Ctrl key is held.
+    pub fn ctrl(&self) -> bool {
+        self.intersects(Self::LCTRL | Self::RCTRL)
+    }
+
+    /// Either Alt key is held.
+    pub fn alt(&self) -> bool {
+        self.intersects(Self::LALT | Self::RALT)
+    }
+
+    /// Either Windows (or Super) key is held.
+    pub fn super_key(&self) -> bool {
+        self.intersects(Self::LSUPER | Self::RSUPER)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyState {
+    Pressed,
+    Released,
+}
+
+/// Whether a button is pressed or released, shared with keys.
+pub type ElementState = KeyState;
+
+/// A mouse button, see [`Event::MouseInput`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    /// Any other button, numbered like X11 does on both platforms (e.g. the back and forward side buttons are `Other(8)` and `Other(9)`).
+    Other(u16),
+}
+
+/// How far a [`Event::MouseWheel`] scrolled. Positive `y` scrolls up (away from the user), positive `x` scrolls right.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScrollDelta {
+    /// In notches of a wheel. A wheel with finer steps (or a touchpad emulating a wheel) reports fractions of a line.
+    Lines { x: f32, y: f32 },
+    /// In physical pixels, from devices that scroll smoothly.
+    Pixels { x: f64, y: f64 },
+}
+
+/// Kind of device behind a pointer event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PointerKind {
+    Mouse,
+    Touch,
+    Pen,
+}
+
+/// State of a stylus, reported with every pen event.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PenState {
+    /// Tip pressure, from `0.0` to `1.0`.
+    pub pressure: f64,
+    /// Tilt along the x and y axes in degrees from perpendicular (`-90.0..=90.0`, positive is right and towards the user).
+    pub tilt: (f64, f64),
+    /// Whether the barrel (side) button is held.
+    pub barrel_button: bool,
+}
+
+/// Queue of events that were produced by the platform but haven't been handed to the application yet.
+pub struct EventQueue {
+    events: Mutex<VecDeque<Event>>,
+}
+
+impl Default for EventQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventQueue {
+    pub fn new() -> Self {
+        Self {
+            events: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Add an event to the back of the queue.
+    pub fn push(&self, event: Event) {
+        if let Ok(mut events) = self.events.lock() {
+            events.push_back(event);
+        }
+    }
+
+    /// Take all queued events (oldest first).
+    pub fn drain(&self) -> Vec<Event> {
+        if let Ok(mut events) = self.events.lock() {
+            events.drain(..).collect()
+        } else {
+            Vec::new()
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Linux joystick interface (`/dev/input/js*`)
+
+use crate::gamepad::{GamepadAxis, GamepadBackend, GamepadButton, GamepadId, GamepadState};
+use log::debug;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// How often `/dev/input` is scanned for newly connected joysticks.
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+const JS_EVENT_BUTTON: u8 = 0x01;
+const JS_EVENT_AXIS: u8 = 0x02;
+/// Set on the synthetic events that report the initial state after opening the device.
+const JS_EVENT_INIT: u8 = 0x80;
+
+/// Button numbers as reported by the `xpad` driver (Xbox and most compatible controllers).
+const BUTTONS: [GamepadButton; 11] = [
+    GamepadButton::South,
+    GamepadButton::East,
+    GamepadButton::West,
+    GamepadButton::North,
+    GamepadButton::LeftShoulder,
+    GamepadButton::RightShoulder,
+    GamepadButton::Select,
+    GamepadButton::Start,
+    GamepadButton::Mode,
+    GamepadButton::LeftStick,
+    GamepadButton::RightStick,
+];
+
+struct Joystick {
+    id: GamepadId,
+    path: PathBuf,
+    file: File,
+    state: GamepadState,
+}
+
+pub(super) struct JoystickBackend {
+    joysticks: Vec<Joystick>,
+    next_id: u32,
+    last_scan: Option<Instant>,
+}
+
+impl JoystickBackend {
+    pub(super) fn new() -> Self {
+        Self {
+            joysticks: Vec::new(),
+            next_id: 0,
+            last_scan: None,
+        }
+    }
+
+    fn scan(&mut self) {
+        let Ok(entries) = std::fs::read_dir("/dev/input") else {
+            return;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_joystick = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("js"));
+
+            if !is_joystick || self.joysticks.iter().any(|joystick| joystick.path == path) {
+                continue;
+            }
+
+            // usually fails with a permission error for devices we aren't allowed to read, which isn't worth reporting every scan
+            let Ok(file) = OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open(&path)
+            else {
+                continue;
+            };
+
+            debug!("Opened joystick {}", path.display());
+
+            self.joysticks.push(Joystick {
+                id: GamepadId(self.next_id),
+                path,
+                file,
+                state: GamepadState::default(),
+            });
+            self.next_id += 1;
+        }
+    }
+}
+
+impl Joystick {
+    /// Apply all pending events. Returns `false` if the device is gone.
+    fn read_events(&mut self) -> bool {
+        // struct js_event { __u32 time; __s16 value; __u8 type; __u8 number; }
+        let mut buffer = [0u8; 8];
+
+        loop {
+            match self.file.read(&mut buffer) {
+                Ok(8) => {
+                    let value = i16::from_ne_bytes([buffer[4], buffer[5]]);
+                    let kind = buffer[6] & !JS_EVENT_INIT;
+                    let number = buffer[7];
+
+                    match kind {
+                        JS_EVENT_BUTTON => self.button(number, value != 0),
+                        JS_EVENT_AXIS => self.axis(number, value),
+                        _ => (),
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return true,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                _ => return false,
+            }
+        }
+    }
+
+    fn button(&mut self, number: u8, pressed: bool) {
+        if let Some(&button) = BUTTONS.get(number as usize) {
+            self.state.set_pressed(button, pressed);
+        }
+    }
+
+    fn axis(&mut self, number: u8, value: i16) {
+        let stick = (value as f32 / i16::MAX as f32).clamp(-1.0, 1.0);
+        let trigger = ((value as f32 + i16::MAX as f32) / (2.0 * i16::MAX as f32)).clamp(0.0, 1.0);
+
+        // axis numbers as reported by `xpad`, the y axes point down
+        match number {
+            0 => self.state.set_axis(GamepadAxis::LeftStickX, stick),
+            1 => self.state.set_axis(GamepadAxis::LeftStickY, -stick),
+            2 => self.state.set_axis(GamepadAxis::LeftTrigger, trigger),
+            3 => self.state.set_axis(GamepadAxis::RightStickX, stick),
+            4 => self.state.set_axis(GamepadAxis::RightStickY, -stick),
+            5 => self.state.set_axis(GamepadAxis::RightTrigger, trigger),
+            // the d-pad is reported as a hat with two axes
+            6 => {
+                self.state.set_pressed(GamepadButton::DPadLeft, value < 0);
+                self.state.set_pressed(GamepadButton::DPadRight, value > 0);
+            }
+            7 => {
+                self.state.set_pressed(GamepadButton::DPadUp, value < 0);
+                self.state.set_pressed(GamepadButton::DPadDown, value > 0);
+            }
+            _ => (),
+        }
+    }
+}
+
+impl GamepadBackend for JoystickBackend {
+    fn poll(&mut self) -> Vec<(GamepadId, GamepadState)> {
+        if self
+            .last_scan
+            .is_none_or(|last_scan| last_scan.elapsed() >= SCAN_INTERVAL)
+        {
+            self.scan();
+            self.last_scan = Some(Instant::now());
+        }
+
+        self.joysticks.retain_mut(|joystick| {
+            let connected = joystick.read_events();
+            if !connected {
+                debug!("Joystick {} disconnected", joystick.path.display());
+            }
+            connected
+        });
+
+        self.joysticks
+            .iter()
+            .map(|joystick| (joystick.id, joystick.state))
+            .collect()
+    }
+}
@@ -0,0 +1,221 @@
+//! Gamepad input
+//!
+//! Gamepads are polled once per [`Engine::process_events`](crate::Engine::process_events), changes since the previous poll are reported as events.
+//! Buttons and axes use the Xbox controller layout, other controllers are mapped onto it as well as the OS allows.
+
+#[cfg(target_os = "linux")]
+mod joystick;
+
+#[cfg(windows)]
+mod xinput;
+
+use crate::event::{Event, EventQueue};
+use hashbrown::HashMap;
+use std::sync::Mutex;
+
+/// Identifies a connected gamepad. Ids are not reused while the engine is running.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GamepadId(pub(crate) u32);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    /// Bottom face button (A on Xbox controllers).
+    South,
+    /// Right face button (B on Xbox controllers).
+    East,
+    /// Left face button (X on Xbox controllers).
+    West,
+    /// Top face button (Y on Xbox controllers).
+    North,
+    LeftShoulder,
+    RightShoulder,
+    /// Back / view / share button.
+    Select,
+    Start,
+    /// Guide / home button (not available through XInput).
+    Mode,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl GamepadButton {
+    pub const ALL: [GamepadButton; 15] = [
+        GamepadButton::South,
+        GamepadButton::East,
+        GamepadButton::West,
+        GamepadButton::North,
+        GamepadButton::LeftShoulder,
+        GamepadButton::RightShoulder,
+        GamepadButton::Select,
+        GamepadButton::Start,
+        GamepadButton::Mode,
+        GamepadButton::LeftStick,
+        GamepadButton::RightStick,
+        GamepadButton::DPadUp,
+        GamepadButton::DPadDown,
+        GamepadButton::DPadLeft,
+        GamepadButton::DPadRight,
+    ];
+}
+
+/// Stick axes range from `-1.0` to `1.0` (positive is right and up), triggers from `0.0` to `1.0`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl GamepadAxis {
+    pub const ALL: [GamepadAxis; 6] = [
+        GamepadAxis::LeftStickX,
+        GamepadAxis::LeftStickY,
+        GamepadAxis::RightStickX,
+        GamepadAxis::RightStickY,
+        GamepadAxis::LeftTrigger,
+        GamepadAxis::RightTrigger,
+    ];
+}
+
+/// Button and axis state of a gamepad at the last poll.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct GamepadState {
+    buttons: u32,
+    axes: [f32; 6],
+}
+
+impl GamepadState {
+    pub fn is_pressed(&self, button: GamepadButton) -> bool {
+        self.buttons & (1 << button as u32) != 0
+    }
+
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes[axis as usize]
+    }
+
+    pub(crate) fn set_pressed(&mut self, button: GamepadButton, pressed: bool) {
+        if pressed {
+            self.buttons |= 1 << button as u32;
+        } else {
+            self.buttons &= !(1 << button as u32);
+        }
+    }
+
+    pub(crate) fn set_axis(&mut self, axis: GamepadAxis, value: f32) {
+        self.axes[axis as usize] = value;
+    }
+}
+
+/// Source of gamepad state for a platform.
+trait GamepadBackend {
+    /// Read the current state of every connected gamepad.
+    fn poll(&mut self) -> Vec<(GamepadId, GamepadState)>;
+}
+
+#[cfg(windows)]
+fn new_backend() -> Option<Box<dyn GamepadBackend>> {
+    Some(Box::new(xinput::XInputBackend::new()))
+}
+
+#[cfg(target_os = "linux")]
+fn new_backend() -> Option<Box<dyn GamepadBackend>> {
+    Some(Box::new(joystick::JoystickBackend::new()))
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn new_backend() -> Option<Box<dyn GamepadBackend>> {
+    None
+}
+
+/// The gamepads known to the engine.
+pub struct Gamepads {
+    backend: Mutex<Option<Box<dyn GamepadBackend>>>,
+    states: Mutex<HashMap<GamepadId, GamepadState>>,
+}
+
+impl Gamepads {
+    pub(crate) fn new() -> Self {
+        Self {
+            backend: Mutex::new(new_backend()),
+            states: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Ids of the currently connected gamepads.
+    pub fn connected(&self) -> Vec<GamepadId> {
+        let Ok(states) = self.states.lock() else {
+            return Vec::new();
+        };
+
+        let mut ids: Vec<GamepadId> = states.keys().copied().collect();
+        ids.sort();
+        ids
+    }
+
+    /// State of a gamepad at the last poll, `None` if it isn't connected.
+    pub fn state(&self, id: GamepadId) -> Option<GamepadState> {
+        self.states.lock().ok()?.get(&id).copied()
+    }
+
+    /// Poll the backend and queue events for everything that changed.
+    pub(crate) fn poll(&self, event_queue: &EventQueue) {
+        let Ok(mut backend) = self.backend.lock() else {
+            return;
+        };
+        let Some(backend) = backend.as_mut() else {
+            return;
+        };
+        let Ok(mut states) = self.states.lock() else {
+            return;
+        };
+
+        let current: HashMap<GamepadId, GamepadState> = backend.poll().into_iter().collect();
+
+        for (&gamepad, state) in &current {
+            let previous = match states.get(&gamepad) {
+                Some(previous) => *previous,
+                None => {
+                    event_queue.push(Event::GamepadConnected { gamepad });
+                    GamepadState::default()
+                }
+            };
+
+            for button in GamepadButton::ALL {
+                let pressed = state.is_pressed(button);
+                if pressed != previous.is_pressed(button) {
+                    event_queue.push(Event::GamepadButton {
+                        gamepad,
+                        button,
+                        pressed,
+                    });
+                }
+            }
+
+            for axis in GamepadAxis::ALL {
+                let value = state.axis(axis);
+                if value != previous.axis(axis) {
+                    event_queue.push(Event::GamepadAxis {
+                        gamepad,
+                        axis,
+                        value,
+                    });
+                }
+            }
+        }
+
+        for &gamepad in states.keys() {
+            if !current.contains_key(&gamepad) {
+                event_queue.push(Event::GamepadDisconnected { gamepad });
+            }
+        }
+
+        *states = current;
+    }
+}
@@ -0,0 +1,104 @@
+//! XInput gamepads
+
+use crate::gamepad::{GamepadAxis, GamepadBackend, GamepadButton, GamepadId, GamepadState};
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::ERROR_SUCCESS;
+use windows::Win32::UI::Input::XboxController::{
+    XINPUT_GAMEPAD_A, XINPUT_GAMEPAD_B, XINPUT_GAMEPAD_BACK, XINPUT_GAMEPAD_BUTTON_FLAGS,
+    XINPUT_GAMEPAD_DPAD_DOWN, XINPUT_GAMEPAD_DPAD_LEFT, XINPUT_GAMEPAD_DPAD_RIGHT,
+    XINPUT_GAMEPAD_DPAD_UP, XINPUT_GAMEPAD_LEFT_SHOULDER, XINPUT_GAMEPAD_LEFT_THUMB,
+    XINPUT_GAMEPAD_RIGHT_SHOULDER, XINPUT_GAMEPAD_RIGHT_THUMB, XINPUT_GAMEPAD_START,
+    XINPUT_GAMEPAD_X, XINPUT_GAMEPAD_Y, XINPUT_STATE, XInputGetState, XUSER_MAX_COUNT,
+};
+
+/// Querying an empty slot is slow, so disconnected slots are only checked this often.
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+const BUTTONS: [(XINPUT_GAMEPAD_BUTTON_FLAGS, GamepadButton); 14] = [
+    (XINPUT_GAMEPAD_A, GamepadButton::South),
+    (XINPUT_GAMEPAD_B, GamepadButton::East),
+    (XINPUT_GAMEPAD_X, GamepadButton::West),
+    (XINPUT_GAMEPAD_Y, GamepadButton::North),
+    (XINPUT_GAMEPAD_LEFT_SHOULDER, GamepadButton::LeftShoulder),
+    (XINPUT_GAMEPAD_RIGHT_SHOULDER, GamepadButton::RightShoulder),
+    (XINPUT_GAMEPAD_BACK, GamepadButton::Select),
+    (XINPUT_GAMEPAD_START, GamepadButton::Start),
+    (XINPUT_GAMEPAD_LEFT_THUMB, GamepadButton::LeftStick),
+    (XINPUT_GAMEPAD_RIGHT_THUMB, GamepadButton::RightStick),
+    (XINPUT_GAMEPAD_DPAD_UP, GamepadButton::DPadUp),
+    (XINPUT_GAMEPAD_DPAD_DOWN, GamepadButton::DPadDown),
+    (XINPUT_GAMEPAD_DPAD_LEFT, GamepadButton::DPadLeft),
+    (XINPUT_GAMEPAD_DPAD_RIGHT, GamepadButton::DPadRight),
+];
+
+pub(super) struct XInputBackend {
+    /// Gamepad id of each user slot, `None` while the slot is empty.
+    slots: [Option<GamepadId>; XUSER_MAX_COUNT as usize],
+    next_id: u32,
+    last_scan: Option<Instant>,
+}
+
+impl XInputBackend {
+    pub(super) fn new() -> Self {
+        Self {
+            slots: [None; XUSER_MAX_COUNT as usize],
+            next_id: 0,
+            last_scan: None,
+        }
+    }
+}
+
+impl GamepadBackend for XInputBackend {
+    fn poll(&mut self) -> Vec<(GamepadId, GamepadState)> {
+        let scan = self
+            .last_scan
+            .is_none_or(|last_scan| last_scan.elapsed() >= SCAN_INTERVAL);
+        if scan {
+            self.last_scan = Some(Instant::now());
+        }
+
+        let mut gamepads = Vec::new();
+
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.is_none() && !scan {
+                continue;
+            }
+
+            let mut state = XINPUT_STATE::default();
+            if unsafe { XInputGetState(index as u32, &mut state) } != ERROR_SUCCESS.0 {
+                *slot = None;
+                continue;
+            }
+
+            let id = *slot.get_or_insert_with(|| {
+                self.next_id += 1;
+                GamepadId(self.next_id - 1)
+            });
+
+            gamepads.push((id, convert_state(&state)));
+        }
+
+        gamepads
+    }
+}
+
+fn convert_state(state: &XINPUT_STATE) -> GamepadState {
+    let pad = &state.Gamepad;
+    let mut result = GamepadState::default();
+
+    for (flag, button) in BUTTONS {
+        result.set_pressed(button, pad.wButtons.contains(flag));
+    }
+
+    let stick = |value: i16| (value as f32 / i16::MAX as f32).clamp(-1.0, 1.0);
+    let trigger = |value: u8| value as f32 / u8::MAX as f32;
+
+    result.set_axis(GamepadAxis::LeftStickX, stick(pad.sThumbLX));
+    result.set_axis(GamepadAxis::LeftStickY, stick(pad.sThumbLY));
+    result.set_axis(GamepadAxis::RightStickX, stick(pad.sThumbRX));
+    result.set_axis(GamepadAxis::RightStickY, stick(pad.sThumbRY));
+    result.set_axis(GamepadAxis::LeftTrigger, trigger(pad.bLeftTrigger));
+    result.set_axis(GamepadAxis::RightTrigger, trigger(pad.bRightTrigger));
+
+    result
+}
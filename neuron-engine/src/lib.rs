@@ -1,5 +1,7 @@
 //! # Neuron Engine
 
+pub mod event;
+pub mod gamepad;
 pub mod os;
 
 #[cfg(target_os = "linux")]
@@ -8,26 +10,100 @@ pub extern crate x11_dl;
 #[cfg(windows)]
 pub extern crate windows;
 
-use crate::os::window::{Window, WindowAttributes, WindowId, WindowManager};
-use crate::os::{OsLoopInputs, Platform, new_platform};
+use crate::event::{Event, EventQueue};
+use crate::gamepad::Gamepads;
+#[cfg(target_os = "linux")]
+use crate::os::new_x11_platform_from_display;
+use crate::os::window::{AttributeReport, Window, WindowAttributes, WindowId, WindowManager};
+use crate::os::{OsLoopInputs, Platform, PointerState, debug_assert_main_thread, new_platform};
+use anyhow::anyhow;
+use log::error;
+use std::cell::Cell;
+use std::fmt;
 use std::process::ExitCode;
-use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, RwLock, Weak};
+use std::time::Duration;
+
+/// How long the engine blocks waiting for OS events (while every window is occluded, or in [`ControlFlow::Wait`]). Gamepads and exit requests are only noticed between waits, so this can't be much longer.
+const EVENT_WAIT_TIMEOUT: Duration = Duration::from_millis(250);
 
 pub struct Engine {
     platform: Arc<dyn Platform>,
     window_manager: Arc<WindowManager>,
     exit_manager: Arc<ExitManager>,
+    event_queue: Arc<EventQueue>,
+    gamepads: Gamepads,
+    wait_when_occluded: Cell<bool>,
 }
 
-impl Engine {
-    pub fn new() -> anyhow::Result<Self> {
-        Ok(Self {
-            platform: new_platform()?,
-            window_manager: Arc::new(WindowManager::new()),
+/// Configures how an [`Engine`] is created, see [`Engine::builder`].
+#[derive(Default)]
+pub struct EngineBuilder {
+    platform: Option<Arc<dyn Platform>>,
+    #[cfg(target_os = "linux")]
+    x11_display: Option<(*mut x11_dl::xlib::Display, bool)>,
+}
+
+impl EngineBuilder {
+    /// Run on an existing X connection instead of opening a new one (e.g. when embedded in an application that already talks to the X server).
+    ///
+    /// With `owns == false` the connection stays open after the engine is dropped.
+    ///
+    /// # Safety
+    /// `display` has to be a valid connection that stays open for as long as the engine exists.
+    #[cfg(target_os = "linux")]
+    pub unsafe fn x11_display(mut self, display: *mut x11_dl::xlib::Display, owns: bool) -> Self {
+        self.x11_display = Some((display, owns));
+        self
+    }
+
+    /// Share the platform of another engine instead of creating one (e.g. for a plugin host running several instances in one process), see [`Engine::platform`].
+    ///
+    /// Each engine keeps its own windows and events: events for a window are delivered to the engine that created it, whichever of the engines processes them.
+    /// Events that don't belong to a window (monitor, lock key and gamepad changes, the global keyboard hook) only reach the engine that processes them.
+    /// All engines sharing a platform have to be used from the platform's thread. Overrides any other platform option.
+    pub fn platform(mut self, platform: Arc<dyn Platform>) -> Self {
+        self.platform = Some(platform);
+        self
+    }
+
+    pub fn build(self) -> anyhow::Result<Engine> {
+        #[cfg(target_os = "linux")]
+        let platform = match (self.platform, self.x11_display) {
+            (Some(platform), _) => platform,
+            (None, Some((display, owns))) => unsafe {
+                new_x11_platform_from_display(display, owns)?
+            },
+            (None, None) => new_platform()?,
+        };
+
+        #[cfg(not(target_os = "linux"))]
+        let platform = match self.platform {
+            Some(platform) => platform,
+            None => new_platform()?,
+        };
+
+        let event_queue = Arc::new(EventQueue::new());
+
+        Ok(Engine {
+            platform,
+            window_manager: Arc::new(WindowManager::with_event_queue(event_queue.clone())),
             exit_manager: Arc::new(ExitManager::new()),
+            event_queue,
+            gamepads: Gamepads::new(),
+            wait_when_occluded: Cell::new(false),
         })
     }
+}
+
+impl Engine {
+    pub fn new() -> anyhow::Result<Self> {
+        Self::builder().build()
+    }
+
+    pub fn builder() -> EngineBuilder {
+        EngineBuilder::default()
+    }
 
     pub fn platform(&self) -> &Arc<dyn Platform> {
         &self.platform
@@ -37,24 +113,257 @@ impl Engine {
         &self.window_manager
     }
 
+    pub fn gamepads(&self) -> &Gamepads {
+        &self.gamepads
+    }
+
     pub fn create_window(
         &self,
         window_attributes: WindowAttributes,
     ) -> anyhow::Result<(WindowId, Weak<dyn Window>)> {
         self.window_manager
-            .create_window(window_attributes, &self.platform)
+            .create_window(window_attributes, &self.platform, &self.loop_inputs())
     }
 
+    /// Like [`Engine::create_window`], but also report which of the requested attributes the platform ignored.
+    pub fn create_window_with_report(
+        &self,
+        window_attributes: WindowAttributes,
+    ) -> anyhow::Result<(WindowId, Weak<dyn Window>, AttributeReport)> {
+        let report = self
+            .platform
+            .supported_window_attributes()
+            .check(&window_attributes);
+        let (id, window) = self.create_window(window_attributes)?;
+        Ok((id, window, report))
+    }
+
+    /// Where the pointer is relative to a window and which buttons are held, see [`Platform::query_pointer`].
+    ///
+    /// `None` for windows that aren't active, including ones that are closing but not destroyed yet.
+    pub fn query_pointer(&self, window: WindowId) -> Option<PointerState> {
+        if !self.window_manager.is_window_active(window) {
+            return None;
+        }
+
+        self.platform.query_pointer(window)
+    }
+
+    /// Handle pending OS events.
+    ///
+    /// With [`Engine::set_wait_when_occluded`] enabled this blocks while every window is occluded, until an OS event arrives (or a short timeout passes).
     pub fn process_events(&self) -> ExitState {
-        self.platform.process_events(&OsLoopInputs {
-            window_manager: self.window_manager.clone(),
-            exit_manager: self.exit_manager.clone(),
-        });
+        if self.wait_when_occluded.get() && self.window_manager.all_windows_occluded() {
+            self.platform.wait_for_events(EVENT_WAIT_TIMEOUT);
+        }
+
+        self.pump_once()
+    }
+
+    /// Sleep until OS events arrive (or `timeout` passes), then handle them. For applications that only redraw in response to input, so they don't spin while idle.
+    ///
+    /// Gamepads are only polled once the wait ends, pass a timeout if their changes should be noticed without other input.
+    ///
+    /// ```no_run
+    /// # use neuron_engine::{Engine, ExitState};
+    /// # use neuron_engine::os::window::WindowAttributes;
+    /// # use std::time::Duration;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let engine = Engine::new()?;
+    /// engine.create_window(WindowAttributes::default())?;
+    ///
+    /// while let ExitState::Running = engine.wait_events(Some(Duration::from_secs(1))) {
+    ///     for event in engine.drain_events() {
+    ///         // handle the event, then redraw
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn wait_events(&self, timeout: Option<Duration>) -> ExitState {
+        debug_assert_main_thread(self.platform.as_ref(), "Engine::wait_events");
+
+        self.platform.wait_events(&self.loop_inputs(), timeout);
+
+        self.finish_pass()
+    }
+
+    /// Make [`Engine::process_events`] block while all windows are minimized, hidden or covered, instead of returning right away (off by default).
+    ///
+    /// A render loop built on `process_events` then drops to a few iterations per second when nothing can be seen, and picks back up once a window is visible again (reported by [`Event::Occluded`]).
+    pub fn set_wait_when_occluded(&self, enabled: bool) {
+        self.wait_when_occluded.set(enabled);
+    }
+
+    /// Do exactly one non-blocking pass over the pending OS events and return.
+    ///
+    /// This is meant for embedding the engine in an application that already owns the main loop (e.g. a GTK app): the engine never loops or waits on its own here, so it can be called from the host loop's idle or timer callback.
+    /// Events handled during the pass are available from [`Engine::drain_events`] afterwards.
+    ///
+    /// ```no_run
+    /// # use neuron_engine::{Engine, ExitState};
+    /// # fn main() -> anyhow::Result<()> {
+    /// let engine = Engine::new()?;
+    ///
+    /// // registered with the host loop, e.g. as a ~16ms timer; returning false unregisters it
+    /// let on_timer = move || match engine.pump_once() {
+    ///     ExitState::Running => {
+    ///         for event in engine.drain_events() {
+    ///             // handle the event
+    ///         }
+    ///         true
+    ///     }
+    ///     _ => false,
+    /// };
+    /// # let _ = on_timer;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pump_once(&self) -> ExitState {
+        debug_assert_main_thread(self.platform.as_ref(), "Engine::pump_once");
+
+        self.platform.process_events(&self.loop_inputs());
+
+        self.finish_pass()
+    }
+
+    /// What follows every pass over the OS events: gamepads, closing windows and the exit state.
+    fn finish_pass(&self) -> ExitState {
+        self.gamepads.poll(&self.event_queue);
 
         self.window_manager.update();
 
         self.exit_manager.take_exit_state()
     }
+
+    /// Handle OS events until none are left, including the ones produced while handling earlier ones.
+    ///
+    /// Unlike a single [`Engine::pump_once`] pass this guarantees no backlog remains when it returns, which is what tests and transitions need after programmatic window operations (requests are flushed to the OS first, see [`Platform::sync`]).
+    /// Stops early once an exit is requested.
+    pub fn process_all_pending(&self) -> ExitState {
+        self.platform.sync();
+
+        loop {
+            match self.pump_once() {
+                ExitState::Running => {}
+                state => return state,
+            }
+
+            if !self.platform.has_pending_events() {
+                return ExitState::Running;
+            }
+        }
+    }
+
+    /// Run the event loop until the handler returns [`ControlFlow::Exit`], an exit is requested or all windows are closed, passing every event to `handler`.
+    ///
+    /// The value returned for the last event decides what happens once the events are handled: [`ControlFlow::Poll`] loops right away (for games rendering continuously), [`ControlFlow::Wait`] sleeps until the OS has new events.
+    /// Without any windows (including when none were created yet) this returns after the first pass.
+    ///
+    /// ```no_run
+    /// # use neuron_engine::{ControlFlow, Engine};
+    /// # use neuron_engine::event::Event;
+    /// # use neuron_engine::os::window::WindowAttributes;
+    /// # use std::process::ExitCode;
+    /// # fn main() -> anyhow::Result<ExitCode> {
+    /// let engine = Engine::new()?;
+    /// engine.create_window(WindowAttributes::default())?;
+    ///
+    /// Ok(engine.run(|event, _engine| match event {
+    ///     Event::KeyboardInput { .. } => ControlFlow::Exit,
+    ///     _ => ControlFlow::Wait,
+    /// }))
+    /// # }
+    /// ```
+    pub fn run<F: FnMut(Event, &Engine) -> ControlFlow>(self, mut handler: F) -> ExitCode {
+        let exit_code = self.run_loop(&mut handler);
+
+        // the handler gets to see the remaining windows go, whatever it returns now doesn't matter anymore
+        self.window_manager.destroy_all_windows();
+        for event in self.drain_events() {
+            handler(event, &self);
+        }
+
+        exit_code
+    }
+
+    fn run_loop<F: FnMut(Event, &Engine) -> ControlFlow>(&self, handler: &mut F) -> ExitCode {
+        let mut control_flow = ControlFlow::default();
+
+        loop {
+            match self.process_events() {
+                ExitState::Running => {}
+                ExitState::ExitSuccess => return ExitCode::SUCCESS,
+                ExitState::ExitError(e) => {
+                    error!("Exiting with an error: {:?}", e);
+                    return ExitCode::FAILURE;
+                }
+                ExitState::ExitErrorGeneric => return ExitCode::FAILURE,
+                ExitState::ExitCode(code) => return ExitCode::from(code),
+            }
+
+            for event in self.drain_events() {
+                control_flow = handler(event, self);
+                if control_flow == ControlFlow::Exit {
+                    return ExitCode::SUCCESS;
+                }
+            }
+
+            if self.window_manager.active_window_ids().is_empty() {
+                return ExitCode::SUCCESS;
+            }
+
+            if control_flow == ControlFlow::Wait {
+                self.platform.wait_for_events(EVENT_WAIT_TIMEOUT);
+            }
+        }
+    }
+
+    /// Ask the engine to exit (e.g. from a quit menu item or shortcut), whether or not windows are still open.
+    ///
+    /// The request is reported as [`ExitState::ExitSuccess`] by the next [`Engine::process_events`], and ends [`Engine::run`].
+    pub fn request_exit(&self) {
+        self.exit_manager.set(ExitState::ExitSuccess);
+    }
+
+    /// Ask the engine to exit because of `err`, which the next [`Engine::process_events`] returns in [`ExitState::ExitError`].
+    pub fn request_exit_with_error(&self, err: anyhow::Error) {
+        self.exit_manager.set(ExitState::ExitError(err));
+    }
+
+    /// Ask the engine to exit with the given process exit code.
+    ///
+    /// The request is reported as [`ExitState::ExitCode`] by the next [`Engine::process_events`].
+    pub fn request_exit_code(&self, code: u8) {
+        self.exit_manager.set(ExitState::ExitCode(code));
+    }
+
+    /// Take all events produced since the last call (oldest first).
+    ///
+    /// Events are collected while [`Engine::process_events`] runs, so call this after it.
+    pub fn drain_events(&self) -> Vec<Event> {
+        self.event_queue.drain()
+    }
+
+    fn loop_inputs(&self) -> OsLoopInputs {
+        OsLoopInputs {
+            window_manager: self.window_manager.clone(),
+            exit_manager: self.exit_manager.clone(),
+            event_queue: self.event_queue.clone(),
+        }
+    }
+}
+
+/// What [`Engine::run`] does after the handler returned.
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+pub enum ControlFlow {
+    /// Process events again right away, whether or not there are new ones.
+    Poll,
+    /// Sleep until new events arrive (gamepads are still polled a few times per second).
+    #[default]
+    Wait,
+    /// Stop the loop, [`Engine::run`] returns [`ExitCode::SUCCESS`].
+    Exit,
 }
 
 #[derive(Default)]
@@ -63,7 +372,52 @@ pub enum ExitState {
     Running,
     ExitSuccess,
     ExitError(anyhow::Error),
+    /// An error exit whose error was already taken by an earlier [`Engine::process_events`].
     ExitErrorGeneric,
+    /// Exit with a specific process exit code (see [`std::process::ExitCode::from`]).
+    ExitCode(u8),
+}
+
+impl fmt::Display for ExitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitState::Running => write!(f, "running"),
+            ExitState::ExitSuccess => write!(f, "exited successfully"),
+            ExitState::ExitError(e) => write!(f, "exited with an error: {}", e),
+            ExitState::ExitErrorGeneric => write!(f, "exited with an error"),
+            ExitState::ExitCode(code) => write!(f, "exited with code {}", code),
+        }
+    }
+}
+
+/// `Ok` while running and for successful exits (including exit code 0), otherwise the error (the original one for [`ExitState::ExitError`]).
+///
+/// Since a successful exit converts to `Ok` just like [`ExitState::Running`], a loop has to check for `Running` itself before converting:
+///
+/// ```no_run
+/// # use neuron_engine::{Engine, ExitState};
+/// # fn main() -> anyhow::Result<()> {
+/// let engine = Engine::new()?;
+/// loop {
+///     let state = engine.process_events();
+///     if !matches!(state, ExitState::Running) {
+///         return state.into();
+///     }
+///
+///     for event in engine.drain_events() {
+///         // handle the event
+///     }
+/// }
+/// # }
+/// ```
+impl From<ExitState> for anyhow::Result<()> {
+    fn from(state: ExitState) -> Self {
+        match state {
+            ExitState::Running | ExitState::ExitSuccess | ExitState::ExitCode(0) => Ok(()),
+            ExitState::ExitError(e) => Err(e),
+            state => Err(anyhow!("Engine {}", state)),
+        }
+    }
 }
 
 pub struct ExitManager {
@@ -80,10 +434,7 @@ impl ExitManager {
     pub fn should_exit(&self) -> bool {
         let value = self.exit_state.read();
         if let Ok(value) = value {
-            match &*value {
-                &ExitState::Running => false,
-                _ => true,
-            }
+            !matches!(*value, ExitState::Running)
         } else {
             false
         }
@@ -96,20 +447,19 @@ impl ExitManager {
         }
     }
 
+    /// The current exit state, with the actual error moved out. The request itself stays (so [`ExitManager::should_exit`] keeps reporting it), later calls get [`ExitState::ExitErrorGeneric`] for an error.
     fn take_exit_state(&self) -> ExitState {
         let Ok(mut l) = self.exit_state.write() else {
             return ExitState::Running;
         };
 
-        let mut es = match *l {
+        let remaining = match *l {
             ExitState::Running => ExitState::Running,
             ExitState::ExitSuccess => ExitState::ExitSuccess,
-            ExitState::ExitError(_) => ExitState::ExitErrorGeneric,
-            ExitState::ExitErrorGeneric => ExitState::ExitErrorGeneric,
+            ExitState::ExitError(_) | ExitState::ExitErrorGeneric => ExitState::ExitErrorGeneric,
+            ExitState::ExitCode(code) => ExitState::ExitCode(code),
         };
 
-        std::mem::swap(&mut *l, &mut es);
-
-        es
+        std::mem::replace(&mut *l, remaining)
     }
 }
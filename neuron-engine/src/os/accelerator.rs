@@ -0,0 +1,140 @@
+//! Keyboard shortcuts (accelerators) registered with the [`WindowManager`](crate::os::window::WindowManager)
+
+use anyhow::{anyhow, bail};
+use std::fmt;
+use std::str::FromStr;
+
+/// A key combination such as Ctrl+S, see [`WindowManager::register_accelerator`](crate::os::window::WindowManager::register_accelerator).
+///
+/// Can be parsed from strings like `"Ctrl+S"`, `"Ctrl+Shift+F5"` or `"Alt+Enter"` (case-insensitive, `+` separated, modifiers first).
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub struct Accelerator {
+    pub modifiers: AcceleratorModifiers,
+    pub key: AcceleratorKey,
+}
+
+/// Modifier keys held as part of an [`Accelerator`]. Left and right variants aren't told apart.
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub struct AcceleratorModifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+/// The non-modifier key of an [`Accelerator`].
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum AcceleratorKey {
+    /// A letter (`'A'..='Z'`, always uppercase) or digit key.
+    Char(char),
+    /// A function key, `F1` to `F24`.
+    Function(u8),
+    Enter,
+    Escape,
+    Tab,
+    Space,
+    Backspace,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Accelerator {
+    pub fn new(modifiers: AcceleratorModifiers, key: AcceleratorKey) -> Self {
+        Self { modifiers, key }
+    }
+}
+
+impl FromStr for Accelerator {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('+').map(str::trim).collect::<Vec<_>>();
+        let key = parts
+            .pop()
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| anyhow!("Accelerator {:?} has no key", s))?;
+
+        let mut modifiers = AcceleratorModifiers::default();
+        for modifier in parts {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "shift" => modifiers.shift = true,
+                "alt" => modifiers.alt = true,
+                _ => bail!("Unknown modifier {:?} in accelerator {:?}", modifier, s),
+            }
+        }
+
+        Ok(Accelerator::new(modifiers, key.parse()?))
+    }
+}
+
+impl FromStr for AcceleratorKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        if let (Some(c), None) = (chars.next(), chars.next())
+            && c.is_ascii_alphanumeric()
+        {
+            return Ok(AcceleratorKey::Char(c.to_ascii_uppercase()));
+        }
+
+        let lower = s.to_ascii_lowercase();
+        if let Some(number) = lower.strip_prefix('f')
+            && let Ok(number) = number.parse::<u8>()
+        {
+            if !(1..=24).contains(&number) {
+                bail!("Function key {:?} is out of range (F1 to F24)", s);
+            }
+            return Ok(AcceleratorKey::Function(number));
+        }
+
+        Ok(match lower.as_str() {
+            "enter" | "return" => AcceleratorKey::Enter,
+            "escape" | "esc" => AcceleratorKey::Escape,
+            "tab" => AcceleratorKey::Tab,
+            "space" => AcceleratorKey::Space,
+            "backspace" => AcceleratorKey::Backspace,
+            "delete" | "del" => AcceleratorKey::Delete,
+            "insert" | "ins" => AcceleratorKey::Insert,
+            "home" => AcceleratorKey::Home,
+            "end" => AcceleratorKey::End,
+            "pageup" => AcceleratorKey::PageUp,
+            "pagedown" => AcceleratorKey::PageDown,
+            "left" => AcceleratorKey::Left,
+            "right" => AcceleratorKey::Right,
+            "up" => AcceleratorKey::Up,
+            "down" => AcceleratorKey::Down,
+            _ => bail!("Unknown accelerator key {:?}", s),
+        })
+    }
+}
+
+impl fmt::Display for Accelerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.ctrl {
+            f.write_str("Ctrl+")?;
+        }
+        if self.modifiers.shift {
+            f.write_str("Shift+")?;
+        }
+        if self.modifiers.alt {
+            f.write_str("Alt+")?;
+        }
+
+        match self.key {
+            AcceleratorKey::Char(c) => write!(f, "{}", c),
+            AcceleratorKey::Function(number) => write!(f, "F{}", number),
+            key => write!(f, "{:?}", key),
+        }
+    }
+}
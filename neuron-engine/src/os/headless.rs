@@ -0,0 +1,393 @@
+//! Platform without a display, for CI machines and offscreen rendering
+//!
+//! Windows only exist as bookkeeping: they keep their size and position but have no surface, and the OS never sends events for them.
+
+use crate::os::monitor::MonitorHandle;
+use crate::os::window::{
+    Fullscreen, PresentMode, Resolution, SupportedWindowAttributes, Window, WindowAttributes,
+    WindowId, WindowPosition,
+};
+use crate::os::{
+    GlobalKeyboardHook, LockState, OsLoopInputs, PlatformKind, PointerInfo, PointerState,
+    unavailable_handle,
+};
+use anyhow::bail;
+use log::warn;
+use raw_window_handle::{
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, WindowHandle,
+};
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::sync::{Arc, Mutex};
+use std::thread::ThreadId;
+use std::time::Duration;
+
+pub struct HeadlessPlatform {
+    main_thread: ThreadId,
+    /// The clipboard is only shared within the process, see [`Platform::set_clipboard_data`](super::Platform::set_clipboard_data).
+    clipboard: Mutex<Vec<(String, Vec<u8>)>>,
+}
+
+impl HeadlessPlatform {
+    pub fn new() -> HeadlessPlatform {
+        HeadlessPlatform {
+            main_thread: std::thread::current().id(),
+            clipboard: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl Default for HeadlessPlatform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HasDisplayHandle for HeadlessPlatform {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        unavailable_handle()
+    }
+}
+
+/// What [`Platform::supported_window_attributes`](crate::os::Platform::supported_window_attributes) reports.
+static SUPPORTED_WINDOW_ATTRIBUTES: SupportedWindowAttributes = SupportedWindowAttributes {
+    title: false,
+    size: false,
+    position: false,
+    has_close_button: false,
+    has_minimize_button: false,
+    has_maximize_button: false,
+    show_border: false,
+    show_title_bar: false,
+    initially_disabled: false,
+    initially_minimized: false,
+    resizable: false,
+    min_size: false,
+    max_size: false,
+    skip_taskbar: false,
+    show_in_taskbar: false,
+    show_in_switcher: false,
+    initially_visible: false,
+    initial_z_order: false,
+    always_on_top: false,
+    auto_hide_on_focus_loss: false,
+    compositor_bypass: false,
+    windows_show_drop_shadow: false,
+    windows_is_dialog_box: false,
+    windows_has_system_menu: false,
+    x11_window_type: false,
+    fullscreen: false,
+    icon: false,
+    transparent: false,
+    background_color: false,
+};
+
+impl super::Platform for HeadlessPlatform {
+    fn name(&self) -> &'static str {
+        if cfg!(windows) {
+            super::names::WINDOWS_HEADLESS
+        } else {
+            super::names::LINUX_HEADLESS
+        }
+    }
+
+    fn kind(&self) -> PlatformKind {
+        if cfg!(windows) {
+            PlatformKind::WindowsHeadless
+        } else {
+            PlatformKind::LinuxHeadless
+        }
+    }
+
+    fn is_headless(&self) -> bool {
+        true
+    }
+
+    fn is_dark_mode(&self) -> Option<bool> {
+        None
+    }
+
+    fn supported_window_attributes(&self) -> &'static SupportedWindowAttributes {
+        &SUPPORTED_WINDOW_ATTRIBUTES
+    }
+
+    fn primary_monitor(&self) -> Option<MonitorHandle> {
+        None
+    }
+
+    fn available_monitors(&self) -> Vec<MonitorHandle> {
+        Vec::new()
+    }
+
+    fn pointer_info(&self) -> PointerInfo {
+        PointerInfo::default()
+    }
+
+    fn lock_state(&self) -> LockState {
+        LockState::default()
+    }
+
+    fn double_click_time(&self) -> Duration {
+        Duration::from_millis(400)
+    }
+
+    fn double_click_distance(&self) -> u32 {
+        5
+    }
+
+    fn drag_threshold(&self) -> u32 {
+        8
+    }
+
+    fn query_pointer(&self, _window: WindowId) -> Option<PointerState> {
+        None
+    }
+
+    fn create_window(
+        &self,
+        window_attributes: WindowAttributes,
+        window_id: WindowId,
+        _inputs: &OsLoopInputs,
+    ) -> anyhow::Result<Arc<dyn Window>> {
+        if let Some(title) = &window_attributes.title
+            && title.contains('\0')
+        {
+            bail!("Window title {:?} contains a nul character", title);
+        }
+
+        let size = window_attributes
+            .size
+            .map_or((800, 600), |size| size.physical_size(1.0));
+        let position = match window_attributes.position {
+            Some(position) => {
+                let (x, y) = position.physical_position(1.0);
+                WindowPosition { x, y }
+            }
+            None => WindowPosition::default(),
+        };
+
+        // the window manager only touches windows on its own thread, so the `Arc` never leaves it
+        #[allow(clippy::arc_with_non_send_sync)]
+        Ok(Arc::new(HeadlessWindow {
+            id: window_id,
+            size: Cell::new(size),
+            position: Cell::new(position),
+            present_mode: Cell::new(None),
+            resizable: Cell::new(window_attributes.resizable),
+            fullscreen: RefCell::new(window_attributes.fullscreen),
+        }))
+    }
+
+    fn set_global_keyboard_hook(&self, _hook: GlobalKeyboardHook) -> anyhow::Result<()> {
+        bail!("There is no keyboard without a display")
+    }
+
+    fn remove_global_keyboard_hook(&self) {}
+
+    fn clipboard_data(&self, mime: &str) -> Option<Vec<u8>> {
+        self.clipboard
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(entry_mime, _)| entry_mime == mime)
+            .map(|(_, data)| data.clone())
+    }
+
+    fn set_clipboard_data(&self, entries: &[(String, Vec<u8>)]) -> anyhow::Result<()> {
+        *self.clipboard.lock().unwrap() = entries.to_vec();
+        Ok(())
+    }
+
+    fn sync(&self) {}
+
+    fn wait_for_events(&self, timeout: Duration) {
+        // nothing will ever arrive, but callers rely on this not spinning
+        std::thread::sleep(timeout);
+    }
+
+    fn has_pending_events(&self) -> bool {
+        false
+    }
+
+    fn main_thread_only(&self) -> bool {
+        false
+    }
+
+    fn main_thread(&self) -> ThreadId {
+        self.main_thread
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn process_events(&self, _inputs: &OsLoopInputs) {}
+}
+
+struct HeadlessWindow {
+    id: WindowId,
+    size: Cell<(u32, u32)>,
+    position: Cell<WindowPosition>,
+    present_mode: Cell<Option<PresentMode>>,
+    resizable: Cell<bool>,
+    fullscreen: RefCell<Option<Fullscreen>>,
+}
+
+impl HasWindowHandle for HeadlessWindow {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        unavailable_handle()
+    }
+}
+
+impl HasDisplayHandle for HeadlessWindow {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        unavailable_handle()
+    }
+}
+
+impl Window for HeadlessWindow {
+    fn set_client_size(&self, size: Resolution<u32>) {
+        let (width, height) = size.physical_size(1.0);
+        self.size.set((width.max(1), height.max(1)));
+    }
+
+    fn inner_size(&self) -> Resolution<u32> {
+        let (width, height) = self.size.get();
+        Resolution::Physical { width, height }
+    }
+
+    fn position(&self) -> WindowPosition {
+        self.position.get()
+    }
+
+    fn set_inner_size(&self, size: Resolution<u32>) {
+        if !self.resizable.get() {
+            warn!(
+                "Window {:?} isn't resizable, not changing its size",
+                self.id
+            );
+            return;
+        }
+
+        self.set_client_size(size);
+    }
+
+    fn set_resizable(&self, resizable: bool) {
+        self.resizable.set(resizable);
+    }
+
+    fn set_position(&self, position: WindowPosition) {
+        self.position.set(position);
+    }
+
+    fn set_preferred_present_mode(&self, mode: Option<PresentMode>) {
+        self.present_mode.set(mode);
+    }
+
+    fn preferred_present_mode(&self) -> Option<PresentMode> {
+        self.present_mode.get()
+    }
+
+    fn is_occluded(&self) -> bool {
+        // nothing is ever shown
+        true
+    }
+
+    fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+        // there is no monitor to cover, only the state is kept
+        *self.fullscreen.borrow_mut() = fullscreen;
+    }
+
+    fn fullscreen(&self) -> Option<Fullscreen> {
+        self.fullscreen.borrow().clone()
+    }
+
+    fn set_title(&self, title: &str) -> anyhow::Result<()> {
+        if title.contains('\0') {
+            bail!("Window title {:?} contains a nul character", title);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Engine;
+    use crate::os::Platform;
+
+    fn headless_engine() -> Engine {
+        Engine::builder()
+            .platform(Arc::new(HeadlessPlatform::new()))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn clipboard_round_trip() {
+        let platform = HeadlessPlatform::new();
+        assert_eq!(platform.clipboard_text().unwrap(), None);
+
+        platform.set_clipboard_text("Hello!").unwrap();
+        assert_eq!(
+            platform.clipboard_text().unwrap().as_deref(),
+            Some("Hello!")
+        );
+
+        platform
+            .set_clipboard_data(&[
+                ("text/plain".to_string(), b"text".to_vec()),
+                ("image/bmp".to_string(), b"BM".to_vec()),
+            ])
+            .unwrap();
+        assert_eq!(platform.clipboard_text().unwrap().as_deref(), Some("text"));
+        assert_eq!(
+            platform.clipboard_data("image/bmp").as_deref(),
+            Some(&b"BM"[..])
+        );
+        assert_eq!(platform.clipboard_data("text/html"), None);
+    }
+
+    #[test]
+    fn hidden_window_stays_active() {
+        let engine = headless_engine();
+        let (id, window) = engine
+            .create_window(WindowAttributes {
+                initially_visible: false,
+                ..Default::default()
+            })
+            .unwrap();
+
+        window.upgrade().unwrap().hide();
+        engine.process_events();
+        assert!(engine.window_manager().is_window_active(id));
+    }
+
+    #[test]
+    fn set_title_rejects_nul() {
+        let engine = headless_engine();
+        let (_, window) = engine.create_window(WindowAttributes::default()).unwrap();
+        let window = window.upgrade().unwrap();
+
+        window.set_title("updated").unwrap();
+        assert!(window.set_title("nul\0byte").is_err());
+    }
+
+    #[test]
+    fn inner_size_matches_requested() {
+        let engine = headless_engine();
+        let (_, window) = engine
+            .create_window(WindowAttributes {
+                size: Some(Resolution::Physical {
+                    width: 800,
+                    height: 600,
+                }),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let size = window.upgrade().unwrap().inner_size();
+        assert_eq!((size.width(), size.height()), (800, 600));
+    }
+}
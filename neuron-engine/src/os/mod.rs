@@ -1,20 +1,37 @@
 //! Platform interface & platform specific code
 
+pub mod accelerator;
+pub mod headless;
+pub mod monitor;
+pub(crate) mod placement;
 pub mod window;
 
-#[cfg(target_os="linux")]
-mod x11;
+#[cfg(target_os = "linux")]
+pub mod x11;
+
+#[cfg(target_os = "linux")]
+pub mod wayland;
 
 #[cfg(windows)]
 mod windows;
 
-use crate::os::window::{SupportedWindowAttributes, Window, WindowAttributes, WindowId, WindowManager};
-use raw_window_handle::HasDisplayHandle;
-use std::sync::Arc;
 use crate::ExitManager;
+use crate::event::{EventQueue, KeyEvent, MouseButton};
+use crate::os::monitor::MonitorHandle;
+use crate::os::window::{
+    SupportedWindowAttributes, Window, WindowAttributes, WindowId, WindowManager,
+};
+use anyhow::bail;
+#[cfg(target_os = "linux")]
+use log::debug;
+use raw_window_handle::{HandleError, HasDisplayHandle};
+use std::any::Any;
+use std::sync::{Arc, Weak};
+use std::thread::ThreadId;
+use std::time::Duration;
 
 /// Generic access to platform specific functions.
-/// Also requires [`raw_window_handle::HasDisplayHandle`] to be implemented.
+/// Also requires [`raw_window_handle::HasDisplayHandle`] to be implemented, platforms without a display (like the headless one) return [`unavailable_handle`] there.
 pub trait Platform: HasDisplayHandle {
     /// Get the name of the current platform
     ///
@@ -47,12 +64,131 @@ pub trait Platform: HasDisplayHandle {
     /// Get information about which window attributes are actually supported on this system.
     fn supported_window_attributes(&self) -> &'static SupportedWindowAttributes;
 
-    fn create_window(&self, window_attributes: WindowAttributes, window_id: WindowId) -> anyhow::Result<Arc<dyn Window>>;
+    /// Get the primary monitor of the system (or `None` if the platform has no monitors).
+    fn primary_monitor(&self) -> Option<MonitorHandle>;
+
+    /// Get all monitors of the system, with the primary monitor first (empty if the platform has no monitors).
+    fn available_monitors(&self) -> Vec<MonitorHandle>;
+
+    /// Get the capabilities of the pointing device (as reported by the OS, which usually combines all connected mice).
+    fn pointer_info(&self) -> PointerInfo;
+
+    /// Which lock keys are currently on (as shown by the keyboard LEDs). Changes are reported as [`Event::LockStateChanged`](crate::event::Event::LockStateChanged).
+    fn lock_state(&self) -> LockState;
+
+    /// Longest time between two clicks that still counts as a double click.
+    fn double_click_time(&self) -> Duration;
+
+    /// How far (in physical pixels, in either direction) the pointer may move between the clicks of a double click.
+    fn double_click_distance(&self) -> u32;
+
+    /// How far (in physical pixels) the pointer has to move with a button held before a drag starts.
+    fn drag_threshold(&self) -> u32;
+
+    /// Where the pointer is relative to a window and which buttons are held, right now (for polling instead of waiting for [`Event::CursorMoved`](crate::event::Event::CursorMoved)).
+    ///
+    /// `None` if the platform doesn't know the window, or can't tell (Wayland only reports the pointer through events, X11 can't while the pointer is on another screen).
+    /// Platforms only see windows until they are destroyed, use [`Engine::query_pointer`](crate::Engine::query_pointer) to also get `None` for windows that are closing.
+    fn query_pointer(&self, window: WindowId) -> Option<PointerState>;
+
+    /// Create a new window. `inputs` are the engine state the window may need to deliver events outside of [`Platform::process_events`].
+    fn create_window(
+        &self,
+        window_attributes: WindowAttributes,
+        window_id: WindowId,
+        inputs: &OsLoopInputs,
+    ) -> anyhow::Result<Arc<dyn Window>>;
+
+    /// Receive every key press and release, whichever application has the focus (for accessibility and automation tools). Replaces any previously set hook.
+    ///
+    /// The hook is called while events are processed, on the thread that processes them. It only observes keys, they are still delivered to the focused application.
+    ///
+    /// # Security
+    /// This is effectively a keylogger, treat the events accordingly. On Windows it installs a low-level keyboard hook, which doesn't see keys sent to elevated applications unless this process is elevated too, and is silently removed by the OS if the hook takes too long. On X11 it uses XInput2 raw key events, which any client can receive (Wayland sessions only deliver them while an X11 window has the focus).
+    fn set_global_keyboard_hook(&self, hook: GlobalKeyboardHook) -> anyhow::Result<()>;
+
+    /// Remove the hook set with [`Platform::set_global_keyboard_hook`].
+    fn remove_global_keyboard_hook(&self);
+
+    /// Read the clipboard contents in the format given by a MIME type (e.g. `"text/plain"` for UTF-8 text, `"image/bmp"`), `None` if the clipboard holds nothing in that format.
+    ///
+    /// Text is always exchanged as UTF-8. Other MIME types are passed through as they are (on Windows unknown types become registered clipboard formats named after the MIME type), so applications can use their own.
+    fn clipboard_data(&self, mime: &str) -> Option<Vec<u8>>;
+
+    /// Replace the clipboard contents with the same data in one or more formats, as `(mime, data)` pairs (see [`Platform::clipboard_data`]).
+    ///
+    /// On X11 the data is served by the engine, so it stays available only while events are processed and the engine is alive.
+    /// Fails if the clipboard couldn't be taken over or a format couldn't be stored (the other formats are still set then).
+    fn set_clipboard_data(&self, entries: &[(String, Vec<u8>)]) -> anyhow::Result<()>;
+
+    /// The clipboard contents as text, `None` if the clipboard holds no text. Fails if the owner handed over text that isn't valid UTF-8.
+    ///
+    /// On X11 this waits (briefly) for the application owning the clipboard to send the text, other engine events stay queued meanwhile.
+    ///
+    /// ```no_run
+    /// # use neuron_engine::Engine;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let engine = Engine::new()?;
+    /// engine.platform().set_clipboard_text("Hello!")?;
+    /// assert_eq!(engine.platform().clipboard_text()?.as_deref(), Some("Hello!"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn clipboard_text(&self) -> anyhow::Result<Option<String>> {
+        let Some(data) = self.clipboard_data("text/plain") else {
+            return Ok(None);
+        };
+
+        match String::from_utf8(data) {
+            Ok(text) => Ok(Some(text)),
+            Err(e) => bail!("The clipboard text isn't valid UTF-8: {}", e),
+        }
+    }
+
+    /// Replace the clipboard contents with text, see [`Platform::set_clipboard_data`].
+    fn set_clipboard_text(&self, text: &str) -> anyhow::Result<()> {
+        self.set_clipboard_data(&[("text/plain".to_string(), text.as_bytes().to_vec())])
+    }
+
+    /// Flush all pending requests to the OS and wait until they have been handled.
+    ///
+    /// This is a barrier for deterministic tests and frame-perfect operations: once it returns, window changes made before the call have been applied by the OS (or the X server), so geometry can be asserted or pixels grabbed.
+    /// Events produced while syncing stay queued for the next [`Platform::process_events`].
+    fn sync(&self);
+
+    /// Block until OS events are available or `timeout` has passed. Returns right away if events are already pending.
+    ///
+    /// The events aren't handled, call [`Platform::process_events`] afterward.
+    fn wait_for_events(&self, timeout: Duration);
+
+    /// Block until OS events arrive or `timeout` has passed, then handle them (plus anything that was already pending), like [`Platform::process_events`].
+    ///
+    /// `None` waits for as long as it takes. The headless platform never gets events, so it only returns after the timeout.
+    fn wait_events(&self, inputs: &OsLoopInputs, timeout: Option<Duration>) {
+        // the waits are capped to the longest timeout the OS takes (weeks), which is as good as forever
+        self.wait_for_events(timeout.unwrap_or(Duration::MAX));
+        self.process_events(inputs);
+    }
+
+    /// Whether OS events are queued that [`Platform::process_events`] hasn't handled yet. Doesn't block.
+    fn has_pending_events(&self) -> bool;
+
+    /// Whether OS calls (creating windows, processing events) have to happen on the thread that created the platform.
+    fn main_thread_only(&self) -> bool;
+
+    /// The thread the platform was created on.
+    fn main_thread(&self) -> ThreadId;
+
+    /// The concrete platform, for platform-specific functionality (e.g. downcast to [`x11::X11Platform`] or [`wayland::WaylandPlatform`] on Linux).
+    fn as_any(&self) -> &dyn Any;
 
     /// Process OS events (most operating systems have some sort of event polling loop that we have to run to actually handle those events, otherwise the window will stop responding).
     fn process_events(&self, inputs: &OsLoopInputs);
 }
 
+/// Callback receiving every key event, see [`Platform::set_global_keyboard_hook`].
+pub type GlobalKeyboardHook = Box<dyn FnMut(KeyEvent)>;
+
 /// Identifier for platforms.
 ///
 /// Non-standard platforms **must** use [`PlatformKind::Custom`].
@@ -67,36 +203,211 @@ pub enum PlatformKind {
     Custom(&'static str),
 }
 
+impl PlatformKind {
+    /// Any of the Linux backends (including headless).
+    pub fn is_linux(&self) -> bool {
+        matches!(
+            self,
+            PlatformKind::LinuxX11 | PlatformKind::LinuxWayland | PlatformKind::LinuxHeadless
+        )
+    }
+
+    /// Windows, with or without windows (headless).
+    pub fn is_windows(&self) -> bool {
+        matches!(self, PlatformKind::Windows | PlatformKind::WindowsHeadless)
+    }
+
+    /// One of the standard headless platforms (see [`Platform::is_headless`], which custom platforms answer themselves).
+    pub fn is_headless(&self) -> bool {
+        matches!(
+            self,
+            PlatformKind::WindowsHeadless | PlatformKind::LinuxHeadless
+        )
+    }
+
+    /// The platform's name, the same as [`Platform::name`] reports.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            PlatformKind::Windows => names::WINDOWS,
+            PlatformKind::WindowsHeadless => names::WINDOWS_HEADLESS,
+            PlatformKind::LinuxX11 => names::LINUX_X11,
+            PlatformKind::LinuxWayland => names::LINUX_WAYLAND,
+            PlatformKind::LinuxHeadless => names::LINUX_HEADLESS,
+            PlatformKind::Custom(name) => name,
+        }
+    }
+}
+
+/// Capabilities of the system's pointing device, see [`Platform::pointer_info`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PointerInfo {
+    /// Number of mouse buttons (0 if there is no mouse).
+    pub button_count: u32,
+    /// Is there a vertical scroll wheel?
+    pub has_wheel: bool,
+    /// Is there a horizontal scroll wheel (or a tiltable wheel)?
+    pub has_horizontal_wheel: bool,
+}
+
+/// The pointer at one moment, see [`Platform::query_pointer`].
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PointerState {
+    /// In physical pixels relative to the top-left of the window's client area, like [`Event::CursorMoved`](crate::event::Event::CursorMoved) (outside of it when the pointer isn't over the window).
+    pub position: (f64, f64),
+    /// Held buttons, one bit per button (see [`PointerState::button_mask`]). X11 only reports the left, middle and right buttons.
+    pub buttons: u32,
+}
+
+impl PointerState {
+    /// The bit of a button in [`PointerState::buttons`]: bit `n - 1` for the `n`th X11 button (left, middle, right are 1, 2, 3), 0 for buttons that don't fit.
+    pub fn button_mask(button: MouseButton) -> u32 {
+        let number = match button {
+            MouseButton::Left => 1,
+            MouseButton::Middle => 2,
+            MouseButton::Right => 3,
+            MouseButton::Other(number) => number as u32,
+        };
+
+        match number {
+            1..=32 => 1 << (number - 1),
+            _ => 0,
+        }
+    }
+
+    /// Whether `button` was held.
+    pub fn is_pressed(&self, button: MouseButton) -> bool {
+        self.buttons & Self::button_mask(button) != 0
+    }
+}
+
+/// State of the lock keys, see [`Platform::lock_state`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub struct LockState {
+    pub caps: bool,
+    pub num: bool,
+    pub scroll: bool,
+}
+
 /// Constants for standard platform names.
 #[allow(missing_docs)]
 pub mod names {
 
-    pub const WINDOWS: &'static str = "windows";
-    pub const LINUX_X11: &'static str = "linux-x11";
-    pub const LINUX_WAYLAND: &'static str = "linux-wayland";
-    pub const WINDOWS_HEADLESS: &'static str = "windows-headless";
-    pub const LINUX_HEADLESS: &'static str = "linux-headless";
+    pub const WINDOWS: &str = "windows";
+    pub const LINUX_X11: &str = "linux-x11";
+    pub const LINUX_WAYLAND: &str = "linux-wayland";
+    pub const WINDOWS_HEADLESS: &str = "windows-headless";
+    pub const LINUX_HEADLESS: &str = "linux-headless";
 }
 
+/// The handle error of platforms and windows that have no OS handle (see [`Platform::is_headless`]), for their `display_handle` and `window_handle`.
+///
+/// Renderers can match on [`HandleError::Unavailable`] to fall back to offscreen rendering instead of creating a surface.
+pub fn unavailable_handle<T>() -> Result<T, HandleError> {
+    Err(HandleError::Unavailable)
+}
+
+/// Panic in debug builds if a main-thread-only platform is used from another thread (the OS calls would silently misbehave otherwise).
+pub(crate) fn debug_assert_main_thread(platform: &dyn Platform, operation: &str) {
+    debug_assert!(
+        !platform.main_thread_only() || platform.main_thread() == std::thread::current().id(),
+        "{} has to be called on the thread that created the platform",
+        operation
+    );
+}
 
+/// Create the platform for the current OS.
+///
+/// Setting the `NEURON_HEADLESS` environment variable to `1` selects the [`headless::HeadlessPlatform`], which is also used on Linux when no X server can be reached.
+///
+/// On Linux the [`wayland::WaylandPlatform`] is tried first when `WAYLAND_DISPLAY` is set, falling back to X11 (which also covers XWayland).
 pub fn new_platform() -> anyhow::Result<Arc<dyn Platform>> {
-    #[cfg(target_os="windows")]
+    if std::env::var_os("NEURON_HEADLESS").is_some_and(|headless| headless == "1") {
+        return Ok(Arc::new(headless::HeadlessPlatform::new()));
+    }
+
+    #[cfg(target_os = "windows")]
     {
-        Ok(Arc::new_cyclic(|weak| windows::WindowsPlatform::new(weak.clone()).expect("windows platform initialization failed")))
+        Ok(Arc::new_cyclic(|weak| {
+            windows::WindowsPlatform::new(weak.clone())
+                .expect("windows platform initialization failed")
+        }))
     }
 
-    #[cfg(target_os="linux")]
+    #[cfg(target_os = "linux")]
     {
-        Ok(Arc::new_cyclic(|weak| x11::X11Platform::new(weak.clone()).expect("X11 platform initialization failed")))
+        if std::env::var_os("WAYLAND_DISPLAY").is_some_and(|display| !display.is_empty()) {
+            match wayland::WaylandPlatform::new() {
+                Ok(platform) => return Ok(platform),
+                Err(e) => debug!(
+                    "Failed to connect to the Wayland compositor, falling back to X11: {}",
+                    e
+                ),
+            }
+        }
+
+        match x11::X11Platform::open_display() {
+            Ok(display) => unsafe { new_x11_platform_from_display(display, true) },
+            Err(e) => {
+                debug!("Failed to connect to the X server, running headless: {}", e);
+                Ok(Arc::new(headless::HeadlessPlatform::new()))
+            }
+        }
     }
 
-    #[cfg(not(any(target_os="windows", target_os="linux")))]
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
     {
         unimplemented!("This platform is not supported")
     }
 }
 
-pub(super) struct OsLoopInputs {
+/// Create the X11 platform on an existing display connection, see [`x11::X11Platform::from_display`].
+///
+/// # Safety
+/// `display` has to be a valid connection that stays open for as long as the platform exists.
+#[cfg(target_os = "linux")]
+pub(crate) unsafe fn new_x11_platform_from_display(
+    display: *mut x11_dl::xlib::Display,
+    owns: bool,
+) -> anyhow::Result<Arc<dyn Platform>> {
+    Ok(unsafe { x11::X11Platform::from_display(display, owns) }?.into_arc())
+}
+
+/// Engine state that platforms need access to while processing OS events.
+#[derive(Clone)]
+pub struct OsLoopInputs {
     pub window_manager: Arc<WindowManager>,
     pub exit_manager: Arc<ExitManager>,
+    pub event_queue: Arc<EventQueue>,
+}
+
+impl OsLoopInputs {
+    /// Keep the inputs without keeping the engine alive (platforms that store them per window would otherwise form a cycle with the window manager).
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub(crate) fn downgrade(&self) -> WeakOsLoopInputs {
+        WeakOsLoopInputs {
+            window_manager: Arc::downgrade(&self.window_manager),
+            exit_manager: Arc::downgrade(&self.exit_manager),
+            event_queue: Arc::downgrade(&self.event_queue),
+        }
+    }
+}
+
+/// [`OsLoopInputs`] of an engine that may be gone, see [`OsLoopInputs::downgrade`].
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) struct WeakOsLoopInputs {
+    window_manager: Weak<WindowManager>,
+    exit_manager: Weak<ExitManager>,
+    event_queue: Weak<EventQueue>,
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+impl WeakOsLoopInputs {
+    pub fn upgrade(&self) -> Option<OsLoopInputs> {
+        Some(OsLoopInputs {
+            window_manager: self.window_manager.upgrade()?,
+            exit_manager: self.exit_manager.upgrade()?,
+            event_queue: self.event_queue.upgrade()?,
+        })
+    }
 }
@@ -0,0 +1,127 @@
+//! Platform generic monitors
+
+use crate::os::window::{Position, Resolution, WindowPosition};
+
+/// Snapshot of a monitor's geometry and scale.
+///
+/// All sizes and positions are in physical pixels, relative to the platform's virtual desktop.
+/// Monitor handles are not updated when the monitor configuration changes, query the [Platform](super::Platform) again instead (changes are reported with [`Event::MonitorsChanged`](crate::event::Event::MonitorsChanged)).
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonitorHandle {
+    pub(crate) name: String,
+    pub(crate) position: WindowPosition,
+    pub(crate) size: (u32, u32),
+    pub(crate) work_area_position: WindowPosition,
+    pub(crate) work_area_size: (u32, u32),
+    pub(crate) scale_factor: f64,
+    pub(crate) bit_depth: u32,
+    pub(crate) hdr_enabled: bool,
+    pub(crate) video_modes: Vec<VideoMode>,
+}
+
+impl MonitorHandle {
+    /// Platform name of the monitor: the display device on Windows (e.g. `\\.\DISPLAY1`), the RandR monitor on X11 (usually the output, e.g. `DP-1`).
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Size of the monitor.
+    pub fn size(&self) -> Resolution<u32> {
+        Resolution::Physical {
+            width: self.size.0,
+            height: self.size.1,
+        }
+    }
+
+    /// Position of the top-left corner of the monitor.
+    pub fn position(&self) -> WindowPosition {
+        self.position
+    }
+
+    /// Size of the part of the monitor that isn't covered by taskbars, docks or panels.
+    pub fn work_area_size(&self) -> Resolution<u32> {
+        Resolution::Physical {
+            width: self.work_area_size.0,
+            height: self.work_area_size.1,
+        }
+    }
+
+    /// Position of the top-left corner of the work area.
+    pub fn work_area_position(&self) -> WindowPosition {
+        self.work_area_position
+    }
+
+    /// Position that centers something of the given size in the work area.
+    ///
+    /// This centers the client area, decorations (especially the title bar) shift the window slightly off-center on platforms where the position applies to the outer frame.
+    pub fn center(&self, size: Resolution<u32>) -> Position {
+        let (width, height) = size.physical_size(self.scale_factor);
+
+        Position::Physical {
+            x: self.work_area_position.x + (self.work_area_size.0 as i32 - width as i32) / 2,
+            y: self.work_area_position.y + (self.work_area_size.1 as i32 - height as i32) / 2,
+        }
+    }
+
+    /// Scale factor of the monitor (`1.0` is 96 dpi).
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Bits per pixel of the desktop's pixel format (usually 24 or 32).
+    pub fn bit_depth(&self) -> u32 {
+        self.bit_depth
+    }
+
+    /// Display modes the monitor supports (for exclusive fullscreen), without duplicates and largest first.
+    ///
+    /// Empty if the platform can't enumerate modes (X11 without RandR).
+    pub fn video_modes(&self) -> Vec<VideoMode> {
+        self.video_modes.clone()
+    }
+
+    /// Whether the OS is currently driving the monitor in HDR (advanced color) mode.
+    ///
+    /// Always `false` on X11, which has no HDR output path.
+    pub fn hdr_enabled(&self) -> bool {
+        self.hdr_enabled
+    }
+}
+
+/// A display mode of a monitor: resolution, color depth and refresh rate.
+///
+/// Ordered by size, then bit depth, then refresh rate.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct VideoMode {
+    pub(crate) size: (u32, u32),
+    pub(crate) bit_depth: u32,
+    pub(crate) refresh_rate_millihertz: u32,
+    /// Platform name of the monitor the mode belongs to (the display device on Windows, the RandR output on X11), used to switch to the mode.
+    pub(crate) monitor_name: String,
+}
+
+impl VideoMode {
+    /// Resolution of the mode.
+    pub fn size(&self) -> Resolution<u32> {
+        Resolution::Physical {
+            width: self.size.0,
+            height: self.size.1,
+        }
+    }
+
+    /// Bits per pixel.
+    pub fn bit_depth(&self) -> u32 {
+        self.bit_depth
+    }
+
+    /// Refresh rate in millihertz (e.g. `59940` for 59.94 Hz).
+    pub fn refresh_rate_millihertz(&self) -> u32 {
+        self.refresh_rate_millihertz
+    }
+}
+
+/// Sort modes largest first and remove duplicates (platforms report the same mode once per scaling or orientation setting).
+pub(crate) fn sort_video_modes(modes: &mut Vec<VideoMode>) {
+    modes.sort_unstable_by(|a, b| b.cmp(a));
+    modes.dedup();
+}
@@ -0,0 +1,289 @@
+//! Window geometry remembered across runs, see [`WindowAttributes::persistence_key`](crate::os::window::WindowAttributes::persistence_key)
+
+use crate::os::monitor::MonitorHandle;
+use log::debug;
+
+/// Where a window was when it was closed: the top-left corner of its frame and the size of its client area, in physical pixels.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SavedPlacement {
+    pub position: (i32, i32),
+    pub size: (u32, u32),
+}
+
+impl SavedPlacement {
+    pub fn load(key: &str) -> Option<Self> {
+        Self::parse(&storage::read(key)?)
+    }
+
+    /// Parse what [`SavedPlacement::save`] stored, `x y width height`. Anything else (e.g. a value edited by hand) is `None`.
+    fn parse(value: &str) -> Option<Self> {
+        let values = value
+            .split_whitespace()
+            .map(|value| value.parse::<i32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        let [x, y, width, height] = values[..] else {
+            return None;
+        };
+
+        // no window is saved with an empty size, so the value is corrupt
+        if width < 1 || height < 1 {
+            return None;
+        }
+
+        Some(SavedPlacement {
+            position: (x, y),
+            size: (width as u32, height as u32),
+        })
+    }
+
+    pub fn save(&self, key: &str) {
+        let value = format!(
+            "{} {} {} {}",
+            self.position.0, self.position.1, self.size.0, self.size.1
+        );
+        if let Err(e) = storage::write(key, &value) {
+            debug!("Failed to save the placement of window {:?}: {}", key, e);
+        }
+    }
+
+    /// The middle of the window, to find the monitor it belongs on.
+    pub fn center(&self) -> (i32, i32) {
+        (
+            self.position.0 + self.size.0 as i32 / 2,
+            self.position.1 + self.size.1 as i32 / 2,
+        )
+    }
+
+    /// Shrink and move the placement so it fits the monitor's work area (the monitor may have changed since it was saved).
+    pub fn clamp_to(self, monitor: &MonitorHandle) -> Self {
+        let area = monitor.work_area_position;
+        let (area_width, area_height) = monitor.work_area_size;
+        let size = (self.size.0.min(area_width), self.size.1.min(area_height));
+
+        SavedPlacement {
+            position: (
+                self.position
+                    .0
+                    .clamp(area.x, area.x + (area_width - size.0) as i32),
+                self.position
+                    .1
+                    .clamp(area.y, area.y + (area_height - size.1) as i32),
+            ),
+            size,
+        }
+    }
+}
+
+/// Placements are stored per application (by executable name), so unrelated games can use the same keys.
+fn application_name() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "neuron-engine".to_string())
+}
+
+/// Keys are used as file and registry value names, so only keep characters that are safe everywhere.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// `$XDG_CONFIG_HOME/neuron-engine/<application>/window-placements/<key>`
+#[cfg(target_os = "linux")]
+mod storage {
+    use std::path::PathBuf;
+
+    fn path(key: &str) -> Option<PathBuf> {
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".config")))?;
+
+        Some(
+            config_home
+                .join("neuron-engine")
+                .join(super::sanitize(&super::application_name()))
+                .join("window-placements")
+                .join(super::sanitize(key)),
+        )
+    }
+
+    pub(super) fn read(key: &str) -> Option<String> {
+        std::fs::read_to_string(path(key)?).ok()
+    }
+
+    pub(super) fn write(key: &str, value: &str) -> anyhow::Result<()> {
+        let path = path(key).ok_or_else(|| anyhow::anyhow!("no config directory"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, value)?;
+        Ok(())
+    }
+}
+
+/// `HKEY_CURRENT_USER\Software\NeuronEngine\<application>\WindowPlacements`, one string value per key.
+#[cfg(windows)]
+mod storage {
+    use widestring::U16CString;
+    use windows::Win32::System::Registry::{
+        HKEY_CURRENT_USER, REG_SZ, RRF_RT_REG_SZ, RegGetValueW, RegSetKeyValueW,
+    };
+    use windows::core::PCWSTR;
+
+    fn subkey() -> anyhow::Result<U16CString> {
+        Ok(U16CString::from_str(format!(
+            "Software\\NeuronEngine\\{}\\WindowPlacements",
+            super::sanitize(&super::application_name())
+        ))?)
+    }
+
+    pub(super) fn read(key: &str) -> Option<String> {
+        let subkey = subkey().ok()?;
+        let name = U16CString::from_str(super::sanitize(key)).ok()?;
+        let mut buffer = [0u16; 64];
+        let mut size = size_of_val(&buffer) as u32;
+
+        unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                PCWSTR(subkey.as_ptr()),
+                PCWSTR(name.as_ptr()),
+                RRF_RT_REG_SZ,
+                None,
+                Some(buffer.as_mut_ptr().cast()),
+                Some(&mut size),
+            )
+            .ok()
+            .ok()?;
+        }
+
+        let len = buffer.iter().position(|c| *c == 0).unwrap_or(buffer.len());
+        Some(String::from_utf16_lossy(&buffer[..len]))
+    }
+
+    pub(super) fn write(key: &str, value: &str) -> anyhow::Result<()> {
+        let subkey = subkey()?;
+        let name = U16CString::from_str(super::sanitize(key))?;
+        let value = U16CString::from_str(value)?;
+        let data = value.as_slice_with_nul();
+
+        unsafe {
+            RegSetKeyValueW(
+                HKEY_CURRENT_USER,
+                PCWSTR(subkey.as_ptr()),
+                PCWSTR(name.as_ptr()),
+                REG_SZ.0,
+                Some(data.as_ptr().cast()),
+                size_of_val(data) as u32,
+            )
+            .ok()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+mod storage {
+    pub(super) fn read(_key: &str) -> Option<String> {
+        None
+    }
+
+    pub(super) fn write(_key: &str, _value: &str) -> anyhow::Result<()> {
+        anyhow::bail!("window placements can't be saved on this platform")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::os::window::WindowPosition;
+
+    /// A 1920x1080 monitor right of the primary one, with a 40 pixel panel at the top.
+    fn monitor() -> MonitorHandle {
+        MonitorHandle {
+            name: "test".to_string(),
+            position: WindowPosition { x: 1920, y: 0 },
+            size: (1920, 1080),
+            work_area_position: WindowPosition { x: 1920, y: 40 },
+            work_area_size: (1920, 1040),
+            scale_factor: 1.0,
+            bit_depth: 32,
+            hdr_enabled: false,
+            video_modes: Vec::new(),
+        }
+    }
+
+    fn placement(position: (i32, i32), size: (u32, u32)) -> SavedPlacement {
+        SavedPlacement { position, size }
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            SavedPlacement::parse("-8 30 800 600"),
+            Some(placement((-8, 30), (800, 600)))
+        );
+        assert_eq!(
+            SavedPlacement::parse(" 10\t20\n800 600\n"),
+            Some(placement((10, 20), (800, 600)))
+        );
+    }
+
+    #[test]
+    fn parse_malformed() {
+        for value in [
+            "",
+            "10 20 800",
+            "10 20 800 600 1",
+            "10 20 800 wide",
+            "10.5 20 800 600",
+            "10 20 99999999999 600",
+        ] {
+            assert_eq!(SavedPlacement::parse(value), None, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn parse_empty_size() {
+        assert_eq!(SavedPlacement::parse("10 20 -800 600"), None);
+        assert_eq!(SavedPlacement::parse("10 20 800 -600"), None);
+        assert_eq!(SavedPlacement::parse("10 20 0 600"), None);
+    }
+
+    #[test]
+    fn clamp_inside_work_area() {
+        let inside = placement((2000, 100), (800, 600));
+        assert_eq!(inside.clamp_to(&monitor()), inside);
+    }
+
+    #[test]
+    fn clamp_larger_than_work_area() {
+        assert_eq!(
+            placement((1900, 0), (2560, 1440)).clamp_to(&monitor()),
+            placement((1920, 40), (1920, 1040))
+        );
+    }
+
+    #[test]
+    fn clamp_off_monitor() {
+        // past the bottom right corner
+        assert_eq!(
+            placement((5000, 3000), (800, 600)).clamp_to(&monitor()),
+            placement((3040, 480), (800, 600))
+        );
+        // on the monitor to the left, and above the panel
+        assert_eq!(
+            placement((100, -500), (800, 600)).clamp_to(&monitor()),
+            placement((1920, 40), (800, 600))
+        );
+    }
+}
@@ -0,0 +1,786 @@
+//! Wayland interop
+
+#![cfg(target_os = "linux")]
+
+mod window;
+
+use crate::event::{ElementState, Event, KeyEvent, KeyState, Modifiers, MouseButton, ScrollDelta};
+use crate::os::monitor::MonitorHandle;
+use crate::os::wayland::window::WaylandWindow;
+use crate::os::window::{
+    SupportedWindowAttributes, Window, WindowAttributes, WindowId, unsupported,
+};
+use crate::os::{
+    GlobalKeyboardHook, LockState, OsLoopInputs, PlatformKind, PointerInfo, PointerState,
+    WeakOsLoopInputs,
+};
+use anyhow::bail;
+use hashbrown::HashMap;
+use log::debug;
+use raw_window_handle::{
+    DisplayHandle, HandleError, HasDisplayHandle, RawDisplayHandle, WaylandDisplayHandle,
+};
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::c_int;
+use std::os::fd::AsRawFd;
+use std::ptr::NonNull;
+use std::sync::{Arc, Weak};
+use std::thread::ThreadId;
+use std::time::Duration;
+use wayland_client::globals::{GlobalListContents, registry_queue_init};
+use wayland_client::protocol::wl_compositor::WlCompositor;
+use wayland_client::protocol::wl_keyboard::{self, WlKeyboard};
+use wayland_client::protocol::wl_pointer::{self, WlPointer};
+use wayland_client::protocol::wl_registry::{self, WlRegistry};
+use wayland_client::protocol::wl_seat::{self, WlSeat};
+use wayland_client::protocol::wl_surface::{self, WlSurface};
+use wayland_client::{Connection, Dispatch, EventQueue, Proxy, QueueHandle, WEnum, delegate_noop};
+use wayland_protocols::xdg::decoration::zv1::client::zxdg_decoration_manager_v1::ZxdgDecorationManagerV1;
+use wayland_protocols::xdg::decoration::zv1::client::zxdg_toplevel_decoration_v1::{
+    self, ZxdgToplevelDecorationV1,
+};
+use wayland_protocols::xdg::shell::client::xdg_surface::{self, XdgSurface};
+use wayland_protocols::xdg::shell::client::xdg_toplevel::{self, XdgToplevel};
+use wayland_protocols::xdg::shell::client::xdg_wm_base::{self, XdgWmBase};
+
+/// Input comes from the compositor's seat: pointer, keys (as X11 keycodes) and modifiers. Without xkbcommon there is no keymap, so no [`Event::ReceivedCharacter`] is reported and keys don't auto-repeat.
+pub struct WaylandPlatform {
+    connection: Connection,
+    event_queue: RefCell<EventQueue<WaylandState>>,
+    queue_handle: QueueHandle<WaylandState>,
+    state: RefCell<WaylandState>,
+    compositor: WlCompositor,
+    wm_base: XdgWmBase,
+    /// `None` if the compositor leaves decorations to the clients (e.g. GNOME), windows are undecorated then.
+    decoration_manager: Option<ZxdgDecorationManagerV1>,
+    main_thread: ThreadId,
+    /// The engine each window was created by, events for a window are delivered to its engine.
+    window_inputs: RefCell<HashMap<WindowId, WeakOsLoopInputs>>,
+    weak: Weak<WaylandPlatform>,
+}
+
+/// What the event queue dispatches into.
+///
+/// The handlers don't have access to the engine, so they only record what happened and [`Platform::process_events`](super::Platform::process_events) turns it into events.
+#[derive(Default)]
+struct WaylandState {
+    /// Client area size of each window. Wayland clients pick their own size, the compositor only suggests one when configuring the window.
+    surface_sizes: HashMap<WindowId, (u32, u32)>,
+    /// Whether each window is decorated, as last configured by the compositor.
+    decorations: HashMap<WindowId, bool>,
+    /// Created while the seat has a pointer.
+    pointer: Option<WlPointer>,
+    /// Created while the seat has a keyboard.
+    keyboard: Option<WlKeyboard>,
+    /// The window the pointer is over.
+    pointer_focus: Option<WindowId>,
+    /// The window with the keyboard focus.
+    keyboard_focus: Option<WindowId>,
+    modifiers: Modifiers,
+    pending: Vec<PendingEvent>,
+}
+
+enum PendingEvent {
+    Resized(WindowId, (u32, u32)),
+    CloseRequested(WindowId),
+    RedrawRequested(WindowId),
+    /// An event that is reported as is, e.g. input from the seat.
+    Input(WindowId, Event),
+}
+
+impl PendingEvent {
+    fn window(&self) -> WindowId {
+        match *self {
+            PendingEvent::Resized(window, _)
+            | PendingEvent::CloseRequested(window)
+            | PendingEvent::RedrawRequested(window)
+            | PendingEvent::Input(window, _) => window,
+        }
+    }
+}
+
+impl WaylandPlatform {
+    /// Connect to the compositor named by `WAYLAND_DISPLAY`.
+    pub fn new() -> anyhow::Result<Arc<WaylandPlatform>> {
+        let connection = Connection::connect_to_env()?;
+        let (globals, event_queue) = registry_queue_init::<WaylandState>(&connection)?;
+        let queue_handle = event_queue.handle();
+
+        let compositor = globals.bind(&queue_handle, 1..=4, ())?;
+        let wm_base = match globals.bind(&queue_handle, 1..=1, ()) {
+            Ok(wm_base) => wm_base,
+            Err(e) => bail!("The compositor doesn't support xdg_shell: {}", e),
+        };
+        // the pointer and keyboard are created once the seat announces them
+        if let Err(e) = globals.bind::<WlSeat, _, _>(&queue_handle, 1..=4, ()) {
+            debug!("The compositor has no seat, windows won't get input: {}", e);
+        }
+        let decoration_manager = globals.bind(&queue_handle, 1..=1, ()).ok();
+
+        Ok(Arc::new_cyclic(|weak| WaylandPlatform {
+            connection,
+            event_queue: RefCell::new(event_queue),
+            queue_handle,
+            state: RefCell::new(WaylandState::default()),
+            compositor,
+            wm_base,
+            decoration_manager,
+            main_thread: std::thread::current().id(),
+            window_inputs: RefCell::new(HashMap::new()),
+            weak: weak.clone(),
+        }))
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    fn flush(&self) {
+        if let Err(e) = self.connection.flush() {
+            debug!("Failed to flush Wayland requests: {}", e);
+        }
+    }
+
+    fn notify_window_destroy(&self, window: WindowId) {
+        self.window_inputs.borrow_mut().remove(&window);
+
+        let mut state = self.state.borrow_mut();
+        state.surface_sizes.remove(&window);
+        state.decorations.remove(&window);
+        if state.pointer_focus == Some(window) {
+            state.pointer_focus = None;
+        }
+        if state.keyboard_focus == Some(window) {
+            state.keyboard_focus = None;
+        }
+        state.pending.retain(|event| event.window() != window);
+    }
+
+    /// Read what the compositor sent into the event queue, waiting up to `timeout` for something to arrive. Returns whether events are queued.
+    fn read_events(&self, timeout: Duration) -> bool {
+        let queue = self.event_queue.borrow();
+        // our requests could be what the compositor's events are waiting on
+        _ = queue.flush();
+
+        let Some(guard) = queue.prepare_read() else {
+            // events are already queued
+            return true;
+        };
+
+        let mut fd = libc::pollfd {
+            fd: guard.connection_fd().as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ready = unsafe {
+            libc::poll(
+                &mut fd,
+                1,
+                timeout.as_millis().min(c_int::MAX as u128) as c_int,
+            )
+        } > 0;
+        if !ready {
+            // dropping the guard cancels the read
+            return false;
+        }
+
+        match guard.read() {
+            Ok(_) => true,
+            Err(e) => {
+                debug!("Failed to read Wayland events: {}", e);
+                false
+            }
+        }
+    }
+}
+
+impl HasDisplayHandle for WaylandPlatform {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        let display = NonNull::new(self.connection.backend().display_ptr().cast())
+            .ok_or(HandleError::Unavailable)?;
+        unsafe {
+            Ok(DisplayHandle::borrow_raw(RawDisplayHandle::Wayland(
+                WaylandDisplayHandle::new(display),
+            )))
+        }
+    }
+}
+
+/// What [`Platform::supported_window_attributes`](crate::os::Platform::supported_window_attributes) reports: clients can't place their windows, and stacking and taskbar entries are up to the compositor.
+///
+/// Decorations are requested through xdg-decoration, which only turns all of them on or off. Compositors without it leave windows undecorated.
+static SUPPORTED_WINDOW_ATTRIBUTES: SupportedWindowAttributes = SupportedWindowAttributes {
+    title: true,
+    size: true,
+    position: false,
+    has_close_button: false,
+    has_minimize_button: false,
+    has_maximize_button: false,
+    show_border: true,
+    show_title_bar: true,
+    initially_disabled: false,
+    initially_minimized: true,
+    resizable: true,
+    min_size: true,
+    max_size: true,
+    skip_taskbar: false,
+    show_in_taskbar: false,
+    show_in_switcher: false,
+    initially_visible: false,
+    initial_z_order: false,
+    always_on_top: false,
+    auto_hide_on_focus_loss: false,
+    compositor_bypass: false,
+    windows_show_drop_shadow: false,
+    windows_is_dialog_box: false,
+    windows_has_system_menu: false,
+    x11_window_type: false,
+    fullscreen: true,
+    icon: false,
+    transparent: true,
+    background_color: false,
+};
+
+impl super::Platform for WaylandPlatform {
+    fn name(&self) -> &'static str {
+        super::names::LINUX_WAYLAND
+    }
+
+    fn main_thread_only(&self) -> bool {
+        // the event queue and window state aren't synchronized
+        true
+    }
+
+    fn main_thread(&self) -> ThreadId {
+        self.main_thread
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn kind(&self) -> PlatformKind {
+        PlatformKind::LinuxWayland
+    }
+
+    fn is_headless(&self) -> bool {
+        false
+    }
+
+    fn is_dark_mode(&self) -> Option<bool> {
+        None
+    }
+
+    fn supported_window_attributes(&self) -> &'static SupportedWindowAttributes {
+        &SUPPORTED_WINDOW_ATTRIBUTES
+    }
+
+    fn primary_monitor(&self) -> Option<MonitorHandle> {
+        // outputs have no notion of a primary one
+        None
+    }
+
+    fn available_monitors(&self) -> Vec<MonitorHandle> {
+        // outputs aren't bound yet
+        Vec::new()
+    }
+
+    fn pointer_info(&self) -> PointerInfo {
+        // seats only say whether there is a pointer, not what it has
+        PointerInfo::default()
+    }
+
+    fn lock_state(&self) -> LockState {
+        LockState::default()
+    }
+
+    fn double_click_time(&self) -> Duration {
+        // defaults match GTK's
+        Duration::from_millis(400)
+    }
+
+    fn double_click_distance(&self) -> u32 {
+        5
+    }
+
+    fn drag_threshold(&self) -> u32 {
+        8
+    }
+
+    fn query_pointer(&self, _window: WindowId) -> Option<PointerState> {
+        // the pointer position is only sent along with pointer events, there is no request for it
+        None
+    }
+
+    fn create_window(
+        &self,
+        window_attributes: WindowAttributes,
+        window_id: WindowId,
+        inputs: &OsLoopInputs,
+    ) -> anyhow::Result<Arc<dyn Window>> {
+        // windows stay on the platform's thread, the `Arc` is shared with the window manager and not between threads
+        #[allow(clippy::arc_with_non_send_sync)]
+        let win = Arc::new(WaylandWindow::new(
+            self.weak.upgrade().unwrap(),
+            window_attributes,
+            window_id,
+        )?);
+        self.window_inputs
+            .borrow_mut()
+            .insert(window_id, inputs.downgrade());
+        Ok(win)
+    }
+
+    fn set_global_keyboard_hook(&self, _hook: GlobalKeyboardHook) -> anyhow::Result<()> {
+        bail!("Wayland doesn't deliver keys sent to other clients")
+    }
+
+    fn remove_global_keyboard_hook(&self) {}
+
+    fn clipboard_data(&self, _mime: &str) -> Option<Vec<u8>> {
+        unsupported("Platform::clipboard_data");
+        None
+    }
+
+    fn set_clipboard_data(&self, _entries: &[(String, Vec<u8>)]) -> anyhow::Result<()> {
+        bail!("The clipboard isn't supported on Wayland yet")
+    }
+
+    fn sync(&self) {
+        let mut queue = self.event_queue.borrow_mut();
+        // the events end up in the pending list, so they are still reported by the next process_events
+        if let Err(e) = queue.roundtrip(&mut self.state.borrow_mut()) {
+            debug!("Wayland roundtrip failed: {}", e);
+        }
+    }
+
+    fn wait_for_events(&self, timeout: Duration) {
+        // e.g. redraw requests, which never reach the compositor
+        if !self.state.borrow().pending.is_empty() {
+            return;
+        }
+
+        self.read_events(timeout);
+    }
+
+    fn has_pending_events(&self) -> bool {
+        !self.state.borrow().pending.is_empty() || self.read_events(Duration::ZERO)
+    }
+
+    fn process_events(&self, inputs: &OsLoopInputs) {
+        self.read_events(Duration::ZERO);
+
+        if let Err(e) = self
+            .event_queue
+            .borrow_mut()
+            .dispatch_pending(&mut self.state.borrow_mut())
+        {
+            debug!("Failed to dispatch Wayland events: {}", e);
+        }
+
+        let pending = std::mem::take(&mut self.state.borrow_mut().pending);
+        for event in pending {
+            let routed = self
+                .window_inputs
+                .borrow()
+                .get(&event.window())
+                .and_then(WeakOsLoopInputs::upgrade);
+            let inputs = routed.as_ref().unwrap_or(inputs);
+
+            match event {
+                PendingEvent::Resized(window, size) => {
+                    inputs.event_queue.push(Event::SurfaceResized {
+                        window,
+                        size,
+                        live_resize: false,
+                    });
+                }
+                PendingEvent::CloseRequested(window) => {
+                    inputs.event_queue.push(Event::CloseRequested { window });
+                    inputs.window_manager.begin_closing_window(window);
+                }
+                PendingEvent::RedrawRequested(window) => {
+                    inputs.event_queue.push(Event::RedrawRequested { window });
+                }
+                PendingEvent::Input(_, event) => inputs.event_queue.push(event),
+            }
+        }
+
+        self.flush();
+    }
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for WaylandState {
+    fn event(
+        _state: &mut Self,
+        _registry: &WlRegistry,
+        _event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _connection: &Connection,
+        _queue_handle: &QueueHandle<Self>,
+    ) {
+        // the globals we need are bound at startup, later ones aren't used
+    }
+}
+
+delegate_noop!(WaylandState: ignore WlCompositor);
+delegate_noop!(WaylandState: ignore ZxdgDecorationManagerV1);
+
+impl Dispatch<WlSurface, WindowId> for WaylandState {
+    fn event(
+        _state: &mut Self,
+        _surface: &WlSurface,
+        _event: wl_surface::Event,
+        _window: &WindowId,
+        _connection: &Connection,
+        _queue_handle: &QueueHandle<Self>,
+    ) {
+        // only tells which outputs the surface is on, which isn't used yet
+    }
+}
+
+impl Dispatch<XdgWmBase, ()> for WaylandState {
+    fn event(
+        _state: &mut Self,
+        wm_base: &XdgWmBase,
+        event: xdg_wm_base::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue_handle: &QueueHandle<Self>,
+    ) {
+        // the compositor considers us unresponsive without an answer
+        if let xdg_wm_base::Event::Ping { serial } = event {
+            wm_base.pong(serial);
+        }
+    }
+}
+
+impl Dispatch<XdgSurface, WindowId> for WaylandState {
+    fn event(
+        _state: &mut Self,
+        xdg_surface: &XdgSurface,
+        event: xdg_surface::Event,
+        _window: &WindowId,
+        _connection: &Connection,
+        _queue_handle: &QueueHandle<Self>,
+    ) {
+        // the toplevel configure before this carried the actual changes
+        if let xdg_surface::Event::Configure { serial } = event {
+            xdg_surface.ack_configure(serial);
+        }
+    }
+}
+
+impl Dispatch<XdgToplevel, WindowId> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _toplevel: &XdgToplevel,
+        event: xdg_toplevel::Event,
+        window: &WindowId,
+        _connection: &Connection,
+        _queue_handle: &QueueHandle<Self>,
+    ) {
+        match event {
+            // a size of 0 leaves it up to us
+            xdg_toplevel::Event::Configure { width, height, .. } if width > 0 && height > 0 => {
+                let size = (width as u32, height as u32);
+                if state.surface_sizes.insert(*window, size) != Some(size) {
+                    state.pending.push(PendingEvent::Resized(*window, size));
+                }
+            }
+            xdg_toplevel::Event::Close => {
+                state.pending.push(PendingEvent::CloseRequested(*window));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZxdgToplevelDecorationV1, WindowId> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _decoration: &ZxdgToplevelDecorationV1,
+        event: zxdg_toplevel_decoration_v1::Event,
+        window: &WindowId,
+        _connection: &Connection,
+        _queue_handle: &QueueHandle<Self>,
+    ) {
+        if let zxdg_toplevel_decoration_v1::Event::Configure { mode } = event {
+            let decorated = mode == WEnum::Value(zxdg_toplevel_decoration_v1::Mode::ServerSide);
+            // the first configure only says what the window starts with
+            if let Some(previous) = state.decorations.insert(*window, decorated)
+                && previous != decorated
+            {
+                state.pending.push(PendingEvent::Input(
+                    *window,
+                    Event::DecorationsChanged {
+                        window: *window,
+                        decorated,
+                    },
+                ));
+            }
+        }
+    }
+}
+
+/// Linux input event codes (`KEY_LEFTSHIFT` and so on) of the modifier keys.
+const MODIFIER_KEYS: [(u32, Modifiers); 8] = [
+    (42, Modifiers::LSHIFT),
+    (54, Modifiers::RSHIFT),
+    (29, Modifiers::LCTRL),
+    (97, Modifiers::RCTRL),
+    (56, Modifiers::LALT),
+    (100, Modifiers::RALT),
+    (125, Modifiers::LSUPER),
+    (126, Modifiers::RSUPER),
+];
+
+/// Linux input event code of the first side button (`BTN_SIDE`), the ones before it are left, right and middle.
+const BTN_SIDE: u32 = 0x113;
+
+/// The button of a Linux input event code, numbered like X11 from the side buttons on (`BTN_SIDE` is back, 8).
+fn mouse_button(button: u32) -> MouseButton {
+    match button {
+        0x110 => MouseButton::Left,
+        0x111 => MouseButton::Right,
+        0x112 => MouseButton::Middle,
+        _ => MouseButton::Other((button.saturating_sub(BTN_SIDE) + 8).min(u16::MAX.into()) as u16),
+    }
+}
+
+impl WaylandState {
+    fn push_input(&mut self, window: WindowId, event: Event) {
+        self.pending.push(PendingEvent::Input(window, event));
+    }
+}
+
+impl Dispatch<WlSeat, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        seat: &WlSeat,
+        event: wl_seat::Event,
+        _data: &(),
+        _connection: &Connection,
+        queue_handle: &QueueHandle<Self>,
+    ) {
+        let wl_seat::Event::Capabilities {
+            capabilities: WEnum::Value(capabilities),
+        } = event
+        else {
+            return;
+        };
+
+        if capabilities.contains(wl_seat::Capability::Pointer) {
+            if state.pointer.is_none() {
+                state.pointer = Some(seat.get_pointer(queue_handle, ()));
+            }
+        } else if let Some(pointer) = state.pointer.take() {
+            if pointer.version() >= 3 {
+                pointer.release();
+            }
+            if let Some(window) = state.pointer_focus.take() {
+                state.push_input(window, Event::CursorLeft { window });
+            }
+        }
+
+        if capabilities.contains(wl_seat::Capability::Keyboard) {
+            if state.keyboard.is_none() {
+                state.keyboard = Some(seat.get_keyboard(queue_handle, ()));
+            }
+        } else if let Some(keyboard) = state.keyboard.take() {
+            if keyboard.version() >= 3 {
+                keyboard.release();
+            }
+            if let Some(window) = state.keyboard_focus.take() {
+                state.push_input(
+                    window,
+                    Event::Focused {
+                        window,
+                        focused: false,
+                    },
+                );
+            }
+        }
+    }
+}
+
+impl Dispatch<WlPointer, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _pointer: &WlPointer,
+        event: wl_pointer::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue_handle: &QueueHandle<Self>,
+    ) {
+        // surfaces aren't scaled, so surface coordinates are physical pixels
+        match event {
+            wl_pointer::Event::Enter {
+                surface,
+                surface_x,
+                surface_y,
+                ..
+            } => {
+                let Some(&window) = surface.data::<WindowId>() else {
+                    return;
+                };
+                state.pointer_focus = Some(window);
+                state.push_input(window, Event::CursorEntered { window });
+                state.push_input(
+                    window,
+                    Event::CursorMoved {
+                        window,
+                        position: (surface_x, surface_y),
+                    },
+                );
+            }
+            wl_pointer::Event::Leave { .. } => {
+                if let Some(window) = state.pointer_focus.take() {
+                    state.push_input(window, Event::CursorLeft { window });
+                }
+            }
+            wl_pointer::Event::Motion {
+                surface_x,
+                surface_y,
+                ..
+            } => {
+                if let Some(window) = state.pointer_focus {
+                    state.push_input(
+                        window,
+                        Event::CursorMoved {
+                            window,
+                            position: (surface_x, surface_y),
+                        },
+                    );
+                }
+            }
+            wl_pointer::Event::Button {
+                button,
+                state: WEnum::Value(button_state),
+                ..
+            } => {
+                if let Some(window) = state.pointer_focus {
+                    let button_state = match button_state {
+                        wl_pointer::ButtonState::Pressed => ElementState::Pressed,
+                        _ => ElementState::Released,
+                    };
+                    state.push_input(
+                        window,
+                        Event::MouseInput {
+                            window,
+                            button: mouse_button(button),
+                            state: button_state,
+                        },
+                    );
+                }
+            }
+            wl_pointer::Event::Axis {
+                axis: WEnum::Value(axis),
+                value,
+                ..
+            } => {
+                // positive values scroll down and right, ours scroll up and right
+                let delta = match axis {
+                    wl_pointer::Axis::VerticalScroll => ScrollDelta::Pixels { x: 0.0, y: -value },
+                    wl_pointer::Axis::HorizontalScroll => ScrollDelta::Pixels { x: value, y: 0.0 },
+                    _ => return,
+                };
+                if let Some(window) = state.pointer_focus {
+                    state.push_input(window, Event::MouseWheel { window, delta });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<WlKeyboard, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _keyboard: &WlKeyboard,
+        event: wl_keyboard::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue_handle: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_keyboard::Event::Enter { surface, .. } => {
+                let Some(&window) = surface.data::<WindowId>() else {
+                    return;
+                };
+                state.keyboard_focus = Some(window);
+                state.push_input(
+                    window,
+                    Event::Focused {
+                        window,
+                        focused: true,
+                    },
+                );
+            }
+            wl_keyboard::Event::Leave { .. } => {
+                let Some(window) = state.keyboard_focus.take() else {
+                    return;
+                };
+                state.push_input(
+                    window,
+                    Event::Focused {
+                        window,
+                        focused: false,
+                    },
+                );
+
+                // key releases go to whichever window has the focus then
+                if !std::mem::take(&mut state.modifiers).is_empty() {
+                    state.push_input(
+                        window,
+                        Event::ModifiersChanged {
+                            window,
+                            modifiers: Modifiers::empty(),
+                        },
+                    );
+                }
+            }
+            wl_keyboard::Event::Key {
+                key,
+                state: WEnum::Value(key_state),
+                ..
+            } => {
+                let Some(window) = state.keyboard_focus else {
+                    return;
+                };
+                let pressed = key_state == wl_keyboard::KeyState::Pressed;
+
+                if let Some((_, modifier)) = MODIFIER_KEYS.iter().find(|(code, _)| *code == key) {
+                    let mut modifiers = state.modifiers;
+                    modifiers.set(*modifier, pressed);
+                    if modifiers != state.modifiers {
+                        state.modifiers = modifiers;
+                        state.push_input(window, Event::ModifiersChanged { window, modifiers });
+                    }
+                }
+
+                state.push_input(
+                    window,
+                    Event::KeyboardInput {
+                        window,
+                        event: KeyEvent {
+                            // X11 keycodes are the Linux ones offset by 8, which keeps scancodes the same as on X11
+                            scancode: key + 8,
+                            state: if pressed {
+                                KeyState::Pressed
+                            } else {
+                                KeyState::Released
+                            },
+                            // the compositor leaves repeating to the client
+                            repeat: false,
+                            modifiers: state.modifiers,
+                        },
+                    },
+                );
+            }
+            // without xkbcommon there is nothing to do with the keymap, dropping its fd closes it
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,346 @@
+use crate::os::wayland::{PendingEvent, WaylandPlatform};
+use crate::os::window::{
+    Fullscreen, PresentMode, Resolution, Window, WindowAttributes, WindowId, WindowPosition,
+    clamp_size,
+};
+use anyhow::bail;
+use log::warn;
+use raw_window_handle::{
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, RawWindowHandle,
+    WaylandWindowHandle, WindowHandle,
+};
+use std::cell::{Cell, RefCell};
+use std::ptr::NonNull;
+use std::sync::Arc;
+use wayland_client::Proxy;
+use wayland_client::protocol::wl_surface::WlSurface;
+use wayland_protocols::xdg::decoration::zv1::client::zxdg_toplevel_decoration_v1::{
+    Mode, ZxdgToplevelDecorationV1,
+};
+use wayland_protocols::xdg::shell::client::xdg_surface::XdgSurface;
+use wayland_protocols::xdg::shell::client::xdg_toplevel::XdgToplevel;
+
+pub(super) struct WaylandWindow {
+    id: WindowId,
+    platform: Arc<WaylandPlatform>,
+    surface: WlSurface,
+    xdg_surface: XdgSurface,
+    toplevel: XdgToplevel,
+    /// `None` if the compositor doesn't support xdg-decoration.
+    decoration: Option<ZxdgToplevelDecorationV1>,
+    present_mode: Cell<Option<PresentMode>>,
+    /// See [`WindowAttributes::resizable`].
+    resizable: Cell<bool>,
+    /// See [`WindowAttributes::min_size`], in surface coordinates.
+    min_size: Cell<Option<(u32, u32)>>,
+    /// See [`WindowAttributes::max_size`], in surface coordinates.
+    max_size: Cell<Option<(u32, u32)>>,
+    /// See [`Window::set_fullscreen`].
+    fullscreen: RefCell<Option<Fullscreen>>,
+}
+
+impl HasWindowHandle for WaylandWindow {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        let surface =
+            NonNull::new(self.surface.id().as_ptr().cast()).ok_or(HandleError::Unavailable)?;
+        unsafe {
+            Ok(WindowHandle::borrow_raw(RawWindowHandle::Wayland(
+                WaylandWindowHandle::new(surface),
+            )))
+        }
+    }
+}
+
+impl HasDisplayHandle for WaylandWindow {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        self.platform.display_handle()
+    }
+}
+
+impl Window for WaylandWindow {
+    fn set_client_size(&self, size: Resolution<u32>) {
+        let (width, height) = size.physical_size(1.0);
+        let size = (width.max(1), height.max(1));
+
+        // clients size their own surfaces, so the new size applies once the renderer draws at it
+        let mut state = self.platform.state.borrow_mut();
+        if state.surface_sizes.insert(self.id, size) != Some(size) {
+            state.pending.push(PendingEvent::Resized(self.id, size));
+        }
+        drop(state);
+
+        if !self.resizable.get() {
+            self.update_size_limits(size);
+        }
+    }
+
+    fn inner_size(&self) -> Resolution<u32> {
+        let (width, height) = self.surface_size();
+        Resolution::Physical { width, height }
+    }
+
+    fn position(&self) -> WindowPosition {
+        // clients aren't told where their windows are
+        WindowPosition::default()
+    }
+
+    fn set_inner_size(&self, size: Resolution<u32>) {
+        if !self.resizable.get() {
+            warn!(
+                "Window {:?} isn't resizable, not changing its size",
+                self.id
+            );
+            return;
+        }
+
+        self.set_client_size(size);
+    }
+
+    fn set_resizable(&self, resizable: bool) {
+        if self.resizable.replace(resizable) == resizable {
+            return;
+        }
+
+        self.update_size_limits(self.surface_size());
+        self.surface.commit();
+        self.platform.flush();
+    }
+
+    fn set_min_size(&self, size: Option<Resolution<u32>>) {
+        self.min_size.set(size.map(|size| size.physical_size(1.0)));
+        self.size_limits_changed();
+    }
+
+    fn set_max_size(&self, size: Option<Resolution<u32>>) {
+        self.max_size.set(size.map(|size| size.physical_size(1.0)));
+        self.size_limits_changed();
+    }
+
+    fn set_preferred_present_mode(&self, mode: Option<PresentMode>) {
+        self.present_mode.set(mode);
+    }
+
+    fn preferred_present_mode(&self) -> Option<PresentMode> {
+        self.present_mode.get()
+    }
+
+    fn is_occluded(&self) -> bool {
+        false
+    }
+
+    fn has_focus(&self) -> bool {
+        self.platform.state.borrow().keyboard_focus == Some(self.id)
+    }
+
+    fn set_title(&self, title: &str) -> anyhow::Result<()> {
+        if title.contains('\0') {
+            bail!("Window title {:?} contains a nul character", title);
+        }
+
+        self.toplevel.set_title(title.to_string());
+        self.platform.flush();
+        Ok(())
+    }
+
+    fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+        // the compositor picks the output and restores the window's size afterwards, clients can't change video modes
+        let fullscreen = match fullscreen {
+            Some(Fullscreen::Exclusive(mode)) => {
+                warn!(
+                    "Can't switch to {:?} on Wayland, using borderless fullscreen instead",
+                    mode
+                );
+                Some(Fullscreen::Borderless(None))
+            }
+            Some(Fullscreen::Borderless(_)) => Some(Fullscreen::Borderless(None)),
+            None => None,
+        };
+
+        if fullscreen.is_some() {
+            self.toplevel.set_fullscreen(None);
+        } else {
+            self.toplevel.unset_fullscreen();
+        }
+        self.platform.flush();
+
+        *self.fullscreen.borrow_mut() = fullscreen;
+    }
+
+    fn fullscreen(&self) -> Option<Fullscreen> {
+        self.fullscreen.borrow().clone()
+    }
+
+    fn request_redraw(&self) {
+        // the compositor doesn't ask for frames unless we commit, so the request never leaves the client
+        let mut state = self.platform.state.borrow_mut();
+        if !state
+            .pending
+            .iter()
+            .any(|event| matches!(event, PendingEvent::RedrawRequested(id) if *id == self.id))
+        {
+            state.pending.push(PendingEvent::RedrawRequested(self.id));
+        }
+    }
+
+    fn minimize(&self) {
+        self.toplevel.set_minimized();
+        self.platform.flush();
+    }
+
+    fn maximize(&self) {
+        self.toplevel.set_maximized();
+        self.platform.flush();
+    }
+
+    fn restore(&self) {
+        // there is no request to un-minimize, only the compositor can bring a minimized window back
+        self.toplevel.unset_maximized();
+        self.platform.flush();
+    }
+}
+
+impl WaylandWindow {
+    pub(super) fn new(
+        platform: Arc<WaylandPlatform>,
+        window_attributes: WindowAttributes,
+        id: WindowId,
+    ) -> anyhow::Result<Self> {
+        let title = window_attributes.title.as_deref().unwrap_or("Window");
+        if title.contains('\0') {
+            bail!("Window title {:?} contains a nul character", title);
+        }
+
+        let min_size = window_attributes
+            .min_size
+            .map(|size| size.physical_size(1.0));
+        let max_size = window_attributes
+            .max_size
+            .map(|size| size.physical_size(1.0));
+
+        let size = window_attributes
+            .size
+            .map_or((800, 600), |size| size.physical_size(1.0));
+        let size = if window_attributes.resizable {
+            clamp_size(size, min_size, max_size)
+        } else {
+            size
+        };
+
+        let surface = platform
+            .compositor
+            .create_surface(&platform.queue_handle, id);
+        let xdg_surface = platform
+            .wm_base
+            .get_xdg_surface(&surface, &platform.queue_handle, id);
+        let toplevel = xdg_surface.get_toplevel(&platform.queue_handle, id);
+        toplevel.set_title(title.to_string());
+
+        let decoration = platform.decoration_manager.as_ref().map(|manager| {
+            let decoration = manager.get_toplevel_decoration(&toplevel, &platform.queue_handle, id);
+            // the compositor draws all decorations or none
+            decoration.set_mode(
+                if window_attributes.show_title_bar || window_attributes.show_border {
+                    Mode::ServerSide
+                } else {
+                    Mode::ClientSide
+                },
+            );
+            decoration
+        });
+
+        let window = Self {
+            id,
+            platform,
+            surface,
+            xdg_surface,
+            toplevel,
+            decoration,
+            present_mode: Cell::new(None),
+            resizable: Cell::new(window_attributes.resizable),
+            min_size: Cell::new(min_size),
+            max_size: Cell::new(max_size),
+            fullscreen: RefCell::new(None),
+        };
+
+        window.update_size_limits(size);
+
+        if window_attributes.fullscreen.is_some() {
+            window.set_fullscreen(window_attributes.fullscreen);
+        }
+
+        if window_attributes.initially_minimized {
+            window.toplevel.set_minimized();
+        }
+
+        window
+            .platform
+            .state
+            .borrow_mut()
+            .surface_sizes
+            .insert(id, size);
+
+        // committing without a buffer asks for the first configure, the window is mapped once the renderer attaches a buffer
+        window.surface.commit();
+        window.platform.flush();
+
+        Ok(window)
+    }
+
+    /// Tell the compositor how far the window may be resized: not at all (at `size`) for windows that aren't resizable, otherwise within the configured limits (0 means no limit).
+    fn update_size_limits(&self, size: (u32, u32)) {
+        let ((min_width, min_height), (max_width, max_height)) = if self.resizable.get() {
+            (
+                self.min_size.get().unwrap_or((0, 0)),
+                self.max_size.get().unwrap_or((0, 0)),
+            )
+        } else {
+            (size, size)
+        };
+
+        self.toplevel
+            .set_min_size(min_width as i32, min_height as i32);
+        self.toplevel
+            .set_max_size(max_width as i32, max_height as i32);
+    }
+
+    /// Send the new limits, and resize the window into them if it doesn't fit anymore.
+    fn size_limits_changed(&self) {
+        let size = self.surface_size();
+        self.update_size_limits(size);
+
+        if self.resizable.get() {
+            let (width, height) = clamp_size(size, self.min_size.get(), self.max_size.get());
+            if (width, height) != size {
+                self.set_client_size(Resolution::Physical { width, height });
+            }
+        }
+
+        self.surface.commit();
+        self.platform.flush();
+    }
+
+    fn surface_size(&self) -> (u32, u32) {
+        self.platform
+            .state
+            .borrow()
+            .surface_sizes
+            .get(&self.id)
+            .copied()
+            .unwrap_or((1, 1))
+    }
+}
+
+impl Drop for WaylandWindow {
+    fn drop(&mut self) {
+        // the decoration has to go before its toplevel
+        if let Some(decoration) = &self.decoration {
+            decoration.destroy();
+        }
+        self.toplevel.destroy();
+        self.xdg_surface.destroy();
+        self.surface.destroy();
+        self.platform.flush();
+
+        self.platform.notify_window_destroy(self.id);
+    }
+}
@@ -1,112 +1,1481 @@
 //! Platform generic windows
 
-use crate::os::Platform;
+use crate::event::{Event, EventQueue};
+use crate::os::accelerator::Accelerator;
+use crate::os::monitor::{MonitorHandle, VideoMode};
+use crate::os::{OsLoopInputs, Platform, debug_assert_main_thread};
+use anyhow::bail;
 use hashbrown::{HashMap, HashSet};
-use log::debug;
-use raw_window_handle::HasWindowHandle;
-use std::cell::RefCell;
-use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::{Arc, Weak};
+use log::{debug, error, warn};
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle, RawWindowHandle};
+use std::cell::{Cell, RefCell};
+use std::cmp;
+use std::fmt;
+use std::mem;
+use std::ops::Mul;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
 
 /// Generic access to a window.
-/// Also requires [`raw_window_handle::HasWindowHandle`] to be implemented.
-pub trait Window: HasWindowHandle {}
+/// Also requires [`raw_window_handle::HasWindowHandle`] and [`raw_window_handle::HasDisplayHandle`] to be implemented (so a window is all a renderer needs to create a surface), windows without an OS window behind them return [`unavailable_handle`](super::unavailable_handle) there.
+///
+/// The display handle is usually the platform's, on X11 it names the screen the window is on (see [`X11SpecificAttributes::screen`]).
+pub trait Window: HasWindowHandle + HasDisplayHandle {
+    /// Resize the window so that its client area (the area excluding decorations) is exactly `size`.
+    ///
+    /// The outer size is set first, then the client size is read back and corrected once if the OS or window manager applied constraints (for example decoration sizes that weren't known in advance).
+    /// This does not loop: if the window manager keeps overriding the size (tiling window managers, min/max constraints, a size larger than the screen) the window keeps whatever size it was given after the retry.
+    /// On X11 the window is the client area, so there is nothing to correct: the size is requested once and this waits (briefly) for the window manager to apply it.
+    fn set_client_size(&self, size: Resolution<u32>);
+
+    /// Current size of the client area (the surface a renderer draws to, excluding decorations), in physical pixels.
+    ///
+    /// ```no_run
+    /// # use neuron_engine::Engine;
+    /// # use neuron_engine::os::window::{Resolution, WindowAttributes};
+    /// # fn main() -> anyhow::Result<()> {
+    /// let engine = Engine::new()?;
+    /// let (_, window) = engine.create_window(WindowAttributes {
+    ///     size: Some(Resolution::Physical { width: 800, height: 600 }),
+    ///     ..Default::default()
+    /// })?;
+    /// engine.process_all_pending();
+    ///
+    /// // the window manager may adjust the size slightly
+    /// let size = window.upgrade().unwrap().inner_size();
+    /// assert!(size.width().abs_diff(800) <= 2 && size.height().abs_diff(600) <= 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn inner_size(&self) -> Resolution<u32>;
+
+    /// Current position of the client area's top-left corner on the virtual desktop, in physical pixels.
+    fn position(&self) -> WindowPosition;
+
+    /// Scale factor of the window (`1.0` is 96 dpi), to size framebuffers and convert logical sizes.
+    ///
+    /// On Windows this follows the monitor the window is on, and changes are reported with [`Event::ScaleFactorChanged`](crate::event::Event::ScaleFactorChanged). X11 has a single scale factor for all monitors.
+    /// Platforms without any DPI information report `1.0`.
+    fn scale_factor(&self) -> f64 {
+        1.0
+    }
+
+    /// Resize the client area to `size` (see [`Window::set_client_size`], decorations are accounted for).
+    ///
+    /// Windows that aren't resizable (see [`WindowAttributes::resizable`] and [`Window::set_resizable`]) keep their size: a warning is logged and nothing happens.
+    fn set_inner_size(&self, size: Resolution<u32>) {
+        let _ = size;
+        unsupported("Window::set_inner_size");
+    }
+
+    /// Move the window so that the top-left corner of its client area ends up at `position` (the same point [`Window::position`] reports), in physical pixels.
+    fn set_position(&self, position: WindowPosition) {
+        let _ = position;
+        unsupported("Window::set_position");
+    }
+
+    /// Record the present mode the application would like its renderer to use for this window.
+    ///
+    /// The engine doesn't render, this is only stored on the window so that the renderer (or tooling) can read it back with [`Window::preferred_present_mode`].
+    fn set_preferred_present_mode(&self, mode: Option<PresentMode>);
+
+    /// The present mode recorded with [`Window::set_preferred_present_mode`], `None` if there is no preference.
+    fn preferred_present_mode(&self) -> Option<PresentMode>;
+
+    /// Set where the window goes when it is restored from being maximized: the top-left corner of its frame and the size of its client area.
+    ///
+    /// This can be called while the window is maximized without un-maximizing it. If the window isn't maximized it is moved and resized right away.
+    fn set_restore_placement(&self, position: Position, size: Resolution<u32>) {
+        let _ = (position, size);
+        unsupported("Window::set_restore_placement");
+    }
+
+    /// Whether the window currently can't be seen (see [`Event::Occluded`](crate::event::Event::Occluded)).
+    fn is_occluded(&self) -> bool;
+
+    /// Whether the window has the keyboard focus right now (changes are reported with [`Event::Focused`](crate::event::Event::Focused)).
+    ///
+    /// Platforms that can't tell report `false`.
+    fn has_focus(&self) -> bool {
+        false
+    }
+
+    /// Make the window fullscreen, or bring it back to the size and position it had before with `None`.
+    ///
+    /// Exclusive fullscreen switches the monitor to the video mode until the window leaves fullscreen or is closed. If the mode can't be set the window is made borderless fullscreen on that monitor instead.
+    fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+        let _ = fullscreen;
+        unsupported("Window::set_fullscreen");
+    }
+
+    /// Change the icon shown in the title bar, taskbar and window switcher. `None` goes back to the default icon.
+    fn set_icon(&self, icon: Option<WindowIcon>) {
+        let _ = icon;
+        unsupported("Window::set_icon");
+    }
+
+    /// The fullscreen mode set with [`Window::set_fullscreen`] (or [`WindowAttributes::fullscreen`]), `None` for a normal window.
+    fn fullscreen(&self) -> Option<Fullscreen> {
+        None
+    }
+
+    /// Change the window's title. Fails if `title` contains a nul character (the OS APIs take nul-terminated strings).
+    ///
+    /// ```no_run
+    /// # use neuron_engine::Engine;
+    /// # use neuron_engine::os::window::WindowAttributes;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let engine = Engine::new()?;
+    /// let (_, window) = engine.create_window(WindowAttributes::default())?;
+    /// let window = window.upgrade().unwrap();
+    ///
+    /// window.set_title("updated")?;
+    /// assert!(window.set_title("nul\0byte").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn set_title(&self, title: &str) -> anyhow::Result<()> {
+        let _ = title;
+        unsupported("Window::set_title");
+        Ok(())
+    }
+
+    /// Show the window (in the state it was in when it was hidden).
+    fn show(&self) {
+        unsupported("Window::show");
+    }
+
+    /// Hide the window, including its taskbar entry. The window stays open: it is still active in the [`WindowManager`] and can be shown again.
+    ///
+    /// ```no_run
+    /// # use neuron_engine::Engine;
+    /// # use neuron_engine::os::window::WindowAttributes;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let engine = Engine::new()?;
+    /// let (id, window) = engine.create_window(WindowAttributes {
+    ///     initially_visible: false,
+    ///     ..Default::default()
+    /// })?;
+    ///
+    /// window.upgrade().unwrap().hide();
+    /// engine.process_events();
+    /// assert!(engine.window_manager().is_window_active(id));
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn hide(&self) {
+        unsupported("Window::hide");
+    }
+
+    /// [`Window::show`] or [`Window::hide`] the window.
+    fn set_visible(&self, visible: bool) {
+        if visible {
+            self.show();
+        } else {
+            self.hide();
+        }
+    }
+
+    /// Whether the window is shown, as last requested (by [`WindowAttributes::initially_visible`], [`Window::set_visible`] or hiding on focus loss). This stays `true` while the window is minimized or covered, see [`Window::is_occluded`] for those.
+    ///
+    /// Platforms that can't hide windows report `true`.
+    fn is_visible(&self) -> bool {
+        true
+    }
+
+    /// Ask for an [`Event::RedrawRequested`](crate::event::Event::RedrawRequested), for renderers that only draw when something changed.
+    ///
+    /// Any number of calls before the event loop runs again produce a single event.
+    fn request_redraw(&self) {
+        unsupported("Window::request_redraw");
+    }
+
+    /// Minimize (iconify) the window.
+    fn minimize(&self) {
+        unsupported("Window::minimize");
+    }
+
+    /// Maximize the window to fill its monitor's work area.
+    fn maximize(&self) {
+        unsupported("Window::maximize");
+    }
+
+    /// Restore a minimized or maximized window. A minimized window goes back to the state it had before (which may be maximized).
+    fn restore(&self) {
+        unsupported("Window::restore");
+    }
+
+    /// Keep the window above all windows that aren't always on top themselves (e.g. for tool palettes or overlays), or stop doing so.
+    ///
+    /// On X11 this is a request to the window manager, which may ignore it.
+    fn set_always_on_top(&self, always_on_top: bool) {
+        let _ = always_on_top;
+        unsupported("Window::set_always_on_top");
+    }
+
+    /// Bring the window to the front and give it the keyboard focus (restoring it if it's minimized).
+    ///
+    /// The OS can refuse this for an application that isn't in the foreground: Windows then only flashes its taskbar button, and X11 window managers with focus stealing prevention may just mark it as demanding attention.
+    fn focus_window(&self) {
+        unsupported("Window::focus_window");
+    }
+
+    /// Draw the user's attention to the window without activating it (e.g. by flashing its taskbar button), `None` stops a previous request.
+    ///
+    /// Windows and most window managers end the request once the window is focused.
+    fn request_user_attention(&self, kind: Option<UserAttention>) {
+        let _ = kind;
+        unsupported("Window::request_user_attention");
+    }
+
+    /// Allow or prevent resizing the window by the user, see [`WindowAttributes::resizable`]. A window that stops being resizable keeps its current size.
+    ///
+    /// On Windows this also removes the maximize button while the window isn't resizable.
+    fn set_resizable(&self, resizable: bool) {
+        let _ = resizable;
+        unsupported("Window::set_resizable");
+    }
+
+    /// Change [`WindowAttributes::min_size`], `None` removes the limit. A window that is smaller is enlarged.
+    ///
+    /// Limits only apply while the window is resizable (one that isn't keeps its size anyway).
+    fn set_min_size(&self, size: Option<Resolution<u32>>) {
+        let _ = size;
+        unsupported("Window::set_min_size");
+    }
+
+    /// Change [`WindowAttributes::max_size`], `None` removes the limit. A window that is larger is shrunk.
+    fn set_max_size(&self, size: Option<Resolution<u32>>) {
+        let _ = size;
+        unsupported("Window::set_max_size");
+    }
+
+    /// Show or hide the window's taskbar entry (e.g. for minimize-to-tray).
+    fn set_skip_taskbar(&self, skip: bool) {
+        let _ = skip;
+        unsupported("Window::set_skip_taskbar");
+    }
+
+    /// Move the input method's composition and candidate windows to a point in the client area (usually the text cursor), in physical pixels.
+    fn set_ime_position(&self, x: i32, y: i32) {
+        let _ = (x, y);
+        unsupported("Window::set_ime_position");
+    }
+
+    /// Change the compositor bypass hint of the window (see [`WindowAttributes::compositor_bypass`]).
+    fn set_compositor_bypass(&self, bypass: CompositorBypass) {
+        let _ = bypass;
+        unsupported("Window::set_compositor_bypass");
+    }
+
+    /// Enable or disable OS key auto-repeat for this window (enabled by default).
+    ///
+    /// When disabled, holding a key produces a single press and a single release instead of repeated presses with [`KeyEvent::repeat`](crate::event::KeyEvent::repeat) set.
+    fn set_key_repeat(&self, enabled: bool) {
+        let _ = enabled;
+        unsupported("Window::set_key_repeat");
+    }
+
+    /// Presentation timing of the window, for adapting frame pacing.
+    ///
+    /// Windows reports what the compositor (DWM) measured. X11 has no such feedback without the Present extension, so the refresh period is derived from the mode of the monitor the window is on and the rest is unknown, see [`FrameStats::measured`].
+    fn frame_stats(&self) -> FrameStats {
+        unsupported("Window::frame_stats");
+        FrameStats::default()
+    }
+
+    /// Whether the user is currently resizing or moving the window (by dragging its border or title bar).
+    ///
+    /// Changes are reported as [`Event::InteractionBegan`](crate::event::Event::InteractionBegan) and [`Event::InteractionEnded`](crate::event::Event::InteractionEnded).
+    /// Windows reports the OS's modal size/move loop exactly. X11 window managers don't announce it, so it is inferred from geometry changes while a mouse button is held, and the end is only noticed the next time events are processed.
+    fn interaction_state(&self) -> InteractionState {
+        unsupported("Window::interaction_state");
+        InteractionState::default()
+    }
+
+    /// Start dragging `data` out of the window to other applications, allowing the drop target to perform `effect` with it.
+    ///
+    /// Call this while the left mouse button is held (usually once the pointer moved past [`Platform::drag_threshold`] after a press): the drag follows the pointer and drops when the button is released, or is cancelled with Escape.
+    /// The outcome is reported as [`Event::DragEnded`](crate::event::Event::DragEnded). On Windows this only returns once the drag is over (events keep being collected meanwhile).
+    fn start_drag(&self, data: DragData, effect: DragEffect) {
+        let _ = (data, effect);
+        unsupported("Window::start_drag");
+    }
+
+    /// Set a callback that repaints the window from inside the resize itself, before control goes back to the OS (`None` removes it).
+    ///
+    /// Without one, parts of the window show stale contents or black while it is being resized: on Windows the engine doesn't get to run at all while the user drags the border (the OS runs its own modal loop), so [`Event::SurfaceResized`](crate::event::Event::SurfaceResized) is only handled after the drag.
+    /// The callback gets the new client size in physical pixels, from the handling of `WM_SIZE` (Windows, including within the modal loop) or `ConfigureNotify` (X11).
+    ///
+    /// # Reentrancy
+    /// The callback runs in the middle of [`Platform::process_events`]. It must not process events or pump the engine itself, and events are still queued as usual (the `SurfaceResized` for this size is already queued when it runs).
+    /// Resizing the window from inside the callback doesn't call it again. It may replace or remove itself.
+    fn set_resize_paint_callback(&self, callback: Option<ResizePaintCallback>) {
+        let _ = callback;
+        unsupported("Window::set_resize_paint_callback");
+    }
+
+    /// Set the name screen readers announce for the window, in place of its title.
+    ///
+    /// Only the window itself is exposed to the accessibility tree, not its contents. Only available on Windows (X11 would need an AT-SPI bridge over D-Bus).
+    fn set_accessible_name(&self, name: &str) {
+        let _ = name;
+        unsupported("Window::set_accessible_name");
+    }
+
+    /// Keep the OS from acting on its own keyboard shortcuts while the window has the focus (off by default), e.g. for fullscreen games that use the Windows key.
+    ///
+    /// On Windows a low-level keyboard hook swallows the Windows keys, Alt+Tab, Alt+Esc and Ctrl+Esc while the window is in the foreground. Ctrl+Alt+Del and the secure desktop (UAC prompts) can't be intercepted, and the OS silently removes hooks that take too long to respond.
+    /// On X11 the keyboard is grabbed while the window has the focus, which keeps the window manager's shortcuts from triggering. Wayland compositors only honor this for their XWayland windows if they allow grabs at all.
+    fn set_keyboard_shortcuts_inhibited(&self, inhibited: bool) {
+        let _ = inhibited;
+        unsupported("Window::set_keyboard_shortcuts_inhibited");
+    }
+
+    /// Keep the cursor inside the window's client area until this is called again with `false` (off by default).
+    ///
+    /// Unlike a plain OS grab, the confinement isn't lost when the window loses focus: it is re-established every time the window gets the focus back, which kiosk and fullscreen applications rely on.
+    fn set_cursor_confined(&self, confined: bool) {
+        let _ = confined;
+        unsupported("Window::set_cursor_confined");
+    }
+
+    /// Hide the cursor while it is over the window (`false`), or show it again.
+    ///
+    /// On Windows the cursor is hidden for the whole thread, so it is also hidden over the other windows of the application.
+    fn set_cursor_visible(&self, visible: bool) {
+        let _ = visible;
+        unsupported("Window::set_cursor_visible");
+    }
+
+    /// Change the cursor shown over the client area (it starts out as [`CursorIcon::Default`]).
+    ///
+    /// On X11 [`CursorIcon::Wait`] and [`CursorIcon::Progress`] look the same, the core cursor font only has a watch.
+    fn set_cursor_icon(&self, icon: CursorIcon) {
+        let _ = icon;
+        unsupported("Window::set_cursor_icon");
+    }
+
+    /// Confine or lock the cursor to the window, see [`CursorGrabMode`].
+    ///
+    /// Both modes are built on [`Window::set_cursor_confined`], so they are re-established when the window gets the focus back. A locked cursor is only held while the window has the focus. It is usually hidden as well, see [`Window::set_cursor_visible`].
+    fn set_cursor_grab(&self, mode: CursorGrabMode) {
+        let _ = mode;
+        unsupported("Window::set_cursor_grab");
+    }
+
+    /// Replace the buttons shown below the window's taskbar thumbnail (e.g. play/pause/next for a media player). Clicks are reported as [`Event::ThumbButtonClicked`](crate::event::Event::ThumbButtonClicked).
+    ///
+    /// Windows shows at most [`ThumbButton::MAX_COUNT`] buttons, any further ones are dropped. Only available on Windows.
+    fn set_thumbnail_toolbar(&self, buttons: Vec<ThumbButton>) {
+        let _ = buttons;
+        unsupported("Window::set_thumbnail_toolbar");
+    }
+
+    /// Snap the window to a part of the work area of the monitor it is on (like dragging it to a screen edge).
+    fn snap(&self, region: SnapRegion) {
+        let _ = region;
+        unsupported("Window::snap");
+    }
+
+    /// Resize the client area to `size` while keeping the `anchor` point of the window where it is (e.g. [`Anchor::BottomRight`] grows the window up and to the left).
+    ///
+    /// The window is moved and resized in a single request, so it doesn't flicker through an intermediate geometry.
+    fn set_size_anchored(&self, size: Resolution<u32>, anchor: Anchor) {
+        let _ = (size, anchor);
+        unsupported("Window::set_size_anchored");
+    }
+}
+
+/// Log that an optional window operation isn't available on the current platform.
+pub(crate) fn unsupported(operation: &str) {
+    warn!("{} is not supported on this platform, ignoring", operation);
+}
+
+/// `size` brought within the [`WindowAttributes::min_size`] and [`WindowAttributes::max_size`] limits (the minimum wins if they contradict each other).
+#[cfg(target_os = "linux")]
+pub(crate) fn clamp_size(
+    (width, height): (u32, u32),
+    min: Option<(u32, u32)>,
+    max: Option<(u32, u32)>,
+) -> (u32, u32) {
+    let (width, height) = max.map_or((width, height), |(max_width, max_height)| {
+        (width.min(max_width), height.min(max_height))
+    });
+    min.map_or((width, height), |(min_width, min_height)| {
+        (width.max(min_width), height.max(min_height))
+    })
+}
 
 /// Set of attributes that control how a window is created.
 ///
 /// > **Note:** Not all of these attributes are actually available on all platforms, however they are all defined.
 /// > Furthermore, you can use the [Platform](super::Platform) object to query which attributes are actually available to be used. Setting unavailable attributes is never an error, however they will simply be ignored.
+/// > [`SupportedWindowAttributes::check`] lists the ones that would be.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct WindowAttributes {
     /// Window title (defaults to `"Window"`)
     pub title: Option<String>,
 
-    /// Size of the window (default is platform-dependent)
-    pub size: Option<Resolution<u32>>,
+    /// Size of the window (default is platform-dependent)
+    pub size: Option<Resolution<u32>>,
+
+    /// Size that is resolved against the primary monitor when the window is created. Takes priority over `size` (if the platform can report a monitor).
+    pub monitor_relative_size: Option<MonitorRelativeSize>,
+
+    /// Position of the window (default is platform-dependent)
+    pub position: Option<Position>,
+
+    /// Center the window in the primary monitor's work area when it is created. Takes priority over `position` (if the platform can report a monitor).
+    pub centered: bool, // = false
+
+    /// Can the user close the window?
+    pub allow_close: bool, // = true
+
+    /// Does the window have a minimize button?
+    pub has_minimize_button: bool, // = true
+
+    /// Does the window have a maximize button?
+    ///
+    /// On Windows the minimize and maximize buttons are part of the title bar, so they are only there when `show_title_bar` is set.
+    ///
+    /// ```no_run
+    /// # use neuron_engine::Engine;
+    /// # use neuron_engine::os::window::WindowAttributes;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let engine = Engine::new()?;
+    /// let (_, window) = engine.create_window(WindowAttributes::default())?;
+    ///
+    /// #[cfg(windows)]
+    /// {
+    ///     use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    ///     use windows::Win32::Foundation::HWND;
+    ///     use windows::Win32::UI::WindowsAndMessaging::{GWL_STYLE, GetWindowLongPtrW, WS_MAXIMIZEBOX};
+    ///
+    ///     let RawWindowHandle::Win32(handle) = window.upgrade().unwrap().window_handle().unwrap().as_raw() else {
+    ///         unreachable!()
+    ///     };
+    ///     let style = unsafe { GetWindowLongPtrW(HWND(handle.hwnd.get() as _), GWL_STYLE) } as u32;
+    ///     assert_ne!(style & WS_MAXIMIZEBOX.0, 0);
+    /// }
+    /// # let _ = window;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub has_maximize_button: bool, // = true
+
+    /// Does the window have a border?
+    pub show_border: bool, // = true
+
+    /// Does the window have a title bar?
+    pub show_title_bar: bool, // = true
+
+    /// Is the window initially disabled?
+    pub initially_disabled: bool, // = false
+
+    /// Is the window initially minimized?
+    pub initially_minimized: bool, // = false
+
+    /// Is the window resizable?
+    pub resizable: bool, // = true
+
+    /// Smallest client area size the user can resize the window to.
+    pub min_size: Option<Resolution<u32>>, // = None
+
+    /// Largest client area size the user can resize the window to.
+    pub max_size: Option<Resolution<u32>>, // = None
+
+    /// Is the window left out of the taskbar? Same as setting `show_in_taskbar` to false.
+    pub skip_taskbar: bool, // = false
+
+    /// Does the window get a taskbar button (a task list entry on X11)?
+    pub show_in_taskbar: bool, // = true
+
+    /// Is the window listed in the window switcher (Alt+Tab on Windows, the pager on X11)?
+    ///
+    /// Independent of `show_in_taskbar`, so a window can have a taskbar button without an Alt+Tab entry and the other way around.
+    pub show_in_switcher: bool, // = true
+
+    /// Is the window initially visible?
+    pub initially_visible: bool, // = true
+
+    /// Where the window is stacked when it is created, e.g. [`InitialZOrder::Bottom`] to open a window without covering the others.
+    pub initial_z_order: InitialZOrder, // = InitialZOrder::NoChange
+
+    /// Keep the window above other windows, see [`Window::set_always_on_top`]. Takes priority over `initial_z_order`.
+    pub always_on_top: bool, // = false
+
+    /// Hide the window when it loses focus, for dropdowns, pickers and other popups.
+    ///
+    /// The window is only hidden, not closed: showing it again is up to the application.
+    pub auto_hide_on_focus_loss: bool, // = false
+
+    /// Remember the window's geometry under this key: it is saved when the window closes and restored (fitted to the current monitors) the next time a window is created with the same key, taking priority over `size` and `position`.
+    ///
+    /// Placements are stored per executable, in the registry on Windows and under `$XDG_CONFIG_HOME` on Linux.
+    pub persistence_key: Option<String>, // = None
+
+    /// Create the window fullscreen, see [`Window::set_fullscreen`]. Leaving fullscreen later restores `size` and `position`.
+    ///
+    /// Monitors and video modes only make sense for the current session, so this isn't serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub fullscreen: Option<Fullscreen>, // = None
+
+    /// Icon of the window, see [`Window::set_icon`]. Not serialized, load it from the image where it is stored instead.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub icon: Option<WindowIcon>, // = None
+
+    /// Give the window a per-pixel alpha channel, so whatever the renderer draws with alpha below 1 shows the desktop behind it (for overlays).
+    ///
+    /// Only the surface is transparent, decorations stay opaque (so this is usually combined with `show_title_bar` and `show_border` off). On X11 this needs a running compositor, otherwise transparent pixels come out black.
+    pub transparent: bool, // = false
+
+    /// Color (red, green, blue) the client area is filled with before anything is rendered to it, `None` uses the system's window background color (on X11 the area is left undefined then).
+    ///
+    /// Ignored for [transparent](WindowAttributes::transparent) windows.
+    pub background_color: Option<(u8, u8, u8)>, // = None
+
+    /// Hint whether the compositor should unredirect the window (only takes effect for fullscreen windows that cover a whole monitor).
+    pub compositor_bypass: CompositorBypass, // = CompositorBypass::Default
+
+    /// Attributes that only exist on one platform. The other platforms ignore them, so they can be set unconditionally.
+    pub platform_specific: PlatformSpecificAttributes,
+}
+
+impl Default for WindowAttributes {
+    fn default() -> Self {
+        Self {
+            title: None,
+            size: None,
+            monitor_relative_size: None,
+            position: None,
+            centered: false,
+            allow_close: true,
+            has_minimize_button: true,
+            has_maximize_button: true,
+            show_border: true,
+            show_title_bar: true,
+            initially_disabled: false,
+            initially_minimized: false,
+            resizable: true,
+            min_size: None,
+            max_size: None,
+            skip_taskbar: false,
+            show_in_taskbar: true,
+            show_in_switcher: true,
+            initially_visible: true,
+            initial_z_order: InitialZOrder::NoChange,
+            always_on_top: false,
+            auto_hide_on_focus_loss: false,
+            persistence_key: None,
+            fullscreen: None,
+            icon: None,
+            transparent: false,
+            background_color: None,
+            compositor_bypass: CompositorBypass::Default,
+            platform_specific: PlatformSpecificAttributes::default(),
+        }
+    }
+}
+
+impl WindowAttributes {
+    /// Start building attributes from the defaults, as an alternative to struct literals with `..Default::default()`.
+    ///
+    /// ```no_run
+    /// # use neuron_engine::Engine;
+    /// # use neuron_engine::os::window::{Resolution, WindowAttributes};
+    /// # fn main() -> anyhow::Result<()> {
+    /// let engine = Engine::new()?;
+    /// let (_, window) = engine.create_window(
+    ///     WindowAttributes::builder()
+    ///         .title("Settings")
+    ///         .size(Resolution::Logical { width: 640, height: 480 })
+    ///         .resizable(false)
+    ///         .build(),
+    /// )?;
+    /// # let _ = window;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder() -> WindowAttributesBuilder {
+        WindowAttributesBuilder::default()
+    }
+}
+
+/// Builds [`WindowAttributes`] one attribute at a time, see [`WindowAttributes::builder`].
+///
+/// Setters for optional attributes take the value itself. [`WindowAttributesBuilder::build`] logs a warning for combinations that can't work as asked, but still returns the attributes as they were set.
+#[derive(Default)]
+pub struct WindowAttributesBuilder {
+    attributes: WindowAttributes,
+    /// Title bar buttons that were explicitly turned on, which need a title bar to show up in.
+    requested_buttons: Vec<&'static str>,
+}
+
+impl WindowAttributesBuilder {
+    /// See [`WindowAttributes::title`].
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.attributes.title = Some(title.into());
+        self
+    }
+
+    /// See [`WindowAttributes::size`].
+    pub fn size(mut self, size: Resolution<u32>) -> Self {
+        self.attributes.size = Some(size);
+        self
+    }
+
+    /// See [`WindowAttributes::monitor_relative_size`].
+    pub fn monitor_relative_size(mut self, size: MonitorRelativeSize) -> Self {
+        self.attributes.monitor_relative_size = Some(size);
+        self
+    }
+
+    /// See [`WindowAttributes::position`].
+    pub fn position(mut self, position: Position) -> Self {
+        self.attributes.position = Some(position);
+        self
+    }
+
+    /// See [`WindowAttributes::centered`].
+    pub fn centered(mut self, centered: bool) -> Self {
+        self.attributes.centered = centered;
+        self
+    }
+
+    /// See [`WindowAttributes::allow_close`].
+    pub fn allow_close(mut self, allow_close: bool) -> Self {
+        self.requested_buttons.retain(|button| *button != "close");
+        if allow_close {
+            self.requested_buttons.push("close");
+        }
+        self.attributes.allow_close = allow_close;
+        self
+    }
+
+    /// See [`WindowAttributes::has_minimize_button`].
+    pub fn has_minimize_button(mut self, has_minimize_button: bool) -> Self {
+        self.requested_buttons
+            .retain(|button| *button != "minimize");
+        if has_minimize_button {
+            self.requested_buttons.push("minimize");
+        }
+        self.attributes.has_minimize_button = has_minimize_button;
+        self
+    }
+
+    /// See [`WindowAttributes::has_maximize_button`].
+    pub fn has_maximize_button(mut self, has_maximize_button: bool) -> Self {
+        self.requested_buttons
+            .retain(|button| *button != "maximize");
+        if has_maximize_button {
+            self.requested_buttons.push("maximize");
+        }
+        self.attributes.has_maximize_button = has_maximize_button;
+        self
+    }
+
+    /// See [`WindowAttributes::show_border`].
+    pub fn show_border(mut self, show_border: bool) -> Self {
+        self.attributes.show_border = show_border;
+        self
+    }
+
+    /// See [`WindowAttributes::show_title_bar`].
+    pub fn show_title_bar(mut self, show_title_bar: bool) -> Self {
+        self.attributes.show_title_bar = show_title_bar;
+        self
+    }
+
+    /// See [`WindowAttributes::initially_disabled`].
+    pub fn initially_disabled(mut self, initially_disabled: bool) -> Self {
+        self.attributes.initially_disabled = initially_disabled;
+        self
+    }
+
+    /// See [`WindowAttributes::initially_minimized`].
+    pub fn initially_minimized(mut self, initially_minimized: bool) -> Self {
+        self.attributes.initially_minimized = initially_minimized;
+        self
+    }
+
+    /// See [`WindowAttributes::resizable`].
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.attributes.resizable = resizable;
+        self
+    }
+
+    /// See [`WindowAttributes::min_size`].
+    pub fn min_size(mut self, size: Resolution<u32>) -> Self {
+        self.attributes.min_size = Some(size);
+        self
+    }
+
+    /// See [`WindowAttributes::max_size`].
+    pub fn max_size(mut self, size: Resolution<u32>) -> Self {
+        self.attributes.max_size = Some(size);
+        self
+    }
+
+    /// See [`WindowAttributes::skip_taskbar`].
+    pub fn skip_taskbar(mut self, skip_taskbar: bool) -> Self {
+        self.attributes.skip_taskbar = skip_taskbar;
+        self
+    }
+
+    /// See [`WindowAttributes::show_in_taskbar`].
+    pub fn show_in_taskbar(mut self, show_in_taskbar: bool) -> Self {
+        self.attributes.show_in_taskbar = show_in_taskbar;
+        self
+    }
+
+    /// See [`WindowAttributes::show_in_switcher`].
+    pub fn show_in_switcher(mut self, show_in_switcher: bool) -> Self {
+        self.attributes.show_in_switcher = show_in_switcher;
+        self
+    }
+
+    /// See [`WindowAttributes::initially_visible`].
+    pub fn initially_visible(mut self, initially_visible: bool) -> Self {
+        self.attributes.initially_visible = initially_visible;
+        self
+    }
+
+    /// See [`WindowAttributes::initial_z_order`].
+    pub fn initial_z_order(mut self, initial_z_order: InitialZOrder) -> Self {
+        self.attributes.initial_z_order = initial_z_order;
+        self
+    }
+
+    /// See [`WindowAttributes::always_on_top`].
+    pub fn always_on_top(mut self, always_on_top: bool) -> Self {
+        self.attributes.always_on_top = always_on_top;
+        self
+    }
+
+    /// See [`WindowAttributes::auto_hide_on_focus_loss`].
+    pub fn auto_hide_on_focus_loss(mut self, auto_hide_on_focus_loss: bool) -> Self {
+        self.attributes.auto_hide_on_focus_loss = auto_hide_on_focus_loss;
+        self
+    }
+
+    /// See [`WindowAttributes::persistence_key`].
+    pub fn persistence_key(mut self, key: impl Into<String>) -> Self {
+        self.attributes.persistence_key = Some(key.into());
+        self
+    }
+
+    /// See [`WindowAttributes::fullscreen`].
+    pub fn fullscreen(mut self, fullscreen: Fullscreen) -> Self {
+        self.attributes.fullscreen = Some(fullscreen);
+        self
+    }
+
+    /// See [`WindowAttributes::icon`].
+    pub fn icon(mut self, icon: WindowIcon) -> Self {
+        self.attributes.icon = Some(icon);
+        self
+    }
+
+    /// See [`WindowAttributes::transparent`].
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.attributes.transparent = transparent;
+        self
+    }
+
+    /// See [`WindowAttributes::background_color`].
+    pub fn background_color(mut self, color: (u8, u8, u8)) -> Self {
+        self.attributes.background_color = Some(color);
+        self
+    }
+
+    /// See [`WindowAttributes::compositor_bypass`].
+    pub fn compositor_bypass(mut self, bypass: CompositorBypass) -> Self {
+        self.attributes.compositor_bypass = bypass;
+        self
+    }
+
+    /// See [`WindowAttributes::platform_specific`].
+    pub fn platform_specific(mut self, attributes: PlatformSpecificAttributes) -> Self {
+        self.attributes.platform_specific = attributes;
+        self
+    }
+
+    pub fn build(self) -> WindowAttributes {
+        let attributes = self.attributes;
+        let title = attributes.title.as_deref().unwrap_or("Window");
+
+        if !attributes.show_title_bar && !self.requested_buttons.is_empty() {
+            warn!(
+                "Window {:?} has no title bar, so its {} button won't show",
+                title,
+                self.requested_buttons.join(" and ")
+            );
+        }
+
+        if let (Some(min_size), Some(max_size)) = (attributes.min_size, attributes.max_size)
+            && mem::discriminant(&min_size) == mem::discriminant(&max_size)
+            && matches!(
+                min_size.partial_cmp(&max_size),
+                None | Some(cmp::Ordering::Greater)
+            )
+        {
+            warn!(
+                "Window {:?} has a minimum size of {:?} that exceeds its maximum size of {:?}",
+                title, min_size, max_size
+            );
+        }
+
+        if attributes.centered && attributes.position.is_some() {
+            warn!("Window {:?} is centered, its position is ignored", title);
+        }
+
+        if attributes.transparent && attributes.background_color.is_some() {
+            warn!(
+                "Window {:?} is transparent, its background color is ignored",
+                title
+            );
+        }
+
+        attributes
+    }
+}
+
+/// Per-platform extensions to [`WindowAttributes`].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct PlatformSpecificAttributes {
+    pub windows: WindowsSpecificAttributes,
+    pub x11: X11SpecificAttributes,
+}
+
+/// Window attributes that only apply on Windows.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct WindowsSpecificAttributes {
+    /// Does the window have a drop shadow?
+    pub show_drop_shadow: bool, // = false
+
+    /// Is the window a dialog box?
+    pub is_dialog_box: bool, // = false
+
+    /// Does the window have a menu bar?
+    pub has_system_menu: bool, // = false
+}
+
+/// Window attributes that only apply on X11.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct X11SpecificAttributes {
+    /// EWMH window type (`_NET_WM_WINDOW_TYPE`), which window managers use to pick decorations and placement. Unset means a normal window.
+    pub window_type: Option<X11WindowType>,
+
+    /// X screen to create the window on, `None` (or a screen that doesn't exist) uses the display's default screen.
+    ///
+    /// Almost every setup has a single screen spanning all monitors, where this changes nothing. Only servers running each monitor as its own screen (e.g. `DISPLAY=:0.1`) have more, and windows can't move between them.
+    pub screen: Option<i32>,
+}
+
+/// Values of `_NET_WM_WINDOW_TYPE`.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum X11WindowType {
+    Normal,
+    Dialog,
+    Utility,
+    Toolbar,
+    Menu,
+    Splash,
+    Dock,
+    Desktop,
+}
+
+impl WindowAttributes {
+    /// Attributes for a window that fits content of `width`x`height` (logical pixels), shrunk to at most `max_fraction_of_workarea` of the monitor's work area while keeping the aspect ratio.
+    ///
+    /// This is what image viewers and similar apps usually want: content is shown at its natural size unless it doesn't fit on the monitor.
+    pub fn fit_content(width: u32, height: u32, max_fraction_of_workarea: f64) -> Self {
+        Self {
+            monitor_relative_size: Some(MonitorRelativeSize::FitContent {
+                width,
+                height,
+                max_fraction: max_fraction_of_workarea,
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+impl WindowAttributes {
+    /// Attributes for a window centered on the primary monitor, taking up the given fractions of its work area (e.g. `0.75, 0.75`).
+    ///
+    /// Fractions are clamped to `0.1..=1.0`, and the window is never made smaller than [`MonitorRelativeSize::MIN_LOGICAL_SIZE`] unless the work area itself is.
+    pub fn relative_to_primary(width_fraction: f64, height_fraction: f64) -> Self {
+        Self {
+            monitor_relative_size: Some(MonitorRelativeSize::FractionOfWorkArea {
+                width: width_fraction,
+                height: height_fraction,
+            }),
+            centered: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Window size that depends on the monitor the window is opened on.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind"))]
+pub enum MonitorRelativeSize {
+    /// Fit content of the given size (logical pixels), shrinking it uniformly so it takes up at most `max_fraction` (clamped to `0.0..=1.0`) of the work area.
+    #[allow(missing_docs)]
+    FitContent {
+        width: u32,
+        height: u32,
+        max_fraction: f64,
+    },
+
+    /// Fractions (clamped to `0.1..=1.0`) of the work area's width and height.
+    #[allow(missing_docs)]
+    FractionOfWorkArea { width: f64, height: f64 },
+}
+
+impl MonitorRelativeSize {
+    /// Smallest size (logical pixels) a [`MonitorRelativeSize::FractionOfWorkArea`] resolves to, so tiny fractions still give a usable window.
+    pub const MIN_LOGICAL_SIZE: (u32, u32) = (320, 240);
+
+    /// Compute the physical size this resolves to on a monitor.
+    pub fn resolve(&self, monitor: &MonitorHandle) -> Resolution<u32> {
+        match *self {
+            MonitorRelativeSize::FitContent {
+                width,
+                height,
+                max_fraction,
+            } => {
+                let max_fraction = if max_fraction.is_finite() {
+                    max_fraction.clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+
+                let content_width = width.max(1) as f64 * monitor.scale_factor;
+                let content_height = height.max(1) as f64 * monitor.scale_factor;
+
+                let max_width = monitor.work_area_size.0 as f64 * max_fraction;
+                let max_height = monitor.work_area_size.1 as f64 * max_fraction;
+
+                let scale = (max_width / content_width)
+                    .min(max_height / content_height)
+                    .min(1.0);
+
+                Resolution::Physical {
+                    width: ((content_width * scale).round() as u32).max(1),
+                    height: ((content_height * scale).round() as u32).max(1),
+                }
+            }
+            MonitorRelativeSize::FractionOfWorkArea { width, height } => {
+                let fraction = |f: f64| {
+                    if f.is_finite() {
+                        f.clamp(0.1, 1.0)
+                    } else {
+                        1.0
+                    }
+                };
+
+                let (area_width, area_height) = monitor.work_area_size;
+                let min_width =
+                    (Self::MIN_LOGICAL_SIZE.0 as f64 * monitor.scale_factor).round() as u32;
+                let min_height =
+                    (Self::MIN_LOGICAL_SIZE.1 as f64 * monitor.scale_factor).round() as u32;
+
+                Resolution::Physical {
+                    width: ((area_width as f64 * fraction(width)).round() as u32)
+                        .max(min_width)
+                        .min(area_width)
+                        .max(1),
+                    height: ((area_height as f64 * fraction(height)).round() as u32)
+                        .max(min_height)
+                        .min(area_height)
+                        .max(1),
+                }
+            }
+        }
+    }
+}
+
+/// Representation of resolutions on systems. Supports both physical resolutions (exact pixels) and logical resolutions (based on dpi).
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind"))]
+pub enum Resolution<T> {
+    /// Physical resolution (based on pixels).
+    #[allow(missing_docs)]
+    Physical { width: T, height: T },
+
+    /// Logical resolution (based on dpi).
+    #[allow(missing_docs)]
+    Logical { width: T, height: T },
+}
+
+impl<T> Copy for Resolution<T> where T: Copy + Clone {}
+
+impl<T> Resolution<T> {
+    /// Width (in the resolution's units).
+    pub fn width(&self) -> T
+    where
+        T: Copy,
+    {
+        match *self {
+            Resolution::Physical { width, .. } | Resolution::Logical { width, .. } => width,
+        }
+    }
+
+    /// Height (in the resolution's units).
+    pub fn height(&self) -> T
+    where
+        T: Copy,
+    {
+        match *self {
+            Resolution::Physical { height, .. } | Resolution::Logical { height, .. } => height,
+        }
+    }
+
+    pub fn is_physical(&self) -> bool {
+        matches!(self, Resolution::Physical { .. })
+    }
+
+    pub fn is_logical(&self) -> bool {
+        matches!(self, Resolution::Logical { .. })
+    }
+
+    /// Convert both dimensions, keeping the variant.
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Resolution<U> {
+        match self {
+            Resolution::Physical { width, height } => Resolution::Physical {
+                width: f(width),
+                height: f(height),
+            },
+            Resolution::Logical { width, height } => Resolution::Logical {
+                width: f(width),
+                height: f(height),
+            },
+        }
+    }
+
+    /// Multiply both dimensions by `factor`, keeping the variant.
+    pub fn scale<F>(self, factor: F) -> Resolution<T::Output>
+    where
+        T: Mul<F>,
+        F: Copy,
+    {
+        self.map(|v| v * factor)
+    }
+
+    /// Width times height.
+    pub fn area(self) -> T::Output
+    where
+        T: Mul,
+    {
+        match self {
+            Resolution::Physical { width, height } | Resolution::Logical { width, height } => {
+                width * height
+            }
+        }
+    }
+}
+
+/// Resolutions are ordered by containment: `a <= b` when `a` fits inside `b` in both dimensions.
+///
+/// Resolutions that don't fit inside each other in either direction, and resolutions of different variants (physical vs logical), are not comparable (`partial_cmp` returns `None`, so `<`, `>`, `<=` and `>=` are all `false`). Convert them to the same variant first.
+impl<T: PartialOrd> PartialOrd for Resolution<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        let ((w1, h1), (w2, h2)) = match (self, other) {
+            (
+                Resolution::Physical {
+                    width: w1,
+                    height: h1,
+                },
+                Resolution::Physical {
+                    width: w2,
+                    height: h2,
+                },
+            )
+            | (
+                Resolution::Logical {
+                    width: w1,
+                    height: h1,
+                },
+                Resolution::Logical {
+                    width: w2,
+                    height: h2,
+                },
+            ) => ((w1, h1), (w2, h2)),
+            _ => return None,
+        };
+
+        match (w1.partial_cmp(w2)?, h1.partial_cmp(h2)?) {
+            (cmp::Ordering::Equal, cmp::Ordering::Equal) => Some(cmp::Ordering::Equal),
+            (
+                cmp::Ordering::Less | cmp::Ordering::Equal,
+                cmp::Ordering::Less | cmp::Ordering::Equal,
+            ) => Some(cmp::Ordering::Less),
+            (
+                cmp::Ordering::Greater | cmp::Ordering::Equal,
+                cmp::Ordering::Greater | cmp::Ordering::Equal,
+            ) => Some(cmp::Ordering::Greater),
+            _ => None,
+        }
+    }
+}
+
+impl Resolution<u32> {
+    /// Width and height in physical pixels, converting logical resolutions with `scale_factor`.
+    pub(crate) fn physical_size(&self, scale_factor: f64) -> (u32, u32) {
+        match *self {
+            Resolution::Physical { width, height } => (width, height),
+            Resolution::Logical { width, height } => (
+                (width as f64 * scale_factor).round() as u32,
+                (height as f64 * scale_factor).round() as u32,
+            ),
+        }
+    }
+}
+
+/// A button in a window's taskbar thumbnail toolbar, see [`Window::set_thumbnail_toolbar`].
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct ThumbButton {
+    /// Reported back in [`Event::ThumbButtonClicked`](crate::event::Event::ThumbButtonClicked).
+    pub id: u32,
+    pub tooltip: String,
+    /// Disabled buttons are shown greyed out and can't be clicked.
+    pub enabled: bool,
+    /// Image of the button (the taskbar shows it at the small icon size, 16x16 at 96 dpi). Without one the button is blank.
+    pub icon: Option<WindowIcon>,
+}
+
+impl ThumbButton {
+    /// The most buttons a thumbnail toolbar can have.
+    pub const MAX_COUNT: usize = 7;
+
+    pub fn new(id: u32, tooltip: impl Into<String>) -> Self {
+        Self {
+            id,
+            tooltip: tooltip.into(),
+            enabled: true,
+            icon: None,
+        }
+    }
+}
+
+/// An icon for [`Window::set_icon`], as straight (not premultiplied) RGBA pixels in rows from the top.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct WindowIcon {
+    pub(crate) rgba: Vec<u8>,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+}
+
+impl WindowIcon {
+    /// Fails unless `bytes` has exactly 4 bytes for each of the `width * height` pixels.
+    ///
+    /// ```
+    /// # use neuron_engine::os::window::WindowIcon;
+    /// assert!(WindowIcon::from_rgba(vec![255; 2 * 2 * 4], 2, 2).is_ok());
+    /// assert!(WindowIcon::from_rgba(vec![255; 2 * 2 * 3], 2, 2).is_err());
+    /// ```
+    pub fn from_rgba(bytes: Vec<u8>, width: u32, height: u32) -> anyhow::Result<Self> {
+        let expected = width as u64 * height as u64 * 4;
+        if width == 0 || height == 0 {
+            bail!("Window icon has no pixels ({}x{})", width, height);
+        }
+        if bytes.len() as u64 != expected {
+            bail!(
+                "Window icon of {}x{} needs {} bytes of RGBA, got {}",
+                width,
+                height,
+                expected,
+                bytes.len()
+            );
+        }
+
+        Ok(Self {
+            rgba: bytes,
+            width,
+            height,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
 
-    /// Position of the window (default is platform-dependent)
-    pub position: Option<WindowPosition>,
+/// Presentation timing of a window, see [`Window::frame_stats`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct FrameStats {
+    /// When the display last refreshed with a new frame, `None` if unknown.
+    pub last_present: Option<Instant>,
+    /// Time between two refreshes of the display, `None` if unknown.
+    pub refresh_period: Option<Duration>,
+    /// Frames the compositor dropped so far because they arrived too late for their refresh, `None` if unknown.
+    pub dropped: Option<u64>,
+    /// Whether the numbers come from the compositor, rather than being estimated from the monitor's refresh rate.
+    pub measured: bool,
+}
 
-    /// Can the user close the window?
-    pub allow_close: bool, // = true
+/// What the user is doing to a window, see [`Window::interaction_state`].
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub struct InteractionState {
+    pub resizing: bool,
+    pub moving: bool,
+}
 
-    /// Does the window have a minimize button?
-    pub has_minimize_button: bool, // = true
+impl InteractionState {
+    /// Neither resizing nor moving.
+    pub fn is_idle(&self) -> bool {
+        !self.resizing && !self.moving
+    }
+}
 
-    /// Does the window have a maximize button?
-    pub has_maximize_button: bool, // = true
+/// Data dragged out of a window, see [`Window::start_drag`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum DragData {
+    Text(String),
+    /// Paths of files (or directories) to hand to the target, e.g. a file manager.
+    Files(Vec<PathBuf>),
+}
 
-    /// Does the window have a drop shadow (only windows)
-    pub show_drop_shadow: bool, // = false
+/// Repaints a window while it is resized, called with the new client size. See [`Window::set_resize_paint_callback`].
+pub type ResizePaintCallback = Box<dyn FnMut(u32, u32)>;
 
-    /// Does the window have a border?
-    pub show_border: bool, // = true
+/// Holds a window's [`ResizePaintCallback`], so it can be replaced from inside itself and isn't called again by resizes it causes.
+#[derive(Default)]
+pub(crate) struct ResizePaintSlot {
+    callback: RefCell<Option<ResizePaintCallback>>,
+    /// Set while the callback runs.
+    running: Cell<bool>,
+    /// What the callback was replaced with while it ran (`Some(None)` when it was removed).
+    replacement: RefCell<Option<Option<ResizePaintCallback>>>,
+}
 
-    /// Does the window have a title bar?
-    pub show_title_bar: bool, // = true
+impl ResizePaintSlot {
+    pub fn set(&self, callback: Option<ResizePaintCallback>) {
+        if self.running.get() {
+            *self.replacement.borrow_mut() = Some(callback);
+        } else {
+            *self.callback.borrow_mut() = callback;
+        }
+    }
 
-    /// Is the window initially disabled?
-    pub initially_disabled: bool, // = false
+    pub fn call(&self, width: u32, height: u32) {
+        if self.running.get() {
+            return;
+        }
 
-    /// Is the window a dialog box? (only windows)
-    pub is_dialog_box: bool, // = false
+        let Some(mut callback) = self.callback.borrow_mut().take() else {
+            return;
+        };
 
-    /// Is the window initially minimized?
-    pub initially_minimized: bool, // = false
+        self.running.set(true);
+        callback(width, height);
+        self.running.set(false);
 
-    /// Is the window resizable?
-    pub resizable: bool, // = true
+        *self.callback.borrow_mut() = self
+            .replacement
+            .borrow_mut()
+            .take()
+            .unwrap_or(Some(callback));
+    }
+}
 
-    /// Does the window have a menu bar? (windows only)
-    pub has_system_menu: bool, // = false
+/// What a drop target may do with dragged data, see [`Window::start_drag`].
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum DragEffect {
+    #[default]
+    Copy,
+    /// The target takes the data over, the application should remove its copy once the drop succeeded.
+    Move,
+    Link,
+}
 
-    /// Is the window initially visible?
-    pub initially_visible: bool, // = true
+/// A part of a monitor's work area a window can snap to, see [`Window::snap`].
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum SnapRegion {
+    LeftHalf,
+    RightHalf,
+    TopHalf,
+    BottomHalf,
+    TopLeftQuarter,
+    TopRightQuarter,
+    BottomLeftQuarter,
+    BottomRightQuarter,
+    /// The whole work area, by maximizing the window.
+    Maximize,
 }
 
-impl Default for WindowAttributes {
-    fn default() -> Self {
-        Self {
-            title: None,
-            size: None,
-            position: None,
-            allow_close: true,
-            has_minimize_button: true,
-            has_maximize_button: true,
-            show_drop_shadow: false,
-            show_border: true,
-            show_title_bar: true,
-            initially_disabled: false,
-            is_dialog_box: false,
-            initially_minimized: false,
-            resizable: true,
-            has_system_menu: false,
-            initially_visible: true,
+impl SnapRegion {
+    /// The outer rectangle (x, y, width, height) of the region within a work area.
+    pub(crate) fn rect(
+        self,
+        work_area_position: WindowPosition,
+        work_area_size: (u32, u32),
+    ) -> (i32, i32, u32, u32) {
+        let (x, y) = (work_area_position.x, work_area_position.y);
+        let (width, height) = work_area_size;
+        let (half_width, half_height) = (width / 2, height / 2);
+        // odd sizes give the extra pixel to the right/bottom part, so the halves cover the whole work area
+        let (right_x, bottom_y) = (x + half_width as i32, y + half_height as i32);
+        let (right_width, bottom_height) = (width - half_width, height - half_height);
+
+        match self {
+            SnapRegion::LeftHalf => (x, y, half_width, height),
+            SnapRegion::RightHalf => (right_x, y, right_width, height),
+            SnapRegion::TopHalf => (x, y, width, half_height),
+            SnapRegion::BottomHalf => (x, bottom_y, width, bottom_height),
+            SnapRegion::TopLeftQuarter => (x, y, half_width, half_height),
+            SnapRegion::TopRightQuarter => (right_x, y, right_width, half_height),
+            SnapRegion::BottomLeftQuarter => (x, bottom_y, half_width, bottom_height),
+            SnapRegion::BottomRightQuarter => (right_x, bottom_y, right_width, bottom_height),
+            SnapRegion::Maximize => (x, y, width, height),
         }
     }
 }
 
-/// Representation of resolutions on systems. Supports both physical resolutions (exact pixels) and logical resolutions (based on dpi).
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub enum Resolution<T> {
-    /// Physical resolution (based on pixels).
-    #[allow(missing_docs)]
-    Physical { width: T, height: T },
-
-    /// Logical resolution (based on dpi).
-    #[allow(missing_docs)]
-    Logical { width: T, height: T },
+/// A point on the edge or in the center of a window, see [`Window::set_size_anchored`].
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
 }
 
+impl Anchor {
+    /// How far the top-left corner has to move when the window goes from `old_size` to `new_size`, so that the anchor point stays in place.
+    pub(crate) fn offset(self, old_size: (u32, u32), new_size: (u32, u32)) -> (i32, i32) {
+        let (fx, fy) = match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (1, 0),
+            Anchor::TopRight => (2, 0),
+            Anchor::Left => (0, 1),
+            Anchor::Center => (1, 1),
+            Anchor::Right => (2, 1),
+            Anchor::BottomLeft => (0, 2),
+            Anchor::Bottom => (1, 2),
+            Anchor::BottomRight => (2, 2),
+        };
 
-impl<T> Copy for Resolution<T> where T: Copy + Clone {}
+        // fx and fy are in halves of the size difference
+        let dx = (old_size.0 as i64 - new_size.0 as i64) * fx / 2;
+        let dy = (old_size.1 as i64 - new_size.1 as i64) * fy / 2;
+        (dx as i32, dy as i32)
+    }
+}
 
-/// Window position
+/// Window position, in physical pixels on the virtual desktop.
+///
+/// Converts into a physical [`Position`], so it can be used for [`WindowAttributes::position`]:
+///
+/// ```
+/// # use neuron_engine::os::window::{Position, WindowAttributes, WindowPosition};
+/// let position = WindowPosition::new(100, 50);
+/// assert_eq!((position.x(), position.y()), (100, 50));
+///
+/// let attributes = WindowAttributes {
+///     position: Some(position.into()),
+///     ..Default::default()
+/// };
+/// assert_eq!(attributes.position, Some(Position::Physical { x: 100, y: 50 }));
+/// ```
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
 pub struct WindowPosition {
     pub x: i32,
     pub y: i32,
 }
 
+impl WindowPosition {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn x(&self) -> i32 {
+        self.x
+    }
+
+    pub fn y(&self) -> i32 {
+        self.y
+    }
+}
+
+/// Position on the virtual desktop, either in physical pixels or in logical units (see [`Resolution`]).
+///
+/// Logical positions are converted with the scale factor of the monitor they end up on.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind"))]
+pub enum Position {
+    /// Physical position (in pixels).
+    #[allow(missing_docs)]
+    Physical { x: i32, y: i32 },
+
+    /// Logical position (based on dpi).
+    #[allow(missing_docs)]
+    Logical { x: f64, y: f64 },
+}
+
+impl Position {
+    pub fn is_physical(&self) -> bool {
+        matches!(self, Position::Physical { .. })
+    }
+
+    pub fn is_logical(&self) -> bool {
+        matches!(self, Position::Logical { .. })
+    }
+
+    /// Position in physical pixels, converting logical positions with `scale_factor`.
+    pub(crate) fn physical_position(&self, scale_factor: f64) -> (i32, i32) {
+        match *self {
+            Position::Physical { x, y } => (x, y),
+            Position::Logical { x, y } => (
+                (x * scale_factor).round() as i32,
+                (y * scale_factor).round() as i32,
+            ),
+        }
+    }
+}
+
+impl From<WindowPosition> for Position {
+    fn from(position: WindowPosition) -> Self {
+        Position::Physical {
+            x: position.x,
+            y: position.y,
+        }
+    }
+}
+
 /// Information about which window attributes are available
 #[derive(Clone, Debug)]
 #[allow(missing_docs)]
@@ -117,80 +1486,459 @@ pub struct SupportedWindowAttributes {
     pub has_close_button: bool,
     pub has_minimize_button: bool,
     pub has_maximize_button: bool,
-    pub show_drop_shadow: bool,
     pub show_border: bool,
     pub show_title_bar: bool,
     pub initially_disabled: bool,
-    pub is_dialog_box: bool,
     pub initially_minimized: bool,
     pub resizable: bool,
-    pub has_system_menu: bool,
+    pub min_size: bool,
+    pub max_size: bool,
+    pub skip_taskbar: bool,
+    pub show_in_taskbar: bool,
+    pub show_in_switcher: bool,
     pub initially_visible: bool,
+    pub initial_z_order: bool,
+    pub always_on_top: bool,
+    pub auto_hide_on_focus_loss: bool,
+    pub compositor_bypass: bool,
+    pub windows_show_drop_shadow: bool,
+    pub windows_is_dialog_box: bool,
+    pub windows_has_system_menu: bool,
+    pub x11_window_type: bool,
+    pub fullscreen: bool,
+    pub icon: bool,
+    pub transparent: bool,
+    pub background_color: bool,
+}
+
+impl SupportedWindowAttributes {
+    /// Which of the attributes `attributes` asks for (sets to something other than the default) would be ignored, see [`AttributeReport`].
+    ///
+    /// ```
+    /// # use neuron_engine::os::Platform;
+    /// # use neuron_engine::os::headless::HeadlessPlatform;
+    /// # use neuron_engine::os::window::WindowAttributes;
+    /// let mut attributes = WindowAttributes::builder().title("Overlay").build();
+    /// attributes.platform_specific.windows.show_drop_shadow = true;
+    ///
+    /// let report = HeadlessPlatform::new().supported_window_attributes().check(&attributes);
+    /// assert_eq!(report.ignored, ["title", "platform_specific.windows.show_drop_shadow"]);
+    /// ```
+    pub fn check(&self, attributes: &WindowAttributes) -> AttributeReport {
+        let windows = &attributes.platform_specific.windows;
+        let x11 = &attributes.platform_specific.x11;
+
+        let requested = [
+            (self.title, attributes.title.is_some(), "title"),
+            (self.size, attributes.size.is_some(), "size"),
+            (
+                self.size,
+                attributes.monitor_relative_size.is_some(),
+                "monitor_relative_size",
+            ),
+            (self.position, attributes.position.is_some(), "position"),
+            (self.position, attributes.centered, "centered"),
+            (
+                self.has_close_button,
+                !attributes.allow_close,
+                "allow_close",
+            ),
+            (
+                self.has_minimize_button,
+                !attributes.has_minimize_button,
+                "has_minimize_button",
+            ),
+            (
+                self.has_maximize_button,
+                !attributes.has_maximize_button,
+                "has_maximize_button",
+            ),
+            (self.show_border, !attributes.show_border, "show_border"),
+            (
+                self.show_title_bar,
+                !attributes.show_title_bar,
+                "show_title_bar",
+            ),
+            (
+                self.initially_disabled,
+                attributes.initially_disabled,
+                "initially_disabled",
+            ),
+            (
+                self.initially_minimized,
+                attributes.initially_minimized,
+                "initially_minimized",
+            ),
+            (self.resizable, !attributes.resizable, "resizable"),
+            (self.min_size, attributes.min_size.is_some(), "min_size"),
+            (self.max_size, attributes.max_size.is_some(), "max_size"),
+            (self.skip_taskbar, attributes.skip_taskbar, "skip_taskbar"),
+            (
+                self.show_in_taskbar,
+                !attributes.show_in_taskbar,
+                "show_in_taskbar",
+            ),
+            (
+                self.show_in_switcher,
+                !attributes.show_in_switcher,
+                "show_in_switcher",
+            ),
+            (
+                self.initially_visible,
+                !attributes.initially_visible,
+                "initially_visible",
+            ),
+            (
+                self.initial_z_order,
+                attributes.initial_z_order != InitialZOrder::NoChange,
+                "initial_z_order",
+            ),
+            (
+                self.always_on_top,
+                attributes.always_on_top,
+                "always_on_top",
+            ),
+            (
+                self.auto_hide_on_focus_loss,
+                attributes.auto_hide_on_focus_loss,
+                "auto_hide_on_focus_loss",
+            ),
+            (
+                self.fullscreen,
+                attributes.fullscreen.is_some(),
+                "fullscreen",
+            ),
+            (self.icon, attributes.icon.is_some(), "icon"),
+            (self.transparent, attributes.transparent, "transparent"),
+            (
+                self.background_color,
+                attributes.background_color.is_some(),
+                "background_color",
+            ),
+            (
+                self.compositor_bypass,
+                attributes.compositor_bypass != CompositorBypass::Default,
+                "compositor_bypass",
+            ),
+            (
+                self.windows_show_drop_shadow,
+                windows.show_drop_shadow,
+                "platform_specific.windows.show_drop_shadow",
+            ),
+            (
+                self.windows_is_dialog_box,
+                windows.is_dialog_box,
+                "platform_specific.windows.is_dialog_box",
+            ),
+            (
+                self.windows_has_system_menu,
+                windows.has_system_menu,
+                "platform_specific.windows.has_system_menu",
+            ),
+            (
+                self.x11_window_type,
+                x11.window_type.is_some(),
+                "platform_specific.x11.window_type",
+            ),
+        ];
+
+        AttributeReport {
+            ignored: requested
+                .into_iter()
+                .filter(|(supported, requested, _)| *requested && !supported)
+                .map(|(_, _, name)| name)
+                .collect(),
+        }
+    }
+}
+
+/// Requested window attributes the platform doesn't support, see [`SupportedWindowAttributes::check`] and [`Engine::create_window_with_report`](crate::Engine::create_window_with_report).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AttributeReport {
+    /// Paths of the ignored [`WindowAttributes`] fields, like `"transparent"` or `"platform_specific.windows.show_drop_shadow"`.
+    pub ignored: Vec<&'static str>,
+}
+
+impl AttributeReport {
+    /// Whether every requested attribute is supported.
+    pub fn is_empty(&self) -> bool {
+        self.ignored.is_empty()
+    }
+}
+
+/// How a renderer presents frames to the window's surface (mirrors the usual Vulkan/wgpu present modes).
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum PresentMode {
+    /// Wait for vertical blank, never tears.
+    Fifo,
+    /// Like `Fifo`, but presents immediately if a vertical blank was missed.
+    FifoRelaxed,
+    /// Replace the queued frame with the newest one, never tears.
+    Mailbox,
+    /// Present immediately, may tear.
+    Immediate,
+}
+
+/// How a window covers a monitor, see [`Window::set_fullscreen`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fullscreen {
+    /// Cover the monitor with an undecorated window, without changing the video mode. `None` uses the monitor the window is on.
+    Borderless(Option<MonitorHandle>),
+    /// Switch the monitor the mode belongs to (see [`MonitorHandle::video_modes`]) over to the mode, and cover it.
+    Exclusive(VideoMode),
+}
+
+/// How a window holds on to the cursor, see [`Window::set_cursor_grab`].
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+pub enum CursorGrabMode {
+    /// The cursor moves freely.
+    #[default]
+    None,
+    /// The cursor can't leave the client area, like [`Window::set_cursor_confined`].
+    Confined,
+    /// The cursor is held at the center of the client area and movement is reported as [`Event::MouseMotion`](crate::event::Event::MouseMotion) instead, for first-person camera controls.
+    Locked,
+}
+
+/// How urgently a window wants attention, see [`Window::request_user_attention`].
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum UserAttention {
+    /// Keep signalling until the window is focused (e.g. the taskbar button flashes continuously on Windows).
+    Critical,
+    /// Signal once (the taskbar button flashes once on Windows and then stays highlighted).
+    Informational,
+}
+
+/// Standard cursor shapes, see [`Window::set_cursor_icon`].
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+pub enum CursorIcon {
+    /// The platform's normal arrow.
+    #[default]
+    Default,
+    /// A hand, for links and other clickable things.
+    Pointer,
+    /// An I-beam, for selectable or editable text.
+    Text,
+    Crosshair,
+    /// Something can be moved in any direction.
+    Move,
+    /// The action under the cursor isn't possible.
+    NotAllowed,
+    /// Resizing horizontally (east-west).
+    EwResize,
+    /// Resizing vertically (north-south).
+    NsResize,
+    /// Resizing along the diagonal from the top-right to the bottom-left corner.
+    NeswResize,
+    /// Resizing along the diagonal from the top-left to the bottom-right corner.
+    NwseResize,
+    /// The application is busy and can't be interacted with.
+    Wait,
+    /// The application is busy, but can still be interacted with.
+    Progress,
+    Help,
+}
+
+/// Whether a fullscreen window should skip the compositor and be scanned out directly.
+///
+/// Bypassing the compositor removes a frame of latency in games. On X11 this sets `_NET_WM_BYPASS_COMPOSITOR`, on Windows the OS already does this for fullscreen flip-model swapchains and the hint is ignored.
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CompositorBypass {
+    /// Let the compositor decide.
+    #[default]
+    Default,
+    /// Ask the compositor to unredirect the window.
+    Bypass,
+    /// Ask the compositor to keep compositing the window (e.g. for a fullscreen video player with overlays).
+    NoBypass,
+}
+
+/// Stacking order of a newly created window, see [`WindowAttributes::initial_z_order`].
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InitialZOrder {
+    /// Above all other (non-topmost) windows.
+    Top,
+    /// Below all other windows, for wallpapers and windows preloaded in the background.
+    Bottom,
+    /// Wherever the OS or window manager places new windows (usually on top).
+    #[default]
+    NoChange,
 }
 
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
 #[repr(transparent)]
 pub struct WindowId(u32);
 
-// TODO: restructure this so that some of the stuff here can be Send (i.e. create an async and multithreaded capable window management system which maintains the common os requirement of the main thread being the only one able to validly interact with the actual os calls).
+/// Where a window is in its lifecycle, see [`WindowManager::window_status`].
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum WindowStatus {
+    /// The window is open.
+    Active,
+    /// The window is closing, but there are still outside references to it.
+    Dying,
+    /// The window has finished closing.
+    Closed,
+    /// The id was never handed out by this window manager (e.g. it comes from another engine).
+    Unknown,
+}
 
+/// Window ids are handed out process-wide, so they stay unique between engines sharing a platform.
+static NEXT_WINDOW_ID: AtomicU32 = AtomicU32::new(0);
+
+/// Tracks the windows of an engine through their lifecycle, plus the registered accelerators.
+///
+/// The manager can be shared between threads (`Arc<WindowManager>` is `Send + Sync`), but the windows themselves are bound to the OS thread that created the manager.
+/// These methods hand out, call into or drop windows and panic on any other thread:
+/// [`create_window`](Self::create_window), [`update`](Self::update), [`try_finish_closing_window`](Self::try_finish_closing_window), [`destroy_all_windows`](Self::destroy_all_windows), [`get_window`](Self::get_window), [`for_each_active`](Self::for_each_active), [`all_windows_occluded`](Self::all_windows_occluded) and [`debug_dump`](Self::debug_dump) (as well as `Debug`/`Display`).
+///
+/// Everything else only looks at window ids or accelerators and works from any thread.
 pub struct WindowManager {
-    window_id_counter: AtomicU32,
-    window_sets: RefCell<WindowSets>, // interior mutability
+    window_sets: Mutex<WindowSets>,
+    /// Receives [`Event::WindowDestroyed`].
+    event_queue: Arc<EventQueue>,
+    accelerators: Mutex<HashMap<Accelerator, u16>>,
+    /// Bumped whenever the accelerators change, so platforms can cache what they build from them.
+    accelerators_generation: AtomicU64,
+    /// The thread the manager was created on, the only one allowed to touch the windows.
+    main_thread: ThreadId,
 }
 
+// SAFETY: the windows are the only part that isn't thread-safe, and every method that clones, calls or drops one asserts that it runs on `main_thread` (so does `Drop`).
+unsafe impl Send for WindowManager {}
+unsafe impl Sync for WindowManager {}
+
 struct WindowSets {
     windows: HashMap<WindowId, Arc<dyn Window>>,
     active_windows: HashSet<WindowId>,
     dying_windows: HashSet<WindowId>,
+    /// Windows of this manager that finished closing (ids are shared between engines, so they can't tell on their own).
+    closed_windows: HashSet<WindowId>,
+}
+
+impl Default for WindowManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl WindowManager {
+    /// A manager with an event queue of its own, which nothing reads: its [`Event::WindowDestroyed`] events are dropped with it.
     pub fn new() -> Self {
+        Self::with_event_queue(Arc::new(EventQueue::new()))
+    }
+
+    /// A manager that reports [`Event::WindowDestroyed`] in `event_queue`, like the one of an [`Engine`](crate::Engine).
+    pub(crate) fn with_event_queue(event_queue: Arc<EventQueue>) -> Self {
         Self {
-            window_id_counter: AtomicU32::new(0),
-            window_sets: RefCell::new(WindowSets {
+            window_sets: Mutex::new(WindowSets {
                 windows: HashMap::new(),
                 active_windows: HashSet::new(),
                 dying_windows: HashSet::new(),
+                closed_windows: HashSet::new(),
             }),
+            event_queue,
+            accelerators: Mutex::new(HashMap::new()),
+            accelerators_generation: AtomicU64::new(0),
+            main_thread: thread::current().id(),
         }
     }
 
+    fn assert_main_thread(&self, operation: &str) {
+        assert!(
+            thread::current().id() == self.main_thread,
+            "{} must be called on the thread that created the window manager",
+            operation
+        );
+    }
+
+    /// Poisoning only means a panic happened elsewhere while the sets were locked, every update leaves them consistent.
+    fn sets(&self) -> MutexGuard<'_, WindowSets> {
+        self.window_sets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn accelerator_map(&self) -> MutexGuard<'_, HashMap<Accelerator, u16>> {
+        self.accelerators
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
     pub fn create_window(
         &self,
-        window_attributes: WindowAttributes,
+        mut window_attributes: WindowAttributes,
         platform: &Arc<dyn Platform>,
+        inputs: &OsLoopInputs,
     ) -> anyhow::Result<(WindowId, Weak<dyn Window>)> {
-        let id = WindowId(self.window_id_counter.fetch_add(1, Ordering::SeqCst));
+        debug_assert_main_thread(platform.as_ref(), "WindowManager::create_window");
+        self.assert_main_thread("WindowManager::create_window");
+
+        if let Some(relative_size) = window_attributes.monitor_relative_size {
+            if let Some(monitor) = platform.primary_monitor() {
+                window_attributes.size = Some(relative_size.resolve(&monitor));
+            } else {
+                debug!(
+                    "No monitor available to resolve {:?}, falling back to the window size",
+                    relative_size
+                );
+            }
+        }
+
+        if window_attributes.centered {
+            match (platform.primary_monitor(), window_attributes.size) {
+                (Some(monitor), Some(size)) => {
+                    window_attributes.position = Some(monitor.center(size));
+                }
+                _ => debug!(
+                    "Can't center a window without a size and a monitor, falling back to the window position"
+                ),
+            }
+        }
 
-        let window = platform.create_window(window_attributes, id)?;
+        let id = WindowId(NEXT_WINDOW_ID.fetch_add(1, Ordering::SeqCst));
+
+        let window = platform.create_window(window_attributes, id, inputs)?;
 
         let weakref = Arc::downgrade(&window);
 
-        _ = self.window_sets.borrow_mut().active_windows.insert(id);
-        _ = self.window_sets.borrow_mut().windows.insert(id, window);
+        let mut sets = self.sets();
+        _ = sets.active_windows.insert(id);
+        _ = sets.windows.insert(id, window);
+        drop(sets);
 
         Ok((id, weakref))
     }
 
     pub fn begin_closing_window(&self, id: WindowId) {
-        self.window_sets.borrow_mut().active_windows.remove(&id);
-        self.window_sets.borrow_mut().dying_windows.insert(id);
+        let mut sets = self.sets();
+        sets.active_windows.remove(&id);
+        sets.dying_windows.insert(id);
+        drop(sets);
         debug!("Beginning process for closing window: {:?}", id);
     }
 
     pub fn update(&self) {
-        let window_ids = self.window_sets.borrow().dying_windows.iter().cloned().collect::<Vec<_>>();
+        self.assert_main_thread("WindowManager::update");
+        let window_ids = self
+            .sets()
+            .dying_windows
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
         for window_id in window_ids {
             self.try_finish_closing_window(window_id);
         }
     }
 
     pub fn try_finish_closing_window(&self, id: WindowId) -> bool {
-        if self.window_sets.borrow().dying_windows.contains(&id) {
-            if let Some(window) = self.window_sets.borrow().windows.get(&id) {
-                if Arc::strong_count(&window) > 1 {
+        self.assert_main_thread("WindowManager::try_finish_closing_window");
+
+        let mut sets = self.sets();
+        if sets.dying_windows.contains(&id) {
+            if let Some(window) = sets.windows.get(&id) {
+                if Arc::strong_count(window) > 1 {
                     debug!(
                         "Cannot finish close window {:?}: There are still outside references to this window.",
                         id
@@ -201,8 +1949,13 @@ impl WindowManager {
                 return true;
             }
 
-            self.window_sets.borrow_mut().dying_windows.remove(&id);
-            self.window_sets.borrow_mut().windows.remove(&id);
+            sets.dying_windows.remove(&id);
+            let window = sets.windows.remove(&id);
+            sets.closed_windows.insert(id);
+            // dropping the window destroys it, which can call back into the window manager (e.g. the window procedure on Windows), so the lock must be released first
+            drop(sets);
+            drop(window);
+            self.event_queue.push(Event::WindowDestroyed { window: id });
 
             debug!("Finished process for closing window: {:?}", id);
         }
@@ -210,25 +1963,469 @@ impl WindowManager {
         true
     }
 
+    /// Let go of every window that is still open or closing, and report each with [`Event::WindowDestroyed`]. Windows are destroyed right away unless the application still holds an `Arc` to them, then they go with the last one.
+    ///
+    /// This is what engine shutdown does. Only [`Engine::run`](crate::Engine::run) delivers these events: it calls this before returning and hands them to its handler.
+    /// Dropping the manager calls it for whatever is left, but those events go to a queue nobody drains anymore.
+    pub fn destroy_all_windows(&self) {
+        self.assert_main_thread("WindowManager::destroy_all_windows");
+
+        let mut sets = self.sets();
+        let windows = mem::take(&mut sets.windows);
+        sets.active_windows.clear();
+        sets.dying_windows.clear();
+        sets.closed_windows.extend(windows.keys().copied());
+        drop(sets);
+
+        for (id, window) in windows {
+            drop(window);
+            self.event_queue.push(Event::WindowDestroyed { window: id });
+            debug!("Destroyed window on shutdown: {:?}", id);
+        }
+    }
+
     pub fn get_window(&self, id: WindowId) -> Option<Arc<dyn Window>> {
-        if self.window_sets.borrow().active_windows.contains(&id) {
-            self.window_sets.borrow().windows.get(&id).cloned()
+        self.assert_main_thread("WindowManager::get_window");
+
+        let sets = self.sets();
+        if sets.active_windows.contains(&id) {
+            sets.windows.get(&id).cloned()
         } else {
             None
         }
     }
 
+    /// Ids of the active windows (not the ones already closing), oldest first.
+    pub fn active_window_ids(&self) -> Vec<WindowId> {
+        let mut ids = self
+            .sets()
+            .active_windows
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+        ids.sort_by_key(|id| id.0);
+        ids
+    }
+
+    /// Call `f` for every active window, oldest first.
+    ///
+    /// No lock is held while `f` runs, so it can create or close windows. A window closed by an earlier call is skipped, windows created during the iteration are not visited.
+    ///
+    /// ```no_run
+    /// # use neuron_engine::Engine;
+    /// # use neuron_engine::os::window::WindowAttributes;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let engine = Engine::new()?;
+    /// engine.create_window(WindowAttributes::default())?;
+    /// engine.create_window(WindowAttributes::default())?;
+    ///
+    /// engine.window_manager().for_each_active(|id, window| {
+    ///     _ = window.set_title(&format!("Window {:?}", id));
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn for_each_active<F: FnMut(WindowId, &Arc<dyn Window>)>(&self, mut f: F) {
+        self.assert_main_thread("WindowManager::for_each_active");
+
+        let windows = {
+            let sets = self.sets();
+            let mut windows = sets
+                .active_windows
+                .iter()
+                .filter_map(|id| Some((*id, sets.windows.get(id)?.clone())))
+                .collect::<Vec<_>>();
+            windows.sort_by_key(|(id, _)| id.0);
+            windows
+        };
+
+        for (id, window) in &windows {
+            if self.is_window_active(*id) {
+                f(*id, window);
+            }
+        }
+    }
+
     pub fn is_window_active(&self, id: WindowId) -> bool {
-        self.window_sets.borrow().active_windows.contains(&id)
+        self.sets().active_windows.contains(&id)
     }
 
     /// Is a window alive? (meaning: is a window either active or dying, but not dead)
     pub fn is_window_alive(&self, id: WindowId) -> bool {
-        self.window_sets.borrow().active_windows.contains(&id)
-            || self.window_sets.borrow().dying_windows.contains(&id)
+        let sets = self.sets();
+        sets.active_windows.contains(&id) || sets.dying_windows.contains(&id)
     }
 
     pub fn is_window_dying(&self, id: WindowId) -> bool {
-        self.window_sets.borrow().dying_windows.contains(&id)
+        self.sets().dying_windows.contains(&id)
+    }
+
+    pub fn window_status(&self, id: WindowId) -> WindowStatus {
+        let sets = self.sets();
+        if sets.active_windows.contains(&id) {
+            WindowStatus::Active
+        } else if sets.dying_windows.contains(&id) {
+            WindowStatus::Dying
+        } else if sets.closed_windows.contains(&id) {
+            WindowStatus::Closed
+        } else {
+            WindowStatus::Unknown
+        }
+    }
+
+    /// Register a keyboard shortcut for all windows. Pressing it reports [`Event::AcceleratorTriggered`](crate::event::Event::AcceleratorTriggered) with `id` instead of the key press.
+    ///
+    /// Registering a chord again replaces its id.
+    pub fn register_accelerator(&self, chord: Accelerator, id: u16) {
+        self.accelerator_map().insert(chord, id);
+        self.accelerators_generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn unregister_accelerator(&self, chord: Accelerator) {
+        if self.accelerator_map().remove(&chord).is_some() {
+            self.accelerators_generation.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[cfg_attr(windows, allow(dead_code))]
+    pub(crate) fn accelerator_id(&self, chord: &Accelerator) -> Option<u16> {
+        self.accelerator_map().get(chord).copied()
+    }
+
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub(crate) fn accelerators(&self) -> Vec<(Accelerator, u16)> {
+        self.accelerator_map()
+            .iter()
+            .map(|(chord, id)| (*chord, *id))
+            .collect()
+    }
+
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub(crate) fn accelerators_generation(&self) -> u64 {
+        self.accelerators_generation.load(Ordering::SeqCst)
+    }
+
+    /// Are there active windows, and are all of them occluded?
+    pub fn all_windows_occluded(&self) -> bool {
+        self.assert_main_thread("WindowManager::all_windows_occluded");
+
+        let windows = {
+            let sets = self.sets();
+            sets.active_windows
+                .iter()
+                .filter_map(|id| sets.windows.get(id).cloned())
+                .collect::<Vec<_>>()
+        };
+        !windows.is_empty() && windows.iter().all(|window| window.is_occluded())
+    }
+    /// A point-in-time description of every window (id, status, native handle, size, position, visibility and interaction state) and the registered accelerators, for bug reports.
+    ///
+    /// Same as formatting the window manager with `{}`.
+    pub fn debug_dump(&self) -> String {
+        self.to_string()
+    }
+
+    /// Copy out what the dump shows, so no lock is held while formatting (or while the windows are queried).
+    fn snapshot(&self) -> (Vec<WindowSnapshot>, usize) {
+        self.assert_main_thread("WindowManager::debug_dump");
+
+        let windows = {
+            let sets = self.sets();
+            let mut windows = sets
+                .windows
+                .iter()
+                .map(|(id, window)| (*id, sets.dying_windows.contains(id), window.clone()))
+                .collect::<Vec<_>>();
+            windows.sort_by_key(|(id, _, _)| id.0);
+            windows
+        };
+
+        let snapshots = windows
+            .into_iter()
+            .map(|(id, dying, window)| WindowSnapshot {
+                id,
+                status: if dying {
+                    WindowStatus::Dying
+                } else {
+                    WindowStatus::Active
+                },
+                handle: window.window_handle().ok().map(|handle| handle.as_raw()),
+                size: window.inner_size(),
+                position: window.position(),
+                occluded: window.is_occluded(),
+                interaction: window.interaction_state(),
+            })
+            .collect();
+
+        (snapshots, self.accelerator_map().len())
+    }
+}
+
+impl Drop for WindowManager {
+    fn drop(&mut self) {
+        if thread::current().id() != self.main_thread {
+            // the windows can't be destroyed from here, leaking them is the only sound option
+            let sets = self
+                .window_sets
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner);
+            error!(
+                "WindowManager dropped off its main thread, leaking {} window(s)",
+                sets.windows.len()
+            );
+            mem::forget(mem::take(&mut sets.windows));
+        } else {
+            self.destroy_all_windows();
+        }
+    }
+}
+
+/// One window as shown by [`WindowManager::debug_dump`].
+#[derive(Debug)]
+struct WindowSnapshot {
+    id: WindowId,
+    status: WindowStatus,
+    handle: Option<RawWindowHandle>,
+    size: Resolution<u32>,
+    position: WindowPosition,
+    occluded: bool,
+    interaction: InteractionState,
+}
+
+impl fmt::Debug for WindowManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (windows, accelerators) = self.snapshot();
+        f.debug_struct("WindowManager")
+            .field("windows", &windows)
+            .field("accelerators", &accelerators)
+            .finish()
+    }
+}
+
+impl fmt::Display for WindowManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (windows, accelerators) = self.snapshot();
+        writeln!(
+            f,
+            "{} window(s), {} accelerator(s)",
+            windows.len(),
+            accelerators
+        )?;
+
+        for window in windows {
+            write!(f, "  {:?} {:?}", window.id, window.status)?;
+            match window.handle {
+                Some(handle) => write!(f, " handle={:?}", handle)?,
+                None => write!(f, " handle=unavailable")?,
+            }
+            writeln!(
+                f,
+                " size={}x{} position=({}, {}) occluded={} resizing={} moving={}",
+                window.size.width(),
+                window.size.height(),
+                window.position.x,
+                window.position.y,
+                window.occluded,
+                window.interaction.resizing,
+                window.interaction.moving
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A monitor whose work area starts at the origin.
+    fn monitor(work_area_size: (u32, u32), scale_factor: f64) -> MonitorHandle {
+        MonitorHandle {
+            name: "test".to_string(),
+            position: WindowPosition::default(),
+            size: work_area_size,
+            work_area_position: WindowPosition::default(),
+            work_area_size,
+            scale_factor,
+            bit_depth: 32,
+            hdr_enabled: false,
+            video_modes: Vec::new(),
+        }
+    }
+
+    fn physical(width: u32, height: u32) -> Resolution<u32> {
+        Resolution::Physical { width, height }
+    }
+
+    #[test]
+    fn fraction_of_work_area() {
+        let monitor = monitor((1920, 1040), 1.0);
+
+        let size = MonitorRelativeSize::FractionOfWorkArea {
+            width: 0.5,
+            height: 0.75,
+        };
+        assert_eq!(size.resolve(&monitor), physical(960, 780));
+
+        // clamped to 0.1..=1.0, then grown to the minimum size
+        let size = MonitorRelativeSize::FractionOfWorkArea {
+            width: 0.0,
+            height: 2.0,
+        };
+        assert_eq!(size.resolve(&monitor), physical(320, 1040));
+
+        let size = MonitorRelativeSize::FractionOfWorkArea {
+            width: f64::NAN,
+            height: f64::INFINITY,
+        };
+        assert_eq!(size.resolve(&monitor), physical(1920, 1040));
+    }
+
+    #[test]
+    fn fraction_of_small_work_area() {
+        // the minimum size doesn't make the window larger than the work area
+        let size = MonitorRelativeSize::FractionOfWorkArea {
+            width: 0.5,
+            height: 0.5,
+        };
+        assert_eq!(size.resolve(&monitor((300, 200), 1.0)), physical(300, 200));
+    }
+
+    #[test]
+    fn fraction_of_scaled_work_area() {
+        let monitor = monitor((3840, 2080), 2.0);
+
+        let size = MonitorRelativeSize::FractionOfWorkArea {
+            width: 0.5,
+            height: 0.5,
+        };
+        assert_eq!(size.resolve(&monitor), physical(1920, 1040));
+
+        // the minimum size is logical
+        let size = MonitorRelativeSize::FractionOfWorkArea {
+            width: 0.1,
+            height: 0.1,
+        };
+        assert_eq!(size.resolve(&monitor), physical(640, 480));
+    }
+
+    #[test]
+    fn fit_content() {
+        let monitor = monitor((1920, 1040), 1.0);
+
+        // content that fits keeps its size
+        let size = MonitorRelativeSize::FitContent {
+            width: 800,
+            height: 600,
+            max_fraction: 0.9,
+        };
+        assert_eq!(size.resolve(&monitor), physical(800, 600));
+
+        // larger content shrinks uniformly until the limiting dimension fits
+        let size = MonitorRelativeSize::FitContent {
+            width: 4000,
+            height: 2000,
+            max_fraction: 0.5,
+        };
+        assert_eq!(size.resolve(&monitor), physical(960, 480));
+
+        // fractions above 1.0 (or not finite) allow the whole work area
+        for max_fraction in [2.0, f64::NAN] {
+            let size = MonitorRelativeSize::FitContent {
+                width: 3000,
+                height: 1000,
+                max_fraction,
+            };
+            assert_eq!(size.resolve(&monitor), physical(1920, 640));
+        }
+    }
+
+    #[test]
+    fn fit_content_scaled() {
+        let monitor = monitor((2880, 1560), 1.5);
+
+        let size = MonitorRelativeSize::FitContent {
+            width: 800,
+            height: 600,
+            max_fraction: 1.0,
+        };
+        assert_eq!(size.resolve(&monitor), physical(1200, 900));
+
+        let size = MonitorRelativeSize::FitContent {
+            width: 800,
+            height: 600,
+            max_fraction: 0.5,
+        };
+        assert_eq!(size.resolve(&monitor), physical(1040, 780));
+    }
+
+    #[test]
+    fn resolution_ordering() {
+        assert!(physical(800, 600) < physical(1920, 1080));
+        assert!(physical(800, 1080) <= physical(1920, 1080));
+        assert!(physical(1920, 1080) > physical(800, 1080));
+
+        // equal sizes are both <= and >=, but neither < nor >
+        assert_eq!(
+            physical(800, 600).partial_cmp(&physical(800, 600)),
+            Some(cmp::Ordering::Equal)
+        );
+        assert!(physical(800, 600) <= physical(800, 600));
+        assert!(physical(800, 600) >= physical(800, 600));
+        assert!(!physical(800, 600).lt(&physical(800, 600)));
+
+        // wider but shorter doesn't fit either way
+        assert_eq!(physical(1920, 600).partial_cmp(&physical(800, 1080)), None);
+        assert!(!physical(1920, 600).lt(&physical(800, 1080)));
+        assert!(!physical(1920, 600).gt(&physical(800, 1080)));
+    }
+
+    #[test]
+    fn physical_and_logical_are_incomparable() {
+        let logical = Resolution::Logical {
+            width: 800,
+            height: 600,
+        };
+
+        // even if the numbers are equal or clearly smaller, since the scale factor isn't known
+        assert_eq!(logical.partial_cmp(&physical(800, 600)), None);
+        assert_ne!(logical, physical(800, 600));
+        assert!(!logical.le(&physical(1920, 1080)));
+        assert!(!logical.ge(&physical(1, 1)));
+    }
+
+    #[test]
+    fn resolution_arithmetic() {
+        assert_eq!(physical(640, 480).scale(2), physical(1280, 960));
+        assert_eq!(physical(640, 480).area(), 307_200);
+
+        // scaling keeps the variant
+        let logical = Resolution::Logical {
+            width: 1280.0,
+            height: 720.0,
+        };
+        assert_eq!(
+            logical.scale(1.25),
+            Resolution::Logical {
+                width: 1600.0,
+                height: 900.0
+            }
+        );
+
+        // integer sizes scale through floats, rounding is up to the caller
+        let scaled = physical(333, 201)
+            .map(f64::from)
+            .scale(1.5)
+            .map(|v| v.round() as u32);
+        assert_eq!(scaled, physical(500, 302));
+
+        // logical sizes are rounded to the nearest physical pixel
+        let logical = Resolution::Logical {
+            width: 333,
+            height: 201,
+        };
+        assert_eq!(logical.physical_size(1.5), (500, 302));
+        assert_eq!(logical.physical_size(1.25), (416, 251));
     }
 }
@@ -0,0 +1,97 @@
+use crate::os::accelerator::{Accelerator, AcceleratorKey};
+use crate::os::window::WindowManager;
+use crate::os::windows::WindowsPlatform;
+use log::debug;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    VIRTUAL_KEY, VK_BACK, VK_DELETE, VK_DOWN, VK_END, VK_ESCAPE, VK_F1, VK_HOME, VK_INSERT,
+    VK_LEFT, VK_NEXT, VK_PRIOR, VK_RETURN, VK_RIGHT, VK_SPACE, VK_TAB, VK_UP,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    ACCEL, ACCEL_VIRT_FLAGS, CreateAcceleratorTableW, DestroyAcceleratorTable, FALT, FCONTROL,
+    FSHIFT, FVIRTKEY, HACCEL,
+};
+
+impl WindowsPlatform {
+    /// The accelerator table for the window manager's accelerators, rebuilt when they changed since the last call. `None` if there are none.
+    pub(super) fn accelerator_table(&self, window_manager: &WindowManager) -> Option<HACCEL> {
+        let generation = window_manager.accelerators_generation();
+        if let Some((built, table)) = self.accelerator_table.get()
+            && built == generation
+        {
+            return table;
+        }
+
+        self.destroy_accelerator_table();
+
+        let entries = window_manager
+            .accelerators()
+            .into_iter()
+            .map(|(chord, id)| accel(&chord, id))
+            .collect::<Vec<_>>();
+
+        let table = if entries.is_empty() {
+            None
+        } else {
+            match unsafe { CreateAcceleratorTableW(&entries) } {
+                Ok(table) => Some(table),
+                Err(e) => {
+                    debug!("Failed to create the accelerator table: {}", e);
+                    None
+                }
+            }
+        };
+
+        self.accelerator_table.set(Some((generation, table)));
+        table
+    }
+
+    pub(super) fn destroy_accelerator_table(&self) {
+        if let Some((_, Some(table))) = self.accelerator_table.take() {
+            unsafe {
+                _ = DestroyAcceleratorTable(table);
+            }
+        }
+    }
+}
+
+fn accel(chord: &Accelerator, id: u16) -> ACCEL {
+    let mut flags = FVIRTKEY.0;
+    if chord.modifiers.ctrl {
+        flags |= FCONTROL.0;
+    }
+    if chord.modifiers.shift {
+        flags |= FSHIFT.0;
+    }
+    if chord.modifiers.alt {
+        flags |= FALT.0;
+    }
+
+    ACCEL {
+        fVirt: ACCEL_VIRT_FLAGS(flags),
+        key: virtual_key(chord.key).0,
+        cmd: id,
+    }
+}
+
+fn virtual_key(key: AcceleratorKey) -> VIRTUAL_KEY {
+    match key {
+        // letters and digits use their (uppercase) ASCII codes
+        AcceleratorKey::Char(c) => VIRTUAL_KEY(c as u16),
+        AcceleratorKey::Function(number) => VIRTUAL_KEY(VK_F1.0 + number as u16 - 1),
+        AcceleratorKey::Enter => VK_RETURN,
+        AcceleratorKey::Escape => VK_ESCAPE,
+        AcceleratorKey::Tab => VK_TAB,
+        AcceleratorKey::Space => VK_SPACE,
+        AcceleratorKey::Backspace => VK_BACK,
+        AcceleratorKey::Delete => VK_DELETE,
+        AcceleratorKey::Insert => VK_INSERT,
+        AcceleratorKey::Home => VK_HOME,
+        AcceleratorKey::End => VK_END,
+        AcceleratorKey::PageUp => VK_PRIOR,
+        AcceleratorKey::PageDown => VK_NEXT,
+        AcceleratorKey::Left => VK_LEFT,
+        AcceleratorKey::Right => VK_RIGHT,
+        AcceleratorKey::Up => VK_UP,
+        AcceleratorKey::Down => VK_DOWN,
+    }
+}
@@ -0,0 +1,185 @@
+use crate::os::windows::WindowsPlatform;
+use anyhow::bail;
+use log::debug;
+use widestring::U16CString;
+use windows::Win32::Foundation::{GlobalFree, HANDLE, HGLOBAL};
+use windows::Win32::System::DataExchange::{
+    CloseClipboard, EmptyClipboard, GetClipboardData, OpenClipboard, RegisterClipboardFormatW,
+    SetClipboardData,
+};
+use windows::Win32::System::Memory::{
+    GMEM_MOVEABLE, GlobalAlloc, GlobalLock, GlobalSize, GlobalUnlock,
+};
+use windows::Win32::System::Ole::{CF_DIB, CF_UNICODETEXT};
+use windows::core::PCWSTR;
+
+/// Size of the `BITMAPFILEHEADER` that starts a BMP file but isn't part of `CF_DIB` data.
+const BMP_FILE_HEADER_SIZE: usize = 14;
+
+impl WindowsPlatform {
+    /// See [`Platform::clipboard_data`](crate::os::Platform::clipboard_data).
+    pub(super) fn read_clipboard(&self, mime: &str) -> Option<Vec<u8>> {
+        let format = clipboard_format(mime)?;
+        let owner = self.message_window().ok()?;
+
+        let data = unsafe {
+            if let Err(e) = OpenClipboard(owner) {
+                debug!("Failed to open the clipboard: {}", e);
+                return None;
+            }
+
+            let data = GetClipboardData(format)
+                .ok()
+                .and_then(|handle| global_bytes(HGLOBAL(handle.0)));
+            _ = CloseClipboard();
+            data?
+        };
+
+        Some(match format {
+            f if f == CF_UNICODETEXT.0 as u32 => {
+                let text = data
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .take_while(|c| *c != 0)
+                    .collect::<Vec<_>>();
+                String::from_utf16_lossy(&text).into_bytes()
+            }
+            f if f == CF_DIB.0 as u32 => bmp_from_dib(data)?,
+            _ => data,
+        })
+    }
+
+    /// See [`Platform::set_clipboard_data`](crate::os::Platform::set_clipboard_data).
+    pub(super) fn write_clipboard(&self, entries: &[(String, Vec<u8>)]) -> anyhow::Result<()> {
+        let owner = self.message_window()?;
+        let mut failed = Vec::new();
+
+        unsafe {
+            if let Err(e) = OpenClipboard(owner) {
+                bail!("Failed to open the clipboard: {}", e);
+            }
+
+            _ = EmptyClipboard();
+
+            for (mime, data) in entries {
+                let Some(format) = clipboard_format(mime) else {
+                    failed.push(mime.as_str());
+                    continue;
+                };
+
+                let data = match format {
+                    f if f == CF_UNICODETEXT.0 as u32 => String::from_utf8_lossy(data)
+                        .encode_utf16()
+                        .chain([0])
+                        .flat_map(u16::to_le_bytes)
+                        .collect(),
+                    f if f == CF_DIB.0 as u32
+                        && data.starts_with(b"BM")
+                        && data.len() > BMP_FILE_HEADER_SIZE =>
+                    {
+                        data[BMP_FILE_HEADER_SIZE..].to_vec()
+                    }
+                    _ => data.clone(),
+                };
+
+                let Ok(memory) = global_alloc(&data) else {
+                    failed.push(mime.as_str());
+                    continue;
+                };
+
+                // the clipboard owns the memory from here on, unless it refused it
+                if let Err(e) = SetClipboardData(format, HANDLE(memory.0)) {
+                    debug!("Failed to set clipboard data for {}: {}", mime, e);
+                    _ = GlobalFree(memory);
+                    failed.push(mime.as_str());
+                }
+            }
+
+            _ = CloseClipboard();
+        }
+
+        if !failed.is_empty() {
+            bail!("Failed to put {} on the clipboard", failed.join(", "));
+        }
+
+        Ok(())
+    }
+}
+
+/// The clipboard format for a MIME type: text and BMP images map to the standard formats, anything else to a format registered under the MIME type's name.
+fn clipboard_format(mime: &str) -> Option<u32> {
+    if mime == "text/plain" || mime.eq_ignore_ascii_case("text/plain;charset=utf-8") {
+        return Some(CF_UNICODETEXT.0 as u32);
+    }
+
+    if mime == "image/bmp" {
+        return Some(CF_DIB.0 as u32);
+    }
+
+    let name = U16CString::from_str(mime).ok()?;
+    match unsafe { RegisterClipboardFormatW(PCWSTR(name.as_ptr())) } {
+        0 => None,
+        format => Some(format),
+    }
+}
+
+/// Copy the contents of a global memory block.
+unsafe fn global_bytes(memory: HGLOBAL) -> Option<Vec<u8>> {
+    unsafe {
+        let size = GlobalSize(memory);
+        let ptr = GlobalLock(memory) as *const u8;
+        if ptr.is_null() {
+            return None;
+        }
+
+        let bytes = std::slice::from_raw_parts(ptr, size).to_vec();
+        _ = GlobalUnlock(memory);
+        Some(bytes)
+    }
+}
+
+/// Allocate a movable global memory block holding `data`, as the clipboard requires.
+pub(super) unsafe fn global_alloc(data: &[u8]) -> windows::core::Result<HGLOBAL> {
+    unsafe {
+        let memory = GlobalAlloc(GMEM_MOVEABLE, data.len().max(1))?;
+        let ptr = GlobalLock(memory) as *mut u8;
+        if !ptr.is_null() {
+            std::ptr::copy_nonoverlapping(data.as_ptr(), ptr, data.len());
+            _ = GlobalUnlock(memory);
+        }
+        Ok(memory)
+    }
+}
+
+/// Turn `CF_DIB` data into a BMP file by putting a `BITMAPFILEHEADER` in front.
+fn bmp_from_dib(dib: Vec<u8>) -> Option<Vec<u8>> {
+    let u32_at = |offset: usize| {
+        Some(u32::from_le_bytes(
+            dib.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+    let header_size = u32_at(0)? as usize;
+    let bit_count = u16::from_le_bytes(dib.get(14..16)?.try_into().ok()?);
+    let compression = u32_at(16)?;
+    let colors_used = u32_at(32)? as usize;
+
+    // BI_BITFIELDS with a plain BITMAPINFOHEADER is followed by three color masks
+    let masks = if compression == 3 && header_size == 40 {
+        12
+    } else {
+        0
+    };
+    let palette = match (colors_used, bit_count) {
+        (0, 1 | 4 | 8) => (1 << bit_count) * 4,
+        (count, _) => count * 4,
+    };
+    let pixel_offset = BMP_FILE_HEADER_SIZE + header_size + masks + palette;
+
+    let mut bmp = Vec::with_capacity(BMP_FILE_HEADER_SIZE + dib.len());
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&((BMP_FILE_HEADER_SIZE + dib.len()) as u32).to_le_bytes());
+    bmp.extend_from_slice(&[0; 4]);
+    bmp.extend_from_slice(&(pixel_offset as u32).to_le_bytes());
+    bmp.extend_from_slice(&dib);
+    Some(bmp)
+}
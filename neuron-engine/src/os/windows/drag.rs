@@ -0,0 +1,236 @@
+use crate::event::Event;
+use crate::os::window::{DragData, DragEffect};
+use crate::os::windows::clipboard::global_alloc;
+use crate::os::windows::window::WindowReferenceBlock;
+use log::debug;
+use std::mem::ManuallyDrop;
+use widestring::U16CString;
+use windows::Win32::Foundation::{
+    BOOL, DATA_S_SAMEFORMATETC, DRAGDROP_S_CANCEL, DRAGDROP_S_DROP, DRAGDROP_S_USEDEFAULTCURSORS,
+    DV_E_FORMATETC, DV_E_TYMED, E_NOTIMPL, OLE_E_ADVISENOTSUPPORTED, POINT, S_OK,
+};
+use windows::Win32::System::Com::{
+    DATADIR_GET, DVASPECT_CONTENT, FORMATETC, IAdviseSink, IDataObject, IDataObject_Impl,
+    IEnumFORMATETC, IEnumSTATDATA, STGMEDIUM, STGMEDIUM_0, TYMED_HGLOBAL,
+};
+use windows::Win32::System::Ole::{
+    CF_HDROP, CF_UNICODETEXT, DROPEFFECT, DROPEFFECT_COPY, DROPEFFECT_LINK, DROPEFFECT_MOVE,
+    DROPEFFECT_NONE, DoDragDrop, IDropSource, IDropSource_Impl, OleInitialize,
+};
+use windows::Win32::System::SystemServices::{MK_LBUTTON, MODIFIERKEYS_FLAGS};
+use windows::Win32::UI::Shell::{DROPFILES, SHCreateStdEnumFmtEtc};
+use windows::core::{HRESULT, implement};
+
+/// Run a drag of `data` out of the window, see [`Window::start_drag`](crate::os::window::Window::start_drag).
+///
+/// `DoDragDrop` runs its own modal loop (window messages keep being dispatched), so this returns once the data was dropped or the drag cancelled.
+pub(super) fn run_drag(block: &WindowReferenceBlock, data: &DragData, effect: DragEffect) {
+    unsafe {
+        // fails harmlessly if it was already called on this thread
+        _ = OleInitialize(None);
+    }
+
+    let data_object: IDataObject = DataObject {
+        formats: drag_formats(data),
+    }
+    .into();
+    let drop_source: IDropSource = DropSource.into();
+
+    let allowed = match effect {
+        DragEffect::Copy => DROPEFFECT_COPY,
+        DragEffect::Move => DROPEFFECT_MOVE,
+        DragEffect::Link => DROPEFFECT_LINK,
+    };
+
+    let mut performed = DROPEFFECT_NONE;
+    let result = unsafe { DoDragDrop(&data_object, &drop_source, allowed, &mut performed) };
+
+    let effect = if result == DRAGDROP_S_DROP {
+        match performed {
+            DROPEFFECT_MOVE => Some(DragEffect::Move),
+            DROPEFFECT_LINK => Some(DragEffect::Link),
+            DROPEFFECT_NONE => None,
+            _ => Some(DragEffect::Copy),
+        }
+    } else {
+        if result.is_err() {
+            debug!("Dragging out of {:?} failed: {}", block.id, result);
+        }
+        None
+    };
+
+    block.event_queue.push(Event::DragEnded {
+        window: block.id,
+        effect,
+    });
+}
+
+/// The clipboard formats a drag offers its data in, with the `HGLOBAL` contents of each.
+fn drag_formats(data: &DragData) -> Vec<(u16, Vec<u8>)> {
+    match data {
+        DragData::Text(text) => {
+            let text = text
+                .encode_utf16()
+                .chain([0])
+                .flat_map(u16::to_le_bytes)
+                .collect();
+            vec![(CF_UNICODETEXT.0, text)]
+        }
+        DragData::Files(paths) => {
+            // a DROPFILES header followed by the nul-separated paths, with an extra nul at the end
+            let header = DROPFILES {
+                pFiles: size_of::<DROPFILES>() as u32,
+                pt: POINT::default(),
+                fNC: BOOL(0),
+                fWide: BOOL(1),
+            };
+
+            let mut hdrop = unsafe {
+                std::slice::from_raw_parts(
+                    (&header as *const DROPFILES).cast::<u8>(),
+                    size_of::<DROPFILES>(),
+                )
+                .to_vec()
+            };
+            for path in paths
+                .iter()
+                .filter_map(|path| U16CString::from_os_str(path).ok())
+            {
+                hdrop.extend(
+                    path.as_slice_with_nul()
+                        .iter()
+                        .flat_map(|c| c.to_le_bytes()),
+                );
+            }
+            hdrop.extend([0, 0]);
+
+            vec![(CF_HDROP.0, hdrop)]
+        }
+    }
+}
+
+/// The dragged data, handed to the drop target.
+#[implement(IDataObject)]
+struct DataObject {
+    formats: Vec<(u16, Vec<u8>)>,
+}
+
+impl DataObject {
+    fn find(&self, format: &FORMATETC) -> Result<&[u8], HRESULT> {
+        if format.tymed & TYMED_HGLOBAL.0 as u32 == 0 {
+            return Err(DV_E_TYMED);
+        }
+
+        self.formats
+            .iter()
+            .find(|(cf, _)| *cf == format.cfFormat)
+            .map(|(_, data)| data.as_slice())
+            .ok_or(DV_E_FORMATETC)
+    }
+}
+
+impl IDataObject_Impl for DataObject_Impl {
+    fn GetData(&self, pformatetcin: *const FORMATETC) -> windows::core::Result<STGMEDIUM> {
+        let data = self.find(unsafe { &*pformatetcin })?;
+        // the receiver frees the memory
+        let memory = unsafe { global_alloc(data)? };
+
+        Ok(STGMEDIUM {
+            tymed: TYMED_HGLOBAL.0 as u32,
+            u: STGMEDIUM_0 { hGlobal: memory },
+            pUnkForRelease: ManuallyDrop::new(None),
+        })
+    }
+
+    fn GetDataHere(
+        &self,
+        _pformatetc: *const FORMATETC,
+        _pmedium: *mut STGMEDIUM,
+    ) -> windows::core::Result<()> {
+        Err(E_NOTIMPL.into())
+    }
+
+    fn QueryGetData(&self, pformatetc: *const FORMATETC) -> HRESULT {
+        match self.find(unsafe { &*pformatetc }) {
+            Ok(_) => S_OK,
+            Err(e) => e,
+        }
+    }
+
+    fn GetCanonicalFormatEtc(
+        &self,
+        _pformatectin: *const FORMATETC,
+        pformatetcout: *mut FORMATETC,
+    ) -> HRESULT {
+        unsafe {
+            (*pformatetcout).ptd = std::ptr::null_mut();
+        }
+        DATA_S_SAMEFORMATETC
+    }
+
+    fn SetData(
+        &self,
+        _pformatetc: *const FORMATETC,
+        _pmedium: *const STGMEDIUM,
+        _frelease: BOOL,
+    ) -> windows::core::Result<()> {
+        Err(E_NOTIMPL.into())
+    }
+
+    fn EnumFormatEtc(&self, dwdirection: u32) -> windows::core::Result<IEnumFORMATETC> {
+        if dwdirection != DATADIR_GET.0 as u32 {
+            return Err(E_NOTIMPL.into());
+        }
+
+        let formats = self
+            .formats
+            .iter()
+            .map(|(cf, _)| FORMATETC {
+                cfFormat: *cf,
+                ptd: std::ptr::null_mut(),
+                dwAspect: DVASPECT_CONTENT.0,
+                lindex: -1,
+                tymed: TYMED_HGLOBAL.0 as u32,
+            })
+            .collect::<Vec<_>>();
+
+        unsafe { SHCreateStdEnumFmtEtc(&formats) }
+    }
+
+    fn DAdvise(
+        &self,
+        _pformatetc: *const FORMATETC,
+        _advf: u32,
+        _padvsink: Option<&IAdviseSink>,
+    ) -> windows::core::Result<u32> {
+        Err(OLE_E_ADVISENOTSUPPORTED.into())
+    }
+
+    fn DUnadvise(&self, _dwconnection: u32) -> windows::core::Result<()> {
+        Err(OLE_E_ADVISENOTSUPPORTED.into())
+    }
+
+    fn EnumDAdvise(&self) -> windows::core::Result<IEnumSTATDATA> {
+        Err(OLE_E_ADVISENOTSUPPORTED.into())
+    }
+}
+
+/// Decides when the drag ends: dropping on release of the left button, cancelling on Escape.
+#[implement(IDropSource)]
+struct DropSource;
+
+impl IDropSource_Impl for DropSource_Impl {
+    fn QueryContinueDrag(&self, fescapepressed: BOOL, grfkeystate: MODIFIERKEYS_FLAGS) -> HRESULT {
+        if fescapepressed.as_bool() {
+            DRAGDROP_S_CANCEL
+        } else if grfkeystate.0 & MK_LBUTTON.0 == 0 {
+            DRAGDROP_S_DROP
+        } else {
+            S_OK
+        }
+    }
+
+    fn GiveFeedback(&self, _dweffect: DROPEFFECT) -> HRESULT {
+        DRAGDROP_S_USEDEFAULTCURSORS
+    }
+}
@@ -0,0 +1,172 @@
+use crate::event::{KeyEvent, KeyState};
+use crate::os::GlobalKeyboardHook;
+use crate::os::windows::{WindowsPlatform, held_modifiers, modifier_of_key};
+use hashbrown::HashSet;
+use std::cell::RefCell;
+use std::rc::Rc;
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetAsyncKeyState, VIRTUAL_KEY, VK_CONTROL, VK_ESCAPE, VK_LWIN, VK_RWIN, VK_TAB,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CallNextHookEx, GetForegroundWindow, HC_ACTION, HHOOK, KBDLLHOOKSTRUCT, LLKHF_ALTDOWN,
+    LLKHF_EXTENDED, LLKHF_UP, SetWindowsHookExW, UnhookWindowsHookEx, WH_KEYBOARD_LL,
+};
+
+/// The installed hook callback. Low-level hooks are called on the thread that installed them, without any context pointer, so it lives in a thread local.
+struct KeyboardHook {
+    callback: GlobalKeyboardHook,
+    /// Low-level hooks don't flag auto-repeats, so held keys are tracked to tell them apart.
+    held_keys: HashSet<u32>,
+}
+
+thread_local! {
+    /// Cloned out while the callback runs, so the callback can replace or remove the hook.
+    static KEYBOARD_HOOK: RefCell<Option<Rc<RefCell<KeyboardHook>>>> = const { RefCell::new(None) };
+
+    /// Windows that swallow the system shortcuts while they are in the foreground, see [`Window::set_keyboard_shortcuts_inhibited`](crate::os::window::Window::set_keyboard_shortcuts_inhibited).
+    static SHORTCUT_INHIBITORS: RefCell<HashSet<isize>> = RefCell::new(HashSet::new());
+}
+
+impl WindowsPlatform {
+    pub(super) fn install_keyboard_hook(&self, callback: GlobalKeyboardHook) -> anyhow::Result<()> {
+        KEYBOARD_HOOK.with_borrow_mut(|hook| {
+            *hook = Some(Rc::new(RefCell::new(KeyboardHook {
+                callback,
+                held_keys: HashSet::new(),
+            })))
+        });
+
+        self.hook_keyboard()
+    }
+
+    pub(super) fn uninstall_keyboard_hook(&self) {
+        KEYBOARD_HOOK.with_borrow_mut(|hook| *hook = None);
+        self.unhook_keyboard_if_unused();
+    }
+
+    pub(super) fn set_keyboard_shortcuts_inhibited(
+        &self,
+        hwnd: HWND,
+        inhibited: bool,
+    ) -> anyhow::Result<()> {
+        if inhibited {
+            SHORTCUT_INHIBITORS.with_borrow_mut(|windows| windows.insert(hwnd.0 as isize));
+            self.hook_keyboard()
+        } else {
+            SHORTCUT_INHIBITORS.with_borrow_mut(|windows| windows.remove(&(hwnd.0 as isize)));
+            self.unhook_keyboard_if_unused();
+            Ok(())
+        }
+    }
+
+    /// Drop the hook callback and all shortcut inhibitors, and remove the hook.
+    pub(super) fn remove_keyboard_hook(&self) {
+        SHORTCUT_INHIBITORS.with_borrow_mut(|windows| windows.clear());
+        self.uninstall_keyboard_hook();
+    }
+
+    /// The same hook serves both the global callback and shortcut inhibition.
+    fn hook_keyboard(&self) -> anyhow::Result<()> {
+        if self.keyboard_hook.get().is_none() {
+            let hhook = unsafe {
+                SetWindowsHookExW(
+                    WH_KEYBOARD_LL,
+                    Some(low_level_keyboard_proc),
+                    self.hinstance,
+                    0,
+                )?
+            };
+            self.keyboard_hook.set(Some(hhook));
+        }
+
+        Ok(())
+    }
+
+    fn unhook_keyboard_if_unused(&self) {
+        let in_use = KEYBOARD_HOOK.with_borrow(Option::is_some)
+            || SHORTCUT_INHIBITORS.with_borrow(|windows| !windows.is_empty());
+        if in_use {
+            return;
+        }
+
+        if let Some(hhook) = self.keyboard_hook.take() {
+            unsafe {
+                _ = UnhookWindowsHookEx(hhook);
+            }
+        }
+    }
+}
+
+/// Whether the key is part of a shortcut the shell handles: the Windows keys, Alt+Tab, Alt+Esc and Ctrl+Esc.
+unsafe fn is_system_shortcut(info: &KBDLLHOOKSTRUCT) -> bool {
+    let alt = info.flags.0 & LLKHF_ALTDOWN.0 != 0;
+
+    match info.vkCode {
+        code if code == VK_LWIN.0 as u32 || code == VK_RWIN.0 as u32 => true,
+        code if code == VK_TAB.0 as u32 => alt,
+        code if code == VK_ESCAPE.0 as u32 => {
+            alt || unsafe { GetAsyncKeyState(VK_CONTROL.0 as i32) } < 0
+        }
+        _ => false,
+    }
+}
+
+unsafe extern "system" fn low_level_keyboard_proc(
+    code: i32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    unsafe {
+        if code == HC_ACTION as i32 {
+            let info = &*(lparam.0 as *const KBDLLHOOKSTRUCT);
+
+            let mut scancode = info.scanCode;
+            if info.flags.0 & LLKHF_EXTENDED.0 != 0 {
+                scancode |= 0xe000;
+            }
+
+            // a callback that pumps messages can get here again while it runs, the nested key isn't reported then
+            if let Some(hook) = KEYBOARD_HOOK.with_borrow(Option::clone)
+                && let Ok(mut hook) = hook.try_borrow_mut()
+            {
+                // the hook runs before the key state is updated, so the key itself is applied on top
+                let released = info.flags.0 & LLKHF_UP.0 != 0;
+                let mut modifiers = held_modifiers(|key| GetAsyncKeyState(key.0 as i32) < 0);
+                if let Some(modifier) = modifier_of_key(VIRTUAL_KEY(info.vkCode as u16)) {
+                    modifiers.set(modifier, !released);
+                }
+
+                let event = if released {
+                    hook.held_keys.remove(&scancode);
+                    KeyEvent {
+                        scancode,
+                        state: KeyState::Released,
+                        repeat: false,
+                        modifiers,
+                    }
+                } else {
+                    KeyEvent {
+                        scancode,
+                        state: KeyState::Pressed,
+                        repeat: !hook.held_keys.insert(scancode),
+                        modifiers,
+                    }
+                };
+
+                (hook.callback)(event);
+            }
+
+            // the callback still observed it, but neither the shell nor the window gets the key
+            let foreground = GetForegroundWindow().0 as isize;
+            if SHORTCUT_INHIBITORS.with_borrow(|windows| windows.contains(&foreground))
+                && is_system_shortcut(info)
+            {
+                return LRESULT(1);
+            }
+        }
+
+        // keys are only swallowed for shortcut inhibition, otherwise other hooks and the focused application still get them
+        CallNextHookEx(HHOOK::default(), code, wparam, lparam)
+    }
+}
@@ -1,39 +1,161 @@
 #![cfg(windows)]
 
+mod accelerator;
+mod clipboard;
+mod drag;
+mod keyboard_hook;
+mod monitor;
 mod window;
+mod window_proc;
 
 use crate::ExitState;
-use crate::os::window::{SupportedWindowAttributes, Window, WindowAttributes, WindowId};
-use crate::os::windows::window::{WindowReferenceBlock, WindowsWindow};
-use crate::os::{OsLoopInputs, Platform, PlatformKind};
+use crate::event::{Event, Modifiers, MouseButton};
+use crate::os::monitor::MonitorHandle;
+use crate::os::window::{
+    SupportedWindowAttributes, Window, WindowAttributes, WindowId, WindowPosition,
+};
+use crate::os::windows::window::WindowsWindow;
+use crate::os::windows::window_proc::generic_window_proc;
+use crate::os::{
+    GlobalKeyboardHook, LockState, OsLoopInputs, Platform, PlatformKind, PointerInfo, PointerState,
+};
 use hashbrown::{HashMap, HashSet};
 use log::debug;
 use raw_window_handle::{DisplayHandle, HandleError, HasDisplayHandle};
-use std::cell::RefCell;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
 use std::hash::Hash;
-use std::mem::MaybeUninit;
 use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::{Arc, Weak};
-use widestring::{U16CStr, U16CString};
+use std::sync::{Arc, Once, Weak};
+use std::thread::ThreadId;
+use std::time::Duration;
+use widestring::U16CString;
 use windows::UI::ViewManagement::{UIColorType, UISettings};
-use windows::Win32::Foundation::{COLORREF, HINSTANCE, HWND, LPARAM, LRESULT, WPARAM};
-use windows::Win32::Graphics::Gdi::{CreateSolidBrush, DeleteObject, HBRUSH};
+use windows::Win32::Foundation::{COLORREF, HINSTANCE, HWND, POINT};
+use windows::Win32::Graphics::Gdi::{
+    CreateSolidBrush, DeleteObject, GdiFlush, HBRUSH, MONITOR_DEFAULTTOPRIMARY, MonitorFromPoint,
+    ScreenToClient,
+};
+use windows::Win32::System::Com::{
+    CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED, CoCreateInstance, CoInitializeEx,
+};
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::Accessibility::{CAccPropServices, IAccPropServices};
 use windows::Win32::UI::HiDpi::{
     DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2, SetProcessDpiAwarenessContext,
 };
-use windows::Win32::UI::WindowsAndMessaging::{CREATESTRUCTW, CS_DROPSHADOW, CS_HREDRAW, CS_NOCLOSE, CS_VREDRAW, DefWindowProcW, DispatchMessageW, MSG, PM_REMOVE, PeekMessageW, RegisterClassExW, TranslateMessage, UnregisterClassW, WM_CREATE, WM_QUIT, WNDCLASS_STYLES, WNDCLASSEXW, WS_OVERLAPPEDWINDOW, GWLP_USERDATA, SetWindowLongPtrW, GetWindowLongPtrW, WM_DESTROY};
-use windows::core::PCWSTR;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetAsyncKeyState, GetDoubleClickTime, GetKeyState, VIRTUAL_KEY, VK_CAPITAL, VK_LBUTTON,
+    VK_LCONTROL, VK_LMENU, VK_LSHIFT, VK_LWIN, VK_MBUTTON, VK_NUMLOCK, VK_RBUTTON, VK_RCONTROL,
+    VK_RMENU, VK_RSHIFT, VK_RWIN, VK_SCROLL, VK_XBUTTON1, VK_XBUTTON2,
+};
+use windows::Win32::UI::Shell::{ITaskbarList3, TaskbarList};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CS_DROPSHADOW, CS_HREDRAW, CS_NOCLOSE, CS_VREDRAW, CreateWindowExW, DestroyWindow,
+    DispatchMessageW, GA_ROOT, GetAncestor, GetCursorPos, GetSystemMetrics, HACCEL, HHOOK, HMENU,
+    HWND_MESSAGE, MSG, MWMO_INPUTAVAILABLE, MsgWaitForMultipleObjectsEx, PM_NOREMOVE, PM_REMOVE,
+    PeekMessageW, QS_ALLINPUT, RegisterClassExW, RegisterWindowMessageW, SM_CMOUSEBUTTONS,
+    SM_CXDOUBLECLK, SM_CXDRAG, SM_MOUSEHORIZONTALWHEELPRESENT, SM_MOUSEWHEELPRESENT, SM_SWAPBUTTON,
+    TranslateAcceleratorW, TranslateMessage, UnregisterClassW, WINDOW_EX_STYLE, WINDOW_STYLE,
+    WM_QUIT, WNDCLASS_STYLES, WNDCLASSEXW, WS_EX_TOOLWINDOW, WS_POPUP,
+};
+use windows::core::{PCWSTR, w};
 
 pub(super) struct WindowsPlatform {
     hinstance: HINSTANCE,
-    window_class_counter: AtomicU32,
     dark_mode: bool,
+    main_thread: ThreadId,
     window_background_brush: HBRUSH,
+    /// Brushes for [`WindowAttributes::background_color`](crate::os::window::WindowAttributes::background_color), one per distinct color (they belong to window classes, which live as long as the platform).
+    background_color_brushes: RefCell<HashMap<(u8, u8, u8), HBRUSH>>,
     registered_window_classes: RefCell<HashMap<WindowClassAttributes, U16CString>>,
+    /// Handles of the windows created by this platform, for lookups by id (see [`Platform::query_pointer`]).
+    window_handles: RefCell<HashMap<WindowId, HWND>>,
+    message_window: Cell<Option<HWND>>,
+    /// Hidden top-level window owning windows that are listed in Alt+Tab without a taskbar button.
+    owner_window: Cell<Option<HWND>>,
+    /// Set by the window procedure when the display configuration changed, so the change is reported once per pass even though every top-level window receives it.
+    monitors_changed: Cell<bool>,
+    /// Last reported lock keys, compared after every pass of the message loop.
+    lock_state: Cell<LockState>,
+    /// Display devices switched to another video mode.
+    changed_video_modes: RefCell<HashSet<String>>,
+    /// Message id of `TaskbarButtonCreated`, sent once the taskbar can take thumbnail toolbar buttons for a window.
+    taskbar_button_created: u32,
+    taskbar_list: RefCell<Option<ITaskbarList3>>,
+    acc_prop_services: RefCell<Option<IAccPropServices>>,
+    /// Low-level keyboard hook, see [`Platform::set_global_keyboard_hook`].
+    keyboard_hook: Cell<Option<HHOOK>>,
+    /// Accelerator table built from the window manager's accelerators, with the generation it was built from.
+    accelerator_table: Cell<Option<(u64, Option<HACCEL>)>>,
     weak: Weak<Self>,
 }
 
+const MESSAGE_WINDOW_CLASS: PCWSTR = w!("neuron_message_window");
+
+/// Class names are numbered process-wide, as classes registered by different platforms would otherwise clash.
+static WINDOW_CLASS_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// DPI awareness can only be set once per process, later calls fail.
+static DPI_AWARENESS: Once = Once::new();
+
+/// The modifier keys held, with `is_held` reading one key's state (e.g. through `GetKeyState`).
+pub(super) fn held_modifiers(is_held: impl Fn(VIRTUAL_KEY) -> bool) -> Modifiers {
+    MODIFIER_KEYS
+        .into_iter()
+        .filter(|(key, _)| is_held(*key))
+        .fold(Modifiers::empty(), |modifiers, (_, modifier)| {
+            modifiers | modifier
+        })
+}
+
+/// The modifier flag of a key, for the side-specific virtual keys (the generic `VK_SHIFT` and the like don't tell the side).
+pub(super) fn modifier_of_key(key: VIRTUAL_KEY) -> Option<Modifiers> {
+    MODIFIER_KEYS
+        .into_iter()
+        .find(|(modifier_key, _)| *modifier_key == key)
+        .map(|(_, modifier)| modifier)
+}
+
+const MODIFIER_KEYS: [(VIRTUAL_KEY, Modifiers); 8] = [
+    (VK_LSHIFT, Modifiers::LSHIFT),
+    (VK_RSHIFT, Modifiers::RSHIFT),
+    (VK_LCONTROL, Modifiers::LCTRL),
+    (VK_RCONTROL, Modifiers::RCTRL),
+    (VK_LMENU, Modifiers::LALT),
+    (VK_RMENU, Modifiers::RALT),
+    (VK_LWIN, Modifiers::LSUPER),
+    (VK_RWIN, Modifiers::RSUPER),
+];
+
+/// The toggle state of the lock keys, as seen by this thread's input state (updated while messages are processed).
+fn current_lock_state() -> LockState {
+    let is_on = |key: VIRTUAL_KEY| unsafe { GetKeyState(key.0 as i32) } & 1 != 0;
+
+    LockState {
+        caps: is_on(VK_CAPITAL),
+        num: is_on(VK_NUMLOCK),
+        scroll: is_on(VK_SCROLL),
+    }
+}
+
+/// Hand a message taken off the queue to its window, after translating accelerators and key presses.
+fn translate_and_dispatch(msg: &MSG, accelerator_table: Option<HACCEL>) {
+    unsafe {
+        // turned into a WM_COMMAND sent to the top-level window, the key press itself is dropped
+        if let Some(table) = accelerator_table
+            && !msg.hwnd.is_invalid()
+            && TranslateAcceleratorW(GetAncestor(msg.hwnd, GA_ROOT), table, msg) != 0
+        {
+            return;
+        }
+
+        // translating first posts the WM_CHAR for a key down, so it follows the WM_KEYDOWN in the queue
+        _ = TranslateMessage(msg);
+        _ = DispatchMessageW(msg);
+    }
+}
+
 #[inline]
 fn is_color_light(color: windows::UI::Color) -> bool {
     ((5 * color.G as u32) + (2 * color.R as u32) + color.B as u32) > (8 * 128)
@@ -56,14 +178,13 @@ impl WindowsPlatform {
     pub(super) fn new(weak: Weak<WindowsPlatform>) -> anyhow::Result<Self> {
         let hinstance = HINSTANCE(unsafe { GetModuleHandleW(PCWSTR::null()) }?.0);
 
-        unsafe {
+        DPI_AWARENESS.call_once(|| unsafe {
             _ = SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2);
-        }
+        });
 
         let dark_mode = is_dark_mode_internal();
 
-        let window_background_color =
-            unsafe { UISettings::new()?.GetColorValue(UIColorType::Background)? };
+        let window_background_color = UISettings::new()?.GetColorValue(UIColorType::Background)?;
 
         debug!(
             "Default Window Background Color: rgb({:02x},{:02x},{:02x})",
@@ -80,14 +201,146 @@ impl WindowsPlatform {
 
         Ok(Self {
             hinstance,
-            window_class_counter: AtomicU32::new(0),
             dark_mode,
+            main_thread: std::thread::current().id(),
             window_background_brush,
+            background_color_brushes: RefCell::new(HashMap::new()),
             registered_window_classes: RefCell::new(HashMap::new()),
+            window_handles: RefCell::new(HashMap::new()),
+            message_window: Cell::new(None),
+            owner_window: Cell::new(None),
+            monitors_changed: Cell::new(false),
+            lock_state: Cell::new(current_lock_state()),
+            changed_video_modes: RefCell::new(HashSet::new()),
+            taskbar_button_created: unsafe { RegisterWindowMessageW(w!("TaskbarButtonCreated")) },
+            taskbar_list: RefCell::new(None),
+            acc_prop_services: RefCell::new(None),
+            keyboard_hook: Cell::new(None),
+            accelerator_table: Cell::new(None),
             weak,
         })
     }
 
+    /// The shell's taskbar interface, created on first use (`None` if COM or the taskbar isn't available).
+    pub(super) fn taskbar_list(&self) -> Option<ITaskbarList3> {
+        if let Some(taskbar_list) = &*self.taskbar_list.borrow() {
+            return Some(taskbar_list.clone());
+        }
+
+        let taskbar_list = unsafe {
+            // fails harmlessly if COM is already initialized on this thread
+            _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+
+            let taskbar_list: ITaskbarList3 =
+                CoCreateInstance(&TaskbarList, None, CLSCTX_INPROC_SERVER)
+                    .inspect_err(|e| debug!("Failed to create the taskbar list: {}", e))
+                    .ok()?;
+            taskbar_list.HrInit().ok()?;
+            taskbar_list
+        };
+
+        *self.taskbar_list.borrow_mut() = Some(taskbar_list.clone());
+        Some(taskbar_list)
+    }
+
+    /// The accessibility annotation service (dynamic annotation, which overrides properties of the system's default accessible objects), created on first use.
+    pub(super) fn acc_prop_services(&self) -> Option<IAccPropServices> {
+        if let Some(services) = &*self.acc_prop_services.borrow() {
+            return Some(services.clone());
+        }
+
+        let services: IAccPropServices = unsafe {
+            _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+
+            CoCreateInstance(&CAccPropServices, None, CLSCTX_INPROC_SERVER)
+                .inspect_err(|e| {
+                    debug!(
+                        "Failed to create the accessibility annotation service: {}",
+                        e
+                    )
+                })
+                .ok()?
+        };
+
+        *self.acc_prop_services.borrow_mut() = Some(services.clone());
+        Some(services)
+    }
+
+    /// Get the hidden message-only window (parented to `HWND_MESSAGE`), creating it on first use.
+    ///
+    /// Features that need a window to receive messages without showing one (hotkeys, tray icons, clipboard ownership, session events) should use this instead of creating their own.
+    /// Its messages are routed through [`generic_window_proc`] like any other window's (it has no reference block, so only the default handling applies).
+    pub(super) fn message_window(&self) -> anyhow::Result<HWND> {
+        if let Some(hwnd) = self.message_window.get() {
+            return Ok(hwnd);
+        }
+
+        let wc = WNDCLASSEXW {
+            cbSize: size_of::<WNDCLASSEXW>() as u32,
+            lpfnWndProc: Some(generic_window_proc),
+            lpszClassName: MESSAGE_WINDOW_CLASS,
+            hInstance: self.hinstance,
+            ..Default::default()
+        };
+
+        let hwnd = unsafe {
+            _ = RegisterClassExW(&wc);
+
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                MESSAGE_WINDOW_CLASS,
+                PCWSTR::null(),
+                WINDOW_STYLE::default(),
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                HMENU::default(),
+                self.hinstance,
+                None,
+            )?
+        };
+
+        debug!("Created message-only window");
+
+        self.message_window.set(Some(hwnd));
+        Ok(hwnd)
+    }
+
+    /// Get the hidden top-level window used as the owner of windows that should be in the Alt+Tab list but not the taskbar, creating it on first use.
+    ///
+    /// Owned windows never get a taskbar button, and as the owner is never shown it has neither a button nor an Alt+Tab entry of its own.
+    /// Message-only windows can't own other windows, so this is a separate (never shown) window of the same class.
+    pub(super) fn owner_window(&self) -> anyhow::Result<HWND> {
+        if let Some(hwnd) = self.owner_window.get() {
+            return Ok(hwnd);
+        }
+
+        // registers the window class
+        self.message_window()?;
+
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WS_EX_TOOLWINDOW,
+                MESSAGE_WINDOW_CLASS,
+                PCWSTR::null(),
+                WS_POPUP,
+                0,
+                0,
+                0,
+                0,
+                HWND::default(),
+                HMENU::default(),
+                self.hinstance,
+                None,
+            )?
+        };
+
+        self.owner_window.set(Some(hwnd));
+        Ok(hwnd)
+    }
+
     fn get_window_class(&self, attributes: WindowClassAttributes) -> anyhow::Result<U16CString> {
         if let Some(name) = self
             .registered_window_classes
@@ -99,15 +352,25 @@ impl WindowsPlatform {
         } else {
             let name = U16CString::from_str(format!(
                 "neuron_windowclass_{:?}",
-                self.window_class_counter.fetch_add(1, Ordering::SeqCst)
+                WINDOW_CLASS_COUNTER.fetch_add(1, Ordering::SeqCst)
             ))?;
-            let mut wc = WNDCLASSEXW::default();
-            wc.cbSize = size_of::<WNDCLASSEXW>() as u32;
-            wc.hbrBackground = self.window_background_brush;
-            wc.lpfnWndProc = Some(generic_window_proc);
-            wc.lpszClassName = PCWSTR(name.as_ptr());
-            wc.style = attributes.style();
-            wc.hInstance = self.hinstance;
+            let background_brush = match attributes.background_color {
+                Some((r, g, b)) => *self
+                    .background_color_brushes
+                    .borrow_mut()
+                    .entry((r, g, b))
+                    .or_insert_with(|| unsafe { CreateSolidBrush(make_colorref(r, g, b)) }),
+                None => self.window_background_brush,
+            };
+            let wc = WNDCLASSEXW {
+                cbSize: size_of::<WNDCLASSEXW>() as u32,
+                hbrBackground: background_brush,
+                lpfnWndProc: Some(generic_window_proc),
+                lpszClassName: PCWSTR(name.as_ptr()),
+                style: attributes.style(),
+                hInstance: self.hinstance,
+                ..Default::default()
+            };
 
             unsafe {
                 _ = RegisterClassExW(&wc);
@@ -133,11 +396,73 @@ fn make_colorref(r: u8, g: u8, b: u8) -> COLORREF {
     COLORREF(((b as u32) << 16) | ((g as u32) << 8) | (r as u32))
 }
 
+/// What [`Platform::supported_window_attributes`](crate::os::Platform::supported_window_attributes) reports.
+static SUPPORTED_WINDOW_ATTRIBUTES: SupportedWindowAttributes = SupportedWindowAttributes {
+    title: true,
+    size: true,
+    position: true,
+    has_close_button: true,
+    has_minimize_button: true,
+    has_maximize_button: true,
+    show_border: true,
+    show_title_bar: true,
+    initially_disabled: true,
+    initially_minimized: true,
+    resizable: true,
+    min_size: true,
+    max_size: true,
+    skip_taskbar: true,
+    show_in_taskbar: true,
+    show_in_switcher: true,
+    initially_visible: true,
+    initial_z_order: true,
+    always_on_top: true,
+    auto_hide_on_focus_loss: true,
+    compositor_bypass: false,
+    windows_show_drop_shadow: true,
+    windows_is_dialog_box: true,
+    windows_has_system_menu: true,
+    x11_window_type: false,
+    fullscreen: true,
+    icon: true,
+    transparent: true,
+    background_color: true,
+};
+
 impl Platform for WindowsPlatform {
     fn name(&self) -> &'static str {
         super::names::WINDOWS
     }
 
+    fn main_thread_only(&self) -> bool {
+        // window messages are delivered to the thread that created the window
+        true
+    }
+
+    fn main_thread(&self) -> ThreadId {
+        self.main_thread
+    }
+
+    fn set_global_keyboard_hook(&self, hook: GlobalKeyboardHook) -> anyhow::Result<()> {
+        self.install_keyboard_hook(hook)
+    }
+
+    fn remove_global_keyboard_hook(&self) {
+        self.uninstall_keyboard_hook();
+    }
+
+    fn clipboard_data(&self, mime: &str) -> Option<Vec<u8>> {
+        self.read_clipboard(mime)
+    }
+
+    fn set_clipboard_data(&self, entries: &[(String, Vec<u8>)]) -> anyhow::Result<()> {
+        self.write_clipboard(entries)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn kind(&self) -> PlatformKind {
         PlatformKind::Windows
     }
@@ -151,56 +476,183 @@ impl Platform for WindowsPlatform {
     }
 
     fn supported_window_attributes(&self) -> &'static SupportedWindowAttributes {
-        &SupportedWindowAttributes {
-            title: true,
-            size: true,
-            position: true,
-            has_close_button: true,
-            has_minimize_button: true,
-            has_maximize_button: true,
-            show_drop_shadow: true,
-            show_border: true,
-            show_title_bar: true,
-            initially_disabled: true,
-            is_dialog_box: true,
-            initially_minimized: true,
-            resizable: true,
-            has_system_menu: true,
-            initially_visible: true,
+        &SUPPORTED_WINDOW_ATTRIBUTES
+    }
+
+    fn primary_monitor(&self) -> Option<MonitorHandle> {
+        // the primary monitor is the one containing the origin of the virtual screen
+        let hmonitor = unsafe { MonitorFromPoint(POINT { x: 0, y: 0 }, MONITOR_DEFAULTTOPRIMARY) };
+        monitor::monitor_handle(hmonitor)
+    }
+
+    fn available_monitors(&self) -> Vec<MonitorHandle> {
+        let mut monitors = monitor::all_monitor_handles();
+        // stable, so the rest keep the enumeration order
+        monitors.sort_by_key(|monitor| monitor.position != WindowPosition { x: 0, y: 0 });
+        monitors
+    }
+
+    fn pointer_info(&self) -> PointerInfo {
+        unsafe {
+            PointerInfo {
+                button_count: GetSystemMetrics(SM_CMOUSEBUTTONS).max(0) as u32,
+                has_wheel: GetSystemMetrics(SM_MOUSEWHEELPRESENT) != 0,
+                has_horizontal_wheel: GetSystemMetrics(SM_MOUSEHORIZONTALWHEELPRESENT) != 0,
+            }
+        }
+    }
+
+    fn lock_state(&self) -> LockState {
+        current_lock_state()
+    }
+
+    fn double_click_time(&self) -> Duration {
+        Duration::from_millis(unsafe { GetDoubleClickTime() } as u64)
+    }
+
+    fn double_click_distance(&self) -> u32 {
+        // the metric is the width of a rectangle centered on the first click
+        (unsafe { GetSystemMetrics(SM_CXDOUBLECLK) }.max(0) / 2) as u32
+    }
+
+    fn drag_threshold(&self) -> u32 {
+        unsafe { GetSystemMetrics(SM_CXDRAG) }.max(0) as u32
+    }
+
+    fn query_pointer(&self, window: WindowId) -> Option<PointerState> {
+        let hwnd = self.window_handles.borrow().get(&window).copied()?;
+
+        let mut position = POINT::default();
+        unsafe {
+            GetCursorPos(&mut position).ok()?;
+            if !ScreenToClient(hwnd, &mut position).as_bool() {
+                return None;
+            }
         }
+
+        // GetAsyncKeyState reports the physical buttons, which are swapped for left-handed users
+        let (left, right) = if unsafe { GetSystemMetrics(SM_SWAPBUTTON) } != 0 {
+            (VK_RBUTTON, VK_LBUTTON)
+        } else {
+            (VK_LBUTTON, VK_RBUTTON)
+        };
+        let buttons = [
+            (left, MouseButton::Left),
+            (VK_MBUTTON, MouseButton::Middle),
+            (right, MouseButton::Right),
+            (VK_XBUTTON1, MouseButton::Other(8)),
+            (VK_XBUTTON2, MouseButton::Other(9)),
+        ]
+        .into_iter()
+        .filter(|(key, _)| unsafe { GetAsyncKeyState(key.0 as i32) } < 0)
+        .fold(0, |buttons, (_, button)| {
+            buttons | PointerState::button_mask(button)
+        });
+
+        Some(PointerState {
+            position: (position.x as f64, position.y as f64),
+            buttons,
+        })
     }
 
     fn create_window(
         &self,
         window_attributes: WindowAttributes,
         window_id: WindowId,
+        inputs: &OsLoopInputs,
     ) -> anyhow::Result<Arc<dyn Window>> {
+        // a window belongs to the thread that created it, the `Arc` is shared with the window manager and not between threads
+        #[allow(clippy::arc_with_non_send_sync)]
         Ok(Arc::new(WindowsWindow::new(
             self.weak.upgrade().unwrap(),
             window_attributes,
             window_id,
+            inputs,
         )?))
     }
 
+    fn sync(&self) {
+        unsafe {
+            _ = GdiFlush();
+
+            // there is no window manager to rebuild the table from, so accelerators changed since the last process_events apply from the next one
+            let accelerator_table = self.accelerator_table.get().and_then(|(_, table)| table);
+
+            // drain everything except WM_QUIT, which has to stay queued so that process_events can report it
+            let mut msg = MSG::default();
+            while PeekMessageW(&mut msg, HWND::default(), 0, 0, PM_NOREMOVE).as_bool()
+                && msg.message != WM_QUIT
+            {
+                if PeekMessageW(&mut msg, HWND::default(), 0, 0, PM_REMOVE).as_bool() {
+                    translate_and_dispatch(&msg, accelerator_table);
+                }
+            }
+        }
+    }
+
+    fn wait_for_events(&self, timeout: Duration) {
+        unsafe {
+            // MWMO_INPUTAVAILABLE also returns for messages that were already in the queue (but not yet removed) before the call
+            MsgWaitForMultipleObjectsEx(
+                None,
+                timeout.as_millis().min(u32::MAX as u128 - 1) as u32,
+                QS_ALLINPUT,
+                MWMO_INPUTAVAILABLE,
+            );
+        }
+    }
+
+    fn has_pending_events(&self) -> bool {
+        let mut msg = MSG::default();
+        unsafe { PeekMessageW(&mut msg, HWND::default(), 0, 0, PM_NOREMOVE).as_bool() }
+    }
+
     fn process_events(&self, inputs: &OsLoopInputs) {
         unsafe {
-            #[allow(invalid_value)]
-            let mut msg = MaybeUninit::<MSG>::uninit().assume_init();
+            let mut msg = MSG::default();
+            let accelerator_table = self.accelerator_table(&inputs.window_manager);
 
             while PeekMessageW(&mut msg, HWND::default(), 0, 0, PM_REMOVE).0 > 0 {
                 if msg.message == WM_QUIT {
                     inputs.exit_manager.set(ExitState::ExitSuccess);
                 }
 
-                _ = DispatchMessageW(&msg);
-                _ = TranslateMessage(&msg);
+                translate_and_dispatch(&msg, accelerator_table);
             }
         }
+
+        if self.monitors_changed.take() {
+            inputs.event_queue.push(Event::MonitorsChanged);
+        }
+
+        let lock_state = current_lock_state();
+        if self.lock_state.replace(lock_state) != lock_state {
+            inputs
+                .event_queue
+                .push(Event::LockStateChanged { state: lock_state });
+        }
     }
 }
 
 impl Drop for WindowsPlatform {
     fn drop(&mut self) {
+        self.restore_all_video_modes();
+        self.remove_keyboard_hook();
+        self.destroy_accelerator_table();
+
+        if let Some(hwnd) = self.owner_window.take() {
+            unsafe {
+                _ = DestroyWindow(hwnd);
+            }
+        }
+
+        if let Some(hwnd) = self.message_window.take() {
+            unsafe {
+                _ = DestroyWindow(hwnd);
+                _ = UnregisterClassW(MESSAGE_WINDOW_CLASS, self.hinstance);
+            }
+        }
+
         for (_, string) in self.registered_window_classes.borrow().iter() {
             unsafe {
                 _ = UnregisterClassW(PCWSTR(string.as_ptr()), self.hinstance);
@@ -209,6 +661,9 @@ impl Drop for WindowsPlatform {
 
         unsafe {
             _ = DeleteObject(self.window_background_brush);
+            for brush in self.background_color_brushes.borrow().values() {
+                _ = DeleteObject(*brush);
+            }
         }
     }
 }
@@ -217,6 +672,8 @@ impl Drop for WindowsPlatform {
 struct WindowClassAttributes {
     allow_close: bool,
     show_drop_shadow: bool,
+    /// The background brush is part of the class, so each color needs its own.
+    background_color: Option<(u8, u8, u8)>,
 }
 
 impl WindowClassAttributes {
@@ -233,36 +690,14 @@ impl WindowClassAttributes {
     }
 }
 
-unsafe extern "system" fn generic_window_proc(
-    hwnd: HWND,
-    message: u32,
-    wparam: WPARAM,
-    lparam: LPARAM,
-) -> LRESULT {
-    let wptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA);
-    if wptr != 0 {
-        let reference_block = wptr as *const WindowReferenceBlock;
-        if let Some(block) = reference_block.as_ref() {
-            match message {
-                WM_DESTROY => {
-                    todo!("Find a way to pass the OsLoopInputs data to this function from the processing function. Not sure how just yet but will find a way (maybe setting it at the start of each loop on every living window's reference block).");
-                },
-                _ => ()
-            }
-
-
-        }
-    }
-
-    match message {
-        WM_CREATE => {
-            let cs = lparam.0 as *const CREATESTRUCTW;
-            SetWindowLongPtrW(hwnd, GWLP_USERDATA, (*cs).lpCreateParams as isize);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            return LRESULT(0);
-        }
-        _ => ()
+    #[test]
+    fn supported_window_attributes() {
+        // the platform-specific attributes must only be reported by their platform
+        assert!(SUPPORTED_WINDOW_ATTRIBUTES.windows_show_drop_shadow);
+        assert!(!SUPPORTED_WINDOW_ATTRIBUTES.x11_window_type);
     }
-
-    DefWindowProcW(hwnd, message, wparam, lparam)
 }
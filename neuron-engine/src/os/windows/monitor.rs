@@ -0,0 +1,287 @@
+use crate::os::monitor::{MonitorHandle, VideoMode, sort_video_modes};
+use crate::os::window::WindowPosition;
+use crate::os::windows::WindowsPlatform;
+use anyhow::bail;
+use widestring::U16CString;
+use windows::Win32::Devices::Display::{
+    DISPLAYCONFIG_DEVICE_INFO_GET_ADVANCED_COLOR_INFO, DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME,
+    DISPLAYCONFIG_GET_ADVANCED_COLOR_INFO, DISPLAYCONFIG_MODE_INFO, DISPLAYCONFIG_PATH_INFO,
+    DISPLAYCONFIG_SOURCE_DEVICE_NAME, DisplayConfigGetDeviceInfo, GetDisplayConfigBufferSizes,
+    QDC_ONLY_ACTIVE_PATHS, QueryDisplayConfig,
+};
+use windows::Win32::Foundation::{BOOL, ERROR_SUCCESS, HWND, LPARAM, RECT, TRUE};
+use windows::Win32::Graphics::Gdi::{
+    BITSPIXEL, CDS_FULLSCREEN, CDS_TYPE, ChangeDisplaySettingsExW, CreateDCW, DEVMODEW,
+    DISP_CHANGE_SUCCESSFUL, DM_BITSPERPEL, DM_DISPLAYFREQUENCY, DM_PELSHEIGHT, DM_PELSWIDTH,
+    DeleteDC, ENUM_DISPLAY_SETTINGS_MODE, EnumDisplayMonitors, EnumDisplaySettingsW, GetDeviceCaps,
+    GetMonitorInfoW, HDC, HMONITOR, MONITORINFO, MONITORINFOEXW,
+};
+use windows::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+use windows::core::PCWSTR;
+
+#[inline]
+fn rect_size(rect: &RECT) -> (u32, u32) {
+    (
+        (rect.right - rect.left).max(0) as u32,
+        (rect.bottom - rect.top).max(0) as u32,
+    )
+}
+
+/// Build a [`MonitorHandle`] from a native monitor handle.
+pub(super) fn monitor_handle(hmonitor: HMONITOR) -> Option<MonitorHandle> {
+    let mut info_ex = MONITORINFOEXW::default();
+    info_ex.monitorInfo.cbSize = size_of::<MONITORINFOEXW>() as u32;
+
+    unsafe {
+        if !GetMonitorInfoW(
+            hmonitor,
+            &mut info_ex as *mut MONITORINFOEXW as *mut MONITORINFO,
+        )
+        .as_bool()
+        {
+            return None;
+        }
+    }
+
+    let info = info_ex.monitorInfo;
+
+    let mut dpix: u32 = 96;
+    let mut dpiy: u32 = 96;
+
+    unsafe {
+        _ = GetDpiForMonitor(hmonitor, MDT_EFFECTIVE_DPI, &mut dpix, &mut dpiy);
+    }
+
+    Some(MonitorHandle {
+        name: device_name(&info_ex.szDevice),
+        position: WindowPosition {
+            x: info.rcMonitor.left,
+            y: info.rcMonitor.top,
+        },
+        size: rect_size(&info.rcMonitor),
+        work_area_position: WindowPosition {
+            x: info.rcWork.left,
+            y: info.rcWork.top,
+        },
+        work_area_size: rect_size(&info.rcWork),
+        scale_factor: dpix as f64 / 96.0,
+        bit_depth: bit_depth(&info_ex.szDevice),
+        hdr_enabled: advanced_color_enabled(&info_ex.szDevice),
+        video_modes: video_modes(&info_ex.szDevice),
+    })
+}
+
+/// Every monitor, in the order the system enumerates them.
+pub(super) fn all_monitor_handles() -> Vec<MonitorHandle> {
+    unsafe extern "system" fn collect(
+        hmonitor: HMONITOR,
+        _hdc: HDC,
+        _rect: *mut RECT,
+        data: LPARAM,
+    ) -> BOOL {
+        let hmonitors = unsafe { &mut *(data.0 as *mut Vec<HMONITOR>) };
+        hmonitors.push(hmonitor);
+        TRUE
+    }
+
+    let mut hmonitors = Vec::new();
+    unsafe {
+        _ = EnumDisplayMonitors(
+            HDC::default(),
+            None,
+            Some(collect),
+            LPARAM(&mut hmonitors as *mut Vec<HMONITOR> as isize),
+        );
+    }
+
+    hmonitors.into_iter().filter_map(monitor_handle).collect()
+}
+
+/// The device name without the nul padding.
+fn device_name(device: &[u16; 32]) -> String {
+    String::from_utf16_lossy(device.split(|c| *c == 0).next().unwrap_or_default())
+}
+
+/// All display modes of the display device.
+fn video_modes(device: &[u16; 32]) -> Vec<VideoMode> {
+    let monitor_name = device_name(device);
+    let mut modes = Vec::new();
+    let mut devmode = DEVMODEW {
+        dmSize: size_of::<DEVMODEW>() as u16,
+        ..Default::default()
+    };
+
+    // modes are numbered from 0, the first index that fails is the end of the list
+    let mut index = 0;
+    while unsafe {
+        EnumDisplaySettingsW(
+            PCWSTR(device.as_ptr()),
+            ENUM_DISPLAY_SETTINGS_MODE(index),
+            &mut devmode,
+        )
+    }
+    .as_bool()
+    {
+        modes.push(VideoMode {
+            size: (devmode.dmPelsWidth, devmode.dmPelsHeight),
+            bit_depth: devmode.dmBitsPerPel,
+            // reported in whole hertz (0 and 1 mean "hardware default")
+            refresh_rate_millihertz: devmode.dmDisplayFrequency.saturating_mul(1000),
+            monitor_name: monitor_name.clone(),
+        });
+        index += 1;
+    }
+
+    sort_video_modes(&mut modes);
+    modes
+}
+
+impl WindowsPlatform {
+    /// Switch the display device a mode belongs to over to that mode until [`WindowsPlatform::restore_video_mode`] (or `Drop`).
+    pub(super) fn set_video_mode(&self, mode: &VideoMode) -> anyhow::Result<()> {
+        let device = U16CString::from_str(&mode.monitor_name)?;
+        let devmode = DEVMODEW {
+            dmSize: size_of::<DEVMODEW>() as u16,
+            dmFields: DM_PELSWIDTH | DM_PELSHEIGHT | DM_BITSPERPEL | DM_DISPLAYFREQUENCY,
+            dmPelsWidth: mode.size.0,
+            dmPelsHeight: mode.size.1,
+            dmBitsPerPel: mode.bit_depth,
+            dmDisplayFrequency: mode.refresh_rate_millihertz / 1000,
+            ..Default::default()
+        };
+
+        // CDS_FULLSCREEN keeps the mode out of the registry, so the desktop mode is what comes back if we never get to restore it
+        let result = unsafe {
+            ChangeDisplaySettingsExW(
+                PCWSTR(device.as_ptr()),
+                Some(&devmode),
+                HWND::default(),
+                CDS_FULLSCREEN,
+                None,
+            )
+        };
+        if result != DISP_CHANGE_SUCCESSFUL {
+            bail!(
+                "Failed to switch {} to {:?} ({:?})",
+                mode.monitor_name,
+                mode,
+                result
+            );
+        }
+
+        self.changed_video_modes
+            .borrow_mut()
+            .insert(mode.monitor_name.clone());
+        Ok(())
+    }
+
+    /// Put a display device back into its registry (desktop) mode.
+    pub(super) fn restore_video_mode(&self, monitor_name: &str) {
+        if !self.changed_video_modes.borrow_mut().remove(monitor_name) {
+            return;
+        }
+
+        let Ok(device) = U16CString::from_str(monitor_name) else {
+            return;
+        };
+
+        unsafe {
+            _ = ChangeDisplaySettingsExW(
+                PCWSTR(device.as_ptr()),
+                None,
+                HWND::default(),
+                CDS_TYPE(0),
+                None,
+            );
+        }
+    }
+
+    /// Restore every display device whose mode was changed.
+    pub(super) fn restore_all_video_modes(&self) {
+        let monitor_names: Vec<String> =
+            self.changed_video_modes.borrow().iter().cloned().collect();
+        for monitor_name in monitor_names {
+            self.restore_video_mode(&monitor_name);
+        }
+    }
+}
+
+/// Bits per pixel of the display device (e.g. `\\.\DISPLAY1`).
+fn bit_depth(device: &[u16; 32]) -> u32 {
+    unsafe {
+        let hdc = CreateDCW(
+            PCWSTR(device.as_ptr()),
+            PCWSTR(device.as_ptr()),
+            PCWSTR::null(),
+            None,
+        );
+        if hdc.is_invalid() {
+            return 0;
+        }
+
+        let bits = GetDeviceCaps(hdc, BITSPIXEL);
+        _ = DeleteDC(hdc);
+
+        bits.max(0) as u32
+    }
+}
+
+/// Whether advanced color (HDR) is enabled for the display device.
+///
+/// Monitors are matched to display config paths by their GDI device name.
+fn advanced_color_enabled(device: &[u16; 32]) -> bool {
+    let mut path_count = 0;
+    let mut mode_count = 0;
+
+    unsafe {
+        if GetDisplayConfigBufferSizes(QDC_ONLY_ACTIVE_PATHS, &mut path_count, &mut mode_count)
+            != ERROR_SUCCESS
+        {
+            return false;
+        }
+
+        let mut paths = vec![DISPLAYCONFIG_PATH_INFO::default(); path_count as usize];
+        let mut modes = vec![DISPLAYCONFIG_MODE_INFO::default(); mode_count as usize];
+
+        if QueryDisplayConfig(
+            QDC_ONLY_ACTIVE_PATHS,
+            &mut path_count,
+            paths.as_mut_ptr(),
+            &mut mode_count,
+            modes.as_mut_ptr(),
+            None,
+        ) != ERROR_SUCCESS
+        {
+            return false;
+        }
+
+        for path in &paths[..path_count as usize] {
+            let mut source = DISPLAYCONFIG_SOURCE_DEVICE_NAME::default();
+            source.header.r#type = DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME;
+            source.header.size = size_of::<DISPLAYCONFIG_SOURCE_DEVICE_NAME>() as u32;
+            source.header.adapterId = path.sourceInfo.adapterId;
+            source.header.id = path.sourceInfo.id;
+
+            if DisplayConfigGetDeviceInfo(&mut source.header) != 0
+                || source.viewGdiDeviceName != *device
+            {
+                continue;
+            }
+
+            let mut color = DISPLAYCONFIG_GET_ADVANCED_COLOR_INFO::default();
+            color.header.r#type = DISPLAYCONFIG_DEVICE_INFO_GET_ADVANCED_COLOR_INFO;
+            color.header.size = size_of::<DISPLAYCONFIG_GET_ADVANCED_COLOR_INFO>() as u32;
+            color.header.adapterId = path.targetInfo.adapterId;
+            color.header.id = path.targetInfo.id;
+
+            if DisplayConfigGetDeviceInfo(&mut color.header) != 0 {
+                return false;
+            }
+
+            // bit 0 is advancedColorSupported, bit 1 advancedColorEnabled
+            return color.Anonymous.value & 0b10 != 0;
+        }
+    }
+
+    false
+}
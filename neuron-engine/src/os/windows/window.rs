@@ -1,28 +1,135 @@
-use crate::os::Platform;
-use crate::os::window::{Resolution, Window, WindowAttributes, WindowId};
+use crate::event::{EventQueue, Modifiers};
+use crate::os::OsLoopInputs;
+use crate::os::placement::SavedPlacement;
+use crate::os::window::{
+    Anchor, CompositorBypass, CursorGrabMode, CursorIcon, DragData, DragEffect, FrameStats,
+    Fullscreen, InitialZOrder, InteractionState, Position, PresentMode, ResizePaintCallback,
+    ResizePaintSlot, Resolution, SnapRegion, ThumbButton, UserAttention, Window, WindowAttributes,
+    WindowIcon, WindowId, WindowManager, WindowPosition,
+};
 use crate::os::windows::{WindowClassAttributes, WindowsPlatform};
+use log::{debug, warn};
 use raw_window_handle::{
-    HandleError, HasWindowHandle, RawWindowHandle, Win32WindowHandle, WindowHandle,
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, RawWindowHandle,
+    Win32WindowHandle, WindowHandle,
 };
+use std::cell::{Cell, RefCell};
 use std::ffi::c_void;
 use std::num::NonZeroIsize;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 use widestring::U16CString;
-use windows::Win32::Foundation::{HINSTANCE, HWND, POINT, RECT};
-use windows::Win32::UI::WindowsAndMessaging::{CW_USEDEFAULT, CreateWindowExW, HMENU, WINDOW_EX_STYLE, WINDOW_STYLE, WS_EX_OVERLAPPEDWINDOW, WS_MINIMIZEBOX, WS_OVERLAPPEDWINDOW, AdjustWindowRectEx};
+use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, POINT, RECT, WPARAM};
+use windows::Win32::Graphics::Dwm::{
+    DWM_BB_BLURREGION, DWM_BB_ENABLE, DWM_BLURBEHIND, DWM_TIMING_INFO, DWMWA_EXTENDED_FRAME_BOUNDS,
+    DwmEnableBlurBehindWindow, DwmGetCompositionTimingInfo, DwmGetWindowAttribute,
+};
+use windows::Win32::Graphics::Gdi::{
+    ClientToScreen, CreateRectRgn, DeleteObject, GetMonitorInfoW, InvalidateRect,
+    MONITOR_DEFAULTTONEAREST, MONITOR_DEFAULTTOPRIMARY, MONITORINFO, MonitorFromPoint,
+    MonitorFromWindow,
+};
+use windows::Win32::System::Performance::{QueryPerformanceCounter, QueryPerformanceFrequency};
+use windows::Win32::UI::Accessibility::PROPID_ACC_NAME;
+use windows::Win32::UI::HiDpi::{
+    AdjustWindowRectExForDpi, GetDpiForMonitor, GetDpiForWindow, MDT_EFFECTIVE_DPI,
+};
+use windows::Win32::UI::Input::Ime::{
+    CANDIDATEFORM, CFS_CANDIDATEPOS, CFS_POINT, COMPOSITIONFORM, ImmGetContext, ImmReleaseContext,
+    ImmSetCandidateWindow, ImmSetCompositionWindow,
+};
+use windows::Win32::UI::Input::KeyboardAndMouse::GetFocus;
+use windows::Win32::UI::Input::Touch::{REGISTER_TOUCH_WINDOW_FLAGS, RegisterTouchWindow};
+use windows::Win32::UI::Shell::{
+    THB_FLAGS, THB_ICON, THB_TOOLTIP, THBF_DISABLED, THBF_ENABLED, THBF_HIDDEN, THUMBBUTTON,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    AdjustWindowRectEx, BringWindowToTop, CHILDID_SELF, CW_USEDEFAULT, ClipCursor, CreateIcon,
+    CreateWindowExW, DestroyIcon, DestroyWindow, FLASHW_ALL, FLASHW_STOP, FLASHW_TIMERNOFG,
+    FLASHW_TRAY, FLASHWINFO, FlashWindowEx, GWL_EXSTYLE, GWL_STYLE, GetClientRect,
+    GetForegroundWindow, GetWindowLongPtrW, GetWindowPlacement, GetWindowRect, HICON, HMENU,
+    HWND_BOTTOM, HWND_NOTOPMOST, HWND_TOP, HWND_TOPMOST, ICON_BIG, ICON_SMALL, IDC_APPSTARTING,
+    IDC_ARROW, IDC_CROSS, IDC_HAND, IDC_HELP, IDC_IBEAM, IDC_NO, IDC_SIZEALL, IDC_SIZENESW,
+    IDC_SIZENS, IDC_SIZENWSE, IDC_SIZEWE, IDC_WAIT, IsIconic, IsWindowVisible, IsZoomed,
+    LoadCursorW, MINMAXINFO, OBJECT_IDENTIFIER, OBJID_CLIENT, OBJID_WINDOW, SW_HIDE, SW_MAXIMIZE,
+    SW_MINIMIZE, SW_RESTORE, SW_SHOW, SW_SHOWMINIMIZED, SW_SHOWMINNOACTIVE, SW_SHOWNA,
+    SW_SHOWNOACTIVATE, SW_SHOWNORMAL, SWP_FRAMECHANGED, SWP_NOACTIVATE, SWP_NOMOVE,
+    SWP_NOOWNERZORDER, SWP_NOSIZE, SWP_NOZORDER, SendMessageW, SetCursor, SetCursorPos,
+    SetForegroundWindow, SetWindowLongPtrW, SetWindowPlacement, SetWindowPos, SetWindowTextW,
+    ShowCursor, ShowWindow, WINDOW_EX_STYLE, WINDOW_STYLE, WINDOWPLACEMENT, WM_SETICON, WS_BORDER,
+    WS_CAPTION, WS_DLGFRAME, WS_EX_APPWINDOW, WS_EX_TOOLWINDOW, WS_MAXIMIZEBOX, WS_MINIMIZEBOX,
+    WS_POPUP, WS_SYSMENU, WS_THICKFRAME, WS_VISIBLE,
+};
 use windows::core::PCWSTR;
-use windows::Win32::Graphics::Gdi::{MonitorFromPoint, MONITOR_DEFAULTTOPRIMARY};
-use windows::Win32::UI::HiDpi::{AdjustWindowRectExForDpi, GetDpiForMonitor, MDT_EFFECTIVE_DPI};
 
 pub(super) struct WindowsWindow {
     handle: HWND,
     id: WindowId,
+    // mostly read through GWLP_USERDATA, and has to live as long as the window
     reference_block: Box<WindowReferenceBlock>,
 }
 
 pub(super) struct WindowReferenceBlock {
     pub id: WindowId,
     pub platform: Arc<WindowsPlatform>,
+    pub event_queue: Arc<EventQueue>,
+    /// Lets the window procedure start closing the window. Weak because the window manager owns the window (and with it this block).
+    pub window_manager: Weak<WindowManager>,
+    pub cursor_inside: Cell<bool>,
+    pub key_repeat: Cell<bool>,
+    /// Last reported modifier keys, see [`Event::ModifiersChanged`](crate::event::Event::ModifiersChanged).
+    pub modifiers: Cell<Modifiers>,
+    pub present_mode: Cell<Option<PresentMode>>,
+    /// Whether the user is moving or resizing the window (between `WM_ENTERSIZEMOVE` and `WM_EXITSIZEMOVE`).
+    pub in_size_move: Cell<bool>,
+    pub resized_during_size_move: Cell<bool>,
+    /// Whether the size/move loop turned out to be a resize or a move, see [`Window::interaction_state`].
+    pub interaction: Cell<InteractionState>,
+    /// See [`Window::set_resize_paint_callback`].
+    pub resize_paint: ResizePaintSlot,
+    /// Minimized or hidden.
+    pub occluded: Cell<bool>,
+    pub auto_hide_on_focus_loss: bool,
+    /// See [`WindowAttributes::transparent`], background erasing is skipped for these.
+    pub transparent: bool,
+    /// Whether the window had a caption or sizing border the last time its frame changed.
+    pub decorated: Cell<bool>,
+    /// Buttons of the taskbar thumbnail toolbar, the button at index `i` is registered with the taskbar as id `i`.
+    pub thumb_buttons: RefCell<Vec<ThumbButton>>,
+    /// Whether the taskbar button exists yet (thumbnail buttons can only be added after `TaskbarButtonCreated`).
+    pub taskbar_button_created: Cell<bool>,
+    /// Whether the thumbnail toolbar slots were added (this can only happen once per window, later changes are updates).
+    pub thumb_buttons_added: Cell<bool>,
+    /// See [`WindowAttributes::persistence_key`].
+    pub persistence_key: Option<String>,
+    /// See [`Window::set_cursor_confined`]. Windows drops the clip rectangle whenever another window is activated, so the window procedure sets it again on activation.
+    pub cursor_confined: Cell<bool>,
+    /// See [`CursorGrabMode::Locked`], the window procedure moves the cursor back to the center after every move.
+    pub cursor_locked: Cell<bool>,
+    /// See [`Window::set_cursor_icon`], set again on every `WM_SETCURSOR` (the window class has no cursor).
+    pub cursor_icon: Cell<CursorIcon>,
+    /// How often [`Window::set_cursor_visible`] decremented the thread's cursor display counter, so exactly that is given back.
+    pub cursor_hide_count: Cell<u32>,
+    /// Whether accessibility properties were annotated, they have to be cleared before the window is gone.
+    pub accessible_name_set: Cell<bool>,
+    /// See [`WindowAttributes::resizable`].
+    pub resizable: Cell<bool>,
+    /// See [`WindowAttributes::has_maximize_button`], the button is only shown while the window is resizable after [`Window::set_resizable`].
+    pub maximize_button: bool,
+    /// See [`WindowAttributes::min_size`], converted to pixels for the DPI the window has when the limit is needed (so logical limits follow the window between monitors).
+    pub min_size: Cell<Option<Resolution<u32>>>,
+    /// See [`WindowAttributes::max_size`].
+    pub max_size: Cell<Option<Resolution<u32>>>,
+    /// The first half of a surrogate pair, characters outside the BMP arrive as two `WM_CHAR` messages.
+    pub high_surrogate: Cell<Option<u16>>,
+    /// See [`Window::set_fullscreen`].
+    pub fullscreen: RefCell<Option<Fullscreen>>,
+    /// Style and placement from before the window went fullscreen, put back when it leaves fullscreen.
+    pub windowed_placement: Cell<Option<(WINDOW_STYLE, WINDOWPLACEMENT)>>,
+    /// The icon set with [`Window::set_icon`], destroyed once it is replaced or the window is gone.
+    pub icon: Cell<Option<HICON>>,
+    /// Icons of the thumbnail toolbar buttons, the taskbar uses them until the buttons are updated.
+    pub thumb_button_icons: RefCell<Vec<HICON>>,
 }
 
 impl HasWindowHandle for WindowsWindow {
@@ -35,56 +142,1275 @@ impl HasWindowHandle for WindowsWindow {
     }
 }
 
-impl Window for WindowsWindow {}
+impl HasDisplayHandle for WindowsWindow {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        self.reference_block.platform.display_handle()
+    }
+}
 
-fn r2s(res: Resolution<u32>, style: WINDOW_STYLE, ex_style: WINDOW_EX_STYLE, position: (i32, i32)) -> (i32, i32) {
-    match res {
-        Resolution::Physical {width, height } => {
+impl Window for WindowsWindow {
+    fn inner_size(&self) -> Resolution<u32> {
+        let (width, height) = self.client_size();
+        Resolution::Physical {
+            width: width.max(0) as u32,
+            height: height.max(0) as u32,
+        }
+    }
+
+    fn position(&self) -> WindowPosition {
+        let mut origin = POINT::default();
+        unsafe {
+            _ = ClientToScreen(self.handle, &mut origin);
+        }
+        WindowPosition {
+            x: origin.x,
+            y: origin.y,
+        }
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.dpi() as f64 / 96.0
+    }
+
+    fn set_inner_size(&self, size: Resolution<u32>) {
+        if !self.reference_block.resizable.get() {
+            warn!(
+                "Window {:?} isn't resizable, not changing its size",
+                self.id
+            );
+            return;
+        }
+
+        self.set_client_size(size);
+    }
+
+    fn set_position(&self, position: WindowPosition) {
+        // SetWindowPos places the outer rect, so offset by how far into it the client area starts
+        let mut outer = RECT::default();
+        let mut origin = POINT::default();
+        unsafe {
+            if GetWindowRect(self.handle, &mut outer).is_err()
+                || !ClientToScreen(self.handle, &mut origin).as_bool()
+            {
+                return;
+            }
+
+            _ = SetWindowPos(
+                self.handle,
+                HWND::default(),
+                position.x - (origin.x - outer.left),
+                position.y - (origin.y - outer.top),
+                0,
+                0,
+                SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+            );
+        }
+    }
+
+    fn set_preferred_present_mode(&self, mode: Option<PresentMode>) {
+        self.reference_block.present_mode.set(mode);
+    }
+
+    fn preferred_present_mode(&self) -> Option<PresentMode> {
+        self.reference_block.present_mode.get()
+    }
+
+    fn set_ime_position(&self, x: i32, y: i32) {
+        let position = POINT { x, y };
+
+        unsafe {
+            let himc = ImmGetContext(self.handle);
+            if himc.is_invalid() {
+                return;
+            }
+
+            let composition = COMPOSITIONFORM {
+                dwStyle: CFS_POINT,
+                ptCurrentPos: position,
+                rcArea: RECT::default(),
+            };
+            _ = ImmSetCompositionWindow(himc, &composition);
+
+            let candidate = CANDIDATEFORM {
+                dwIndex: 0,
+                dwStyle: CFS_CANDIDATEPOS,
+                ptCurrentPos: position,
+                rcArea: RECT::default(),
+            };
+            _ = ImmSetCandidateWindow(himc, &candidate);
+
+            _ = ImmReleaseContext(self.handle, himc);
+        }
+    }
+
+    fn set_compositor_bypass(&self, bypass: CompositorBypass) {
+        // DWM bypasses composition for fullscreen flip-model swapchains on its own
+        debug!("Ignoring compositor bypass hint {:?}", bypass);
+    }
+
+    fn set_key_repeat(&self, enabled: bool) {
+        self.reference_block.key_repeat.set(enabled);
+    }
+
+    fn frame_stats(&self) -> FrameStats {
+        let mut timing = DWM_TIMING_INFO {
+            cbSize: size_of::<DWM_TIMING_INFO>() as u32,
+            ..Default::default()
+        };
+        let (mut now, mut frequency) = (0i64, 0i64);
+
+        unsafe {
+            // the timing is global since Windows 8.1, a window handle is an error
+            if let Err(e) = DwmGetCompositionTimingInfo(HWND::default(), &mut timing) {
+                debug!("Failed to query the composition timing: {}", e);
+                return FrameStats::default();
+            }
+            _ = QueryPerformanceCounter(&mut now);
+            _ = QueryPerformanceFrequency(&mut frequency);
+        }
+
+        if frequency <= 0 {
+            return FrameStats::default();
+        }
+
+        let qpc_duration = |ticks: u64| Duration::from_secs_f64(ticks as f64 / frequency as f64);
+        let instant_now = Instant::now();
+        // the vblank can be the upcoming one
+        let last_present = match (now as u64).checked_sub(timing.qpcVBlank) {
+            Some(ago) => instant_now.checked_sub(qpc_duration(ago)),
+            None => instant_now.checked_add(qpc_duration(timing.qpcVBlank - now as u64)),
+        };
+
+        FrameStats {
+            last_present,
+            refresh_period: Some(qpc_duration(timing.qpcRefreshPeriod))
+                .filter(|period| !period.is_zero()),
+            dropped: Some(timing.cFramesDropped),
+            measured: true,
+        }
+    }
+
+    fn start_drag(&self, data: DragData, effect: DragEffect) {
+        super::drag::run_drag(&self.reference_block, &data, effect);
+    }
+
+    fn set_accessible_name(&self, name: &str) {
+        let Some(services) = self.reference_block.platform.acc_prop_services() else {
+            return;
+        };
+        let Ok(name) = U16CString::from_str(name) else {
+            return;
+        };
+
+        // the window object is what gets announced on focus changes, the client object what is navigated into
+        for object in ACCESSIBLE_OBJECTS {
+            let result = unsafe {
+                services.SetHwndPropStr(
+                    self.handle,
+                    object.0 as u32,
+                    CHILDID_SELF,
+                    PROPID_ACC_NAME,
+                    PCWSTR(name.as_ptr()),
+                )
+            };
+            if let Err(e) = result {
+                debug!("Failed to set the accessible name of {:?}: {}", self.id, e);
+                return;
+            }
+        }
+
+        self.reference_block.accessible_name_set.set(true);
+    }
+
+    fn set_cursor_visible(&self, visible: bool) {
+        let block = &self.reference_block;
+        unsafe {
+            if visible {
+                for _ in 0..block.cursor_hide_count.take() {
+                    ShowCursor(true);
+                }
+            } else if block.cursor_hide_count.get() == 0 {
+                // the cursor is only hidden once the counter is below zero, which takes more than one call if something else showed it too
+                let mut hide_count = 1;
+                while ShowCursor(false) >= 0 {
+                    hide_count += 1;
+                }
+                block.cursor_hide_count.set(hide_count);
+            }
+        }
+    }
+
+    fn set_cursor_icon(&self, icon: CursorIcon) {
+        self.reference_block.cursor_icon.set(icon);
+
+        // otherwise the change only shows once the cursor moves
+        if self.reference_block.cursor_inside.get() {
             unsafe {
-                let mut r = RECT {
-                    top: 0,
-                    left: 0,
-                    bottom: height as i32,
-                    right: width as i32,
+                apply_cursor_icon(&self.reference_block);
+            }
+        }
+    }
+
+    fn set_cursor_grab(&self, mode: CursorGrabMode) {
+        self.set_cursor_confined(mode != CursorGrabMode::None);
+        self.reference_block
+            .cursor_locked
+            .set(mode == CursorGrabMode::Locked);
+
+        unsafe {
+            if mode == CursorGrabMode::Locked && GetForegroundWindow() == self.handle {
+                center_cursor(self.handle);
+            }
+        }
+    }
+
+    fn set_cursor_confined(&self, confined: bool) {
+        self.reference_block.cursor_confined.set(confined);
+
+        unsafe {
+            // the clip rectangle is global, only touch it while we're the one that set it
+            if GetForegroundWindow() != self.handle {
+                return;
+            }
+
+            if confined {
+                clip_cursor_to_client(self.handle);
+            } else {
+                _ = ClipCursor(None);
+            }
+        }
+    }
+
+    fn set_resize_paint_callback(&self, callback: Option<ResizePaintCallback>) {
+        self.reference_block.resize_paint.set(callback);
+    }
+
+    fn set_skip_taskbar(&self, skip: bool) {
+        let (_, ex_style) = self.styles();
+        let ex_style = if skip {
+            (ex_style & !WS_EX_APPWINDOW) | WS_EX_TOOLWINDOW
+        } else {
+            (ex_style & !WS_EX_TOOLWINDOW) | WS_EX_APPWINDOW
+        };
+
+        unsafe {
+            // the taskbar only picks up the new styles when the window is shown again
+            let visible = IsWindowVisible(self.handle).as_bool();
+            if visible {
+                _ = ShowWindow(self.handle, SW_HIDE);
+            }
+
+            SetWindowLongPtrW(self.handle, GWL_EXSTYLE, ex_style.0 as isize);
+
+            if visible {
+                _ = ShowWindow(self.handle, SW_SHOWNA);
+            }
+        }
+    }
+
+    fn is_occluded(&self) -> bool {
+        self.reference_block.occluded.get()
+    }
+
+    fn has_focus(&self) -> bool {
+        unsafe { GetFocus() == self.handle }
+    }
+
+    fn set_keyboard_shortcuts_inhibited(&self, inhibited: bool) {
+        if let Err(e) = self
+            .reference_block
+            .platform
+            .set_keyboard_shortcuts_inhibited(self.handle, inhibited)
+        {
+            debug!(
+                "Failed to install the keyboard hook to inhibit shortcuts: {}",
+                e
+            );
+        }
+    }
+
+    fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+        let block = &self.reference_block;
+        let previous = block.fullscreen.replace(fullscreen.clone());
+
+        // a new mode for the same monitor replaces the old one, restoring first would only flicker through the desktop mode
+        if let Some(Fullscreen::Exclusive(previous_mode)) = &previous
+            && !matches!(&fullscreen, Some(Fullscreen::Exclusive(mode)) if mode.monitor_name == previous_mode.monitor_name)
+        {
+            block
+                .platform
+                .restore_video_mode(&previous_mode.monitor_name);
+        }
+
+        let Some(fullscreen) = fullscreen else {
+            self.leave_fullscreen();
+            return;
+        };
+
+        let monitor = match &fullscreen {
+            Fullscreen::Borderless(monitor) => monitor.clone(),
+            Fullscreen::Exclusive(mode) => {
+                let switched = block.platform.set_video_mode(mode);
+
+                // the monitor's geometry changed with the mode, so it is looked up afterwards
+                let monitor = super::monitor::all_monitor_handles()
+                    .into_iter()
+                    .find(|monitor| monitor.name == mode.monitor_name);
+                if let Err(e) = switched {
+                    warn!(
+                        "Failed to switch to {:?}, using borderless fullscreen instead: {}",
+                        mode, e
+                    );
+                    *block.fullscreen.borrow_mut() = Some(Fullscreen::Borderless(monitor.clone()));
+                }
+                monitor
+            }
+        }
+        .or_else(|| {
+            super::monitor::monitor_handle(unsafe {
+                MonitorFromWindow(self.handle, MONITOR_DEFAULTTONEAREST)
+            })
+        });
+
+        let Some(monitor) = monitor else {
+            warn!(
+                "Window {:?} isn't on any monitor, not making it fullscreen",
+                self.id
+            );
+            *block.fullscreen.borrow_mut() = None;
+            return;
+        };
+
+        unsafe {
+            let (style, _) = self.styles();
+
+            if previous.is_none() {
+                let mut placement = WINDOWPLACEMENT {
+                    length: size_of::<WINDOWPLACEMENT>() as u32,
+                    ..Default::default()
                 };
+                _ = GetWindowPlacement(self.handle, &mut placement);
+                block.windowed_placement.set(Some((style, placement)));
+            }
+
+            let frame = WS_CAPTION
+                | WS_DLGFRAME
+                | WS_BORDER
+                | WS_THICKFRAME
+                | WS_SYSMENU
+                | WS_MINIMIZEBOX
+                | WS_MAXIMIZEBOX;
+            SetWindowLongPtrW(
+                self.handle,
+                GWL_STYLE,
+                ((style & !frame) | WS_POPUP).0 as isize,
+            );
+
+            _ = SetWindowPos(
+                self.handle,
+                HWND_TOP,
+                monitor.position.x,
+                monitor.position.y,
+                monitor.size.0 as i32,
+                monitor.size.1 as i32,
+                SWP_FRAMECHANGED | SWP_NOOWNERZORDER,
+            );
+        }
+    }
+
+    fn fullscreen(&self) -> Option<Fullscreen> {
+        self.reference_block.fullscreen.borrow().clone()
+    }
 
-                _ = AdjustWindowRectEx(&mut r, style, false, ex_style);
-                (r.right - r.left, r.bottom - r.top)
+    fn set_icon(&self, icon: Option<WindowIcon>) {
+        let hicon = match icon.as_ref().map(create_icon).transpose() {
+            Ok(hicon) => hicon,
+            Err(e) => {
+                warn!("Failed to create the icon of window {:?}: {}", self.id, e);
+                return;
             }
-        },
-        Resolution::Logical {width, height} => {
+        };
+
+        // the window shows the icon scaled for both sizes, a null icon goes back to the class icon
+        let lparam = LPARAM(hicon.map_or(0, |hicon| hicon.0 as isize));
+        unsafe {
+            SendMessageW(self.handle, WM_SETICON, WPARAM(ICON_BIG as usize), lparam);
+            SendMessageW(self.handle, WM_SETICON, WPARAM(ICON_SMALL as usize), lparam);
+        }
+
+        if let Some(previous) = self.reference_block.icon.replace(hicon) {
             unsafe {
-                let mut r = RECT {
-                    top: 0,
-                    left: 0,
-                    bottom: height as i32,
-                    right: width as i32,
-                };
+                _ = DestroyIcon(previous);
+            }
+        }
+    }
+
+    fn set_title(&self, title: &str) -> anyhow::Result<()> {
+        let title = U16CString::from_str(title)?;
+
+        unsafe {
+            SetWindowTextW(self.handle, PCWSTR(title.as_ptr()))?;
+        }
+
+        Ok(())
+    }
+
+    fn show(&self) {
+        unsafe {
+            _ = ShowWindow(self.handle, SW_SHOW);
+        }
+    }
+
+    fn hide(&self) {
+        unsafe {
+            _ = ShowWindow(self.handle, SW_HIDE);
+        }
+    }
+
+    fn is_visible(&self) -> bool {
+        // this is the WS_VISIBLE style, minimized and covered windows keep it
+        unsafe { IsWindowVisible(self.handle).as_bool() }
+    }
+
+    fn set_always_on_top(&self, always_on_top: bool) {
+        set_topmost(self.handle, always_on_top);
+    }
+
+    fn focus_window(&self) {
+        unsafe {
+            if IsIconic(self.handle).as_bool() {
+                _ = ShowWindow(self.handle, SW_RESTORE);
+            }
+            _ = BringWindowToTop(self.handle);
+            // fails (and flashes the taskbar button instead) unless this process is allowed to take the foreground
+            if !SetForegroundWindow(self.handle).as_bool() {
+                debug!("Window {:?} wasn't allowed to take the foreground", self.id);
+            }
+        }
+    }
+
+    fn request_user_attention(&self, kind: Option<UserAttention>) {
+        // after flashing a limited number of times the taskbar button stays highlighted until the window is activated
+        let (flags, count) = match kind {
+            Some(UserAttention::Critical) => (FLASHW_ALL | FLASHW_TIMERNOFG, 0),
+            Some(UserAttention::Informational) => (FLASHW_TRAY, 1),
+            None => (FLASHW_STOP, 0),
+        };
+
+        let info = FLASHWINFO {
+            cbSize: size_of::<FLASHWINFO>() as u32,
+            hwnd: self.handle,
+            dwFlags: flags,
+            uCount: count,
+            dwTimeout: 0,
+        };
+        unsafe {
+            _ = FlashWindowEx(&info);
+        }
+    }
+
+    fn set_min_size(&self, size: Option<Resolution<u32>>) {
+        self.reference_block.min_size.set(size);
+        enforce_size_limits(self.handle);
+    }
+
+    fn set_max_size(&self, size: Option<Resolution<u32>>) {
+        self.reference_block.max_size.set(size);
+        enforce_size_limits(self.handle);
+    }
+
+    fn set_resizable(&self, resizable: bool) {
+        let block = &self.reference_block;
+        if block.resizable.replace(resizable) == resizable {
+            return;
+        }
+
+        let update = |style: WINDOW_STYLE| {
+            if resizable {
+                // the maximize box needs a system menu, like at creation
+                if block.maximize_button && style.contains(WS_SYSMENU) {
+                    style | WS_THICKFRAME | WS_MAXIMIZEBOX
+                } else {
+                    style | WS_THICKFRAME
+                }
+            } else {
+                style & !(WS_THICKFRAME | WS_MAXIMIZEBOX)
+            }
+        };
+
+        // a fullscreen window has no frame, the style it gets back when leaving fullscreen is the one to change
+        if let Some((style, placement)) = block.windowed_placement.take() {
+            block
+                .windowed_placement
+                .set(Some((update(style), placement)));
+            return;
+        }
+
+        unsafe {
+            let style = WINDOW_STYLE(GetWindowLongPtrW(self.handle, GWL_STYLE) as u32);
+            SetWindowLongPtrW(self.handle, GWL_STYLE, update(style).0 as isize);
+            _ = SetWindowPos(
+                self.handle,
+                HWND::default(),
+                0,
+                0,
+                0,
+                0,
+                SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOOWNERZORDER | SWP_FRAMECHANGED,
+            );
+        }
+    }
+
+    fn request_redraw(&self) {
+        // invalidated areas accumulate until the next WM_PAINT, which is only generated once the queue is empty
+        unsafe {
+            _ = InvalidateRect(self.handle, None, false);
+        }
+    }
+
+    fn minimize(&self) {
+        unsafe {
+            _ = ShowWindow(self.handle, SW_MINIMIZE);
+        }
+    }
+
+    fn maximize(&self) {
+        unsafe {
+            _ = ShowWindow(self.handle, SW_MAXIMIZE);
+        }
+    }
+
+    fn restore(&self) {
+        unsafe {
+            _ = ShowWindow(self.handle, SW_RESTORE);
+        }
+    }
+
+    fn interaction_state(&self) -> InteractionState {
+        self.reference_block.interaction.get()
+    }
+
+    fn set_restore_placement(&self, position: Position, size: Resolution<u32>) {
+        let (x, y) = physical_position(position);
+        let dpi = self.dpi();
+        let (width, height) = size.physical_size(dpi as f64 / 96.0);
+        let (width, height) =
+            self.outer_size_for_client(width.max(1) as i32, height.max(1) as i32, dpi);
+
+        let mut placement = WINDOWPLACEMENT {
+            length: size_of::<WINDOWPLACEMENT>() as u32,
+            ..Default::default()
+        };
+
+        unsafe {
+            if GetWindowPlacement(self.handle, &mut placement).is_err() {
+                return;
+            }
+
+            // rcNormalPosition is in workspace coordinates (relative to the work area, so a taskbar on the left or top shifts it), except for tool windows
+            let (_, ex_style) = self.styles();
+            let (offset_x, offset_y) = if ex_style.contains(WS_EX_TOOLWINDOW) {
+                (0, 0)
+            } else {
+                workspace_offset(POINT { x, y })
+            };
+
+            placement.rcNormalPosition = RECT {
+                left: x - offset_x,
+                top: y - offset_y,
+                right: x - offset_x + width,
+                bottom: y - offset_y + height,
+            };
+
+            // keep the current show state, without activating or showing the window
+            placement.showCmd = if !IsWindowVisible(self.handle).as_bool() {
+                SW_HIDE.0 as u32
+            } else if placement.showCmd == SW_SHOWNORMAL.0 as u32 {
+                SW_SHOWNOACTIVATE.0 as u32
+            } else if placement.showCmd == SW_SHOWMINIMIZED.0 as u32 {
+                SW_SHOWMINNOACTIVE.0 as u32
+            } else {
+                placement.showCmd
+            };
+
+            _ = SetWindowPlacement(self.handle, &placement);
+        }
+    }
+
+    fn set_thumbnail_toolbar(&self, mut buttons: Vec<ThumbButton>) {
+        if buttons.len() > ThumbButton::MAX_COUNT {
+            debug!(
+                "Dropping {} thumbnail toolbar buttons over the limit",
+                buttons.len() - ThumbButton::MAX_COUNT
+            );
+            buttons.truncate(ThumbButton::MAX_COUNT);
+        }
+
+        *self.reference_block.thumb_buttons.borrow_mut() = buttons;
+        update_thumb_buttons(self.handle, &self.reference_block);
+    }
+
+    fn snap(&self, region: SnapRegion) {
+        unsafe {
+            if region == SnapRegion::Maximize {
+                _ = ShowWindow(self.handle, SW_MAXIMIZE);
+                return;
+            }
+
+            if IsZoomed(self.handle).as_bool() {
+                _ = ShowWindow(self.handle, SW_RESTORE);
+            }
+
+            let Some(monitor) = super::monitor::monitor_handle(MonitorFromWindow(
+                self.handle,
+                MONITOR_DEFAULTTONEAREST,
+            )) else {
+                return;
+            };
+
+            let (x, y, width, height) =
+                region.rect(monitor.work_area_position, monitor.work_area_size);
+            let (left, top, right, bottom) = self.invisible_borders();
+
+            _ = SetWindowPos(
+                self.handle,
+                HWND::default(),
+                x - left,
+                y - top,
+                width as i32 + left + right,
+                height as i32 + top + bottom,
+                SWP_NOZORDER | SWP_NOACTIVATE,
+            );
+        }
+    }
+
+    fn set_size_anchored(&self, size: Resolution<u32>, anchor: Anchor) {
+        let dpi = self.dpi();
+        let (width, height) = size.physical_size(dpi as f64 / 96.0);
+        let (width, height) = (width.max(1) as i32, height.max(1) as i32);
+
+        let mut outer = RECT::default();
+        unsafe {
+            _ = GetWindowRect(self.handle, &mut outer);
+        }
+
+        let (outer_width, outer_height) = self.outer_size_for_client(width, height, dpi);
+        let (dx, dy) = anchor.offset(
+            (
+                (outer.right - outer.left) as u32,
+                (outer.bottom - outer.top) as u32,
+            ),
+            (outer_width as u32, outer_height as u32),
+        );
+
+        unsafe {
+            _ = SetWindowPos(
+                self.handle,
+                HWND::default(),
+                outer.left + dx,
+                outer.top + dy,
+                outer_width,
+                outer_height,
+                SWP_NOZORDER | SWP_NOACTIVATE,
+            );
+        }
+    }
+
+    fn set_client_size(&self, size: Resolution<u32>) {
+        let dpi = self.dpi();
+        let (width, height) = size.physical_size(dpi as f64 / 96.0);
+        let (width, height) = (width.max(1) as i32, height.max(1) as i32);
+
+        let (outer_width, outer_height) = self.outer_size_for_client(width, height, dpi);
+        self.set_outer_size(outer_width, outer_height);
+
+        // menus wrapping or WM_GETMINMAXINFO constraints can still leave the client area off, so correct by the remaining difference once
+        let (actual_width, actual_height) = self.client_size();
+        if (actual_width, actual_height) != (width, height) {
+            debug!(
+                "Window {:?} has client size {:?} instead of {:?}, retrying once",
+                self.id,
+                (actual_width, actual_height),
+                (width, height)
+            );
+            self.set_outer_size(
+                outer_width + (width - actual_width),
+                outer_height + (height - actual_height),
+            );
+        }
+    }
+}
+
+impl WindowsWindow {
+    /// Put back the style and placement saved when the window went fullscreen.
+    fn leave_fullscreen(&self) {
+        let Some((style, placement)) = self.reference_block.windowed_placement.take() else {
+            return;
+        };
+
+        unsafe {
+            SetWindowLongPtrW(self.handle, GWL_STYLE, style.0 as isize);
+            _ = SetWindowPlacement(self.handle, &placement);
+            _ = SetWindowPos(
+                self.handle,
+                HWND::default(),
+                0,
+                0,
+                0,
+                0,
+                SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOOWNERZORDER | SWP_FRAMECHANGED,
+            );
+        }
+    }
+
+    fn dpi(&self) -> u32 {
+        match unsafe { GetDpiForWindow(self.handle) } {
+            0 => 96,
+            dpi => dpi,
+        }
+    }
+
+    fn styles(&self) -> (WINDOW_STYLE, WINDOW_EX_STYLE) {
+        unsafe {
+            (
+                WINDOW_STYLE(GetWindowLongPtrW(self.handle, GWL_STYLE) as u32),
+                WINDOW_EX_STYLE(GetWindowLongPtrW(self.handle, GWL_EXSTYLE) as u32),
+            )
+        }
+    }
+
+    /// Current size of the client area.
+    fn client_size(&self) -> (i32, i32) {
+        let mut r = RECT::default();
+        unsafe {
+            _ = GetClientRect(self.handle, &mut r);
+        }
+        (r.right - r.left, r.bottom - r.top)
+    }
+
+    /// Outer window size needed for a client area of the given size with the window's current styles.
+    fn outer_size_for_client(&self, width: i32, height: i32, dpi: u32) -> (i32, i32) {
+        let (style, ex_style) = self.styles();
+        let mut r = RECT {
+            top: 0,
+            left: 0,
+            bottom: height,
+            right: width,
+        };
+
+        unsafe {
+            _ = AdjustWindowRectExForDpi(&mut r, style, false, ex_style, dpi);
+        }
+
+        (r.right - r.left, r.bottom - r.top)
+    }
+
+    /// Width of the invisible resize borders (left, top, right, bottom) that are part of the window rect but not of the visible frame.
+    fn invisible_borders(&self) -> (i32, i32, i32, i32) {
+        let mut outer = RECT::default();
+        let mut visible = RECT::default();
+
+        unsafe {
+            if GetWindowRect(self.handle, &mut outer).is_err()
+                || DwmGetWindowAttribute(
+                    self.handle,
+                    DWMWA_EXTENDED_FRAME_BOUNDS,
+                    &mut visible as *mut RECT as *mut c_void,
+                    size_of::<RECT>() as u32,
+                )
+                .is_err()
+            {
+                return (0, 0, 0, 0);
+            }
+        }
+
+        (
+            visible.left - outer.left,
+            visible.top - outer.top,
+            outer.right - visible.right,
+            outer.bottom - visible.bottom,
+        )
+    }
+
+    fn set_outer_size(&self, width: i32, height: i32) {
+        unsafe {
+            _ = SetWindowPos(
+                self.handle,
+                HWND::default(),
+                0,
+                0,
+                width,
+                height,
+                SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE,
+            );
+        }
+    }
+}
+
+impl Drop for WindowsWindow {
+    fn drop(&mut self) {
+        if let Some(Fullscreen::Exclusive(mode)) = &*self.reference_block.fullscreen.borrow() {
+            self.reference_block
+                .platform
+                .restore_video_mode(&mode.monitor_name);
+        }
+
+        // the reference block is only freed after this, so it is still valid for the messages sent during destruction
+        unsafe {
+            _ = DestroyWindow(self.handle);
+
+            if let Some(icon) = self.reference_block.icon.take() {
+                _ = DestroyIcon(icon);
+            }
+
+            for icon in self.reference_block.thumb_button_icons.take() {
+                _ = DestroyIcon(icon);
+            }
+
+            for _ in 0..self.reference_block.cursor_hide_count.take() {
+                ShowCursor(true);
+            }
+        }
+    }
+}
+
+/// Send the thumbnail toolbar buttons to the taskbar (nothing happens until the taskbar button exists, the window procedure calls this again then).
+///
+/// Buttons can only be added once, so all slots are added up front and unused ones are hidden.
+pub(super) fn update_thumb_buttons(hwnd: HWND, block: &WindowReferenceBlock) {
+    if !block.taskbar_button_created.get() {
+        return;
+    }
+
+    let Some(taskbar_list) = block.platform.taskbar_list() else {
+        return;
+    };
+
+    let buttons = block.thumb_buttons.borrow();
+    let mut icons = Vec::new();
+    let thumb_buttons: [THUMBBUTTON; ThumbButton::MAX_COUNT] = std::array::from_fn(|slot| {
+        // a slot without an icon clears the one it had before
+        let mut thumb_button = THUMBBUTTON {
+            dwMask: THB_FLAGS | THB_ICON | THB_TOOLTIP,
+            iId: slot as u32,
+            dwFlags: THBF_HIDDEN,
+            ..Default::default()
+        };
+
+        if let Some(button) = buttons.get(slot) {
+            // leave room for the terminating nul
+            for (dst, src) in thumb_button.szTip[..259]
+                .iter_mut()
+                .zip(button.tooltip.encode_utf16())
+            {
+                *dst = src;
+            }
+            thumb_button.dwFlags = if button.enabled {
+                THBF_ENABLED
+            } else {
+                THBF_DISABLED
+            };
 
-                let mut pt = POINT { x: position.0, y: position.1 };
-                let monitor = MonitorFromPoint(pt, MONITOR_DEFAULTTOPRIMARY);
+            match button.icon.as_ref().map(create_icon).transpose() {
+                Ok(icon) => {
+                    thumb_button.hIcon = icon.unwrap_or_default();
+                    icons.extend(icon);
+                }
+                Err(e) => debug!(
+                    "Failed to create the icon of thumbnail button {}: {}",
+                    button.id, e
+                ),
+            }
+        }
 
-                let mut dpix: u32 = 96;
-                let mut dpiy: u32 = 0;
+        thumb_button
+    });
+
+    let result = unsafe {
+        if block.thumb_buttons_added.replace(true) {
+            taskbar_list.ThumbBarUpdateButtons(hwnd, &thumb_buttons)
+        } else {
+            taskbar_list.ThumbBarAddButtons(hwnd, &thumb_buttons)
+        }
+    };
 
-                _ = GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpix, &mut dpiy);
+    if let Err(e) = result {
+        debug!(
+            "Failed to update the thumbnail toolbar of {:?}: {}",
+            block.id, e
+        );
+    }
 
-                _ = AdjustWindowRectExForDpi(&mut r, style, false, ex_style, dpix);
-                (r.right - r.left, r.bottom - r.top)
+    for icon in block.thumb_button_icons.replace(icons) {
+        unsafe {
+            _ = DestroyIcon(icon);
+        }
+    }
+}
+
+/// The default accessible objects of a window that [`Window::set_accessible_name`] annotates.
+const ACCESSIBLE_OBJECTS: [OBJECT_IDENTIFIER; 2] = [OBJID_WINDOW, OBJID_CLIENT];
+
+/// Remove the window's accessibility annotations (the annotation service would otherwise keep them after the window is destroyed).
+pub(super) fn clear_accessible_properties(hwnd: HWND, block: &WindowReferenceBlock) {
+    if !block.accessible_name_set.replace(false) {
+        return;
+    }
+
+    if let Some(services) = block.platform.acc_prop_services() {
+        for object in ACCESSIBLE_OBJECTS {
+            unsafe {
+                _ = services.ClearHwndProps(
+                    hwnd,
+                    object.0 as u32,
+                    CHILDID_SELF,
+                    &[PROPID_ACC_NAME],
+                );
             }
         }
     }
 }
 
+/// Move the window into (or out of) the topmost band of the z-order.
+fn set_topmost(hwnd: HWND, topmost: bool) {
+    let insert_after = if topmost {
+        HWND_TOPMOST
+    } else {
+        HWND_NOTOPMOST
+    };
+    unsafe {
+        _ = SetWindowPos(
+            hwnd,
+            insert_after,
+            0,
+            0,
+            0,
+            0,
+            SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+        );
+    }
+}
+
+/// Resize the window into its size limits. Setting the current size again is enough, the default `WM_WINDOWPOSCHANGING` handling clamps it with the `WM_GETMINMAXINFO` answer.
+fn enforce_size_limits(hwnd: HWND) {
+    let mut rect = RECT::default();
+    unsafe {
+        _ = GetWindowRect(hwnd, &mut rect);
+        _ = SetWindowPos(
+            hwnd,
+            HWND::default(),
+            0,
+            0,
+            rect.right - rect.left,
+            rect.bottom - rect.top,
+            SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE,
+        );
+    }
+}
+
+/// Answer `WM_GETMINMAXINFO` with the window's size limits. They are given for the client area, the track sizes include the frame.
+pub(super) unsafe fn apply_size_limits(
+    hwnd: HWND,
+    block: &WindowReferenceBlock,
+    info: &mut MINMAXINFO,
+) {
+    // windows that aren't resizable have no sizing border, and programmatic resizes of them shouldn't be limited
+    if !block.resizable.get() {
+        return;
+    }
+
+    unsafe {
+        let dpi = GetDpiForWindow(hwnd);
+        let scale_factor = dpi as f64 / 96.0;
+
+        let style = WINDOW_STYLE(GetWindowLongPtrW(hwnd, GWL_STYLE) as u32);
+        let ex_style = WINDOW_EX_STYLE(GetWindowLongPtrW(hwnd, GWL_EXSTYLE) as u32);
+        let mut frame = RECT::default();
+        _ = AdjustWindowRectExForDpi(&mut frame, style, false, ex_style, dpi);
+        let (frame_width, frame_height) = (frame.right - frame.left, frame.bottom - frame.top);
+
+        if let Some(size) = block.min_size.get() {
+            let (width, height) = size.physical_size(scale_factor);
+            info.ptMinTrackSize = POINT {
+                x: width as i32 + frame_width,
+                y: height as i32 + frame_height,
+            };
+        }
+
+        if let Some(size) = block.max_size.get() {
+            let (width, height) = size.physical_size(scale_factor);
+            info.ptMaxTrackSize = POINT {
+                x: width as i32 + frame_width,
+                y: height as i32 + frame_height,
+            };
+        }
+    }
+}
+
+/// Let DWM compose the client area with its alpha channel. Blur-behind with an empty region blurs nothing, it only switches the window to per-pixel alpha (unlike `WS_EX_LAYERED`, this works for swapchain surfaces).
+fn enable_transparency(hwnd: HWND) {
+    unsafe {
+        let region = CreateRectRgn(0, 0, -1, -1);
+        let blur_behind = DWM_BLURBEHIND {
+            dwFlags: DWM_BB_ENABLE | DWM_BB_BLURREGION,
+            fEnable: true.into(),
+            hRgnBlur: region,
+            fTransitionOnMaximized: false.into(),
+        };
+        if let Err(e) = DwmEnableBlurBehindWindow(hwnd, &blur_behind) {
+            warn!("Failed to make the window transparent: {}", e);
+        }
+        _ = DeleteObject(region);
+    }
+}
+
+/// Show the window's [`CursorIcon`].
+pub(super) unsafe fn apply_cursor_icon(block: &WindowReferenceBlock) {
+    let name = match block.cursor_icon.get() {
+        CursorIcon::Default => IDC_ARROW,
+        CursorIcon::Pointer => IDC_HAND,
+        CursorIcon::Text => IDC_IBEAM,
+        CursorIcon::Crosshair => IDC_CROSS,
+        CursorIcon::Move => IDC_SIZEALL,
+        CursorIcon::NotAllowed => IDC_NO,
+        CursorIcon::EwResize => IDC_SIZEWE,
+        CursorIcon::NsResize => IDC_SIZENS,
+        CursorIcon::NeswResize => IDC_SIZENESW,
+        CursorIcon::NwseResize => IDC_SIZENWSE,
+        CursorIcon::Wait => IDC_WAIT,
+        CursorIcon::Progress => IDC_APPSTARTING,
+        CursorIcon::Help => IDC_HELP,
+    };
+
+    unsafe {
+        // system cursors are shared, they don't have to be destroyed
+        if let Ok(cursor) = LoadCursorW(HINSTANCE::default(), name) {
+            SetCursor(cursor);
+        }
+    }
+}
+
+/// Move the cursor to the center of the window's client area.
+pub(super) unsafe fn center_cursor(hwnd: HWND) {
+    unsafe {
+        let mut rect = RECT::default();
+        if GetClientRect(hwnd, &mut rect).is_err() {
+            return;
+        }
+
+        let mut center = POINT {
+            x: rect.right / 2,
+            y: rect.bottom / 2,
+        };
+        _ = ClientToScreen(hwnd, &mut center);
+        _ = SetCursorPos(center.x, center.y);
+    }
+}
+
+/// Confine the cursor to the window's current client area.
+pub(super) unsafe fn clip_cursor_to_client(hwnd: HWND) {
+    unsafe {
+        let mut rect = RECT::default();
+        if GetClientRect(hwnd, &mut rect).is_err() {
+            return;
+        }
+
+        let mut top_left = POINT {
+            x: rect.left,
+            y: rect.top,
+        };
+        let mut bottom_right = POINT {
+            x: rect.right,
+            y: rect.bottom,
+        };
+        _ = ClientToScreen(hwnd, &mut top_left);
+        _ = ClientToScreen(hwnd, &mut bottom_right);
+
+        let clip = RECT {
+            left: top_left.x,
+            top: top_left.y,
+            right: bottom_right.x,
+            bottom: bottom_right.y,
+        };
+        if let Err(e) = ClipCursor(Some(&clip)) {
+            debug!("Failed to confine the cursor: {}", e);
+        }
+    }
+}
+
+/// Save the window's normal (not minimized or maximized) geometry under its persistence key.
+pub(super) fn save_placement(hwnd: HWND, block: &WindowReferenceBlock) {
+    let Some(key) = &block.persistence_key else {
+        return;
+    };
+
+    let mut placement = WINDOWPLACEMENT {
+        length: size_of::<WINDOWPLACEMENT>() as u32,
+        ..Default::default()
+    };
+
+    unsafe {
+        if GetWindowPlacement(hwnd, &mut placement).is_err() {
+            return;
+        }
+
+        let style = WINDOW_STYLE(GetWindowLongPtrW(hwnd, GWL_STYLE) as u32);
+        let ex_style = WINDOW_EX_STYLE(GetWindowLongPtrW(hwnd, GWL_EXSTYLE) as u32);
+        let rect = placement.rcNormalPosition;
+
+        // see set_restore_placement for the coordinate space of rcNormalPosition
+        let (offset_x, offset_y) = if ex_style.contains(WS_EX_TOOLWINDOW) {
+            (0, 0)
+        } else {
+            workspace_offset(POINT {
+                x: rect.left,
+                y: rect.top,
+            })
+        };
+
+        // the frame around an empty client area
+        let mut frame = RECT::default();
+        _ = AdjustWindowRectExForDpi(&mut frame, style, false, ex_style, GetDpiForWindow(hwnd));
+
+        SavedPlacement {
+            position: (rect.left + offset_x, rect.top + offset_y),
+            size: (
+                ((rect.right - rect.left) - (frame.right - frame.left)).max(1) as u32,
+                ((rect.bottom - rect.top) - (frame.bottom - frame.top)).max(1) as u32,
+            ),
+        }
+        .save(key);
+    }
+}
+
+/// Create a 32-bit icon. Its alpha channel decides transparency, so the AND mask is left empty.
+fn create_icon(icon: &WindowIcon) -> windows::core::Result<HICON> {
+    let bgra: Vec<u8> = icon
+        .rgba
+        .chunks_exact(4)
+        .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+        .collect();
+
+    // monochrome rows are padded to 16 bits
+    let and_mask = vec![0u8; icon.width.div_ceil(16) as usize * 2 * icon.height as usize];
+
+    unsafe {
+        CreateIcon(
+            None,
+            icon.width as i32,
+            icon.height as i32,
+            1,
+            32,
+            and_mask.as_ptr(),
+            bgra.as_ptr(),
+        )
+    }
+}
+
+/// Does a window with this style get a frame from the OS?
+pub(super) fn has_decorations(style: WINDOW_STYLE) -> bool {
+    style.0 & (WS_CAPTION.0 | WS_THICKFRAME.0) != 0
+}
+
+/// Offset between screen and workspace coordinates on the monitor containing `point`.
+fn workspace_offset(point: POINT) -> (i32, i32) {
+    let mut info = MONITORINFO {
+        cbSize: size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+
+    unsafe {
+        let monitor = MonitorFromPoint(point, MONITOR_DEFAULTTONEAREST);
+        if !GetMonitorInfoW(monitor, &mut info).as_bool() {
+            return (0, 0);
+        }
+    }
+
+    (
+        info.rcWork.left - info.rcMonitor.left,
+        info.rcWork.top - info.rcMonitor.top,
+    )
+}
+
+/// Convert a position to physical pixels, logical positions are scaled by the dpi of the monitor they are on.
+fn physical_position(position: Position) -> (i32, i32) {
+    let Position::Logical { x, y } = position else {
+        return position.physical_position(1.0);
+    };
+
+    let mut dpix: u32 = 96;
+    let mut dpiy: u32 = 0;
+
+    unsafe {
+        let monitor = MonitorFromPoint(
+            POINT {
+                x: x.round() as i32,
+                y: y.round() as i32,
+            },
+            MONITOR_DEFAULTTONEAREST,
+        );
+        _ = GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpix, &mut dpiy);
+    }
+
+    position.physical_position(dpix as f64 / 96.0)
+}
+
+fn r2s(
+    res: Resolution<u32>,
+    style: WINDOW_STYLE,
+    ex_style: WINDOW_EX_STYLE,
+    position: (i32, i32),
+) -> (i32, i32) {
+    match res {
+        Resolution::Physical { width, height } => unsafe {
+            let mut r = RECT {
+                top: 0,
+                left: 0,
+                bottom: height as i32,
+                right: width as i32,
+            };
+
+            _ = AdjustWindowRectEx(&mut r, style, false, ex_style);
+            (r.right - r.left, r.bottom - r.top)
+        },
+        Resolution::Logical { width, height } => unsafe {
+            let mut r = RECT {
+                top: 0,
+                left: 0,
+                bottom: height as i32,
+                right: width as i32,
+            };
+
+            let pt = POINT {
+                x: position.0,
+                y: position.1,
+            };
+            let monitor = MonitorFromPoint(pt, MONITOR_DEFAULTTOPRIMARY);
+
+            let mut dpix: u32 = 96;
+            let mut dpiy: u32 = 0;
+
+            _ = GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpix, &mut dpiy);
+
+            _ = AdjustWindowRectExForDpi(&mut r, style, false, ex_style, dpix);
+            (r.right - r.left, r.bottom - r.top)
+        },
+    }
+}
+
 impl WindowsWindow {
     pub(super) fn new(
         platform: Arc<WindowsPlatform>,
         window_attributes: WindowAttributes,
         id: WindowId,
+        inputs: &OsLoopInputs,
     ) -> anyhow::Result<Self> {
         let wc = platform.get_window_class(WindowClassAttributes {
             allow_close: window_attributes.allow_close,
-            show_drop_shadow: window_attributes.show_drop_shadow,
+            show_drop_shadow: window_attributes.platform_specific.windows.show_drop_shadow,
+            background_color: window_attributes
+                .background_color
+                .filter(|_| !window_attributes.transparent),
         })?;
 
         let title = U16CString::from_str(window_attributes.title.unwrap_or("Window".to_string()))?;
@@ -92,26 +1418,129 @@ impl WindowsWindow {
         let reference_block = Box::new(WindowReferenceBlock {
             id,
             platform: platform.clone(),
+            event_queue: inputs.event_queue.clone(),
+            window_manager: Arc::downgrade(&inputs.window_manager),
+            cursor_inside: Cell::new(false),
+            key_repeat: Cell::new(true),
+            modifiers: Cell::new(Modifiers::empty()),
+            present_mode: Cell::new(None),
+            in_size_move: Cell::new(false),
+            resized_during_size_move: Cell::new(false),
+            interaction: Cell::new(InteractionState::default()),
+            resize_paint: ResizePaintSlot::default(),
+            occluded: Cell::new(!window_attributes.initially_visible),
+            decorated: Cell::new(true),
+            auto_hide_on_focus_loss: window_attributes.auto_hide_on_focus_loss,
+            transparent: window_attributes.transparent,
+            thumb_buttons: RefCell::new(Vec::new()),
+            taskbar_button_created: Cell::new(false),
+            thumb_buttons_added: Cell::new(false),
+            persistence_key: window_attributes.persistence_key.clone(),
+            cursor_confined: Cell::new(false),
+            cursor_locked: Cell::new(false),
+            cursor_hide_count: Cell::new(0),
+            cursor_icon: Cell::new(CursorIcon::Default),
+            accessible_name_set: Cell::new(false),
+            resizable: Cell::new(window_attributes.resizable),
+            maximize_button: window_attributes.has_maximize_button,
+            min_size: Cell::new(window_attributes.min_size),
+            max_size: Cell::new(window_attributes.max_size),
+            high_surrogate: Cell::new(None),
+            fullscreen: RefCell::new(None),
+            windowed_placement: Cell::new(None),
+            icon: Cell::new(None),
+            thumb_button_icons: RefCell::new(Vec::new()),
         });
 
         let mut ex_style = WINDOW_EX_STYLE::default();
         let mut style = WINDOW_STYLE::default();
 
-        if window_attributes.has_minimize_button {
-            style |= WS_MINIMIZEBOX;
+        // a tool window is left out of Alt+Tab and the taskbar, an app window always gets a taskbar button, and an owned window never does (but is still listed in Alt+Tab).
+        // A tool window with an app window's taskbar button is asked for explicitly once the window exists (WS_EX_APPWINDOW would win over WS_EX_TOOLWINDOW and list it in Alt+Tab)
+        let show_in_taskbar = window_attributes.show_in_taskbar && !window_attributes.skip_taskbar;
+        let mut owner = HWND::default();
+        match (show_in_taskbar, window_attributes.show_in_switcher) {
+            (true, true) => ex_style |= WS_EX_APPWINDOW,
+            (true, false) => ex_style |= WS_EX_TOOLWINDOW,
+            (false, true) => owner = platform.owner_window()?,
+            (false, false) => ex_style |= WS_EX_TOOLWINDOW,
         }
 
-        if window_attributes.has_maximize_button {
-            style |= WS_MINIMIZEBOX;
+        // WS_CAPTION is WS_BORDER | WS_DLGFRAME, and the minimize and maximize boxes only show up with a caption and a system menu.
+        // overlapped windows always get a caption, so a window without one has to be a popup
+        if window_attributes.show_title_bar {
+            style |= WS_CAPTION | WS_SYSMENU;
+
+            if window_attributes.has_minimize_button {
+                style |= WS_MINIMIZEBOX;
+            }
+
+            if window_attributes.has_maximize_button {
+                style |= WS_MAXIMIZEBOX;
+            }
+        } else {
+            style |= WS_POPUP;
+        }
+
+        if window_attributes.show_border {
+            style |= WS_BORDER;
+        }
+
+        if window_attributes.resizable {
+            style |= WS_THICKFRAME;
+        }
+
+        // a window created visible is stacked on top, one that should start at the bottom is only shown once it was moved there
+        let show_at_bottom = window_attributes.initially_visible
+            && matches!(window_attributes.initial_z_order, InitialZOrder::Bottom);
+        if window_attributes.initially_visible && !show_at_bottom {
+            style |= WS_VISIBLE;
         }
 
         let (x, y) = window_attributes
             .position
-            .map_or((CW_USEDEFAULT, CW_USEDEFAULT), |p| (p.x, p.y));
+            .map_or((CW_USEDEFAULT, CW_USEDEFAULT), physical_position);
 
         let (width, height) = window_attributes
             .size
-            .map_or((CW_USEDEFAULT, CW_USEDEFAULT), |s| { r2s(s, style, ex_style, (x, y))});
+            .map_or((CW_USEDEFAULT, CW_USEDEFAULT), |s| {
+                r2s(s, style, ex_style, (x, y))
+            });
+
+        let saved_placement = window_attributes
+            .persistence_key
+            .as_deref()
+            .and_then(SavedPlacement::load)
+            .map(|placement| {
+                let (center_x, center_y) = placement.center();
+                let hmonitor = unsafe {
+                    MonitorFromPoint(
+                        POINT {
+                            x: center_x,
+                            y: center_y,
+                        },
+                        MONITOR_DEFAULTTONEAREST,
+                    )
+                };
+                match super::monitor::monitor_handle(hmonitor) {
+                    Some(monitor) => placement.clamp_to(&monitor),
+                    None => placement,
+                }
+            });
+
+        let ((x, y), (width, height)) = match saved_placement {
+            Some(placement) => {
+                let size = Resolution::Physical {
+                    width: placement.size.0,
+                    height: placement.size.1,
+                };
+                (
+                    placement.position,
+                    r2s(size, style, ex_style, placement.position),
+                )
+            }
+            None => ((x, y), (width, height)),
+        };
 
         let handle = unsafe {
             CreateWindowExW(
@@ -123,17 +1552,172 @@ impl WindowsWindow {
                 y,
                 width,
                 height,
-                HWND::default(),
+                owner,
                 HMENU::default(),
                 platform.hinstance,
                 Some((&*reference_block as *const WindowReferenceBlock) as *const c_void),
             )?
         };
+        platform.window_handles.borrow_mut().insert(id, handle);
+
+        // this only decides where the window appears once it is shown
+        let insert_after = match window_attributes.initial_z_order {
+            InitialZOrder::Top => Some(HWND_TOP),
+            InitialZOrder::Bottom => Some(HWND_BOTTOM),
+            InitialZOrder::NoChange => None,
+        };
+        if let Some(insert_after) = insert_after {
+            unsafe {
+                _ = SetWindowPos(
+                    handle,
+                    insert_after,
+                    0,
+                    0,
+                    0,
+                    0,
+                    SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+                );
+            }
+        }
 
-        Ok(Self {
+        if show_at_bottom {
+            unsafe {
+                _ = ShowWindow(handle, SW_SHOWNA);
+            }
+        }
+
+        if show_in_taskbar && !window_attributes.show_in_switcher {
+            // explorer forgets the button when it restarts, tool windows aren't told about that
+            if let Some(taskbar_list) = platform.taskbar_list()
+                && let Err(e) = unsafe { taskbar_list.AddTab(handle) }
+            {
+                debug!("Failed to add a taskbar button: {}", e);
+            }
+        }
+
+        if window_attributes.always_on_top {
+            set_topmost(handle, true);
+        }
+
+        if window_attributes.transparent {
+            enable_transparency(handle);
+        }
+
+        // the first WM_GETMINMAXINFO arrives before the reference block is attached, so the creation size isn't limited yet
+        if window_attributes.min_size.is_some() || window_attributes.max_size.is_some() {
+            enforce_size_limits(handle);
+        }
+
+        // overlapped windows always get a caption, whatever the requested style was
+        reference_block
+            .decorated
+            .set(has_decorations(WINDOW_STYLE(
+                unsafe { GetWindowLongPtrW(handle, GWL_STYLE) } as u32,
+            )));
+
+        // without this touch input only arrives as emulated mouse messages
+        if let Err(e) = unsafe { RegisterTouchWindow(handle, REGISTER_TOUCH_WINDOW_FLAGS(0)) } {
+            debug!("Failed to register window for touch input: {}", e);
+        }
+
+        let window = Self {
             handle,
             id,
             reference_block,
-        })
+        };
+
+        if window_attributes.icon.is_some() {
+            window.set_icon(window_attributes.icon);
+        }
+
+        if window_attributes.fullscreen.is_some() {
+            window.set_fullscreen(window_attributes.fullscreen);
+        }
+
+        Ok(window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Engine;
+    use crate::event::Event;
+    use windows::Win32::UI::WindowsAndMessaging::{WM_CHAR, WM_DEADCHAR};
+
+    fn hwnd(window: &dyn Window) -> HWND {
+        let RawWindowHandle::Win32(handle) = window.window_handle().unwrap().as_raw() else {
+            panic!("not a Win32 window");
+        };
+        HWND(handle.hwnd.get() as *mut c_void)
+    }
+
+    #[test]
+    fn inner_size_matches_requested() {
+        let engine = Engine::new().unwrap();
+        let (_, window) = engine
+            .create_window(WindowAttributes {
+                size: Some(Resolution::Physical {
+                    width: 800,
+                    height: 600,
+                }),
+                ..Default::default()
+            })
+            .unwrap();
+        engine.process_all_pending();
+
+        let size = window.upgrade().unwrap().inner_size();
+        assert!(size.width().abs_diff(800) <= 2 && size.height().abs_diff(600) <= 2);
+    }
+
+    #[test]
+    fn maximize_button_style() {
+        let engine = Engine::new().unwrap();
+        let style = |attributes: WindowAttributes| {
+            let (_, window) = engine.create_window(attributes).unwrap();
+            let window = window.upgrade().unwrap();
+            WINDOW_STYLE(unsafe { GetWindowLongPtrW(hwnd(window.as_ref()), GWL_STYLE) } as u32)
+        };
+
+        let with_button = style(WindowAttributes::default());
+        assert!(with_button.contains(WS_MAXIMIZEBOX));
+        assert!(with_button.contains(WS_CAPTION | WS_SYSMENU));
+
+        let without_button = style(WindowAttributes {
+            has_maximize_button: false,
+            ..Default::default()
+        });
+        assert!(!without_button.contains(WS_MAXIMIZEBOX));
+        assert!(without_button.contains(WS_MINIMIZEBOX));
+    }
+
+    #[test]
+    fn composed_characters_are_one_event() {
+        let engine = Engine::new().unwrap();
+        let (id, window) = engine.create_window(WindowAttributes::default()).unwrap();
+        let hwnd = hwnd(window.upgrade().unwrap().as_ref());
+        engine.process_all_pending();
+        engine.drain_events();
+
+        unsafe {
+            // what TranslateMessage makes of ' then e on a layout where ' is a dead key
+            SendMessageW(hwnd, WM_DEADCHAR, WPARAM('\'' as usize), LPARAM(0));
+            SendMessageW(hwnd, WM_CHAR, WPARAM('é' as usize), LPARAM(0));
+
+            // a character outside the BMP arrives in two halves
+            for unit in "😀".encode_utf16() {
+                SendMessageW(hwnd, WM_CHAR, WPARAM(unit as usize), LPARAM(0));
+            }
+        }
+
+        let chars = engine
+            .drain_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::ReceivedCharacter { window, ch } if window == id => Some(ch),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(chars, ['é', '😀']);
     }
 }
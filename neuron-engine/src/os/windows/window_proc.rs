@@ -0,0 +1,745 @@
+//! Window procedure shared by all of our window classes
+
+use crate::event::{
+    ElementState, Event, KeyEvent, KeyState, Modifiers, MouseButton, PenState, ScrollDelta,
+};
+use crate::os::window::InteractionState;
+use crate::os::windows::held_modifiers;
+use crate::os::windows::window::{
+    WindowReferenceBlock, apply_cursor_icon, apply_size_limits, center_cursor,
+    clear_accessible_properties, clip_cursor_to_client, has_decorations, save_placement,
+    update_thumb_buttons,
+};
+use std::ffi::c_void;
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, POINT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::{
+    BeginPaint, ClientToScreen, EndPaint, PAINTSTRUCT, ScreenToClient,
+};
+use windows::Win32::System::SystemServices::{
+    MK_LBUTTON, MK_MBUTTON, MK_RBUTTON, MK_XBUTTON1, MK_XBUTTON2,
+};
+use windows::Win32::UI::Controls::WM_MOUSELEAVE;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetCapture, GetKeyState, ReleaseCapture, SetCapture, TME_LEAVE, TRACKMOUSEEVENT,
+    TrackMouseEvent,
+};
+use windows::Win32::UI::Input::Pointer::{GetPointerPenInfo, GetPointerType, POINTER_PEN_INFO};
+use windows::Win32::UI::Input::Touch::{
+    CloseTouchInputHandle, GetTouchInputInfo, HTOUCHINPUT, TOUCHEVENTF_DOWN, TOUCHEVENTF_MOVE,
+    TOUCHEVENTF_UP, TOUCHINPUT,
+};
+use windows::Win32::UI::Shell::THBN_CLICKED;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CREATESTRUCTW, DefWindowProcW, GWL_STYLE, GWLP_USERDATA, GetClientRect, GetForegroundWindow,
+    GetWindowLongPtrW, HTCLIENT, IsIconic, IsWindowVisible, MINMAXINFO, PEN_FLAG_BARREL,
+    PEN_MASK_PRESSURE, POINTER_INPUT_TYPE, PT_PEN, SIZE_MINIMIZED, SW_HIDE, SWP_NOACTIVATE,
+    SWP_NOZORDER, SetWindowLongPtrW, SetWindowPos, ShowWindow, WA_INACTIVE, WHEEL_DELTA,
+    WINDOW_STYLE, WM_ACTIVATE, WM_CHAR, WM_CLOSE, WM_COMMAND, WM_CREATE, WM_DESTROY,
+    WM_DISPLAYCHANGE, WM_DPICHANGED, WM_ENTERSIZEMOVE, WM_ERASEBKGND, WM_EXITSIZEMOVE,
+    WM_GETMINMAXINFO, WM_KEYDOWN, WM_KEYUP, WM_KILLFOCUS, WM_LBUTTONDOWN, WM_LBUTTONUP,
+    WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEHWHEEL, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_MOVING,
+    WM_NCCALCSIZE, WM_NCDESTROY, WM_PAINT, WM_POINTERDOWN, WM_POINTERUP, WM_POINTERUPDATE,
+    WM_RBUTTONDOWN, WM_RBUTTONUP, WM_SETCURSOR, WM_SETFOCUS, WM_SHOWWINDOW, WM_SIZE, WM_SIZING,
+    WM_SYSKEYDOWN, WM_SYSKEYUP, WM_THEMECHANGED, WM_TOUCH, WM_WINDOWPOSCHANGED, WM_XBUTTONDOWN,
+    WM_XBUTTONUP, XBUTTON1,
+};
+
+/// What a message handler did with a message.
+///
+/// Unhandled messages are passed on to `DefWindowProcW`, handled ones return `result` directly. Handlers that only observe a message (to emit an event) should still pass it on, so the default behaviour is kept.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct EventResponse {
+    pub handled: bool,
+    pub result: LRESULT,
+}
+
+impl EventResponse {
+    /// Let `DefWindowProcW` handle the message.
+    pub const fn pass() -> Self {
+        Self {
+            handled: false,
+            result: LRESULT(0),
+        }
+    }
+
+    /// The message was fully handled, return `result` from the window procedure.
+    pub const fn handled(result: LRESULT) -> Self {
+        Self {
+            handled: true,
+            result,
+        }
+    }
+}
+
+pub(super) unsafe extern "system" fn generic_window_proc(
+    hwnd: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    unsafe {
+        let response = if message == WM_CREATE {
+            on_create(hwnd, lparam)
+        } else if let Some(block) = reference_block(hwnd) {
+            handle_message(hwnd, block, message, wparam, lparam)
+        } else {
+            EventResponse::pass()
+        };
+
+        if response.handled {
+            response.result
+        } else {
+            DefWindowProcW(hwnd, message, wparam, lparam)
+        }
+    }
+}
+
+/// The reference block stored in `GWLP_USERDATA` (`None` for windows without one, e.g. the message window).
+unsafe fn reference_block<'a>(hwnd: HWND) -> Option<&'a WindowReferenceBlock> {
+    unsafe {
+        let wptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA);
+        (wptr as *const WindowReferenceBlock).as_ref()
+    }
+}
+
+/// Store the reference block passed to `CreateWindowExW` so later messages can find it.
+unsafe fn on_create(hwnd: HWND, lparam: LPARAM) -> EventResponse {
+    unsafe {
+        let cs = lparam.0 as *const CREATESTRUCTW;
+        SetWindowLongPtrW(hwnd, GWLP_USERDATA, (*cs).lpCreateParams as isize);
+    }
+
+    EventResponse::handled(LRESULT(0))
+}
+
+unsafe fn handle_message(
+    hwnd: HWND,
+    block: &WindowReferenceBlock,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> EventResponse {
+    unsafe {
+        match message {
+            WM_MOUSEMOVE => on_mouse_move(hwnd, block, lparam),
+            WM_LBUTTONDOWN | WM_LBUTTONUP | WM_RBUTTONDOWN | WM_RBUTTONUP | WM_MBUTTONDOWN
+            | WM_MBUTTONUP | WM_XBUTTONDOWN | WM_XBUTTONUP => {
+                on_mouse_button(hwnd, block, message, wparam)
+            }
+            WM_MOUSEWHEEL | WM_MOUSEHWHEEL => {
+                // one notch is WHEEL_DELTA, the same line per notch X11 reports. Finer wheels and touchpads send fractions of it
+                let delta = ((wparam.0 >> 16) & 0xffff) as i16 as f32 / WHEEL_DELTA as f32;
+                block.event_queue.push(Event::MouseWheel {
+                    window: block.id,
+                    delta: if message == WM_MOUSEWHEEL {
+                        ScrollDelta::Lines { x: 0.0, y: delta }
+                    } else {
+                        ScrollDelta::Lines { x: delta, y: 0.0 }
+                    },
+                });
+                EventResponse::handled(LRESULT(0))
+            }
+            WM_SETFOCUS | WM_KILLFOCUS => {
+                block.event_queue.push(Event::Focused {
+                    window: block.id,
+                    focused: message == WM_SETFOCUS,
+                });
+                // key releases go to whichever window has the focus then
+                if message == WM_KILLFOCUS {
+                    set_modifiers(block, Modifiers::empty());
+                }
+                EventResponse::pass()
+            }
+            // capturing cancels leave tracking, which posts a WM_MOUSELEAVE even though the cursor is still inside. Tracking is requested again when the capture is released
+            WM_MOUSELEAVE if GetCapture() == hwnd => EventResponse::pass(),
+            WM_MOUSELEAVE => {
+                if block.cursor_inside.replace(false) {
+                    block
+                        .event_queue
+                        .push(Event::CursorLeft { window: block.id });
+                }
+                EventResponse::pass()
+            }
+            WM_POINTERDOWN | WM_POINTERUPDATE | WM_POINTERUP => {
+                on_pointer(hwnd, block, message, wparam)
+            }
+            WM_KEYDOWN | WM_SYSKEYDOWN | WM_KEYUP | WM_SYSKEYUP => on_key(block, message, lparam),
+            WM_CHAR => on_char(block, wparam, lparam),
+            WM_SIZE => on_size(hwnd, block, wparam, lparam),
+            WM_SHOWWINDOW => {
+                // a window shown while minimized is still only a taskbar button
+                set_occluded(block, wparam.0 == 0 || IsIconic(hwnd).as_bool());
+                EventResponse::pass()
+            }
+            // style changes only take effect with SWP_FRAMECHANGED, which recalculates the frame (wparam is set)
+            WM_NCCALCSIZE if wparam.0 != 0 => {
+                update_decorations(hwnd, block);
+                EventResponse::pass()
+            }
+            WM_THEMECHANGED => {
+                update_decorations(hwnd, block);
+                EventResponse::pass()
+            }
+            WM_ACTIVATE
+                if block.cursor_confined.get() && (wparam.0 & 0xffff) as u32 != WA_INACTIVE =>
+            {
+                clip_cursor_to_client(hwnd);
+                // otherwise the first move after switching back reports the whole way from where the cursor was
+                if block.cursor_locked.get() {
+                    center_cursor(hwnd);
+                }
+                EventResponse::pass()
+            }
+            // the clip rectangle doesn't follow the window
+            WM_WINDOWPOSCHANGED if block.cursor_confined.get() && GetForegroundWindow() == hwnd => {
+                clip_cursor_to_client(hwnd);
+                EventResponse::pass()
+            }
+            WM_ACTIVATE
+                if block.auto_hide_on_focus_loss && (wparam.0 & 0xffff) as u32 == WA_INACTIVE =>
+            {
+                _ = ShowWindow(hwnd, SW_HIDE);
+                EventResponse::pass()
+            }
+            // outside the client area DefWindowProc picks the resize and default cursors
+            WM_SETCURSOR if (lparam.0 & 0xffff) as u32 == HTCLIENT => {
+                apply_cursor_icon(block);
+                EventResponse::handled(LRESULT(1))
+            }
+            WM_GETMINMAXINFO => {
+                apply_size_limits(hwnd, block, &mut *(lparam.0 as *mut MINMAXINFO));
+                EventResponse::handled(LRESULT(0))
+            }
+            // painting the class background brush would cover the alpha the renderer drew
+            WM_ERASEBKGND if block.transparent => EventResponse::handled(LRESULT(1)),
+            WM_PAINT => {
+                // validating the window is what stops further WM_PAINTs, nothing is drawn here
+                let mut paint = PAINTSTRUCT::default();
+                _ = BeginPaint(hwnd, &mut paint);
+                _ = EndPaint(hwnd, &paint);
+                block
+                    .event_queue
+                    .push(Event::RedrawRequested { window: block.id });
+                EventResponse::handled(LRESULT(0))
+            }
+            WM_ENTERSIZEMOVE => {
+                block.in_size_move.set(true);
+                block.resized_during_size_move.set(false);
+                EventResponse::pass()
+            }
+            // only sent within the size/move loop, which is where we learn whether it is a resize or a move
+            WM_SIZING => {
+                begin_interaction(
+                    block,
+                    InteractionState {
+                        resizing: true,
+                        moving: false,
+                    },
+                );
+                EventResponse::pass()
+            }
+            WM_MOVING => {
+                begin_interaction(
+                    block,
+                    InteractionState {
+                        resizing: false,
+                        moving: true,
+                    },
+                );
+                EventResponse::pass()
+            }
+            WM_EXITSIZEMOVE => on_exit_size_move(hwnd, block),
+            WM_DPICHANGED => on_dpi_changed(hwnd, block, wparam, lparam),
+            WM_DISPLAYCHANGE => {
+                block.platform.monitors_changed.set(true);
+                EventResponse::pass()
+            }
+            WM_TOUCH => on_touch(hwnd, block, wparam, lparam),
+            // the default handling would destroy the window right away, instead it is destroyed once the window manager drops it (like on X11)
+            WM_CLOSE => {
+                block
+                    .event_queue
+                    .push(Event::CloseRequested { window: block.id });
+                if let Some(window_manager) = block.window_manager.upgrade() {
+                    window_manager.begin_closing_window(block.id);
+                }
+                EventResponse::handled(LRESULT(0))
+            }
+            WM_COMMAND if ((wparam.0 >> 16) & 0xffff) as u32 == THBN_CLICKED => {
+                let slot = wparam.0 & 0xffff;
+                if let Some(button) = block.thumb_buttons.borrow().get(slot) {
+                    block.event_queue.push(Event::ThumbButtonClicked {
+                        window: block.id,
+                        id: button.id,
+                    });
+                }
+                EventResponse::handled(LRESULT(0))
+            }
+            // accelerators send their command id with a notification code of 1
+            WM_COMMAND if (wparam.0 >> 16) & 0xffff == 1 => {
+                block.event_queue.push(Event::AcceleratorTriggered {
+                    window: block.id,
+                    id: (wparam.0 & 0xffff) as u16,
+                });
+                EventResponse::handled(LRESULT(0))
+            }
+            // registered at runtime, so it can't be a pattern
+            message if message == block.platform.taskbar_button_created => {
+                block.taskbar_button_created.set(true);
+                // explorer restarts send this again, and the new taskbar has no buttons yet
+                block.thumb_buttons_added.set(false);
+                update_thumb_buttons(hwnd, block);
+                EventResponse::pass()
+            }
+            WM_NCDESTROY => {
+                clear_accessible_properties(hwnd, block);
+                _ = block.platform.set_keyboard_shortcuts_inhibited(hwnd, false);
+                block.platform.window_handles.borrow_mut().remove(&block.id);
+                EventResponse::pass()
+            }
+            WM_DESTROY => {
+                // every way of destroying the window ends up here, not only close requests
+                save_placement(hwnd, block);
+                // destroyed by something other than the window manager dropping it (e.g. its owner was destroyed), so the window manager has to catch up
+                if let Some(window_manager) = block.window_manager.upgrade()
+                    && window_manager.is_window_active(block.id)
+                {
+                    window_manager.begin_closing_window(block.id);
+                }
+                EventResponse::pass()
+            }
+            _ => EventResponse::pass(),
+        }
+    }
+}
+
+unsafe fn on_mouse_move(hwnd: HWND, block: &WindowReferenceBlock, lparam: LPARAM) -> EventResponse {
+    // the coordinates are signed (they can be negative with multiple monitors or while captured)
+    let (x, y) = (
+        (lparam.0 & 0xffff) as i16 as i32,
+        ((lparam.0 >> 16) & 0xffff) as i16 as i32,
+    );
+
+    // while a button is held the window has the capture and keeps getting moves outside the client area, those decide enter and leave instead of WM_MOUSELEAVE
+    let mut client = RECT::default();
+    unsafe {
+        _ = GetClientRect(hwnd, &mut client);
+    }
+    let inside = x >= client.left && x < client.right && y >= client.top && y < client.bottom;
+
+    if inside && !block.cursor_inside.replace(true) {
+        // there is no enter message, so the first move while we aren't tracking is the enter. Tracking is requested right away so a quick exit still produces WM_MOUSELEAVE (it is posted immediately if the cursor is already gone).
+        // Tracking only lasts until the next WM_MOUSELEAVE, so it is requested again on every enter.
+        unsafe {
+            track_mouse_leave(hwnd);
+        }
+
+        block
+            .event_queue
+            .push(Event::CursorEntered { window: block.id });
+    } else if !inside && block.cursor_inside.replace(false) {
+        block
+            .event_queue
+            .push(Event::CursorLeft { window: block.id });
+    }
+
+    // the move back to the center produces a move to the center, which is skipped
+    if block.cursor_locked.get() && unsafe { GetForegroundWindow() } == hwnd {
+        let center = (client.right / 2, client.bottom / 2);
+        if (x, y) != center {
+            block.event_queue.push(Event::MouseMotion {
+                window: block.id,
+                delta: ((x - center.0) as f64, (y - center.1) as f64),
+            });
+            unsafe {
+                center_cursor(hwnd);
+            }
+        }
+        return EventResponse::pass();
+    }
+
+    block.event_queue.push(Event::CursorMoved {
+        window: block.id,
+        position: (x as f64, y as f64),
+    });
+
+    EventResponse::pass()
+}
+
+/// Request a WM_MOUSELEAVE once the cursor leaves the client area.
+unsafe fn track_mouse_leave(hwnd: HWND) {
+    let mut tme = TRACKMOUSEEVENT {
+        cbSize: size_of::<TRACKMOUSEEVENT>() as u32,
+        dwFlags: TME_LEAVE,
+        hwndTrack: hwnd,
+        dwHoverTime: 0,
+    };
+    unsafe {
+        _ = TrackMouseEvent(&mut tme);
+    }
+}
+
+unsafe fn on_mouse_button(
+    hwnd: HWND,
+    block: &WindowReferenceBlock,
+    message: u32,
+    wparam: WPARAM,
+) -> EventResponse {
+    let (button, state) = match message {
+        WM_LBUTTONDOWN => (MouseButton::Left, ElementState::Pressed),
+        WM_LBUTTONUP => (MouseButton::Left, ElementState::Released),
+        WM_RBUTTONDOWN => (MouseButton::Right, ElementState::Pressed),
+        WM_RBUTTONUP => (MouseButton::Right, ElementState::Released),
+        WM_MBUTTONDOWN => (MouseButton::Middle, ElementState::Pressed),
+        WM_MBUTTONUP => (MouseButton::Middle, ElementState::Released),
+        // numbered like the X11 back and forward buttons
+        _ => {
+            let button = if ((wparam.0 >> 16) & 0xffff) as u16 == XBUTTON1 {
+                8
+            } else {
+                9
+            };
+            let state = if message == WM_XBUTTONDOWN {
+                ElementState::Pressed
+            } else {
+                ElementState::Released
+            };
+            (MouseButton::Other(button), state)
+        }
+    };
+
+    // X11 grabs the pointer while a button is held, capturing does the same so the release is seen even outside the window
+    let held = MK_LBUTTON.0 | MK_RBUTTON.0 | MK_MBUTTON.0 | MK_XBUTTON1.0 | MK_XBUTTON2.0;
+    unsafe {
+        if state == ElementState::Pressed {
+            SetCapture(hwnd);
+        } else if wparam.0 as u32 & held == 0 {
+            _ = ReleaseCapture();
+            // the capture cancelled leave tracking, a cursor that left while captured was already reported by on_mouse_move
+            if block.cursor_inside.get() {
+                track_mouse_leave(hwnd);
+            }
+        }
+    }
+
+    block.event_queue.push(Event::MouseInput {
+        window: block.id,
+        button,
+        state,
+    });
+
+    // the X button messages are documented to return TRUE
+    if matches!(message, WM_XBUTTONDOWN | WM_XBUTTONUP) {
+        EventResponse::handled(LRESULT(1))
+    } else {
+        EventResponse::pass()
+    }
+}
+
+fn on_key(block: &WindowReferenceBlock, message: u32, lparam: LPARAM) -> EventResponse {
+    // bit 30 of lparam is the previous key state, so a key down for a key that was already down is an auto-repeat
+    let pressed = message == WM_KEYDOWN || message == WM_SYSKEYDOWN;
+    let repeat = pressed && lparam.0 & (1 << 30) != 0;
+
+    // the thread's key state already includes this key while its message is handled
+    let modifiers = held_modifiers(|key| unsafe { GetKeyState(key.0 as i32) } < 0);
+    set_modifiers(block, modifiers);
+
+    if !repeat || block.key_repeat.get() {
+        let mut scancode = ((lparam.0 >> 16) & 0xff) as u32;
+        if lparam.0 & (1 << 24) != 0 {
+            scancode |= 0xe000;
+        }
+
+        block.event_queue.push(Event::KeyboardInput {
+            window: block.id,
+            event: KeyEvent {
+                scancode,
+                state: if pressed {
+                    KeyState::Pressed
+                } else {
+                    KeyState::Released
+                },
+                repeat,
+                modifiers,
+            },
+        });
+    }
+
+    // passed on so system keys (Alt+F4, the window menu) keep working
+    EventResponse::pass()
+}
+
+/// Report the modifiers if they changed.
+fn set_modifiers(block: &WindowReferenceBlock, modifiers: Modifiers) {
+    if block.modifiers.replace(modifiers) != modifiers {
+        block.event_queue.push(Event::ModifiersChanged {
+            window: block.id,
+            modifiers,
+        });
+    }
+}
+
+/// `TranslateMessage` already composed dead keys, `wparam` is a UTF-16 code unit.
+fn on_char(block: &WindowReferenceBlock, wparam: WPARAM, lparam: LPARAM) -> EventResponse {
+    let unit = wparam.0 as u16;
+    let repeat = lparam.0 & (1 << 30) != 0;
+
+    if (0xd800..0xdc00).contains(&unit) {
+        block.high_surrogate.set(Some(unit));
+        return EventResponse::handled(LRESULT(0));
+    }
+
+    let ch = match block.high_surrogate.take() {
+        Some(high) => char::decode_utf16([high, unit]).next().and_then(Result::ok),
+        None => char::from_u32(unit as u32),
+    };
+
+    if let Some(ch) = ch
+        && (!repeat || block.key_repeat.get())
+    {
+        block.event_queue.push(Event::ReceivedCharacter {
+            window: block.id,
+            ch,
+        });
+    }
+
+    EventResponse::handled(LRESULT(0))
+}
+
+fn on_size(
+    hwnd: HWND,
+    block: &WindowReferenceBlock,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> EventResponse {
+    if wparam.0 as u32 == SIZE_MINIMIZED {
+        set_occluded(block, true);
+        return EventResponse::pass();
+    }
+
+    // hidden windows get WM_SIZE too
+    set_occluded(block, !unsafe { IsWindowVisible(hwnd) }.as_bool());
+
+    let size = (
+        (lparam.0 & 0xffff) as u32,
+        ((lparam.0 >> 16) & 0xffff) as u32,
+    );
+    let live_resize = block.in_size_move.get();
+    if live_resize {
+        block.resized_during_size_move.set(true);
+    }
+
+    block.event_queue.push(Event::SurfaceResized {
+        window: block.id,
+        size,
+        live_resize,
+    });
+
+    // within the modal size/move loop this is the only chance to draw before the resized window is shown
+    block.resize_paint.call(size.0, size.1);
+
+    EventResponse::pass()
+}
+
+unsafe fn update_decorations(hwnd: HWND, block: &WindowReferenceBlock) {
+    let style = WINDOW_STYLE(unsafe { GetWindowLongPtrW(hwnd, GWL_STYLE) } as u32);
+    let decorated = has_decorations(style);
+
+    if block.decorated.replace(decorated) != decorated {
+        block.event_queue.push(Event::DecorationsChanged {
+            window: block.id,
+            decorated,
+        });
+    }
+}
+
+fn set_occluded(block: &WindowReferenceBlock, occluded: bool) {
+    if block.occluded.replace(occluded) != occluded {
+        block.event_queue.push(Event::Occluded {
+            window: block.id,
+            occluded,
+        });
+    }
+}
+
+fn begin_interaction(block: &WindowReferenceBlock, state: InteractionState) {
+    let previous = block.interaction.get();
+    block.interaction.set(InteractionState {
+        resizing: previous.resizing || state.resizing,
+        moving: previous.moving || state.moving,
+    });
+
+    if previous.is_idle() {
+        block.event_queue.push(Event::InteractionBegan {
+            window: block.id,
+            state,
+        });
+    }
+}
+
+unsafe fn on_exit_size_move(hwnd: HWND, block: &WindowReferenceBlock) -> EventResponse {
+    block.in_size_move.set(false);
+
+    if !block.interaction.take().is_idle() {
+        block
+            .event_queue
+            .push(Event::InteractionEnded { window: block.id });
+    }
+
+    // report the final size again so renderers know the drag is over
+    let mut rect = RECT::default();
+    if block.resized_during_size_move.take() && unsafe { GetClientRect(hwnd, &mut rect) }.is_ok() {
+        block.event_queue.push(Event::SurfaceResized {
+            window: block.id,
+            size: (
+                (rect.right - rect.left).max(0) as u32,
+                (rect.bottom - rect.top).max(0) as u32,
+            ),
+            live_resize: false,
+        });
+    }
+
+    EventResponse::pass()
+}
+
+/// The window moved to a monitor with a different DPI. The high and low words of `wparam` are the new DPI, `lparam` points to the suggested window rectangle.
+unsafe fn on_dpi_changed(
+    hwnd: HWND,
+    block: &WindowReferenceBlock,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> EventResponse {
+    let dpi = (wparam.0 & 0xffff) as u32;
+    block.event_queue.push(Event::ScaleFactorChanged {
+        window: block.id,
+        scale_factor: dpi as f64 / 96.0,
+    });
+
+    // the suggested rectangle keeps the logical size and keeps the window on the monitor the change was for
+    unsafe {
+        let suggested = &*(lparam.0 as *const RECT);
+        _ = SetWindowPos(
+            hwnd,
+            HWND::default(),
+            suggested.left,
+            suggested.top,
+            suggested.right - suggested.left,
+            suggested.bottom - suggested.top,
+            SWP_NOZORDER | SWP_NOACTIVATE,
+        );
+    }
+
+    EventResponse::handled(LRESULT(0))
+}
+
+/// Translate a `WM_TOUCH` message into touch events. Touch positions are reported in hundredths of a screen pixel.
+unsafe fn on_touch(
+    hwnd: HWND,
+    block: &WindowReferenceBlock,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> EventResponse {
+    let handle = HTOUCHINPUT(lparam.0 as *mut c_void);
+    let mut inputs = vec![TOUCHINPUT::default(); (wparam.0 & 0xffff).max(1)];
+
+    unsafe {
+        if GetTouchInputInfo(handle, &mut inputs, size_of::<TOUCHINPUT>() as i32).is_ok() {
+            let mut origin = POINT::default();
+            _ = ClientToScreen(hwnd, &mut origin);
+
+            for input in &inputs {
+                let window = block.id;
+                let id = input.dwID as u64;
+                let position = (
+                    input.x as f64 / 100.0 - origin.x as f64,
+                    input.y as f64 / 100.0 - origin.y as f64,
+                );
+
+                if input.dwFlags.contains(TOUCHEVENTF_DOWN) {
+                    block.event_queue.push(Event::TouchBegin {
+                        window,
+                        id,
+                        position,
+                    });
+                } else if input.dwFlags.contains(TOUCHEVENTF_UP) {
+                    block.event_queue.push(Event::TouchEnd {
+                        window,
+                        id,
+                        position,
+                    });
+                } else if input.dwFlags.contains(TOUCHEVENTF_MOVE) {
+                    block.event_queue.push(Event::TouchMove {
+                        window,
+                        id,
+                        position,
+                    });
+                }
+            }
+        }
+
+        _ = CloseTouchInputHandle(handle);
+    }
+
+    EventResponse::handled(LRESULT(0))
+}
+
+/// Translate a pointer message from a pen into a pen event.
+///
+/// Only pens are handled here, other pointers are passed on so they get converted into the legacy mouse and touch messages.
+unsafe fn on_pointer(
+    hwnd: HWND,
+    block: &WindowReferenceBlock,
+    message: u32,
+    wparam: WPARAM,
+) -> EventResponse {
+    let pointer_id = (wparam.0 & 0xffff) as u32;
+
+    unsafe {
+        let mut pointer_type = POINTER_INPUT_TYPE::default();
+        if GetPointerType(pointer_id, &mut pointer_type).is_err() || pointer_type != PT_PEN {
+            return EventResponse::pass();
+        }
+
+        let mut info = POINTER_PEN_INFO::default();
+        if GetPointerPenInfo(pointer_id, &mut info).is_err() {
+            return EventResponse::pass();
+        }
+
+        let mut location = info.pointerInfo.ptPixelLocation;
+        _ = ScreenToClient(hwnd, &mut location);
+
+        let window = block.id;
+        let position = (location.x as f64, location.y as f64);
+        let pen = PenState {
+            // pressure is reported in 0..=1024
+            pressure: if info.penMask & PEN_MASK_PRESSURE != 0 {
+                info.pressure as f64 / 1024.0
+            } else {
+                0.0
+            },
+            tilt: (info.tiltX as f64, info.tiltY as f64),
+            barrel_button: info.penFlags & PEN_FLAG_BARREL != 0,
+        };
+
+        block.event_queue.push(match message {
+            WM_POINTERDOWN => Event::PenDown {
+                window,
+                position,
+                pen,
+            },
+            WM_POINTERUP => Event::PenUp {
+                window,
+                position,
+                pen,
+            },
+            _ => Event::PenMove {
+                window,
+                position,
+                pen,
+            },
+        });
+    }
+
+    EventResponse::handled(LRESULT(0))
+}
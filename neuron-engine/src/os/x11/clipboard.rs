@@ -0,0 +1,280 @@
+use crate::os::x11::X11Platform;
+use anyhow::bail;
+use log::debug;
+use std::ffi::{CString, c_int, c_long, c_uchar, c_ulong, c_void};
+use std::time::{Duration, Instant};
+use x11_dl::xlib;
+
+/// How long to wait for the selection owner to hand over the clipboard contents.
+const SELECTION_TIMEOUT: Duration = Duration::from_secs(1);
+
+impl X11Platform {
+    /// Contents of the `CLIPBOARD` selection converted to `mime`, see [`Platform::clipboard_data`](crate::os::Platform::clipboard_data).
+    pub(super) fn read_clipboard(&self, mime: &str) -> Option<Vec<u8>> {
+        let target = self.mime_target(mime)?;
+        let window = self.clipboard_window();
+        let clipboard = self.intern_atom(c"CLIPBOARD");
+
+        // converting our own selection would deadlock waiting for ourselves
+        if unsafe { (self.xlib.XGetSelectionOwner)(self.display, clipboard) } == window {
+            return self
+                .clipboard_contents
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(atom, _)| *atom == target)
+                .map(|(_, data)| data.clone());
+        }
+
+        let property = self.intern_atom(c"NEURON_CLIPBOARD");
+
+        unsafe {
+            (self.xlib.XConvertSelection)(
+                self.display,
+                clipboard,
+                target,
+                property,
+                window,
+                xlib::CurrentTime,
+            );
+
+            let selection = self.wait_for_selection_notify(window)?;
+            if selection.property == 0 {
+                // the owner can't convert to this target
+                return None;
+            }
+
+            self.take_property_bytes(window, property)
+        }
+    }
+
+    /// Take ownership of the `CLIPBOARD` selection and serve `entries` to other clients, see [`Platform::set_clipboard_data`](crate::os::Platform::set_clipboard_data).
+    pub(super) fn write_clipboard(&self, entries: &[(String, Vec<u8>)]) -> anyhow::Result<()> {
+        let contents = entries
+            .iter()
+            .filter_map(|(mime, data)| Some((self.mime_target(mime)?, data.clone())))
+            .collect::<Vec<_>>();
+        *self.clipboard_contents.lock().unwrap() = contents;
+
+        let window = self.clipboard_window();
+        let clipboard = self.intern_atom(c"CLIPBOARD");
+        unsafe {
+            (self.xlib.XSetSelectionOwner)(self.display, clipboard, window, xlib::CurrentTime);
+            // the server refuses silently, asking for the owner is the only way to find out
+            if (self.xlib.XGetSelectionOwner)(self.display, clipboard) != window {
+                bail!("Failed to take ownership of the clipboard");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Answer another client asking for our clipboard contents.
+    pub(super) fn handle_selection_request(&self, request: &xlib::XSelectionRequestEvent) {
+        let targets = self.intern_atom(c"TARGETS");
+        // obsolete clients don't name a property, the target is used instead
+        let property = if request.property == 0 {
+            request.target
+        } else {
+            request.property
+        };
+        let drag_source = self.drag_source.lock().unwrap();
+        let clipboard_contents = self.clipboard_contents.lock().unwrap();
+        // the drop target converts XdndSelection the same way as the clipboard
+        let contents = match &*drag_source {
+            Some(drag) if request.selection == self.intern_atom(c"XdndSelection") => &drag.contents,
+            _ => &*clipboard_contents,
+        };
+
+        let served = unsafe {
+            if request.target == targets {
+                let mut atoms = vec![targets];
+                atoms.extend(contents.iter().map(|(atom, _)| *atom));
+
+                (self.xlib.XChangeProperty)(
+                    self.display,
+                    request.requestor,
+                    property,
+                    xlib::XA_ATOM,
+                    32,
+                    xlib::PropModeReplace,
+                    atoms.as_ptr() as *const c_uchar,
+                    atoms.len() as c_int,
+                );
+                true
+            } else if let Some((_, data)) =
+                contents.iter().find(|(atom, _)| *atom == request.target)
+            {
+                // large transfers would need the INCR protocol, which isn't implemented
+                (self.xlib.XChangeProperty)(
+                    self.display,
+                    request.requestor,
+                    property,
+                    request.target,
+                    8,
+                    xlib::PropModeReplace,
+                    data.as_ptr(),
+                    data.len() as c_int,
+                );
+                true
+            } else {
+                false
+            }
+        };
+
+        let mut event = xlib::XEvent {
+            selection: xlib::XSelectionEvent {
+                type_: xlib::SelectionNotify,
+                serial: 0,
+                send_event: xlib::True,
+                display: self.display,
+                requestor: request.requestor,
+                selection: request.selection,
+                target: request.target,
+                property: if served { property } else { 0 },
+                time: request.time,
+            },
+        };
+
+        unsafe {
+            (self.xlib.XSendEvent)(self.display, request.requestor, xlib::False, 0, &mut event);
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+
+    /// Another client took over the clipboard.
+    pub(super) fn handle_selection_clear(&self, clear: &xlib::XSelectionClearEvent) {
+        if clear.selection == self.intern_atom(c"CLIPBOARD") {
+            self.clipboard_contents.lock().unwrap().clear();
+        }
+    }
+
+    /// The target atom for a MIME type. Plain text is exchanged as `UTF8_STRING`, everything else uses the MIME type itself (the convention of current toolkits).
+    fn mime_target(&self, mime: &str) -> Option<xlib::Atom> {
+        if mime == "text/plain" || mime.eq_ignore_ascii_case("text/plain;charset=utf-8") {
+            return Some(self.intern_atom(c"UTF8_STRING"));
+        }
+
+        let name = CString::new(mime).ok()?;
+        Some(self.intern_atom(&name))
+    }
+
+    /// The hidden window that owns our selections and receives converted clipboard contents, created on first use.
+    fn clipboard_window(&self) -> xlib::Window {
+        if let Some(window) = self.clipboard_window.get() {
+            return window;
+        }
+
+        let window = unsafe {
+            let mut attributes: xlib::XSetWindowAttributes = std::mem::zeroed();
+            (self.xlib.XCreateWindow)(
+                self.display,
+                self.root_window,
+                0,
+                0,
+                1,
+                1,
+                0,
+                0,
+                xlib::InputOnly as u32,
+                std::ptr::null_mut(),
+                0,
+                &mut attributes,
+            )
+        };
+
+        self.clipboard_window.set(Some(window));
+        window
+    }
+
+    /// Wait for the `SelectionNotify` answering an `XConvertSelection`, leaving other events queued.
+    unsafe fn wait_for_selection_notify(
+        &self,
+        window: xlib::Window,
+    ) -> Option<xlib::XSelectionEvent> {
+        let deadline = Instant::now() + SELECTION_TIMEOUT;
+        let mut event: xlib::XEvent = unsafe { std::mem::zeroed() };
+
+        loop {
+            unsafe {
+                if (self.xlib.XCheckTypedWindowEvent)(
+                    self.display,
+                    window,
+                    xlib::SelectionNotify,
+                    &mut event,
+                ) == xlib::True
+                {
+                    return Some(event.selection);
+                }
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                debug!("Timed out waiting for the clipboard owner");
+                return None;
+            }
+
+            let mut fd = libc::pollfd {
+                fd: unsafe { (self.xlib.XConnectionNumber)(self.display) },
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            unsafe {
+                libc::poll(
+                    &mut fd,
+                    1,
+                    remaining.as_millis().min(c_int::MAX as u128) as c_int,
+                );
+            }
+        }
+    }
+
+    /// Read and delete a property holding 8-bit data.
+    unsafe fn take_property_bytes(
+        &self,
+        window: xlib::Window,
+        property: xlib::Atom,
+    ) -> Option<Vec<u8>> {
+        let mut actual_type: xlib::Atom = 0;
+        let mut actual_format: c_int = 0;
+        let mut nitems: c_ulong = 0;
+        let mut bytes_after: c_ulong = 0;
+        let mut data: *mut c_uchar = std::ptr::null_mut();
+
+        unsafe {
+            let status = (self.xlib.XGetWindowProperty)(
+                self.display,
+                window,
+                property,
+                0,
+                c_long::MAX / 4,
+                xlib::True,
+                xlib::AnyPropertyType as xlib::Atom,
+                &mut actual_type,
+                &mut actual_format,
+                &mut nitems,
+                &mut bytes_after,
+                &mut data,
+            );
+
+            if status != xlib::Success as c_int || data.is_null() {
+                return None;
+            }
+
+            let bytes = if actual_type == self.intern_atom(c"INCR") {
+                debug!(
+                    "Clipboard contents are too large to transfer at once (INCR isn't supported)"
+                );
+                None
+            } else if actual_format == 8 {
+                Some(std::slice::from_raw_parts(data, nitems as usize).to_vec())
+            } else {
+                None
+            };
+
+            (self.xlib.XFree)(data as *mut c_void);
+
+            bytes
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use crate::os::window::CursorIcon;
+use crate::os::x11::X11Platform;
+use std::ffi::{c_char, c_uint};
+use x11_dl::xlib;
+
+/// The shape from the core cursor font (`X11/cursorfont.h`), x11-dl doesn't have the constants.
+fn font_shape(icon: CursorIcon) -> c_uint {
+    match icon {
+        // XC_left_ptr
+        CursorIcon::Default => 68,
+        // XC_hand2
+        CursorIcon::Pointer => 60,
+        // XC_xterm
+        CursorIcon::Text => 152,
+        // XC_crosshair
+        CursorIcon::Crosshair => 34,
+        // XC_fleur
+        CursorIcon::Move => 52,
+        // XC_X_cursor
+        CursorIcon::NotAllowed => 0,
+        // XC_sb_h_double_arrow
+        CursorIcon::EwResize => 108,
+        // XC_sb_v_double_arrow
+        CursorIcon::NsResize => 116,
+        // XC_bottom_left_corner
+        CursorIcon::NeswResize => 12,
+        // XC_bottom_right_corner
+        CursorIcon::NwseResize => 14,
+        // XC_watch
+        CursorIcon::Wait | CursorIcon::Progress => 150,
+        // XC_question_arrow
+        CursorIcon::Help => 92,
+    }
+}
+
+impl X11Platform {
+    pub(super) fn set_cursor_visible(&self, window: xlib::Window, visible: bool) {
+        if visible {
+            self.hidden_cursor_windows.lock().unwrap().remove(&window);
+        } else {
+            self.hidden_cursor_windows.lock().unwrap().insert(window);
+        }
+        self.apply_cursor(window);
+    }
+
+    pub(super) fn set_cursor_icon(&self, window: xlib::Window, icon: CursorIcon) {
+        if icon == CursorIcon::Default {
+            self.cursor_icons.lock().unwrap().remove(&window);
+        } else {
+            self.cursor_icons.lock().unwrap().insert(window, icon);
+        }
+        self.apply_cursor(window);
+    }
+
+    /// Define the cursor the window should show, hiding wins over the icon.
+    fn apply_cursor(&self, window: xlib::Window) {
+        let hidden = self.hidden_cursor_windows.lock().unwrap().contains(&window);
+        let icon = self.cursor_icons.lock().unwrap().get(&window).copied();
+
+        unsafe {
+            match (hidden, icon) {
+                (true, _) => {
+                    (self.xlib.XDefineCursor)(self.display, window, self.invisible_cursor());
+                }
+                (false, Some(icon)) => {
+                    (self.xlib.XDefineCursor)(
+                        self.display,
+                        window,
+                        self.font_cursor(font_shape(icon)),
+                    );
+                }
+                // inherit the root window's cursor, which is what the user configured as the default
+                (false, None) => {
+                    (self.xlib.XUndefineCursor)(self.display, window);
+                }
+            }
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+
+    fn font_cursor(&self, shape: c_uint) -> xlib::Cursor {
+        *self
+            .font_cursors
+            .lock()
+            .unwrap()
+            .entry(shape)
+            .or_insert_with(|| unsafe { (self.xlib.XCreateFontCursor)(self.display, shape) })
+    }
+
+    /// X11 has no hidden cursor, so this is a cursor made from a 1x1 pixmap with an empty mask.
+    fn invisible_cursor(&self) -> xlib::Cursor {
+        if self.invisible_cursor.get() == 0 {
+            unsafe {
+                let data: [c_char; 1] = [0];
+                let bitmap = (self.xlib.XCreateBitmapFromData)(
+                    self.display,
+                    self.root_window,
+                    data.as_ptr(),
+                    1,
+                    1,
+                );
+                let mut black: xlib::XColor = std::mem::zeroed();
+                let cursor = (self.xlib.XCreatePixmapCursor)(
+                    self.display,
+                    bitmap,
+                    bitmap,
+                    &mut black,
+                    &mut black,
+                    0,
+                    0,
+                );
+                (self.xlib.XFreePixmap)(self.display, bitmap);
+                self.invisible_cursor.set(cursor);
+            }
+        }
+
+        self.invisible_cursor.get()
+    }
+
+    /// Free every cursor we created, before the connection goes away.
+    pub(super) fn free_cursors(&self) {
+        unsafe {
+            if self.invisible_cursor.get() != 0 {
+                (self.xlib.XFreeCursor)(self.display, self.invisible_cursor.replace(0));
+            }
+
+            for (_, cursor) in self.font_cursors.lock().unwrap().drain() {
+                (self.xlib.XFreeCursor)(self.display, cursor);
+            }
+        }
+    }
+}
@@ -0,0 +1,433 @@
+use crate::event::Event;
+use crate::os::OsLoopInputs;
+use crate::os::window::{DragData, DragEffect, WindowId};
+use crate::os::x11::X11Platform;
+use log::debug;
+use std::ffi::{c_int, c_long, c_uchar, c_uint};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use x11_dl::keysym;
+use x11_dl::xlib;
+
+/// Newest version of the XDND protocol we speak, older targets (down to 3) get the version they announce.
+const XDND_VERSION: c_long = 5;
+
+/// An outgoing drag (the XDND source side), see [`Window::start_drag`](crate::os::window::Window::start_drag).
+pub(super) struct DragSource {
+    window: xlib::Window,
+    id: WindowId,
+    /// What we serve while owning `XdndSelection`, by target atom.
+    pub(super) contents: Vec<(xlib::Atom, Vec<u8>)>,
+    action: xlib::Atom,
+    /// The XDND aware window under the pointer, with the protocol version used with it.
+    target: Option<(xlib::Window, c_long)>,
+    /// The action the target agreed to in its last `XdndStatus`, `None` while it won't take the drop.
+    accepted_action: Option<xlib::Atom>,
+    /// Whether `XdndDrop` was sent, the drag is over once the target answers with `XdndFinished`.
+    dropped: bool,
+}
+
+impl X11Platform {
+    pub(super) fn start_drag(
+        &self,
+        window: xlib::Window,
+        id: WindowId,
+        data: &DragData,
+        effect: DragEffect,
+    ) {
+        // a dropped drag whose target never finished doesn't hold up new ones
+        if self.is_dragging() {
+            debug!("A drag is already in progress, not starting another one");
+            return;
+        }
+
+        let contents = self.drag_contents(data);
+
+        unsafe {
+            // the pointer and keyboard follow the drag (and Escape) wherever it goes
+            let status = (self.xlib.XGrabPointer)(
+                self.display,
+                window,
+                xlib::False,
+                (xlib::PointerMotionMask | xlib::ButtonReleaseMask) as c_uint,
+                xlib::GrabModeAsync,
+                xlib::GrabModeAsync,
+                0,
+                0,
+                xlib::CurrentTime,
+            );
+            if status != xlib::GrabSuccess {
+                debug!("Failed to grab the pointer for a drag (status {})", status);
+                return;
+            }
+            (self.xlib.XGrabKeyboard)(
+                self.display,
+                window,
+                xlib::False,
+                xlib::GrabModeAsync,
+                xlib::GrabModeAsync,
+                xlib::CurrentTime,
+            );
+
+            (self.xlib.XSetSelectionOwner)(
+                self.display,
+                self.intern_atom(c"XdndSelection"),
+                window,
+                xlib::CurrentTime,
+            );
+
+            // XdndEnter only has room for three types
+            if contents.len() > 3 {
+                let types = contents.iter().map(|(atom, _)| *atom).collect::<Vec<_>>();
+                (self.xlib.XChangeProperty)(
+                    self.display,
+                    window,
+                    self.intern_atom(c"XdndTypeList"),
+                    xlib::XA_ATOM,
+                    32,
+                    xlib::PropModeReplace,
+                    types.as_ptr() as *const c_uchar,
+                    types.len() as c_int,
+                );
+            }
+
+            (self.xlib.XFlush)(self.display);
+        }
+
+        *self.drag_source.lock().unwrap() = Some(DragSource {
+            window,
+            id,
+            contents,
+            action: self.action_atom(effect),
+            target: None,
+            accepted_action: None,
+            dropped: false,
+        });
+    }
+
+    /// Whether the pointer is currently dragging data out of one of our windows (the drop doesn't count, its pointer grab is already released).
+    pub(super) fn is_dragging(&self) -> bool {
+        self.drag_source
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|drag| !drag.dropped)
+    }
+
+    /// Follow the pointer: switch targets when it moves to another window and tell the current target where it is.
+    pub(super) fn drag_motion(&self, motion: &xlib::XMotionEvent) {
+        let target = self.xdnd_target_at(motion.x_root, motion.y_root);
+        let mut drag_source = self.drag_source.lock().unwrap();
+        let Some(drag) = drag_source.as_mut() else {
+            return;
+        };
+
+        if drag.target.map(|(window, _)| window) != target.map(|(window, _)| window) {
+            if let Some((old_target, _)) = drag.target {
+                self.send_xdnd_message(
+                    old_target,
+                    c"XdndLeave",
+                    [drag.window as c_long, 0, 0, 0, 0],
+                );
+            }
+
+            if let Some((new_target, version)) = target {
+                let mut data = [
+                    drag.window as c_long,
+                    (version << 24) | (drag.contents.len() > 3) as c_long,
+                    0,
+                    0,
+                    0,
+                ];
+                for (slot, (atom, _)) in data[2..].iter_mut().zip(&drag.contents) {
+                    *slot = *atom as c_long;
+                }
+                self.send_xdnd_message(new_target, c"XdndEnter", data);
+            }
+
+            drag.target = target;
+            drag.accepted_action = None;
+        }
+
+        if let Some((target, _)) = drag.target {
+            self.send_xdnd_message(
+                target,
+                c"XdndPosition",
+                [
+                    drag.window as c_long,
+                    0,
+                    ((motion.x_root as c_long) << 16) | (motion.y_root as c_long & 0xffff),
+                    motion.time as c_long,
+                    drag.action as c_long,
+                ],
+            );
+        }
+    }
+
+    /// The button was released: drop on the target if it accepts, otherwise the drag is over.
+    pub(super) fn drag_release(&self, release: &xlib::XButtonEvent, inputs: &OsLoopInputs) {
+        self.ungrab_for_drag();
+
+        let dropped = {
+            let mut drag_source = self.drag_source.lock().unwrap();
+            let Some(drag) = drag_source.as_mut() else {
+                return;
+            };
+
+            match (drag.target, drag.accepted_action) {
+                (Some((target, _)), Some(_)) => {
+                    self.send_xdnd_message(
+                        target,
+                        c"XdndDrop",
+                        [drag.window as c_long, 0, release.time as c_long, 0, 0],
+                    );
+                    drag.dropped = true;
+                    true
+                }
+                (target, _) => {
+                    if let Some((target, _)) = target {
+                        self.send_xdnd_message(
+                            target,
+                            c"XdndLeave",
+                            [drag.window as c_long, 0, 0, 0, 0],
+                        );
+                    }
+                    false
+                }
+            }
+        };
+
+        if !dropped {
+            self.end_drag(None, inputs);
+        }
+    }
+
+    /// Cancel the drag if Escape was pressed.
+    pub(super) fn drag_key_press(&self, key: &xlib::XKeyEvent, inputs: &OsLoopInputs) {
+        let keysym = unsafe { (self.xlib.XLookupKeysym)(key as *const _ as *mut _, 0) } as c_uint;
+        if keysym != keysym::XK_Escape {
+            return;
+        }
+
+        self.ungrab_for_drag();
+
+        if let Some(drag) = &*self.drag_source.lock().unwrap()
+            && let Some((target, _)) = drag.target
+        {
+            self.send_xdnd_message(target, c"XdndLeave", [drag.window as c_long, 0, 0, 0, 0]);
+        }
+
+        self.end_drag(None, inputs);
+    }
+
+    /// Handle the target's answers (`XdndStatus` and `XdndFinished`). Returns whether the message was one of them.
+    pub(super) fn handle_xdnd_message(
+        &self,
+        message: &xlib::XClientMessageEvent,
+        inputs: &OsLoopInputs,
+    ) -> bool {
+        let is_status = message.message_type == self.intern_atom(c"XdndStatus");
+        let is_finished = message.message_type == self.intern_atom(c"XdndFinished");
+        if !is_status && !is_finished {
+            return false;
+        }
+
+        let finished_effect = {
+            let mut drag_source = self.drag_source.lock().unwrap();
+            let Some(drag) = drag_source.as_mut() else {
+                return true;
+            };
+            let Some((target, version)) = drag.target else {
+                return true;
+            };
+            // answers for a target the pointer already left
+            if message.data.get_long(0) as xlib::Window != target {
+                return true;
+            }
+
+            if is_status {
+                drag.accepted_action = (message.data.get_long(1) & 1 != 0).then(|| {
+                    match message.data.get_long(4) as xlib::Atom {
+                        0 => drag.action,
+                        action => action,
+                    }
+                });
+                return true;
+            }
+
+            if !drag.dropped {
+                return true;
+            }
+
+            // before version 5 the target doesn't say whether (or how) it took the data
+            if version >= 5 {
+                (message.data.get_long(1) & 1 != 0).then(|| message.data.get_long(2) as xlib::Atom)
+            } else {
+                drag.accepted_action
+            }
+        };
+
+        self.end_drag(
+            finished_effect.map(|action| self.action_effect(action)),
+            inputs,
+        );
+        true
+    }
+
+    /// Forget about a drag whose source window is being destroyed (nobody is left to report the outcome to).
+    pub(super) fn drop_drag_source(&self, window: xlib::Window) {
+        let mut drag_source = self.drag_source.lock().unwrap();
+        if drag_source
+            .as_ref()
+            .is_some_and(|drag| drag.window == window)
+        {
+            *drag_source = None;
+        }
+    }
+
+    /// Release the selection and report the outcome, `effect` is `None` for cancelled or rejected drags.
+    fn end_drag(&self, effect: Option<DragEffect>, inputs: &OsLoopInputs) {
+        let Some(drag) = self.drag_source.lock().unwrap().take() else {
+            return;
+        };
+
+        unsafe {
+            let selection = self.intern_atom(c"XdndSelection");
+            if (self.xlib.XGetSelectionOwner)(self.display, selection) == drag.window {
+                (self.xlib.XSetSelectionOwner)(self.display, selection, 0, xlib::CurrentTime);
+            }
+            (self.xlib.XFlush)(self.display);
+        }
+
+        inputs.event_queue.push(Event::DragEnded {
+            window: drag.id,
+            effect,
+        });
+    }
+
+    fn ungrab_for_drag(&self) {
+        unsafe {
+            (self.xlib.XUngrabPointer)(self.display, xlib::CurrentTime);
+            (self.xlib.XUngrabKeyboard)(self.display, xlib::CurrentTime);
+        }
+
+        self.restore_keyboard_grab();
+    }
+
+    /// The topmost XDND aware window at a root position, with the protocol version to use with it.
+    ///
+    /// Window managers put the aware client windows inside frames, so this descends from the root until it finds one.
+    fn xdnd_target_at(&self, x: c_int, y: c_int) -> Option<(xlib::Window, c_long)> {
+        let xdnd_aware = self.intern_atom(c"XdndAware");
+        let mut window = self.root_window;
+
+        loop {
+            let (mut child_x, mut child_y, mut child) = (0, 0, 0);
+            unsafe {
+                (self.xlib.XTranslateCoordinates)(
+                    self.display,
+                    self.root_window,
+                    window,
+                    x,
+                    y,
+                    &mut child_x,
+                    &mut child_y,
+                    &mut child,
+                );
+            }
+            if child == 0 {
+                return None;
+            }
+
+            if let Some(version) = self
+                .get_property_longs(child, xdnd_aware, xlib::XA_ATOM)
+                .and_then(|values| values.first().copied())
+            {
+                return (version >= 3).then_some((child, version.min(XDND_VERSION)));
+            }
+
+            window = child;
+        }
+    }
+
+    fn send_xdnd_message(
+        &self,
+        target: xlib::Window,
+        message_type: &std::ffi::CStr,
+        data: [c_long; 5],
+    ) {
+        let mut event = xlib::XEvent {
+            client_message: xlib::XClientMessageEvent {
+                type_: xlib::ClientMessage,
+                serial: 0,
+                send_event: xlib::True,
+                display: self.display,
+                window: target,
+                message_type: self.intern_atom(message_type),
+                format: 32,
+                data: xlib::ClientMessageData::from(data),
+            },
+        };
+
+        unsafe {
+            (self.xlib.XSendEvent)(
+                self.display,
+                target,
+                xlib::False,
+                xlib::NoEventMask,
+                &mut event,
+            );
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+
+    /// The targets a drag offers its data as. Text is offered in the usual text targets, files as a `text/uri-list`.
+    fn drag_contents(&self, data: &DragData) -> Vec<(xlib::Atom, Vec<u8>)> {
+        match data {
+            DragData::Text(text) => [c"UTF8_STRING", c"text/plain;charset=utf-8", c"text/plain"]
+                .into_iter()
+                .map(|target| (self.intern_atom(target), text.clone().into_bytes()))
+                .collect(),
+            DragData::Files(paths) => {
+                let uri_list = paths
+                    .iter()
+                    .map(|path| file_uri(path) + "\r\n")
+                    .collect::<String>();
+                vec![(self.intern_atom(c"text/uri-list"), uri_list.into_bytes())]
+            }
+        }
+    }
+
+    fn action_atom(&self, effect: DragEffect) -> xlib::Atom {
+        self.intern_atom(match effect {
+            DragEffect::Copy => c"XdndActionCopy",
+            DragEffect::Move => c"XdndActionMove",
+            DragEffect::Link => c"XdndActionLink",
+        })
+    }
+
+    /// Unknown actions (e.g. `XdndActionPrivate`) are reported as copies.
+    fn action_effect(&self, action: xlib::Atom) -> DragEffect {
+        if action == self.intern_atom(c"XdndActionMove") {
+            DragEffect::Move
+        } else if action == self.intern_atom(c"XdndActionLink") {
+            DragEffect::Link
+        } else {
+            DragEffect::Copy
+        }
+    }
+}
+
+/// A `file://` URI for an absolute path, percent-encoding everything but unreserved characters and slashes.
+fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.as_os_str().as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(*byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
@@ -0,0 +1,271 @@
+use crate::event::Event;
+use crate::os::OsLoopInputs;
+use crate::os::x11::X11Platform;
+use log::debug;
+use std::ffi::{CStr, CString, c_char, c_int, c_short, c_ulong, c_ushort, c_void};
+use std::ptr;
+use x11_dl::xlib;
+use x11_dl::xlib::Xlib;
+
+/// `XIMStyles` (not defined by x11-dl).
+#[repr(C)]
+struct XIMStyles {
+    count_styles: c_ushort,
+    supported_styles: *mut c_ulong,
+}
+
+/// Switches `LC_CTYPE` to the user's locale until dropped, then restores the application's.
+///
+/// The locale is process-wide (and `setlocale` isn't thread-safe), so it is only changed while the input method is opened, which happens once when the platform is created.
+struct UserCtypeLocale(Option<CString>);
+
+impl UserCtypeLocale {
+    fn set() -> UserCtypeLocale {
+        unsafe {
+            let previous = libc::setlocale(libc::LC_CTYPE, ptr::null());
+            let previous = (!previous.is_null()).then(|| CStr::from_ptr(previous).to_owned());
+            libc::setlocale(libc::LC_CTYPE, c"".as_ptr());
+            UserCtypeLocale(previous)
+        }
+    }
+}
+
+impl Drop for UserCtypeLocale {
+    fn drop(&mut self) {
+        if let Some(previous) = &self.0 {
+            unsafe {
+                libc::setlocale(libc::LC_CTYPE, previous.as_ptr());
+            }
+        }
+    }
+}
+
+/// Connection to the X input method.
+pub(super) struct Ime {
+    xim: xlib::XIM,
+    /// Input style used for every input context.
+    style: c_ulong,
+}
+
+impl Ime {
+    pub(super) fn new(xlib: &Xlib, display: *mut xlib::Display) -> Option<Ime> {
+        unsafe {
+            // input methods are selected through the locale (and `XMODIFIERS`, which an empty modifier list picks up),
+            // the input method keeps the locale it was opened with
+            let locale = UserCtypeLocale::set();
+            if (xlib.XSupportsLocale)() == xlib::False {
+                debug!("Locale isn't supported by Xlib, input methods are unavailable");
+                return None;
+            }
+            (xlib.XSetLocaleModifiers)(c"".as_ptr());
+
+            let xim = (xlib.XOpenIM)(display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+            drop(locale);
+            if xim.is_null() {
+                debug!("Failed to open an input method");
+                return None;
+            }
+
+            let mut styles: *mut XIMStyles = ptr::null_mut();
+            let failed = (xlib.XGetIMValues)(
+                xim,
+                xlib::XNQueryInputStyle_0.as_ptr() as *const c_char,
+                &mut styles,
+                ptr::null_mut::<c_void>(),
+            );
+
+            let supported = if failed.is_null() && !styles.is_null() {
+                let supported = std::slice::from_raw_parts(
+                    (*styles).supported_styles,
+                    (*styles).count_styles as usize,
+                )
+                .to_vec();
+                (xlib.XFree)(styles as *mut c_void);
+                supported
+            } else {
+                Vec::new()
+            };
+
+            // "over the spot" lets us place the preedit and candidate windows at the text cursor
+            let over_the_spot = (xlib::XIMPreeditPosition | xlib::XIMStatusNothing) as c_ulong;
+            let root = (xlib::XIMPreeditNothing | xlib::XIMStatusNothing) as c_ulong;
+
+            let style = if supported.contains(&over_the_spot) {
+                over_the_spot
+            } else if supported.contains(&root) {
+                root
+            } else {
+                debug!("Input method doesn't support a usable input style");
+                (xlib.XCloseIM)(xim);
+                return None;
+            };
+
+            Some(Ime { xim, style })
+        }
+    }
+
+    pub(super) fn close(&self, xlib: &Xlib) {
+        unsafe {
+            (xlib.XCloseIM)(self.xim);
+        }
+    }
+}
+
+impl X11Platform {
+    /// Create the input context for a new window.
+    pub(super) fn create_input_context(&self, window: xlib::Window) {
+        let Some(ime) = &self.ime else {
+            return;
+        };
+
+        let ic = unsafe {
+            (self.xlib.XCreateIC)(
+                ime.xim,
+                xlib::XNInputStyle_0.as_ptr() as *const c_char,
+                ime.style,
+                xlib::XNClientWindow_0.as_ptr() as *const c_char,
+                window,
+                xlib::XNFocusWindow_0.as_ptr() as *const c_char,
+                window,
+                ptr::null_mut::<c_void>(),
+            )
+        };
+
+        if ic.is_null() {
+            debug!("Failed to create an input context for window {}", window);
+            return;
+        }
+
+        self.input_contexts.lock().unwrap().insert(window, ic);
+    }
+
+    pub(super) fn destroy_input_context(&self, window: xlib::Window) {
+        if let Some(ic) = self.input_contexts.lock().unwrap().remove(&window) {
+            unsafe {
+                (self.xlib.XDestroyIC)(ic);
+            }
+        }
+    }
+
+    /// Tell the input method whether the window has keyboard focus.
+    pub(super) fn set_input_context_focus(&self, window: xlib::Window, focused: bool) {
+        if let Some(&ic) = self.input_contexts.lock().unwrap().get(&window) {
+            unsafe {
+                if focused {
+                    (self.xlib.XSetICFocus)(ic);
+                } else {
+                    (self.xlib.XUnsetICFocus)(ic);
+                }
+            }
+        }
+    }
+
+    /// Move the preedit spot (where the input method shows the composition and candidates) of a window.
+    pub(super) fn set_ime_position(&self, window: xlib::Window, x: i32, y: i32) {
+        let Some(ime) = &self.ime else {
+            return;
+        };
+        let Some(&ic) = self.input_contexts.lock().unwrap().get(&window) else {
+            return;
+        };
+
+        if ime.style & xlib::XIMPreeditPosition as c_ulong == 0 {
+            debug!("Input method doesn't support positioning the preedit window");
+            return;
+        }
+
+        let mut spot = xlib::XPoint {
+            x: x.clamp(c_short::MIN as i32, c_short::MAX as i32) as c_short,
+            y: y.clamp(c_short::MIN as i32, c_short::MAX as i32) as c_short,
+        };
+
+        unsafe {
+            let attributes = (self.xlib.XVaCreateNestedList)(
+                0,
+                xlib::XNSpotLocation_0.as_ptr() as *const c_char,
+                &mut spot,
+                ptr::null_mut::<c_void>(),
+            );
+
+            (self.xlib.XSetICValues)(
+                ic,
+                xlib::XNPreeditAttributes_0.as_ptr() as *const c_char,
+                attributes,
+                ptr::null_mut::<c_void>(),
+            );
+
+            (self.xlib.XFree)(attributes);
+        }
+    }
+
+    /// Report the text a key press typed, one [`Event::ReceivedCharacter`] per character.
+    pub(super) fn push_received_characters(
+        &self,
+        key: &mut xlib::XKeyEvent,
+        inputs: &OsLoopInputs,
+    ) {
+        if let Some(window) = self.window_id(key.window) {
+            for ch in self.lookup_text(key).chars() {
+                inputs
+                    .event_queue
+                    .push(Event::ReceivedCharacter { window, ch });
+            }
+        }
+    }
+
+    /// The text a key press typed. The window's input context composes dead keys and input method text, without one only Latin-1 is available.
+    fn lookup_text(&self, key: &mut xlib::XKeyEvent) -> String {
+        let ic = self
+            .input_contexts
+            .lock()
+            .unwrap()
+            .get(&key.window)
+            .copied();
+        let mut buffer = vec![0u8; 64];
+
+        unsafe {
+            let Some(ic) = ic else {
+                let len = (self.xlib.XLookupString)(
+                    key,
+                    buffer.as_mut_ptr() as *mut c_char,
+                    buffer.len() as c_int,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                );
+                return buffer[..len.max(0) as usize]
+                    .iter()
+                    .map(|&b| b as char)
+                    .collect();
+            };
+
+            let mut status = 0;
+            let mut len = (self.xlib.Xutf8LookupString)(
+                ic,
+                key,
+                buffer.as_mut_ptr() as *mut c_char,
+                buffer.len() as c_int,
+                ptr::null_mut(),
+                &mut status,
+            );
+
+            // committed input method text can be longer than a buffer for a single key, the required size is returned then
+            if status == xlib::XBufferOverflow {
+                buffer.resize(len.max(0) as usize, 0);
+                len = (self.xlib.Xutf8LookupString)(
+                    ic,
+                    key,
+                    buffer.as_mut_ptr() as *mut c_char,
+                    buffer.len() as c_int,
+                    ptr::null_mut(),
+                    &mut status,
+                );
+            }
+
+            if status == xlib::XLookupChars || status == xlib::XLookupBoth {
+                String::from_utf8_lossy(&buffer[..len.max(0) as usize]).into_owned()
+            } else {
+                String::new()
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::event::Event;
+use crate::os::LockState;
+use crate::os::OsLoopInputs;
+use crate::os::x11::X11Platform;
+use log::debug;
+use std::ffi::{CStr, c_int, c_uint};
+use x11_dl::xlib;
+use x11_dl::xlib::Xlib;
+
+/// `XkbUseCoreKbd`, the device spec of the core keyboard.
+const USE_CORE_KEYBOARD: c_uint = 0x0100;
+
+/// XKB extension state, used to follow the keyboard indicators (LEDs).
+pub(super) struct Xkb {
+    pub(super) event_base: c_int,
+    /// Indicator indices of Caps Lock, Num Lock and Scroll Lock.
+    indicators: [c_int; 3],
+}
+
+impl Xkb {
+    pub(super) fn new(xlib: &Xlib, display: *mut xlib::Display) -> Option<Xkb> {
+        let (mut opcode, mut event_base, mut error_base) = (0, 0, 0);
+        let (mut major, mut minor) = (1, 0);
+
+        unsafe {
+            if (xlib.XkbQueryExtension)(
+                display,
+                &mut opcode,
+                &mut event_base,
+                &mut error_base,
+                &mut major,
+                &mut minor,
+            ) == xlib::False
+            {
+                debug!("XKB extension is not available");
+                return None;
+            }
+
+            (xlib.XkbSelectEvents)(
+                display,
+                USE_CORE_KEYBOARD,
+                xlib::XkbIndicatorStateNotifyMask,
+                xlib::XkbIndicatorStateNotifyMask,
+            );
+        }
+
+        // the usual layout, for keymaps that don't name their indicators
+        let mut indicators = [0, 1, 2];
+        for (index, name) in indicators
+            .iter_mut()
+            .zip([c"Caps Lock", c"Num Lock", c"Scroll Lock"])
+        {
+            if let Some(named) = named_indicator(xlib, display, name) {
+                *index = named;
+            }
+        }
+
+        Some(Xkb {
+            event_base,
+            indicators,
+        })
+    }
+
+    fn lock_state(&self, indicator_state: c_uint) -> LockState {
+        let is_on = |index: c_int| indicator_state & (1 << index) != 0;
+
+        LockState {
+            caps: is_on(self.indicators[0]),
+            num: is_on(self.indicators[1]),
+            scroll: is_on(self.indicators[2]),
+        }
+    }
+}
+
+fn named_indicator(xlib: &Xlib, display: *mut xlib::Display, name: &CStr) -> Option<c_int> {
+    let mut index = 0;
+
+    unsafe {
+        let atom = (xlib.XInternAtom)(display, name.as_ptr(), xlib::True);
+        if atom == 0
+            || (xlib.XkbGetNamedIndicator)(
+                display,
+                atom,
+                &mut index,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            ) == xlib::False
+        {
+            return None;
+        }
+    }
+
+    Some(index)
+}
+
+impl X11Platform {
+    pub(super) fn current_lock_state(&self) -> LockState {
+        let Some(xkb) = &self.xkb else {
+            return LockState::default();
+        };
+
+        let mut state = 0;
+        unsafe {
+            (self.xlib.XkbGetIndicatorState)(self.display, USE_CORE_KEYBOARD, &mut state);
+        }
+
+        xkb.lock_state(state)
+    }
+
+    /// Handle an XKB event, reporting changed lock keys.
+    pub(super) fn handle_xkb_event(&self, event: &xlib::XEvent, inputs: &OsLoopInputs) {
+        let Some(xkb) = &self.xkb else {
+            return;
+        };
+
+        let event =
+            unsafe { &*(event as *const xlib::XEvent as *const xlib::XkbIndicatorNotifyEvent) };
+        if event.xkb_type != xlib::XkbIndicatorStateNotify {
+            return;
+        }
+
+        let state = xkb.lock_state(event.state);
+        if self.lock_state.replace(state) != state {
+            inputs.event_queue.push(Event::LockStateChanged { state });
+        }
+    }
+}
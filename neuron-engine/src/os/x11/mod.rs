@@ -2,38 +2,155 @@
 
 #![cfg(target_os = "linux")]
 
+mod clipboard;
+mod cursor;
+mod drag;
+mod ime;
+mod lock_state;
+mod monitor;
 mod window;
+mod xinput;
+mod xsettings;
 
-use crate::os::window::{SupportedWindowAttributes, Window, WindowAttributes, WindowId};
+use crate::event::{ElementState, Event, KeyEvent, KeyState, Modifiers, MouseButton, ScrollDelta};
+use crate::os::accelerator::{Accelerator, AcceleratorKey, AcceleratorModifiers};
+use crate::os::monitor::MonitorHandle;
+use crate::os::window::{
+    CursorIcon, InteractionState, ResizePaintCallback, ResizePaintSlot, SupportedWindowAttributes,
+    Window, WindowAttributes, WindowId,
+};
+use crate::os::x11::drag::DragSource;
+use crate::os::x11::ime::Ime;
+use crate::os::x11::lock_state::Xkb;
+use crate::os::x11::monitor::SavedCrtc;
 use crate::os::x11::window::X11Window;
-use crate::os::{OsLoopInputs, PlatformKind};
+use crate::os::x11::xinput::XInput;
+use crate::os::{
+    GlobalKeyboardHook, LockState, OsLoopInputs, PlatformKind, PointerInfo, PointerState,
+    WeakOsLoopInputs,
+};
 use anyhow::bail;
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
+use log::debug;
 use raw_window_handle::{
     DisplayHandle, HandleError, HasDisplayHandle, RawDisplayHandle, XlibDisplayHandle,
 };
-use std::cell::RefCell;
-use std::ffi::{CStr, c_long, c_ulong, c_void};
-use std::mem::MaybeUninit;
+use std::any::Any;
+use std::cell::{Cell, OnceCell, RefCell};
+use std::ffi::{CStr, c_char, c_int, c_long, c_uchar, c_uint, c_ulong, c_void};
 use std::ptr::NonNull;
-use std::sync::{Arc, Weak};
-use log::debug;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::{self, ThreadId};
+use std::time::Duration;
+use x11_dl::keysym;
 use x11_dl::xlib;
 use x11_dl::xlib::{XEvent, Xlib};
+use x11_dl::xrandr;
+use x11_dl::xrandr::Xrandr;
 
-pub(super) struct X11Platform {
+/// The per-window bookkeeping is behind mutexes, but the display connection is not: Xlib is used without `XInitThreads` (the connection may come from a host application), so the platform is `!Send + !Sync` and stays bound to the thread that created it.
+/// Window creation, the event loop (`wait_for_events`, `has_pending_events`, `process_events`, `sync`), the clipboard and the global keyboard hook additionally assert that they run on that thread, in release builds as well.
+pub struct X11Platform {
     pub(self) xlib: Xlib,
+    xrandr: Option<Xrandr>,
+    /// First event number of the RandR extension, if it is available.
+    xrandr_event_base: Option<c_int>,
+    xinput: Option<XInput>,
+    xkb: Option<Xkb>,
+    /// Last reported lock keys, to only report actual changes (indicator events also cover other LEDs).
+    lock_state: Cell<LockState>,
+    /// Last reported modifier keys. The keyboard isn't per window, so neither are these.
+    modifiers: Cell<Modifiers>,
+    ime: Option<Ime>,
     pub(self) display: *mut xlib::Display,
+    /// Whether the connection was opened by us (and should be closed on drop).
+    owns_display: bool,
+    main_thread: ThreadId,
     pub(self) default_screen: i32,
     pub(self) root_window: xlib::Window,
     pub(self) xa_wm_delete_window: xlib::Atom,
     pub(self) xa_wm_protocols: xlib::Atom,
-    window_map: RefCell<HashMap<xlib::Window, WindowId>>,
-    weak: Weak<X11Platform>,
+    window_map: Mutex<HashMap<xlib::Window, WindowId>>,
+    /// The engine each window was created by. Events for a window are delivered to its engine, whichever engine sharing the platform processes them.
+    window_inputs: Mutex<HashMap<xlib::Window, WeakOsLoopInputs>>,
+    hovered_window: Cell<Option<WindowId>>,
+    key_repeat_disabled: Mutex<HashSet<xlib::Window>>,
+    input_contexts: Mutex<HashMap<xlib::Window, xlib::XIC>>,
+    /// Last known client size of each window, to tell resizes apart from moves in `ConfigureNotify`.
+    surface_sizes: Mutex<HashMap<xlib::Window, (u32, u32)>>,
+    /// See [`Window::set_resize_paint_callback`]. Shared so a callback can run without the map being borrowed.
+    resize_paint_callbacks: Mutex<HashMap<xlib::Window, Rc<ResizePaintSlot>>>,
+    /// Windows the user is resizing or moving, as far as can be told from `ConfigureNotify`, see [`Window::interaction_state`].
+    interactions: Mutex<HashMap<xlib::Window, InteractionState>>,
+    /// Windows the window manager currently reports as maximized.
+    maximized_windows: Mutex<HashSet<xlib::Window>>,
+    /// Geometry (x, y, width, height) to apply when a maximized window is restored, see [`Window::set_restore_placement`].
+    restore_placements: Mutex<HashMap<xlib::Window, (i32, i32, u32, u32)>>,
+    /// Windows that are unmapped or fully obscured.
+    occluded_windows: Mutex<HashSet<xlib::Window>>,
+    /// `ClientMessage` types that are passed on as [`Event::RawClientMessage`].
+    client_message_subscriptions: Mutex<HashSet<xlib::Atom>>,
+    /// Windows that were last seen without decorations (windows start out decorated).
+    undecorated_windows: Mutex<HashSet<xlib::Window>>,
+    /// Windows created with [`WindowAttributes::auto_hide_on_focus_loss`].
+    auto_hide_windows: Mutex<HashSet<xlib::Window>>,
+    /// Windows that were hidden (by the application or on focus loss), see [`Window::is_visible`]. The map state can't tell, a minimized window is unmapped as well.
+    hidden_windows: Mutex<HashSet<xlib::Window>>,
+    /// Windows that keep the pointer grabbed, see [`Window::set_cursor_confined`]. The grab is taken again whenever they get the focus back.
+    confined_windows: Mutex<HashSet<xlib::Window>>,
+    /// Windows with a locked cursor (see [`CursorGrabMode::Locked`](crate::os::window::CursorGrabMode::Locked)), the pointer is warped back to the center after every move while they have the focus.
+    locked_windows: Mutex<HashSet<xlib::Window>>,
+    /// Our window with the keyboard focus, as last told by `FocusIn`/`FocusOut` (cheaper than asking the server on every move).
+    input_focus: Cell<Option<xlib::Window>>,
+    /// Blank cursor for [`Window::set_cursor_visible`], created on first use (0 until then).
+    invisible_cursor: Cell<xlib::Cursor>,
+    /// Windows whose cursor is hidden, see [`Window::set_cursor_visible`].
+    hidden_cursor_windows: Mutex<HashSet<xlib::Window>>,
+    /// Shapes set with [`Window::set_cursor_icon`], windows without an entry show the default cursor.
+    cursor_icons: Mutex<HashMap<xlib::Window, CursorIcon>>,
+    /// Cursors created from the cursor font, by shape. They are kept until the platform is dropped.
+    font_cursors: Mutex<HashMap<c_uint, xlib::Cursor>>,
+    /// Windows that grab the keyboard while focused, see [`Window::set_keyboard_shortcuts_inhibited`].
+    shortcut_inhibiting_windows: Mutex<HashSet<xlib::Window>>,
+    /// Windows with a synthetic `Expose` on its way, see [`Window::request_redraw`].
+    pending_redraws: Mutex<HashSet<xlib::Window>>,
+    /// Monitors switched to another video mode, with the configuration to restore.
+    changed_video_modes: Mutex<HashMap<String, SavedCrtc>>,
+    /// Hidden window owning the clipboard selection, see [`Platform::set_clipboard_data`].
+    clipboard_window: Cell<Option<xlib::Window>>,
+    /// What we serve while owning the clipboard, by target atom.
+    clipboard_contents: Mutex<Vec<(xlib::Atom, Vec<u8>)>>,
+    /// The drag we're the source of, see [`Window::start_drag`].
+    drag_source: Mutex<Option<DragSource>>,
+    /// Called with raw key events, see [`Platform::set_global_keyboard_hook`]. Cloned out while the hook runs, so the hook can replace or remove itself.
+    global_keyboard_hook: Mutex<Option<Rc<RefCell<GlobalKeyboardHook>>>>,
+    /// Set once the platform is in its `Arc`, see [`X11Platform::into_arc`].
+    weak: OnceCell<Weak<X11Platform>>,
 }
 
+const MODIFIER_KEYSYMS: [(c_uint, Modifiers); 8] = [
+    (keysym::XK_Shift_L, Modifiers::LSHIFT),
+    (keysym::XK_Shift_R, Modifiers::RSHIFT),
+    (keysym::XK_Control_L, Modifiers::LCTRL),
+    (keysym::XK_Control_R, Modifiers::RCTRL),
+    (keysym::XK_Alt_L, Modifiers::LALT),
+    (keysym::XK_Alt_R, Modifiers::RALT),
+    (keysym::XK_Super_L, Modifiers::LSUPER),
+    (keysym::XK_Super_R, Modifiers::RSUPER),
+];
+
+/// The core modifier masks with the keys behind them (left first), assuming the usual mapping of Alt to `Mod1` and Super to `Mod4`.
+const MODIFIER_MASKS: [(c_uint, Modifiers, Modifiers); 4] = [
+    (xlib::ShiftMask, Modifiers::LSHIFT, Modifiers::RSHIFT),
+    (xlib::ControlMask, Modifiers::LCTRL, Modifiers::RCTRL),
+    (xlib::Mod1Mask, Modifiers::LALT, Modifiers::RALT),
+    (xlib::Mod4Mask, Modifiers::LSUPER, Modifiers::RSUPER),
+];
+
 impl X11Platform {
-    pub fn new(weak: Weak<X11Platform>) -> anyhow::Result<X11Platform> {
+    /// Open a connection to the X server named by `DISPLAY`, to pass to [`X11Platform::from_display`].
+    pub(crate) fn open_display() -> anyhow::Result<*mut xlib::Display> {
         let xlib = Xlib::open()?;
         let display = unsafe { (xlib.XOpenDisplay)(std::ptr::null()) };
 
@@ -41,28 +158,129 @@ impl X11Platform {
             bail!("Failed to connect to X server.");
         }
 
+        Ok(display)
+    }
+
+    /// Use an existing X connection (e.g. one owned by a host application the engine is embedded in) instead of opening a new one.
+    ///
+    /// When `owns` is false the connection is left open when the platform is dropped.
+    ///
+    /// # Safety
+    /// `display` has to be a valid connection that stays open for as long as the platform exists.
+    pub unsafe fn from_display(
+        display: *mut xlib::Display,
+        owns: bool,
+    ) -> anyhow::Result<X11Platform> {
+        if display.is_null() {
+            bail!("X display connection is null.");
+        }
+
+        Self::with_display(Xlib::open()?, display, owns)
+    }
+
+    /// Put the platform in an `Arc`, windows keep a reference to the platform they were created by.
+    pub(crate) fn into_arc(self) -> Arc<X11Platform> {
+        // the display keeps the platform on its thread (see the type docs), the `Arc` is only shared with its windows
+        #[allow(clippy::arc_with_non_send_sync)]
+        let platform = Arc::new(self);
+        _ = platform.weak.set(Arc::downgrade(&platform));
+        platform
+    }
+
+    fn with_display(
+        xlib: Xlib,
+        display: *mut xlib::Display,
+        owns_display: bool,
+    ) -> anyhow::Result<X11Platform> {
+        let xrandr = Xrandr::open().ok();
+
         let default_screen = unsafe { (xlib.XDefaultScreen)(display) };
 
         let root_window = unsafe { (xlib.XRootWindow)(display, default_screen) };
 
-        let xa_wm_delete_window_name = CStr::from_bytes_with_nul(b"WM_DELETE_WINDOW\0")?;
-        let xa_wm_protocols_name = CStr::from_bytes_with_nul(b"WM_PROTOCOLS\0")?;
+        let xinput = XInput::new(&xlib, display, root_window);
+
+        let xkb = Xkb::new(&xlib, display);
+
+        let ime = Ime::new(&xlib, display);
+
+        let xrandr_event_base = xrandr.as_ref().and_then(|xrandr| unsafe {
+            let (mut event_base, mut error_base) = (0, 0);
+            if (xrandr.XRRQueryExtension)(display, &mut event_base, &mut error_base) == xlib::False
+            {
+                return None;
+            }
+
+            // monitor changes are reported to the root window
+            (xrandr.XRRSelectInput)(
+                display,
+                root_window,
+                xrandr::RRScreenChangeNotifyMask
+                    | xrandr::RRCrtcChangeNotifyMask
+                    | xrandr::RROutputChangeNotifyMask,
+            );
+
+            Some(event_base)
+        });
+
+        let xa_wm_delete_window_name = c"WM_DELETE_WINDOW";
+        let xa_wm_protocols_name = c"WM_PROTOCOLS";
 
         let xa_wm_delete_window =
             unsafe { (xlib.XInternAtom)(display, xa_wm_delete_window_name.as_ptr(), xlib::False) };
         let xa_wm_protocols =
             unsafe { (xlib.XInternAtom)(display, xa_wm_protocols_name.as_ptr(), xlib::False) };
 
-        Ok(X11Platform {
+        let platform = X11Platform {
             xlib,
+            xrandr,
+            xrandr_event_base,
+            xinput,
+            xkb,
+            lock_state: Cell::new(LockState::default()),
+            modifiers: Cell::new(Modifiers::empty()),
+            ime,
             display,
+            owns_display,
+            main_thread: std::thread::current().id(),
             default_screen,
             root_window,
             xa_wm_delete_window,
             xa_wm_protocols,
-            weak,
-            window_map: RefCell::new(HashMap::new()),
-        })
+            weak: OnceCell::new(),
+            window_map: Mutex::new(HashMap::new()),
+            window_inputs: Mutex::new(HashMap::new()),
+            hovered_window: Cell::new(None),
+            input_focus: Cell::new(None),
+            key_repeat_disabled: Mutex::new(HashSet::new()),
+            input_contexts: Mutex::new(HashMap::new()),
+            surface_sizes: Mutex::new(HashMap::new()),
+            resize_paint_callbacks: Mutex::new(HashMap::new()),
+            interactions: Mutex::new(HashMap::new()),
+            maximized_windows: Mutex::new(HashSet::new()),
+            restore_placements: Mutex::new(HashMap::new()),
+            occluded_windows: Mutex::new(HashSet::new()),
+            client_message_subscriptions: Mutex::new(HashSet::new()),
+            undecorated_windows: Mutex::new(HashSet::new()),
+            auto_hide_windows: Mutex::new(HashSet::new()),
+            hidden_windows: Mutex::new(HashSet::new()),
+            confined_windows: Mutex::new(HashSet::new()),
+            locked_windows: Mutex::new(HashSet::new()),
+            invisible_cursor: Cell::new(0),
+            hidden_cursor_windows: Mutex::new(HashSet::new()),
+            cursor_icons: Mutex::new(HashMap::new()),
+            font_cursors: Mutex::new(HashMap::new()),
+            shortcut_inhibiting_windows: Mutex::new(HashSet::new()),
+            pending_redraws: Mutex::new(HashSet::new()),
+            changed_video_modes: Mutex::new(HashMap::new()),
+            clipboard_window: Cell::new(None),
+            clipboard_contents: Mutex::new(Vec::new()),
+            drag_source: Mutex::new(None),
+            global_keyboard_hook: Mutex::new(None),
+        };
+
+        platform.lock_state.set(platform.current_lock_state());
+        Ok(platform)
     }
 
     pub fn display(&self) -> *mut xlib::Display {
@@ -77,15 +295,957 @@ impl X11Platform {
         self.root_window
     }
 
+    /// Deliver `ClientMessage` events of type `atom` sent to our windows as [`Event::RawClientMessage`].
+    ///
+    /// `WM_PROTOCOLS` messages are still handled by the platform, subscribing to them is ignored.
+    pub fn subscribe_client_message(&self, atom: xlib::Atom) {
+        self.client_message_subscriptions
+            .lock()
+            .unwrap()
+            .insert(atom);
+    }
+
+    /// Stop delivering `ClientMessage` events of type `atom`.
+    pub fn unsubscribe_client_message(&self, atom: xlib::Atom) {
+        self.client_message_subscriptions
+            .lock()
+            .unwrap()
+            .remove(&atom);
+    }
+
     pub fn notify_window_destroy(&self, window: xlib::Window) {
-        self.window_map.borrow_mut().remove(&window);
+        self.key_repeat_disabled.lock().unwrap().remove(&window);
+        self.window_inputs.lock().unwrap().remove(&window);
+        self.surface_sizes.lock().unwrap().remove(&window);
+        self.resize_paint_callbacks.lock().unwrap().remove(&window);
+        self.interactions.lock().unwrap().remove(&window);
+        self.maximized_windows.lock().unwrap().remove(&window);
+        self.restore_placements.lock().unwrap().remove(&window);
+        self.occluded_windows.lock().unwrap().remove(&window);
+        self.undecorated_windows.lock().unwrap().remove(&window);
+        self.auto_hide_windows.lock().unwrap().remove(&window);
+        self.hidden_windows.lock().unwrap().remove(&window);
+        self.confined_windows.lock().unwrap().remove(&window);
+        if self.locked_windows.lock().unwrap().remove(&window) {
+            self.select_raw_motion_events(!self.locked_windows.lock().unwrap().is_empty());
+        }
+        if self.input_focus.get() == Some(window) {
+            self.input_focus.set(None);
+        }
+        self.hidden_cursor_windows.lock().unwrap().remove(&window);
+        self.cursor_icons.lock().unwrap().remove(&window);
+        self.shortcut_inhibiting_windows
+            .lock()
+            .unwrap()
+            .remove(&window);
+        self.pending_redraws.lock().unwrap().remove(&window);
+        self.drop_drag_source(window);
+        self.destroy_input_context(window);
+
+        if let Some(id) = self.window_map.lock().unwrap().remove(&window)
+            && self.hovered_window.get() == Some(id)
+        {
+            self.hovered_window.set(None);
+        }
+    }
+
+    /// Xlib is only initialized for single-threaded use, so everything that talks to the display checks this (not just in debug builds).
+    fn assert_main_thread(&self, operation: &str) {
+        assert!(
+            thread::current().id() == self.main_thread,
+            "X11Platform::{} has to be called on the thread that created the platform",
+            operation
+        );
+    }
+
+    fn window_id(&self, window: xlib::Window) -> Option<WindowId> {
+        self.window_map.lock().unwrap().get(&window).copied()
+    }
+
+    fn set_key_repeat(&self, window: xlib::Window, enabled: bool) {
+        if enabled {
+            self.key_repeat_disabled.lock().unwrap().remove(&window);
+        } else {
+            self.key_repeat_disabled.lock().unwrap().insert(window);
+        }
+    }
+
+    fn set_cursor_confined(&self, window: xlib::Window, confined: bool) {
+        if confined {
+            self.confined_windows.lock().unwrap().insert(window);
+            // fails while the window isn't viewable or someone else holds a grab, the next FocusIn tries again
+            self.grab_pointer(window);
+        } else if self.confined_windows.lock().unwrap().remove(&window) {
+            unsafe {
+                (self.xlib.XUngrabPointer)(self.display, xlib::CurrentTime);
+                (self.xlib.XFlush)(self.display);
+            }
+        }
+    }
+
+    fn set_cursor_locked(&self, window: xlib::Window, locked: bool) {
+        if locked {
+            self.locked_windows.lock().unwrap().insert(window);
+            // an unfocused window only takes the pointer once it gets the focus
+            if self.focused_window() == Some(window) {
+                self.center_pointer(window);
+            }
+        } else {
+            self.locked_windows.lock().unwrap().remove(&window);
+        }
+
+        self.select_raw_motion_events(!self.locked_windows.lock().unwrap().is_empty());
+    }
+
+    fn center_pointer(&self, window: xlib::Window) {
+        let Some((width, height)) = self.surface_sizes.lock().unwrap().get(&window).copied() else {
+            return;
+        };
+
+        unsafe {
+            (self.xlib.XWarpPointer)(
+                self.display,
+                0,
+                window,
+                0,
+                0,
+                0,
+                0,
+                (width / 2) as c_int,
+                (height / 2) as c_int,
+            );
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+
+    fn is_cursor_locked(&self, window: xlib::Window) -> bool {
+        self.locked_windows.lock().unwrap().contains(&window)
+    }
+
+    /// The window holding the pointer, a locked window that has the focus.
+    fn locking_window(&self) -> Option<xlib::Window> {
+        self.input_focus
+            .get()
+            .filter(|&window| self.is_cursor_locked(window))
+    }
+
+    /// Report how far a locked pointer moved, and put it back. The warp produces a move to the center, which is skipped.
+    ///
+    /// Only used without XInput2: moves queued before the warp are measured from the center as well, so fast motion is overcounted. Raw motion events (see `handle_raw_motion`) don't have that problem.
+    fn handle_locked_motion(&self, motion: &xlib::XMotionEvent, inputs: &OsLoopInputs) {
+        let Some((width, height)) = self
+            .surface_sizes
+            .lock()
+            .unwrap()
+            .get(&motion.window)
+            .copied()
+        else {
+            return;
+        };
+
+        let center = ((width / 2) as c_int, (height / 2) as c_int);
+        if (motion.x, motion.y) == center {
+            return;
+        }
+
+        if let Some(window) = self.window_id(motion.window) {
+            inputs.event_queue.push(Event::MouseMotion {
+                window,
+                delta: ((motion.x - center.0) as f64, (motion.y - center.1) as f64),
+            });
+        }
+        self.center_pointer(motion.window);
+    }
+
+    /// Grab the pointer, confined to `window`. Events are still reported to the windows they happen in.
+    fn grab_pointer(&self, window: xlib::Window) {
+        let status = unsafe {
+            (self.xlib.XGrabPointer)(
+                self.display,
+                window,
+                xlib::True,
+                (xlib::ButtonPressMask | xlib::ButtonReleaseMask | xlib::PointerMotionMask)
+                    as c_uint,
+                xlib::GrabModeAsync,
+                xlib::GrabModeAsync,
+                window,
+                0,
+                xlib::CurrentTime,
+            )
+        };
+
+        if status != xlib::GrabSuccess {
+            debug!(
+                "Failed to confine the cursor to window {} (status {})",
+                window, status
+            );
+        }
+    }
+
+    fn set_keyboard_shortcuts_inhibited(&self, window: xlib::Window, inhibited: bool) {
+        let focused = self.focused_window() == Some(window);
+
+        if inhibited {
+            self.shortcut_inhibiting_windows
+                .lock()
+                .unwrap()
+                .insert(window);
+            if focused {
+                self.grab_keyboard(window);
+            }
+        } else if self
+            .shortcut_inhibiting_windows
+            .lock()
+            .unwrap()
+            .remove(&window)
+            && focused
+        {
+            unsafe {
+                (self.xlib.XUngrabKeyboard)(self.display, xlib::CurrentTime);
+                (self.xlib.XFlush)(self.display);
+            }
+        }
+    }
+
+    /// Take the keyboard grab again if the focused window inhibits shortcuts (after another grab replaced it).
+    fn restore_keyboard_grab(&self) {
+        if let Some(window) = self.focused_window()
+            && self
+                .shortcut_inhibiting_windows
+                .lock()
+                .unwrap()
+                .contains(&window)
+        {
+            self.grab_keyboard(window);
+        }
+    }
+
+    /// Grab the keyboard for `window`, so key events (including the window manager's shortcuts) only go to it.
+    fn grab_keyboard(&self, window: xlib::Window) {
+        let status = unsafe {
+            (self.xlib.XGrabKeyboard)(
+                self.display,
+                window,
+                xlib::True,
+                xlib::GrabModeAsync,
+                xlib::GrabModeAsync,
+                xlib::CurrentTime,
+            )
+        };
+
+        if status != xlib::GrabSuccess {
+            debug!(
+                "Failed to grab the keyboard for window {} (status {})",
+                window, status
+            );
+        }
+    }
+
+    fn focused_window(&self) -> Option<xlib::Window> {
+        let mut focus = 0;
+        let mut revert_to = 0;
+
+        unsafe {
+            (self.xlib.XGetInputFocus)(self.display, &mut focus, &mut revert_to);
+        }
+
+        Some(focus).filter(|focus| *focus != 0)
+    }
+
+    /// Check if a `KeyRelease` is half of an auto-repeat, and if so consume the `KeyPress` that follows it.
+    ///
+    /// X11 reports auto-repeat as a release immediately followed by a press of the same key with the same timestamp.
+    unsafe fn take_repeated_key_press(&self, release: &xlib::XKeyEvent) -> Option<XEvent> {
+        unsafe {
+            if (self.xlib.XPending)(self.display) == 0 {
+                return None;
+            }
+
+            let mut next: XEvent = std::mem::zeroed();
+            (self.xlib.XPeekEvent)(self.display, &mut next);
+
+            if next.type_ == xlib::KeyPress
+                && next.key.window == release.window
+                && next.key.keycode == release.keycode
+                && next.key.time == release.time
+            {
+                (self.xlib.XNextEvent)(self.display, &mut next);
+                Some(next)
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Bring the modifiers in line with the `state` of an event and the key of a key event (as `(keysym, pressed)`), reporting a change to the window.
+    ///
+    /// `state` is from before the event and doesn't tell left and right apart, so it only releases modifiers that were missed, and a modifier held since before the focus changed counts as the left one.
+    fn update_modifiers(
+        &self,
+        xwindow: xlib::Window,
+        state: c_uint,
+        key: Option<(c_uint, bool)>,
+        inputs: &OsLoopInputs,
+    ) -> Modifiers {
+        let mut modifiers = self.modifiers.get();
+        for (mask, left, right) in MODIFIER_MASKS {
+            if state & mask == 0 {
+                modifiers.remove(left | right);
+            } else if !modifiers.intersects(left | right) {
+                modifiers.insert(left);
+            }
+        }
+
+        if let Some((keysym, pressed)) = key
+            && let Some((_, modifier)) = MODIFIER_KEYSYMS
+                .into_iter()
+                .find(|(modifier_keysym, _)| *modifier_keysym == keysym)
+        {
+            modifiers.set(modifier, pressed);
+        }
+
+        if self.modifiers.replace(modifiers) != modifiers
+            && let Some(window) = self.window_id(xwindow)
+        {
+            inputs
+                .event_queue
+                .push(Event::ModifiersChanged { window, modifiers });
+        }
+
+        modifiers
+    }
+
+    /// The modifier keys held on the whole keyboard, for key events that don't belong to one of our windows.
+    fn global_modifiers(&self) -> Modifiers {
+        let mut keys = [0 as c_char; 32];
+        unsafe {
+            (self.xlib.XQueryKeymap)(self.display, keys.as_mut_ptr());
+        }
+
+        MODIFIER_KEYSYMS
+            .into_iter()
+            .filter(|(keysym, _)| {
+                let keycode =
+                    unsafe { (self.xlib.XKeysymToKeycode)(self.display, *keysym as xlib::KeySym) }
+                        as usize;
+                keycode != 0 && keys[keycode / 8] as u8 & (1 << (keycode % 8)) != 0
+            })
+            .fold(Modifiers::empty(), |modifiers, (_, modifier)| {
+                modifiers | modifier
+            })
+    }
+
+    /// Returns whether the key press triggered an accelerator instead (it doesn't type anything then).
+    fn push_key_event(
+        &self,
+        key: &xlib::XKeyEvent,
+        state: KeyState,
+        repeat: bool,
+        inputs: &OsLoopInputs,
+    ) -> bool {
+        let keysym = unsafe { (self.xlib.XLookupKeysym)(key as *const _ as *mut _, 0) } as c_uint;
+        let modifiers = self.update_modifiers(
+            key.window,
+            key.state,
+            Some((keysym, state == KeyState::Pressed)),
+            inputs,
+        );
+
+        if let Some(window) = self.window_id(key.window) {
+            if state == KeyState::Pressed
+                && let Some(chord) = self.accelerator_chord(key)
+                && let Some(id) = inputs.window_manager.accelerator_id(&chord)
+            {
+                inputs
+                    .event_queue
+                    .push(Event::AcceleratorTriggered { window, id });
+                return true;
+            }
+
+            inputs.event_queue.push(Event::KeyboardInput {
+                window,
+                event: KeyEvent {
+                    scancode: key.keycode,
+                    state,
+                    repeat,
+                    modifiers,
+                },
+            });
+        }
+
+        false
+    }
+
+    /// Report a core button event. Buttons 4 to 7 are wheel notches (up, down, left, right), pressed and immediately released.
+    fn push_button_event(&self, button: &xlib::XButtonEvent, inputs: &OsLoopInputs) {
+        let Some(window) = self.window_id(button.window) else {
+            return;
+        };
+        self.update_modifiers(button.window, button.state, None, inputs);
+
+        let pressed = button.type_ == xlib::ButtonPress;
+        let event = match button.button {
+            4..=7 if !pressed => return,
+            4..=7 => {
+                let (x, y) = match button.button {
+                    4 => (0.0, 1.0),
+                    5 => (0.0, -1.0),
+                    6 => (-1.0, 0.0),
+                    _ => (1.0, 0.0),
+                };
+                Event::MouseWheel {
+                    window,
+                    delta: ScrollDelta::Lines { x, y },
+                }
+            }
+            number => Event::MouseInput {
+                window,
+                button: match number {
+                    1 => MouseButton::Left,
+                    2 => MouseButton::Middle,
+                    3 => MouseButton::Right,
+                    other => MouseButton::Other(other as u16),
+                },
+                state: if pressed {
+                    ElementState::Pressed
+                } else {
+                    ElementState::Released
+                },
+            },
+        };
+
+        inputs.event_queue.push(event);
+    }
+
+    /// The accelerator a key press would trigger, `None` for keys that can't be part of one.
+    fn accelerator_chord(&self, key: &xlib::XKeyEvent) -> Option<Accelerator> {
+        // the unshifted keysym, so Ctrl+Shift+1 is still the 1 key
+        let keysym = unsafe { (self.xlib.XLookupKeysym)(key as *const _ as *mut _, 0) } as c_uint;
+
+        let accelerator_key = match keysym {
+            keysym::XK_a..=keysym::XK_z => {
+                AcceleratorKey::Char((b'A' + (keysym - keysym::XK_a) as u8) as char)
+            }
+            keysym::XK_0..=keysym::XK_9 => {
+                AcceleratorKey::Char((b'0' + (keysym - keysym::XK_0) as u8) as char)
+            }
+            keysym::XK_F1..=keysym::XK_F24 => {
+                AcceleratorKey::Function((keysym - keysym::XK_F1 + 1) as u8)
+            }
+            keysym::XK_Return | keysym::XK_KP_Enter => AcceleratorKey::Enter,
+            keysym::XK_Escape => AcceleratorKey::Escape,
+            keysym::XK_Tab => AcceleratorKey::Tab,
+            keysym::XK_space => AcceleratorKey::Space,
+            keysym::XK_BackSpace => AcceleratorKey::Backspace,
+            keysym::XK_Delete => AcceleratorKey::Delete,
+            keysym::XK_Insert => AcceleratorKey::Insert,
+            keysym::XK_Home => AcceleratorKey::Home,
+            keysym::XK_End => AcceleratorKey::End,
+            keysym::XK_Page_Up => AcceleratorKey::PageUp,
+            keysym::XK_Page_Down => AcceleratorKey::PageDown,
+            keysym::XK_Left => AcceleratorKey::Left,
+            keysym::XK_Right => AcceleratorKey::Right,
+            keysym::XK_Up => AcceleratorKey::Up,
+            keysym::XK_Down => AcceleratorKey::Down,
+            _ => return None,
+        };
+
+        let modifiers = AcceleratorModifiers {
+            ctrl: key.state & xlib::ControlMask != 0,
+            shift: key.state & xlib::ShiftMask != 0,
+            alt: key.state & xlib::Mod1Mask != 0,
+        };
+
+        Some(Accelerator::new(modifiers, accelerator_key))
+    }
+
+    fn handle_configure(&self, configure: &xlib::XConfigureEvent, inputs: &OsLoopInputs) {
+        let Some(window) = self.window_id(configure.window) else {
+            return;
+        };
+
+        let size = (
+            configure.width.max(0) as u32,
+            configure.height.max(0) as u32,
+        );
+        let resized = self
+            .surface_sizes
+            .lock()
+            .unwrap()
+            .insert(configure.window, size)
+            != Some(size);
+        // window managers send a synthetic ConfigureNotify (in root coordinates) when they move a window
+        let moved = !resized && configure.send_event != 0;
+
+        // the window manager grabs the pointer for interactive resizes and moves, so a held button is the only hint we get
+        let live = (resized || moved) && self.pointer_button_held();
+        if live {
+            self.begin_interaction(
+                configure.window,
+                window,
+                InteractionState {
+                    resizing: resized,
+                    moving: moved,
+                },
+                inputs,
+            );
+        }
+
+        if !resized {
+            return;
+        }
+
+        inputs.event_queue.push(Event::SurfaceResized {
+            window,
+            size,
+            live_resize: live,
+        });
+
+        let resize_paint = self
+            .resize_paint_callbacks
+            .lock()
+            .unwrap()
+            .get(&configure.window)
+            .cloned();
+        if let Some(resize_paint) = resize_paint {
+            resize_paint.call(size.0, size.1);
+        }
+    }
+
+    fn begin_interaction(
+        &self,
+        xwindow: xlib::Window,
+        window: WindowId,
+        state: InteractionState,
+        inputs: &OsLoopInputs,
+    ) {
+        let mut interactions = self.interactions.lock().unwrap();
+        let interaction = interactions.entry(xwindow).or_default();
+        let began = interaction.is_idle();
+
+        interaction.resizing |= state.resizing;
+        interaction.moving |= state.moving;
+
+        if began {
+            inputs
+                .event_queue
+                .push(Event::InteractionBegan { window, state });
+        }
+    }
+
+    /// End the interactions once all buttons are released (the release itself goes to the window manager).
+    fn end_interactions(&self, inputs: &OsLoopInputs) {
+        if self.interactions.lock().unwrap().is_empty() || self.pointer_button_held() {
+            return;
+        }
+
+        for (xwindow, _) in self.interactions.lock().unwrap().drain() {
+            if let Some(window) = self.window_id(xwindow) {
+                inputs.event_queue.push(Event::InteractionEnded { window });
+            }
+        }
+    }
+
+    fn interaction_state(&self, window: xlib::Window) -> InteractionState {
+        self.interactions
+            .lock()
+            .unwrap()
+            .get(&window)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn set_resize_paint_callback(
+        &self,
+        window: xlib::Window,
+        callback: Option<ResizePaintCallback>,
+    ) {
+        let slot = self
+            .resize_paint_callbacks
+            .lock()
+            .unwrap()
+            .entry(window)
+            .or_default()
+            .clone();
+        slot.set(callback);
+    }
+
+    /// Whether the window manager has the window maximized (in either direction) according to `_NET_WM_STATE`.
+    fn is_maximized(&self, window: xlib::Window) -> bool {
+        let maximized = [
+            self.intern_atom(c"_NET_WM_STATE_MAXIMIZED_VERT"),
+            self.intern_atom(c"_NET_WM_STATE_MAXIMIZED_HORZ"),
+        ];
+
+        self.get_property_longs(window, self.intern_atom(c"_NET_WM_STATE"), xlib::XA_ATOM)
+            .unwrap_or_default()
+            .iter()
+            .any(|state| maximized.contains(&(*state as xlib::Atom)))
+    }
+
+    /// Store the geometry a maximized window should get once it is restored.
+    fn set_restore_placement(&self, window: xlib::Window, geometry: (i32, i32, u32, u32)) {
+        self.restore_placements
+            .lock()
+            .unwrap()
+            .insert(window, geometry);
+    }
+
+    fn handle_property_notify(&self, property: &xlib::XPropertyEvent, inputs: &OsLoopInputs) {
+        let Some(window) = self.window_id(property.window) else {
+            return;
+        };
+
+        if property.atom == self.intern_atom(c"_NET_WM_STATE") {
+            self.update_maximized(property.window);
+        } else if property.atom == self.intern_atom(c"_MOTIF_WM_HINTS")
+            || property.atom == self.intern_atom(c"_NET_FRAME_EXTENTS")
+            || property.atom == self.intern_atom(c"_GTK_FRAME_EXTENTS")
+        {
+            let decorated = self.is_decorated(property.window);
+            let changed = if decorated {
+                self.undecorated_windows
+                    .lock()
+                    .unwrap()
+                    .remove(&property.window)
+            } else {
+                self.undecorated_windows
+                    .lock()
+                    .unwrap()
+                    .insert(property.window)
+            };
+
+            if changed {
+                inputs
+                    .event_queue
+                    .push(Event::DecorationsChanged { window, decorated });
+            }
+        }
+    }
+
+    /// Whether the window manager currently decorates the window.
+    ///
+    /// Motif hints are what applications (and some window managers' "toggle decorations" bindings) use to turn decorations off, the frame extents are what the window manager actually draws. `_GTK_FRAME_EXTENTS` is only set by clients drawing their own shadows, so it doesn't change the answer, but a change to it is a good moment to check again.
+    fn is_decorated(&self, window: xlib::Window) -> bool {
+        // struct { flags, functions, decorations, input_mode, status }, flag 2 means `decorations` is set
+        let motif_hints = self.intern_atom(c"_MOTIF_WM_HINTS");
+        if let Some(hints) = self.get_property_longs(window, motif_hints, motif_hints)
+            && hints.len() >= 3
+            && hints[0] & 2 != 0
+            && hints[2] == 0
+        {
+            return false;
+        }
+
+        let frame_extents = self.intern_atom(c"_NET_FRAME_EXTENTS");
+        self.get_property_longs(window, frame_extents, xlib::XA_CARDINAL)
+            .is_none_or(|extents| extents.iter().any(|extent| *extent != 0))
+    }
+
+    fn update_maximized(&self, window: xlib::Window) {
+        let maximized = self.is_maximized(window);
+        let was_maximized = if maximized {
+            !self.maximized_windows.lock().unwrap().insert(window)
+        } else {
+            self.maximized_windows.lock().unwrap().remove(&window)
+        };
+
+        // the window manager restores its own saved geometry first, ours is applied on top of it. The placement is only used once, after that the window manager remembers the geometry again.
+        if was_maximized
+            && !maximized
+            && let Some((x, y, width, height)) =
+                self.restore_placements.lock().unwrap().remove(&window)
+        {
+            unsafe {
+                (self.xlib.XMoveResizeWindow)(self.display, window, x, y, width, height);
+            }
+        }
+    }
+
+    fn is_occluded(&self, window: xlib::Window) -> bool {
+        self.occluded_windows.lock().unwrap().contains(&window)
+    }
+
+    /// Record whether a window can be seen and report changes.
+    fn set_occluded(&self, window: xlib::Window, occluded: bool, inputs: Option<&OsLoopInputs>) {
+        let changed = if occluded {
+            self.occluded_windows.lock().unwrap().insert(window)
+        } else {
+            self.occluded_windows.lock().unwrap().remove(&window)
+        };
+
+        if changed
+            && let Some(inputs) = inputs
+            && let Some(id) = self.window_id(window)
+        {
+            inputs.event_queue.push(Event::Occluded {
+                window: id,
+                occluded,
+            });
+        }
+    }
+
+    /// Send the window an `Expose` of our own, unless one is already queued.
+    fn request_redraw(&self, window: xlib::Window) {
+        if !self.pending_redraws.lock().unwrap().insert(window) {
+            return;
+        }
+
+        let mut event = xlib::XEvent {
+            expose: xlib::XExposeEvent {
+                type_: xlib::Expose,
+                serial: 0,
+                send_event: xlib::True,
+                display: self.display,
+                window,
+                x: 0,
+                y: 0,
+                width: 0,
+                height: 0,
+                count: 0,
+            },
+        };
+
+        unsafe {
+            (self.xlib.XSendEvent)(
+                self.display,
+                window,
+                xlib::False,
+                xlib::ExposureMask,
+                &mut event,
+            );
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+
+    fn handle_expose(&self, expose: &xlib::XExposeEvent, inputs: &OsLoopInputs) {
+        // the last event of a series has count 0, one redraw covers all of them
+        if expose.count != 0 {
+            return;
+        }
+
+        // a real exposure answers a pending request as well, the synthetic event arriving afterwards is dropped
+        let requested = self.pending_redraws.lock().unwrap().remove(&expose.window);
+        if expose.send_event != xlib::False && !requested {
+            return;
+        }
+
+        if let Some(window) = self.window_id(expose.window) {
+            inputs.event_queue.push(Event::RedrawRequested { window });
+        }
+    }
+
+    fn handle_client_message(&self, message: &xlib::XClientMessageEvent, inputs: &OsLoopInputs) {
+        let Some(window) = self.window_id(message.window) else {
+            return;
+        };
+
+        if self.handle_xdnd_message(message, inputs) {
+            return;
+        }
+
+        if message.message_type == self.xa_wm_protocols {
+            if message.format == 32
+                && message.data.get_long(0) == self.xa_wm_delete_window as c_long
+            {
+                inputs.event_queue.push(Event::CloseRequested { window });
+                inputs.window_manager.begin_closing_window(window);
+            }
+        } else if self
+            .client_message_subscriptions
+            .lock()
+            .unwrap()
+            .contains(&message.message_type)
+        {
+            inputs.event_queue.push(Event::RawClientMessage {
+                window,
+                message_type: message.message_type,
+                data: std::array::from_fn(|i| message.data.get_long(i)),
+            });
+        }
+    }
+
+    fn push_focus_event(
+        &self,
+        focus_change: &xlib::XFocusChangeEvent,
+        focused: bool,
+        inputs: &OsLoopInputs,
+    ) {
+        // grabs only borrow the focus, and focus moving between the window and its children (or following the pointer) stays within the window
+        let real_change = focus_change.mode != xlib::NotifyGrab
+            && focus_change.mode != xlib::NotifyUngrab
+            && focus_change.detail != xlib::NotifyInferior
+            && focus_change.detail != xlib::NotifyPointer;
+
+        if real_change && let Some(window) = self.window_id(focus_change.window) {
+            inputs.event_queue.push(Event::Focused { window, focused });
+
+            // key releases go to whichever window has the focus then
+            if !focused && !self.modifiers.replace(Modifiers::empty()).is_empty() {
+                inputs.event_queue.push(Event::ModifiersChanged {
+                    window,
+                    modifiers: Modifiers::empty(),
+                });
+            }
+        }
+    }
+
+    /// Whether a mouse button is currently held.
+    ///
+    /// X11 doesn't announce interactive resizes, but window managers only run them while a button is held (keyboard-driven resizes aren't detected).
+    fn pointer_button_held(&self) -> bool {
+        let (mut root, mut child) = (0, 0);
+        let (mut root_x, mut root_y, mut x, mut y) = (0, 0, 0, 0);
+        let mut mask: c_uint = 0;
+
+        unsafe {
+            (self.xlib.XQueryPointer)(
+                self.display,
+                self.root_window,
+                &mut root,
+                &mut child,
+                &mut root_x,
+                &mut root_y,
+                &mut x,
+                &mut y,
+                &mut mask,
+            );
+        }
+
+        mask & (xlib::Button1Mask | xlib::Button2Mask | xlib::Button3Mask) != 0
+    }
+
+    /// Track which window the pointer is in, so enter/leave events stay paired even when crossings are missed or doubled by grabs.
+    fn set_hovered_window(&self, hovered: Option<WindowId>, inputs: &OsLoopInputs) {
+        let previous = self.hovered_window.replace(hovered);
+        if previous == hovered {
+            return;
+        }
+
+        if let Some(window) = previous {
+            inputs.event_queue.push(Event::CursorLeft { window });
+        }
+
+        if let Some(window) = hovered {
+            inputs.event_queue.push(Event::CursorEntered { window });
+        }
+    }
+
+    fn intern_atom(&self, name: &CStr) -> xlib::Atom {
+        unsafe { (self.xlib.XInternAtom)(self.display, name.as_ptr(), xlib::False) }
+    }
+
+    /// Name of an atom, empty for atoms the server doesn't know.
+    fn atom_name(&self, atom: xlib::Atom) -> String {
+        unsafe {
+            let name = (self.xlib.XGetAtomName)(self.display, atom);
+            if name.is_null() {
+                return String::new();
+            }
+
+            let owned = CStr::from_ptr(name).to_string_lossy().into_owned();
+            (self.xlib.XFree)(name as *mut _);
+            owned
+        }
+    }
+
+    /// Log if the window manager doesn't list an EWMH hint in `_NET_SUPPORTED`. The request is still sent, some window managers don't keep the list complete.
+    fn check_wm_supports(&self, hint: &CStr) {
+        let atom = self.intern_atom(hint) as c_long;
+        let supported = self
+            .get_property_longs(
+                self.root_window,
+                self.intern_atom(c"_NET_SUPPORTED"),
+                xlib::XA_ATOM,
+            )
+            .is_some_and(|atoms| atoms.contains(&atom));
+
+        if !supported {
+            debug!(
+                "The window manager doesn't list {:?} as supported, it will probably be ignored",
+                hint
+            );
+        }
+    }
+
+    /// Read a 32-bit format property (such as a `CARDINAL` array) from a window.
+    fn get_property_longs(
+        &self,
+        window: xlib::Window,
+        property: xlib::Atom,
+        property_type: xlib::Atom,
+    ) -> Option<Vec<c_long>> {
+        let mut actual_type: xlib::Atom = 0;
+        let mut actual_format: c_int = 0;
+        let mut nitems: c_ulong = 0;
+        let mut bytes_after: c_ulong = 0;
+        let mut data: *mut c_uchar = std::ptr::null_mut();
+
+        unsafe {
+            let status = (self.xlib.XGetWindowProperty)(
+                self.display,
+                window,
+                property,
+                0,
+                c_long::MAX / 4,
+                xlib::False,
+                property_type,
+                &mut actual_type,
+                &mut actual_format,
+                &mut nitems,
+                &mut bytes_after,
+                &mut data,
+            );
+
+            if status != xlib::Success as c_int || data.is_null() {
+                return None;
+            }
+
+            let values = if actual_type == property_type && actual_format == 32 {
+                Some(std::slice::from_raw_parts(data as *const c_long, nitems as usize).to_vec())
+            } else {
+                None
+            };
+
+            (self.xlib.XFree)(data as *mut c_void);
+
+            values
+        }
     }
 }
 
 impl Drop for X11Platform {
     fn drop(&mut self) {
-        unsafe {
-            (self.xlib.XCloseDisplay)(self.display);
+        // the X server keeps the mode after we disconnect
+        self.restore_all_video_modes();
+
+        for (_, ic) in self.input_contexts.lock().unwrap().drain() {
+            unsafe {
+                (self.xlib.XDestroyIC)(ic);
+            }
+        }
+
+        if let Some(ime) = &self.ime {
+            ime.close(&self.xlib);
+        }
+
+        if let Some(window) = self.clipboard_window.get() {
+            unsafe {
+                (self.xlib.XDestroyWindow)(self.display, window);
+            }
+        }
+
+        self.free_cursors();
+
+        if self.owns_display {
+            unsafe {
+                (self.xlib.XCloseDisplay)(self.display);
+            }
+        } else {
+            // the host keeps using the connection, so our requests (e.g. restored video modes) still have to go out
+            unsafe {
+                (self.xlib.XSync)(self.display, xlib::False);
+            }
         }
     }
 }
@@ -103,11 +1263,57 @@ impl HasDisplayHandle for X11Platform {
     }
 }
 
+/// What [`Platform::supported_window_attributes`](crate::os::Platform::supported_window_attributes) reports.
+static SUPPORTED_WINDOW_ATTRIBUTES: SupportedWindowAttributes = SupportedWindowAttributes {
+    title: true,
+    size: true,
+    position: true,
+    has_close_button: false,
+    has_minimize_button: false,
+    has_maximize_button: false,
+    show_border: false,
+    show_title_bar: false,
+    initially_disabled: false,
+    initially_minimized: false,
+    resizable: true,
+    min_size: true,
+    max_size: true,
+    skip_taskbar: true,
+    show_in_taskbar: true,
+    show_in_switcher: true,
+    initially_visible: true,
+    initial_z_order: true,
+    always_on_top: true,
+    auto_hide_on_focus_loss: true,
+    compositor_bypass: true,
+    windows_show_drop_shadow: false,
+    windows_is_dialog_box: false,
+    windows_has_system_menu: false,
+    x11_window_type: true,
+    fullscreen: true,
+    icon: true,
+    transparent: true,
+    background_color: true,
+};
+
 impl super::Platform for X11Platform {
     fn name(&self) -> &'static str {
         super::names::LINUX_X11
     }
 
+    fn main_thread_only(&self) -> bool {
+        // Xlib connections aren't used with XInitThreads, so they can't be shared between threads
+        true
+    }
+
+    fn main_thread(&self) -> ThreadId {
+        self.main_thread
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn kind(&self) -> PlatformKind {
         PlatformKind::LinuxX11
     }
@@ -121,64 +1327,452 @@ impl super::Platform for X11Platform {
     }
 
     fn supported_window_attributes(&self) -> &'static SupportedWindowAttributes {
-        &SupportedWindowAttributes {
-            title: true,
-            size: true,
-            position: true,
-            has_close_button: false,
-            has_minimize_button: false,
-            has_maximize_button: false,
-            show_drop_shadow: false,
-            show_border: false,
-            show_title_bar: false,
-            initially_disabled: false,
-            is_dialog_box: false,
-            initially_minimized: false,
-            resizable: true,
-            has_system_menu: false,
-            initially_visible: true,
+        &SUPPORTED_WINDOW_ATTRIBUTES
+    }
+
+    fn primary_monitor(&self) -> Option<MonitorHandle> {
+        let monitors = self.query_monitors();
+        monitors
+            .iter()
+            .find(|(primary, _)| *primary)
+            .or(monitors.first())
+            .map(|(_, monitor)| monitor.clone())
+    }
+
+    fn available_monitors(&self) -> Vec<MonitorHandle> {
+        let mut monitors = self.query_monitors();
+        // stable, so the rest keep the RandR order
+        monitors.sort_by_key(|(primary, _)| !primary);
+        monitors.into_iter().map(|(_, monitor)| monitor).collect()
+    }
+
+    fn pointer_info(&self) -> PointerInfo {
+        // core X only reports the length of the button map, scrolling is buttons 4/5 (vertical) and 6/7 (horizontal)
+        let mut map = [0 as c_uchar; 256];
+        let count = unsafe {
+            (self.xlib.XGetPointerMapping)(self.display, map.as_mut_ptr(), map.len() as c_int)
+        }
+        .max(0) as u32;
+
+        // buttons 4 to 7 aren't physical buttons
+        let scroll_buttons = count.clamp(3, 7) - 3;
+
+        PointerInfo {
+            button_count: count - scroll_buttons,
+            has_wheel: count >= 5,
+            has_horizontal_wheel: count >= 7,
+        }
+    }
+
+    fn lock_state(&self) -> LockState {
+        self.current_lock_state()
+    }
+
+    fn double_click_time(&self) -> Duration {
+        // defaults match GTK's
+        let millis = self
+            .xsetting_int("Net/DoubleClickTime")
+            .filter(|t| *t > 0)
+            .unwrap_or(400);
+        Duration::from_millis(millis as u64)
+    }
+
+    fn double_click_distance(&self) -> u32 {
+        self.xsetting_int("Net/DoubleClickDistance")
+            .map_or(5, |d| d.max(0) as u32)
+    }
+
+    fn drag_threshold(&self) -> u32 {
+        self.xsetting_int("Net/DndDragThreshold")
+            .map_or(8, |d| d.max(0) as u32)
+    }
+
+    fn query_pointer(&self, window: WindowId) -> Option<PointerState> {
+        let xwindow = self
+            .window_map
+            .lock()
+            .unwrap()
+            .iter()
+            .find_map(|(xwindow, id)| (*id == window).then_some(*xwindow))?;
+
+        let (mut root, mut child) = (0, 0);
+        let (mut root_x, mut root_y, mut x, mut y) = (0, 0, 0, 0);
+        let mut mask: c_uint = 0;
+
+        let same_screen = unsafe {
+            (self.xlib.XQueryPointer)(
+                self.display,
+                xwindow,
+                &mut root,
+                &mut child,
+                &mut root_x,
+                &mut root_y,
+                &mut x,
+                &mut y,
+                &mut mask,
+            )
+        };
+        if same_screen == xlib::False {
+            return None;
         }
+
+        // buttons 4 and 5 are the scroll wheel, and later buttons have no mask bit
+        let buttons = [
+            (xlib::Button1Mask, MouseButton::Left),
+            (xlib::Button2Mask, MouseButton::Middle),
+            (xlib::Button3Mask, MouseButton::Right),
+        ]
+        .into_iter()
+        .filter(|(button_mask, _)| mask & button_mask != 0)
+        .fold(0, |buttons, (_, button)| {
+            buttons | PointerState::button_mask(button)
+        });
+
+        Some(PointerState {
+            position: (x as f64, y as f64),
+            buttons,
+        })
     }
 
     fn create_window(
         &self,
         window_attributes: WindowAttributes,
         window_id: WindowId,
+        inputs: &OsLoopInputs,
     ) -> anyhow::Result<Arc<dyn Window>> {
+        self.assert_main_thread("create_window");
+        // windows stay on the platform's thread, the `Arc` is shared with the window manager and not between threads
+        #[allow(clippy::arc_with_non_send_sync)]
         let win = Arc::new(X11Window::new(
-            self.weak.upgrade().unwrap(),
+            self.weak.get().and_then(Weak::upgrade).unwrap(),
             window_attributes,
             window_id,
         )?);
-        self.window_map.borrow_mut().insert(win.window, window_id);
+        self.window_map
+            .lock()
+            .unwrap()
+            .insert(win.window, window_id);
+        self.window_inputs
+            .lock()
+            .unwrap()
+            .insert(win.window, inputs.downgrade());
         Ok(win)
     }
 
+    fn set_global_keyboard_hook(&self, hook: GlobalKeyboardHook) -> anyhow::Result<()> {
+        self.assert_main_thread("set_global_keyboard_hook");
+        if !self.select_raw_key_events(true) {
+            bail!("XInput 2.2 is required for global key events");
+        }
+
+        *self.global_keyboard_hook.lock().unwrap() = Some(Rc::new(RefCell::new(hook)));
+        Ok(())
+    }
+
+    fn remove_global_keyboard_hook(&self) {
+        self.assert_main_thread("remove_global_keyboard_hook");
+        self.select_raw_key_events(false);
+        *self.global_keyboard_hook.lock().unwrap() = None;
+    }
+
+    fn clipboard_data(&self, mime: &str) -> Option<Vec<u8>> {
+        self.assert_main_thread("clipboard_data");
+        self.read_clipboard(mime)
+    }
+
+    fn set_clipboard_data(&self, entries: &[(String, Vec<u8>)]) -> anyhow::Result<()> {
+        self.assert_main_thread("set_clipboard_data");
+        self.write_clipboard(entries)
+    }
+
+    fn sync(&self) {
+        self.assert_main_thread("sync");
+        unsafe {
+            (self.xlib.XSync)(self.display, xlib::False);
+        }
+    }
+
+    fn wait_for_events(&self, timeout: Duration) {
+        self.assert_main_thread("wait_for_events");
+        unsafe {
+            // also flushes our requests, which could be what the server's reply is waiting on
+            if (self.xlib.XPending)(self.display) > 0 {
+                return;
+            }
+
+            let mut fd = libc::pollfd {
+                fd: (self.xlib.XConnectionNumber)(self.display),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            libc::poll(
+                &mut fd,
+                1,
+                timeout.as_millis().min(c_int::MAX as u128) as c_int,
+            );
+        }
+    }
+
+    fn has_pending_events(&self) -> bool {
+        self.assert_main_thread("has_pending_events");
+        unsafe { (self.xlib.XPending)(self.display) > 0 }
+    }
+
     fn process_events(&self, inputs: &OsLoopInputs) {
-        #[allow(invalid_value)]
-        let mut event = unsafe { MaybeUninit::<XEvent>::uninit().assume_init() };
+        self.assert_main_thread("process_events");
+        let mut event: XEvent = unsafe { std::mem::zeroed() };
+        // a single change usually produces a burst of RandR events, report it once
+        let mut monitors_changed = false;
 
         unsafe {
             while (self.xlib.XPending)(self.display) > 0 {
                 (self.xlib.XNextEvent)(self.display, &mut event);
 
+                // the input method gets to see (and swallow) events first
+                if (self.xlib.XFilterEvent)(&mut event, 0) == xlib::True {
+                    continue;
+                }
+
+                // extension events don't have a window in the common header, they go to the engine processing them
+                let routed = if event.type_ < xlib::GenericEvent {
+                    self.window_inputs
+                        .lock()
+                        .unwrap()
+                        .get(&event.any.window)
+                        .and_then(WeakOsLoopInputs::upgrade)
+                } else {
+                    None
+                };
+                let inputs = routed.as_ref().unwrap_or(inputs);
+
                 match event.type_ {
                     xlib::ClientMessage => {
-                        if event.client_message.message_type == self.xa_wm_protocols
-                            && event.client_message.format == 32
+                        self.handle_client_message(&event.client_message, inputs);
+                    }
+                    // crossings into/out of child windows don't change whether the pointer is over our window
+                    xlib::EnterNotify if event.crossing.detail != xlib::NotifyInferior => {
+                        if let Some(wid) = self.window_id(event.crossing.window) {
+                            self.set_hovered_window(Some(wid), inputs);
+                            // like the first WM_MOUSEMOVE on Windows, the enter also tells where the cursor is
+                            inputs.event_queue.push(Event::CursorMoved {
+                                window: wid,
+                                position: (event.crossing.x as f64, event.crossing.y as f64),
+                            });
+                        }
+                    }
+                    xlib::LeaveNotify if event.crossing.detail != xlib::NotifyInferior => {
+                        if let Some(wid) = self.window_id(event.crossing.window)
+                            && self.hovered_window.get() == Some(wid)
                         {
-                            if event.client_message.data.as_longs()[0]
-                                == (self.xa_wm_delete_window as c_long)
+                            self.set_hovered_window(None, inputs);
+                        }
+                    }
+                    // during a drag the pointer and keyboard are grabbed, their events belong to the drag
+                    xlib::MotionNotify if self.is_dragging() => {
+                        self.drag_motion(&event.motion);
+                    }
+                    xlib::ButtonRelease if self.is_dragging() => {
+                        self.drag_release(&event.button, inputs);
+                    }
+                    xlib::MotionNotify
+                        if self.locking_window() == Some(event.motion.window)
+                            && self.xinput.is_none() =>
+                    {
+                        self.handle_locked_motion(&event.motion, inputs);
+                    }
+                    // the pointer doesn't visibly move while it is locked, raw motion reports the deltas
+                    xlib::MotionNotify if self.locking_window() == Some(event.motion.window) => {}
+                    xlib::MotionNotify => {
+                        self.update_modifiers(
+                            event.motion.window,
+                            event.motion.state,
+                            None,
+                            inputs,
+                        );
+                        if let Some(window) = self.window_id(event.motion.window) {
+                            inputs.event_queue.push(Event::CursorMoved {
+                                window,
+                                position: (event.motion.x as f64, event.motion.y as f64),
+                            });
+                        }
+                    }
+                    xlib::ButtonPress | xlib::ButtonRelease => {
+                        self.push_button_event(&event.button, inputs);
+                    }
+                    xlib::KeyPress if self.is_dragging() => {
+                        self.drag_key_press(&event.key, inputs);
+                    }
+                    // input methods commit composed text with a synthetic key press that has no key
+                    xlib::KeyPress if event.key.keycode == 0 => {
+                        self.push_received_characters(&mut event.key, inputs);
+                    }
+                    xlib::KeyPress => {
+                        let accelerator =
+                            self.push_key_event(&event.key, KeyState::Pressed, false, inputs);
+                        if !accelerator {
+                            self.push_received_characters(&mut event.key, inputs);
+                        }
+                    }
+                    xlib::KeyRelease => {
+                        if let Some(mut press) = self.take_repeated_key_press(&event.key) {
+                            let repeat_disabled = self
+                                .key_repeat_disabled
+                                .lock()
+                                .unwrap()
+                                .contains(&event.key.window);
+                            if !repeat_disabled
+                                && !self.push_key_event(&press.key, KeyState::Pressed, true, inputs)
+                                && (self.xlib.XFilterEvent)(&mut press, 0) == xlib::False
                             {
-                                if let Some(wid) = self.window_map.borrow().get(&event.any.window) {
-                                    inputs.window_manager.begin_closing_window(wid.clone());
-                                }
+                                self.push_received_characters(&mut press.key, inputs);
                             }
+                        } else {
+                            self.push_key_event(&event.key, KeyState::Released, false, inputs);
+                        }
+                    }
+                    xlib::ConfigureNotify => {
+                        self.handle_configure(&event.configure, inputs);
+                    }
+                    xlib::Expose => {
+                        self.handle_expose(&event.expose, inputs);
+                    }
+                    // minimized windows are unmapped by the window manager
+                    xlib::MapNotify => {
+                        self.set_occluded(event.map.window, false, Some(inputs));
+                    }
+                    xlib::UnmapNotify => {
+                        self.set_occluded(event.unmap.window, true, Some(inputs));
+                    }
+                    xlib::VisibilityNotify => {
+                        self.set_occluded(
+                            event.visibility.window,
+                            event.visibility.state == xlib::VisibilityFullyObscured,
+                            Some(inputs),
+                        );
+                    }
+                    xlib::PropertyNotify => {
+                        self.handle_property_notify(&event.property, inputs);
+                    }
+                    xlib::FocusIn => {
+                        if self.window_id(event.focus_change.window).is_some() {
+                            self.input_focus.set(Some(event.focus_change.window));
+                        }
+                        self.set_input_context_focus(event.focus_change.window, true);
+                        self.push_focus_event(&event.focus_change, true, inputs);
+
+                        if self
+                            .confined_windows
+                            .lock()
+                            .unwrap()
+                            .contains(&event.focus_change.window)
+                        {
+                            self.grab_pointer(event.focus_change.window);
+                        }
+
+                        // otherwise the first move after switching back reports the whole way from where the pointer was
+                        if self.is_cursor_locked(event.focus_change.window) {
+                            self.center_pointer(event.focus_change.window);
+                        }
+
+                        if self
+                            .shortcut_inhibiting_windows
+                            .lock()
+                            .unwrap()
+                            .contains(&event.focus_change.window)
+                        {
+                            self.grab_keyboard(event.focus_change.window);
                         }
                     }
+                    xlib::FocusOut => {
+                        if event.focus_change.detail != xlib::NotifyInferior
+                            && self.input_focus.get() == Some(event.focus_change.window)
+                        {
+                            self.input_focus.set(None);
+                        }
+                        self.set_input_context_focus(event.focus_change.window, false);
+                        self.push_focus_event(&event.focus_change, false, inputs);
+
+                        // grabs (e.g. the window manager's alt-tab switcher or a menu) only borrow the focus, and focus moving to a child stays within the window
+                        let focus_change = &event.focus_change;
+                        if focus_change.mode == xlib::NotifyNormal
+                            && focus_change.detail != xlib::NotifyInferior
+                            && self
+                                .auto_hide_windows
+                                .lock()
+                                .unwrap()
+                                .contains(&focus_change.window)
+                        {
+                            (self.xlib.XUnmapWindow)(self.display, focus_change.window);
+                            self.hidden_windows
+                                .lock()
+                                .unwrap()
+                                .insert(focus_change.window);
+                        }
+
+                        // our own keyboard grab turns focus changes into NotifyWhileGrabbed ones
+                        if (focus_change.mode == xlib::NotifyNormal
+                            || focus_change.mode == xlib::NotifyWhileGrabbed)
+                            && focus_change.detail != xlib::NotifyInferior
+                            && self
+                                .shortcut_inhibiting_windows
+                                .lock()
+                                .unwrap()
+                                .contains(&focus_change.window)
+                        {
+                            (self.xlib.XUngrabKeyboard)(self.display, xlib::CurrentTime);
+                        }
+                    }
+                    xlib::SelectionRequest => {
+                        self.handle_selection_request(&event.selection_request);
+                    }
+                    xlib::SelectionClear => {
+                        self.handle_selection_clear(&event.selection_clear);
+                    }
+                    xlib::GenericEvent => {
+                        self.handle_generic_event(&mut event.generic_event_cookie, inputs);
+                    }
+                    event_type
+                        if self
+                            .xkb
+                            .as_ref()
+                            .is_some_and(|xkb| xkb.event_base == event_type) =>
+                    {
+                        self.handle_xkb_event(&event, inputs);
+                    }
+                    event_type
+                        if let Some(base) = self.xrandr_event_base
+                            && (event_type == base + xrandr::RRScreenChangeNotify
+                                || event_type == base + xrandr::RRNotify) =>
+                    {
+                        if event_type == base + xrandr::RRScreenChangeNotify
+                            && let Some(xrandr) = &self.xrandr
+                        {
+                            (xrandr.XRRUpdateConfiguration)(&mut event);
+                        }
+
+                        monitors_changed = true;
+                    }
                     _ => (),
                 }
             }
         }
+
+        self.end_interactions(inputs);
+
+        if monitors_changed {
+            inputs.event_queue.push(Event::MonitorsChanged);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn supported_window_attributes() {
+        // the platform-specific attributes must only be reported by their platform
+        assert!(!SUPPORTED_WINDOW_ATTRIBUTES.windows_show_drop_shadow);
+        assert!(SUPPORTED_WINDOW_ATTRIBUTES.x11_window_type);
     }
 }
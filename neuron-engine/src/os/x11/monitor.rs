@@ -0,0 +1,547 @@
+use crate::os::monitor::{MonitorHandle, VideoMode, sort_video_modes};
+use crate::os::window::WindowPosition;
+use crate::os::x11::X11Platform;
+use anyhow::bail;
+use std::ffi::{CStr, c_int};
+use x11_dl::xlib;
+use x11_dl::xrandr;
+
+impl X11Platform {
+    /// The monitor containing a point, falling back to the primary monitor.
+    pub(super) fn monitor_at(&self, x: i32, y: i32) -> Option<MonitorHandle> {
+        let monitors = self.query_monitors();
+        monitors
+            .iter()
+            .find(|(_, monitor)| {
+                (monitor.position.x..monitor.position.x + monitor.size.0 as i32).contains(&x)
+                    && (monitor.position.y..monitor.position.y + monitor.size.1 as i32).contains(&y)
+            })
+            .or(monitors.iter().find(|(primary, _)| *primary))
+            .or(monitors.first())
+            .map(|(_, monitor)| monitor.clone())
+    }
+
+    /// Refresh rate of the CRTC showing a point, `None` without RandR or if no CRTC shows it.
+    pub(super) fn refresh_rate_millihertz_at(&self, x: i32, y: i32) -> Option<u32> {
+        let xrandr = self.xrandr.as_ref()?;
+
+        unsafe {
+            let resources = (xrandr.XRRGetScreenResourcesCurrent)(self.display, self.root_window);
+            if resources.is_null() {
+                return None;
+            }
+
+            let mut refresh_rate = None;
+            for &crtc in raw_slice((*resources).crtcs, (*resources).ncrtc) {
+                let info = (xrandr.XRRGetCrtcInfo)(self.display, resources, crtc);
+                if info.is_null() {
+                    continue;
+                }
+
+                let contains = (*info).mode != 0
+                    && ((*info).x..(*info).x + (*info).width as i32).contains(&x)
+                    && ((*info).y..(*info).y + (*info).height as i32).contains(&y);
+                let mode = (*info).mode;
+                (xrandr.XRRFreeCrtcInfo)(info);
+
+                if contains {
+                    refresh_rate = resource_modes(resources)
+                        .iter()
+                        .find(|info| info.id == mode)
+                        .map(refresh_rate_millihertz);
+                    break;
+                }
+            }
+
+            (xrandr.XRRFreeScreenResources)(resources);
+            refresh_rate.filter(|rate| *rate > 0)
+        }
+    }
+
+    /// Query all monitors (the flag marks the primary monitor).
+    ///
+    /// Uses RandR 1.5 monitors when available and otherwise treats the whole default screen as a single monitor.
+    pub(super) fn query_monitors(&self) -> Vec<(bool, MonitorHandle)> {
+        let bit_depth = unsafe { (self.xlib.XDefaultDepth)(self.display(), self.default_screen()) }
+            .max(0) as u32;
+        let work_area = self.desktop_work_area();
+
+        let mut rects = Vec::new();
+
+        if let Some(xrandr) = &self.xrandr {
+            unsafe {
+                let mut count: c_int = 0;
+                let monitors = (xrandr.XRRGetMonitors)(
+                    self.display(),
+                    self.root_window(),
+                    xlib::True,
+                    &mut count,
+                );
+
+                if !monitors.is_null() {
+                    let resources =
+                        (xrandr.XRRGetScreenResourcesCurrent)(self.display(), self.root_window());
+
+                    for info in std::slice::from_raw_parts(monitors, count.max(0) as usize) {
+                        let video_modes = if resources.is_null() || info.outputs.is_null() {
+                            Vec::new()
+                        } else {
+                            let outputs = std::slice::from_raw_parts(
+                                info.outputs,
+                                info.noutput.max(0) as usize,
+                            );
+                            output_video_modes(
+                                xrandr,
+                                self.display(),
+                                resources,
+                                outputs,
+                                bit_depth,
+                            )
+                        };
+
+                        rects.push((
+                            self.atom_name(info.name),
+                            info.primary != 0,
+                            info.x,
+                            info.y,
+                            info.width.max(0) as u32,
+                            info.height.max(0) as u32,
+                            info.mwidth,
+                            video_modes,
+                        ));
+                    }
+
+                    if !resources.is_null() {
+                        (xrandr.XRRFreeScreenResources)(resources);
+                    }
+                    (xrandr.XRRFreeMonitors)(monitors);
+                }
+            }
+        }
+
+        if rects.is_empty() {
+            let (width, height) = unsafe {
+                (
+                    (self.xlib.XDisplayWidth)(self.display(), self.default_screen()),
+                    (self.xlib.XDisplayHeight)(self.display(), self.default_screen()),
+                )
+            };
+            let name = format!("screen {}", self.default_screen());
+            let width_mm =
+                unsafe { (self.xlib.XDisplayWidthMM)(self.display(), self.default_screen()) };
+            rects.push((
+                name,
+                true,
+                0,
+                0,
+                width.max(0) as u32,
+                height.max(0) as u32,
+                width_mm,
+                Vec::new(),
+            ));
+        }
+
+        // X11 has a single scale factor for all monitors, see `scale_factor`
+        let scale_factor = self.resource_scale_factor().unwrap_or_else(|| {
+            rects
+                .iter()
+                .find(|rect| rect.1)
+                .or(rects.first())
+                .map_or(1.0, |rect| physical_scale_factor(rect.4, rect.6))
+        });
+
+        rects
+            .into_iter()
+            .map(|(name, primary, x, y, width, height, _, video_modes)| {
+                let (work_area_position, work_area_size) = work_area
+                    .and_then(|area| intersect((x, y, width, height), area))
+                    .map_or(((x, y), (width, height)), |(ax, ay, aw, ah)| {
+                        ((ax, ay), (aw, ah))
+                    });
+
+                (
+                    primary,
+                    MonitorHandle {
+                        name,
+                        position: WindowPosition { x, y },
+                        size: (width, height),
+                        work_area_position: WindowPosition {
+                            x: work_area_position.0,
+                            y: work_area_position.1,
+                        },
+                        work_area_size,
+                        scale_factor,
+                        bit_depth,
+                        hdr_enabled: false,
+                        video_modes,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Work area of the current desktop from the EWMH `_NET_WORKAREA` root window property.
+    fn desktop_work_area(&self) -> Option<(i32, i32, u32, u32)> {
+        let workarea = self.intern_atom(c"_NET_WORKAREA");
+        let values = self.get_property_longs(self.root_window(), workarea, xlib::XA_CARDINAL)?;
+
+        let current_desktop = self.intern_atom(c"_NET_CURRENT_DESKTOP");
+        let desktop = self
+            .get_property_longs(self.root_window(), current_desktop, xlib::XA_CARDINAL)
+            .and_then(|v| v.first().copied())
+            .unwrap_or(0)
+            .max(0) as usize;
+
+        let area = values
+            .chunks_exact(4)
+            .nth(desktop)
+            .or(values.chunks_exact(4).next())?;
+
+        Some((
+            area[0] as i32,
+            area[1] as i32,
+            area[2].max(0) as u32,
+            area[3].max(0) as u32,
+        ))
+    }
+
+    /// Scale factor of every monitor and window, used to convert logical sizes.
+    ///
+    /// From `Xft.dpi` when the desktop sets it, otherwise from the physical size of the primary monitor (`1.0` without monitors).
+    pub(super) fn scale_factor(&self) -> f64 {
+        match self.resource_scale_factor() {
+            Some(scale_factor) => scale_factor,
+            // every monitor gets the same one
+            None => self
+                .query_monitors()
+                .first()
+                .map_or(1.0, |(_, monitor)| monitor.scale_factor),
+        }
+    }
+
+    /// Scale factor derived from the `Xft.dpi` X resource (which is what most desktop environments set when scaling).
+    fn resource_scale_factor(&self) -> Option<f64> {
+        let resources = unsafe { (self.xlib.XResourceManagerString)(self.display()) };
+        if resources.is_null() {
+            return None;
+        }
+
+        let resources = unsafe { CStr::from_ptr(resources as *const _) }.to_string_lossy();
+
+        resources
+            .lines()
+            .filter_map(|line| line.strip_prefix("Xft.dpi:"))
+            .filter_map(|value| value.trim().parse::<f64>().ok())
+            .find(|dpi| *dpi > 0.0)
+            .map(|dpi| dpi / 96.0)
+    }
+}
+
+/// Modes supported by all outputs of a monitor (a monitor only has several outputs when they are cloned, and then only the common modes can be used).
+///
+/// # Safety
+/// `resources` has to be a valid screen resources pointer.
+unsafe fn output_video_modes(
+    xrandr: &xrandr::Xrandr,
+    display: *mut xlib::Display,
+    resources: *mut xrandr::XRRScreenResources,
+    outputs: &[xrandr::RROutput],
+    bit_depth: u32,
+) -> Vec<VideoMode> {
+    let mut common: Option<Vec<xrandr::RRMode>> = None;
+    let mut monitor_name = None;
+
+    unsafe {
+        for &output in outputs {
+            let info = (xrandr.XRRGetOutputInfo)(display, resources, output);
+            if info.is_null() {
+                continue;
+            }
+
+            let modes = if (*info).modes.is_null() {
+                &[][..]
+            } else {
+                std::slice::from_raw_parts((*info).modes, (*info).nmode.max(0) as usize)
+            };
+            monitor_name.get_or_insert_with(|| output_name(&*info));
+            match &mut common {
+                Some(common) => common.retain(|mode| modes.contains(mode)),
+                None => common = Some(modes.to_vec()),
+            }
+
+            (xrandr.XRRFreeOutputInfo)(info);
+        }
+
+        let (Some(common), Some(monitor_name)) = (common, monitor_name) else {
+            return Vec::new();
+        };
+
+        let mode_infos = if (*resources).modes.is_null() {
+            &[][..]
+        } else {
+            std::slice::from_raw_parts((*resources).modes, (*resources).nmode.max(0) as usize)
+        };
+
+        let mut modes: Vec<VideoMode> = mode_infos
+            .iter()
+            .filter(|info| common.contains(&info.id))
+            .map(|info| VideoMode {
+                size: (info.width, info.height),
+                bit_depth,
+                refresh_rate_millihertz: refresh_rate_millihertz(info),
+                monitor_name: monitor_name.clone(),
+            })
+            .collect();
+
+        sort_video_modes(&mut modes);
+        modes
+    }
+}
+
+/// CRTC configuration from before a video mode change.
+pub(super) struct SavedCrtc {
+    crtc: xrandr::RRCrtc,
+    x: c_int,
+    y: c_int,
+    mode: xrandr::RRMode,
+    rotation: xrandr::Rotation,
+    outputs: Vec<xrandr::RROutput>,
+}
+
+impl X11Platform {
+    /// Switch the monitor a mode belongs to over to that mode, remembering the previous configuration for [`X11Platform::restore_video_mode`] (and `Drop`).
+    ///
+    /// Only modes that fit into the current screen size can be set, which covers the usual case of lowering the resolution for exclusive fullscreen.
+    pub(super) fn set_video_mode(&self, mode: &VideoMode) -> anyhow::Result<()> {
+        let Some(xrandr) = &self.xrandr else {
+            bail!("Can't change video modes without RandR");
+        };
+
+        unsafe {
+            let resources = (xrandr.XRRGetScreenResourcesCurrent)(self.display, self.root_window);
+            if resources.is_null() {
+                bail!("Failed to query RandR screen resources");
+            }
+
+            let result = self.set_crtc_mode(xrandr, resources, mode);
+            (xrandr.XRRFreeScreenResources)(resources);
+            result
+        }
+    }
+
+    unsafe fn set_crtc_mode(
+        &self,
+        xrandr: &xrandr::Xrandr,
+        resources: *mut xrandr::XRRScreenResources,
+        mode: &VideoMode,
+    ) -> anyhow::Result<()> {
+        unsafe {
+            let Some((crtc, output_modes)) =
+                find_output(xrandr, self.display, resources, &mode.monitor_name)
+            else {
+                bail!("Output {} isn't connected to a CRTC", mode.monitor_name);
+            };
+
+            let Some(mode_id) = resource_modes(resources)
+                .iter()
+                .find(|info| {
+                    output_modes.contains(&info.id)
+                        && (info.width, info.height) == mode.size
+                        && refresh_rate_millihertz(info) == mode.refresh_rate_millihertz
+                })
+                .map(|info| info.id)
+            else {
+                bail!("Output {} doesn't support {:?}", mode.monitor_name, mode);
+            };
+
+            let crtc_info = (xrandr.XRRGetCrtcInfo)(self.display, resources, crtc);
+            if crtc_info.is_null() {
+                bail!("Failed to query CRTC {}", crtc);
+            }
+
+            // only the first change is saved, switching again should still restore the desktop mode
+            self.changed_video_modes
+                .lock()
+                .unwrap()
+                .entry(mode.monitor_name.clone())
+                .or_insert_with(|| SavedCrtc {
+                    crtc,
+                    x: (*crtc_info).x,
+                    y: (*crtc_info).y,
+                    mode: (*crtc_info).mode,
+                    rotation: (*crtc_info).rotation,
+                    outputs: raw_slice((*crtc_info).outputs, (*crtc_info).noutput).to_vec(),
+                });
+
+            let status = (xrandr.XRRSetCrtcConfig)(
+                self.display,
+                resources,
+                crtc,
+                xlib::CurrentTime,
+                (*crtc_info).x,
+                (*crtc_info).y,
+                mode_id,
+                (*crtc_info).rotation,
+                (*crtc_info).outputs,
+                (*crtc_info).noutput,
+            );
+            (xrandr.XRRFreeCrtcInfo)(crtc_info);
+
+            if status != xlib::Success as c_int {
+                bail!(
+                    "Failed to switch output {} to {:?} (status {})",
+                    mode.monitor_name,
+                    mode,
+                    status
+                );
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Put a monitor back into the mode it had before [`X11Platform::set_video_mode`].
+    pub(super) fn restore_video_mode(&self, monitor_name: &str) {
+        let Some(saved) = self
+            .changed_video_modes
+            .lock()
+            .unwrap()
+            .remove(monitor_name)
+        else {
+            return;
+        };
+        let Some(xrandr) = &self.xrandr else {
+            return;
+        };
+
+        unsafe {
+            let resources = (xrandr.XRRGetScreenResourcesCurrent)(self.display, self.root_window);
+            if resources.is_null() {
+                return;
+            }
+
+            let mut outputs = saved.outputs;
+            (xrandr.XRRSetCrtcConfig)(
+                self.display,
+                resources,
+                saved.crtc,
+                xlib::CurrentTime,
+                saved.x,
+                saved.y,
+                saved.mode,
+                saved.rotation,
+                outputs.as_mut_ptr(),
+                outputs.len() as c_int,
+            );
+            (xrandr.XRRFreeScreenResources)(resources);
+        }
+    }
+
+    /// Restore every monitor whose mode was changed.
+    pub(super) fn restore_all_video_modes(&self) {
+        let monitor_names: Vec<String> = self
+            .changed_video_modes
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        for monitor_name in monitor_names {
+            self.restore_video_mode(&monitor_name);
+        }
+    }
+}
+
+/// The CRTC and supported modes of the output with the given name.
+unsafe fn find_output(
+    xrandr: &xrandr::Xrandr,
+    display: *mut xlib::Display,
+    resources: *mut xrandr::XRRScreenResources,
+    name: &str,
+) -> Option<(xrandr::RRCrtc, Vec<xrandr::RRMode>)> {
+    unsafe {
+        for &output in raw_slice((*resources).outputs, (*resources).noutput) {
+            let info = (xrandr.XRRGetOutputInfo)(display, resources, output);
+            if info.is_null() {
+                continue;
+            }
+
+            let found = (output_name(&*info) == name && (*info).crtc != 0).then(|| {
+                (
+                    (*info).crtc,
+                    raw_slice((*info).modes, (*info).nmode).to_vec(),
+                )
+            });
+            (xrandr.XRRFreeOutputInfo)(info);
+
+            if found.is_some() {
+                return found;
+            }
+        }
+
+        None
+    }
+}
+
+unsafe fn output_name(info: &xrandr::XRROutputInfo) -> String {
+    let name = unsafe { raw_slice(info.name as *const u8, info.nameLen) };
+    String::from_utf8_lossy(name).into_owned()
+}
+
+unsafe fn resource_modes<'a>(
+    resources: *mut xrandr::XRRScreenResources,
+) -> &'a [xrandr::XRRModeInfo] {
+    unsafe { raw_slice((*resources).modes, (*resources).nmode) }
+}
+
+/// Xlib arrays are a pointer and a count, with a null pointer for empty arrays.
+unsafe fn raw_slice<'a, T>(data: *const T, count: c_int) -> &'a [T] {
+    if data.is_null() || count <= 0 {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(data, count as usize) }
+    }
+}
+
+/// Refresh rate of a mode from its pixel clock and timings.
+fn refresh_rate_millihertz(info: &xrandr::XRRModeInfo) -> u32 {
+    let mut v_total = info.vTotal as u64;
+    if info.modeFlags & xrandr::RR_DoubleScan as xrandr::XRRModeFlags != 0 {
+        v_total *= 2;
+    }
+    if info.modeFlags & xrandr::RR_Interlace as xrandr::XRRModeFlags != 0 {
+        v_total /= 2;
+    }
+
+    let pixels = info.hTotal as u64 * v_total;
+    if pixels == 0 {
+        return 0;
+    }
+
+    (info.dotClock * 1000 / pixels).min(u32::MAX as u64) as u32
+}
+
+/// Scale factor from the primary monitor's physical width, for desktops that don't set `Xft.dpi`.
+///
+/// Rounded to quarter steps and never below `1.0`, since reported physical sizes are often slightly off (and sometimes made up, e.g. for projectors).
+fn physical_scale_factor(width: u32, width_mm: c_int) -> f64 {
+    if width_mm <= 0 {
+        return 1.0;
+    }
+
+    let dpi = width as f64 * 25.4 / width_mm as f64;
+    ((dpi / 96.0 * 4.0).round() / 4.0).max(1.0)
+}
+
+fn intersect(a: (i32, i32, u32, u32), b: (i32, i32, u32, u32)) -> Option<(i32, i32, u32, u32)> {
+    let left = a.0.max(b.0);
+    let top = a.1.max(b.1);
+    let right = (a.0 + a.2 as i32).min(b.0 + b.2 as i32);
+    let bottom = (a.1 + a.3 as i32).min(b.1 + b.3 as i32);
+
+    if right > left && bottom > top {
+        Some((left, top, (right - left) as u32, (bottom - top) as u32))
+    } else {
+        None
+    }
+}
@@ -1,25 +1,52 @@
-use std::ffi::{c_ulong, CStr, CString};
-use crate::os::window::{Resolution, Window, WindowAttributes, WindowId};
+use crate::os::placement::SavedPlacement;
+use crate::os::window::{
+    Anchor, CompositorBypass, CursorGrabMode, CursorIcon, DragData, DragEffect, FrameStats,
+    Fullscreen, InitialZOrder, InteractionState, Position, PresentMode, ResizePaintCallback,
+    Resolution, SnapRegion, UserAttention, Window, WindowAttributes, WindowIcon, WindowId,
+    WindowPosition, X11WindowType, clamp_size,
+};
 use crate::os::x11::X11Platform;
+use log::{debug, warn};
 use raw_window_handle::{
-    HandleError, HasWindowHandle, RawWindowHandle, WindowHandle, XlibWindowHandle,
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, RawDisplayHandle,
+    RawWindowHandle, WindowHandle, XlibDisplayHandle, XlibWindowHandle,
 };
-use std::mem::MaybeUninit;
+use std::cell::{Cell, RefCell};
+use std::ffi::{CString, c_int, c_long, c_uchar, c_uint, c_ulong, c_void};
+use std::ptr::NonNull;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use x11_dl::xlib;
 use x11_dl::xlib::{
     ButtonMotionMask, ButtonPressMask, ButtonReleaseMask, CWEventMask, ColormapChangeMask,
     EnterWindowMask, ExposureMask, FocusChangeMask, InputOutput, KeyPressMask, KeyReleaseMask,
     KeymapStateMask, LeaveWindowMask, OwnerGrabButtonMask, PMaxSize, PMinSize, PPosition, PSize,
     PointerMotionMask, PropertyChangeMask, StructureNotifyMask, SubstructureNotifyMask,
-    VisibilityChangeMask, XSetWindowAttributes, XSizeHints,
+    VisibilityChangeMask, XSetWindowAttributes, XSizeHints, XVisualInfo,
 };
 
 pub(super) struct X11Window {
     pub(super) window: xlib::Window,
     id: WindowId,
     visual_id: u64,
+    /// The X screen the window was created on, and its root window.
+    screen: c_int,
+    root: xlib::Window,
+    /// Colormap created for a transparent window's visual, 0 for windows using the default visual.
+    colormap: xlib::Colormap,
     platform: Arc<X11Platform>,
+    present_mode: Cell<Option<PresentMode>>,
+    persistence_key: Option<String>,
+    /// See [`WindowAttributes::resizable`].
+    resizable: Cell<bool>,
+    /// See [`WindowAttributes::min_size`], in physical pixels.
+    min_size: Cell<Option<(u32, u32)>>,
+    /// See [`WindowAttributes::max_size`], in physical pixels.
+    max_size: Cell<Option<(u32, u32)>>,
+    /// See [`Window::set_fullscreen`].
+    fullscreen: RefCell<Option<Fullscreen>>,
+    /// Client area position and size from before the window went fullscreen.
+    windowed_geometry: Cell<Option<(WindowPosition, (u32, u32))>>,
 }
 
 impl HasWindowHandle for X11Window {
@@ -33,7 +60,550 @@ impl HasWindowHandle for X11Window {
     }
 }
 
-impl Window for X11Window {}
+impl HasDisplayHandle for X11Window {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        unsafe {
+            Ok(DisplayHandle::borrow_raw(RawDisplayHandle::Xlib(
+                XlibDisplayHandle::new(
+                    NonNull::new(self.platform.display as *mut c_void),
+                    self.screen,
+                ),
+            )))
+        }
+    }
+}
+
+impl Window for X11Window {
+    fn set_skip_taskbar(&self, skip: bool) {
+        self.set_net_wm_state(
+            self.platform.intern_atom(c"_NET_WM_STATE_SKIP_TASKBAR"),
+            skip,
+        );
+    }
+
+    fn set_always_on_top(&self, always_on_top: bool) {
+        self.platform.check_wm_supports(c"_NET_WM_STATE_ABOVE");
+        self.set_net_wm_state(
+            self.platform.intern_atom(c"_NET_WM_STATE_ABOVE"),
+            always_on_top,
+        );
+    }
+
+    fn focus_window(&self) {
+        if self.is_iconic() {
+            // a minimized window is unmapped, so it can't take the focus itself. Activating it through the window manager also restores it
+            // (source indication: normal application, no timestamp, no window of ours is currently active)
+            self.send_to_window_manager(
+                self.platform.intern_atom(c"_NET_ACTIVE_WINDOW"),
+                [1, 0, 0, 0, 0],
+            );
+            unsafe {
+                (self.platform.xlib.XFlush)(self.platform.display);
+            }
+            return;
+        }
+
+        if !self.is_mapped() {
+            // the focus can only go to a viewable window
+            debug!("Window {:?} isn't mapped, not focusing it", self.id);
+            return;
+        }
+
+        unsafe {
+            // both are redirected to the window manager, which decides whether to follow them
+            (self.platform.xlib.XRaiseWindow)(self.platform.display, self.window);
+            (self.platform.xlib.XSetInputFocus)(
+                self.platform.display,
+                self.window,
+                xlib::RevertToParent,
+                xlib::CurrentTime,
+            );
+            (self.platform.xlib.XFlush)(self.platform.display);
+        }
+    }
+
+    fn request_user_attention(&self, kind: Option<UserAttention>) {
+        self.set_net_wm_state(
+            self.platform
+                .intern_atom(c"_NET_WM_STATE_DEMANDS_ATTENTION"),
+            kind.is_some(),
+        );
+
+        // the ICCCM urgency hint is the stronger request, some window managers only react to that one
+        unsafe {
+            let mut hints = (self.platform.xlib.XGetWMHints)(self.platform.display, self.window);
+            if hints.is_null() {
+                hints = (self.platform.xlib.XAllocWMHints)();
+            }
+            if hints.is_null() {
+                return;
+            }
+
+            if kind == Some(UserAttention::Critical) {
+                (*hints).flags |= xlib::XUrgencyHint;
+            } else {
+                (*hints).flags &= !xlib::XUrgencyHint;
+            }
+
+            (self.platform.xlib.XSetWMHints)(self.platform.display, self.window, hints);
+            (self.platform.xlib.XFree)(hints as *mut _);
+            (self.platform.xlib.XFlush)(self.platform.display);
+        }
+    }
+
+    fn set_resizable(&self, resizable: bool) {
+        if self.resizable.replace(resizable) == resizable {
+            return;
+        }
+
+        self.update_size_hints();
+    }
+
+    fn set_min_size(&self, size: Option<Resolution<u32>>) {
+        self.min_size
+            .set(size.map(|size| size.physical_size(self.platform.scale_factor())));
+        self.size_limits_changed();
+    }
+
+    fn set_max_size(&self, size: Option<Resolution<u32>>) {
+        self.max_size
+            .set(size.map(|size| size.physical_size(self.platform.scale_factor())));
+        self.size_limits_changed();
+    }
+
+    fn inner_size(&self) -> Resolution<u32> {
+        let (width, height) = self.client_size();
+        Resolution::Physical { width, height }
+    }
+
+    fn position(&self) -> WindowPosition {
+        let (x, y) = self.client_position();
+        WindowPosition { x, y }
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.platform.scale_factor()
+    }
+
+    fn set_inner_size(&self, size: Resolution<u32>) {
+        if !self.resizable.get() {
+            warn!(
+                "Window {:?} isn't resizable, not changing its size",
+                self.id
+            );
+            return;
+        }
+
+        self.set_client_size(size);
+    }
+
+    fn set_position(&self, position: WindowPosition) {
+        // the window manager places the frame (with the default north-west gravity), so move it by the decoration size to land the client area on `position`
+        let [left, _, top, _] = self.frame_extents().unwrap_or_default();
+
+        unsafe {
+            (self.platform.xlib.XMoveWindow)(
+                self.platform.display,
+                self.window,
+                position.x - left as c_int,
+                position.y - top as c_int,
+            );
+            (self.platform.xlib.XFlush)(self.platform.display);
+        }
+    }
+
+    fn set_preferred_present_mode(&self, mode: Option<PresentMode>) {
+        self.present_mode.set(mode);
+    }
+
+    fn preferred_present_mode(&self) -> Option<PresentMode> {
+        self.present_mode.get()
+    }
+
+    fn set_ime_position(&self, x: i32, y: i32) {
+        self.platform.set_ime_position(self.window, x, y);
+    }
+
+    fn set_compositor_bypass(&self, bypass: CompositorBypass) {
+        set_bypass_compositor(&self.platform, self.window, bypass);
+        unsafe {
+            (self.platform.xlib.XFlush)(self.platform.display);
+        }
+    }
+
+    fn set_key_repeat(&self, enabled: bool) {
+        self.platform.set_key_repeat(self.window, enabled);
+    }
+
+    fn frame_stats(&self) -> FrameStats {
+        let (x, y) = self.client_position();
+        let (width, height) = self.client_size();
+
+        FrameStats {
+            refresh_period: self
+                .platform
+                .refresh_rate_millihertz_at(x + width as i32 / 2, y + height as i32 / 2)
+                .map(|rate| Duration::from_secs_f64(1000.0 / rate as f64)),
+            ..FrameStats::default()
+        }
+    }
+
+    fn start_drag(&self, data: DragData, effect: DragEffect) {
+        self.platform
+            .start_drag(self.window, self.id, &data, effect);
+    }
+
+    fn set_cursor_confined(&self, confined: bool) {
+        self.platform.set_cursor_confined(self.window, confined);
+    }
+
+    fn set_cursor_visible(&self, visible: bool) {
+        self.platform.set_cursor_visible(self.window, visible);
+    }
+
+    fn set_cursor_icon(&self, icon: CursorIcon) {
+        self.platform.set_cursor_icon(self.window, icon);
+    }
+
+    fn set_cursor_grab(&self, mode: CursorGrabMode) {
+        self.platform
+            .set_cursor_confined(self.window, mode != CursorGrabMode::None);
+        self.platform
+            .set_cursor_locked(self.window, mode == CursorGrabMode::Locked);
+    }
+
+    fn set_resize_paint_callback(&self, callback: Option<ResizePaintCallback>) {
+        self.platform
+            .set_resize_paint_callback(self.window, callback);
+    }
+
+    fn is_occluded(&self) -> bool {
+        self.platform.is_occluded(self.window)
+    }
+
+    fn has_focus(&self) -> bool {
+        self.platform.focused_window() == Some(self.window)
+    }
+
+    fn set_keyboard_shortcuts_inhibited(&self, inhibited: bool) {
+        self.platform
+            .set_keyboard_shortcuts_inhibited(self.window, inhibited);
+    }
+
+    fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+        let previous = self.fullscreen.replace(fullscreen.clone());
+        let state = self.platform.intern_atom(c"_NET_WM_STATE_FULLSCREEN");
+
+        // a new mode for the same monitor replaces the old one, restoring first would only flicker through the desktop mode
+        if let Some(Fullscreen::Exclusive(previous_mode)) = &previous
+            && !matches!(&fullscreen, Some(Fullscreen::Exclusive(mode)) if mode.monitor_name == previous_mode.monitor_name)
+        {
+            self.platform
+                .restore_video_mode(&previous_mode.monitor_name);
+        }
+
+        let Some(fullscreen) = fullscreen else {
+            if previous.is_some() {
+                self.set_net_wm_state(state, false);
+            }
+
+            // the window manager restores the geometry on its own, but not a move to another monitor made on the way in
+            if let Some((position, (width, height))) = self.windowed_geometry.take() {
+                self.set_position(position);
+                self.resize_and_wait(width, height);
+            }
+            return;
+        };
+
+        let monitor = match &fullscreen {
+            Fullscreen::Borderless(monitor) => monitor.clone(),
+            Fullscreen::Exclusive(mode) => {
+                let switched = self.platform.set_video_mode(mode);
+
+                // the monitor's geometry changed with the mode, so it is looked up afterwards
+                let monitor = self
+                    .platform
+                    .query_monitors()
+                    .into_iter()
+                    .map(|(_, monitor)| monitor)
+                    .find(|monitor| monitor.name == mode.monitor_name);
+                if let Err(e) = switched {
+                    warn!(
+                        "Failed to switch to {:?}, using borderless fullscreen instead: {}",
+                        mode, e
+                    );
+                    *self.fullscreen.borrow_mut() = Some(Fullscreen::Borderless(monitor.clone()));
+                }
+                monitor
+            }
+        };
+
+        if previous.is_none() {
+            self.windowed_geometry
+                .set(Some((self.position(), self.client_size())));
+        } else if monitor.is_some() {
+            // window managers don't move fullscreen windows, so it has to be a normal window again for the move
+            self.set_net_wm_state(state, false);
+        }
+
+        // window managers make the window cover the monitor it is on
+        if let Some(monitor) = monitor {
+            unsafe {
+                (self.platform.xlib.XMoveWindow)(
+                    self.platform.display,
+                    self.window,
+                    monitor.position.x,
+                    monitor.position.y,
+                );
+            }
+        }
+
+        self.set_net_wm_state(state, true);
+    }
+
+    fn fullscreen(&self) -> Option<Fullscreen> {
+        self.fullscreen.borrow().clone()
+    }
+
+    fn set_icon(&self, icon: Option<WindowIcon>) {
+        let net_wm_icon = self.platform.intern_atom(c"_NET_WM_ICON");
+
+        unsafe {
+            match icon {
+                Some(icon) => {
+                    // width, height, then one ARGB pixel per cardinal (which Xlib passes as longs)
+                    let data: Vec<c_long> = [icon.width as c_long, icon.height as c_long]
+                        .into_iter()
+                        .chain(icon.rgba.chunks_exact(4).map(|pixel| {
+                            let [r, g, b, a] =
+                                [pixel[0], pixel[1], pixel[2], pixel[3]].map(c_long::from);
+                            (a << 24) | (r << 16) | (g << 8) | b
+                        }))
+                        .collect();
+
+                    (self.platform.xlib.XChangeProperty)(
+                        self.platform.display,
+                        self.window,
+                        net_wm_icon,
+                        xlib::XA_CARDINAL,
+                        32,
+                        xlib::PropModeReplace,
+                        data.as_ptr() as *const c_uchar,
+                        data.len() as c_int,
+                    );
+                }
+                None => {
+                    (self.platform.xlib.XDeleteProperty)(
+                        self.platform.display,
+                        self.window,
+                        net_wm_icon,
+                    );
+                }
+            }
+
+            (self.platform.xlib.XFlush)(self.platform.display);
+        }
+    }
+
+    fn set_title(&self, title: &str) -> anyhow::Result<()> {
+        set_title(&self.platform, self.window, title)?;
+
+        unsafe {
+            (self.platform.xlib.XFlush)(self.platform.display);
+        }
+
+        Ok(())
+    }
+
+    fn show(&self) {
+        self.platform
+            .hidden_windows
+            .lock()
+            .unwrap()
+            .remove(&self.window);
+
+        unsafe {
+            (self.platform.xlib.XMapWindow)(self.platform.display, self.window);
+            (self.platform.xlib.XFlush)(self.platform.display);
+        }
+    }
+
+    fn hide(&self) {
+        self.platform
+            .hidden_windows
+            .lock()
+            .unwrap()
+            .insert(self.window);
+
+        unsafe {
+            // unlike a plain unmap this also tells the window manager (a minimized window is already unmapped)
+            (self.platform.xlib.XWithdrawWindow)(self.platform.display, self.window, self.screen);
+            (self.platform.xlib.XFlush)(self.platform.display);
+        }
+    }
+
+    fn is_visible(&self) -> bool {
+        !self
+            .platform
+            .hidden_windows
+            .lock()
+            .unwrap()
+            .contains(&self.window)
+    }
+
+    fn request_redraw(&self) {
+        self.platform.request_redraw(self.window);
+    }
+
+    fn minimize(&self) {
+        unsafe {
+            // sends the ICCCM WM_CHANGE_STATE request, `_NET_WM_STATE_HIDDEN` can only be set by the window manager
+            (self.platform.xlib.XIconifyWindow)(self.platform.display, self.window, self.screen);
+            (self.platform.xlib.XFlush)(self.platform.display);
+        }
+    }
+
+    fn maximize(&self) {
+        self.set_net_wm_state(
+            self.platform.intern_atom(c"_NET_WM_STATE_MAXIMIZED_VERT"),
+            true,
+        );
+        self.set_net_wm_state(
+            self.platform.intern_atom(c"_NET_WM_STATE_MAXIMIZED_HORZ"),
+            true,
+        );
+    }
+
+    fn restore(&self) {
+        // mapping an iconic window deiconifies it, back to whatever state it had
+        if self.is_iconic() {
+            self.show();
+            return;
+        }
+
+        self.set_net_wm_state(
+            self.platform.intern_atom(c"_NET_WM_STATE_MAXIMIZED_VERT"),
+            false,
+        );
+        self.set_net_wm_state(
+            self.platform.intern_atom(c"_NET_WM_STATE_MAXIMIZED_HORZ"),
+            false,
+        );
+    }
+
+    fn interaction_state(&self) -> InteractionState {
+        self.platform.interaction_state(self.window)
+    }
+
+    fn set_restore_placement(&self, position: Position, size: Resolution<u32>) {
+        let scale_factor = self.platform.scale_factor();
+        let (x, y) = position.physical_position(scale_factor);
+        let (width, height) = size.physical_size(scale_factor);
+        let geometry = (x, y, width.max(1), height.max(1));
+
+        // there is no EWMH way to change a window manager's saved geometry, so it's tracked by the platform and applied when the maximized state goes away
+        if self.is_mapped() && self.platform.is_maximized(self.window) {
+            self.platform.set_restore_placement(self.window, geometry);
+            return;
+        }
+
+        unsafe {
+            (self.platform.xlib.XMoveResizeWindow)(
+                self.platform.display,
+                self.window,
+                geometry.0,
+                geometry.1,
+                geometry.2,
+                geometry.3,
+            );
+            (self.platform.xlib.XFlush)(self.platform.display);
+        }
+    }
+
+    fn snap(&self, region: SnapRegion) {
+        let maximized_vert = self.platform.intern_atom(c"_NET_WM_STATE_MAXIMIZED_VERT");
+        let maximized_horz = self.platform.intern_atom(c"_NET_WM_STATE_MAXIMIZED_HORZ");
+        let maximize = region == SnapRegion::Maximize;
+
+        // window managers ignore geometry requests for maximized windows, and the saved restore geometry would undo the snap
+        self.platform
+            .restore_placements
+            .lock()
+            .unwrap()
+            .remove(&self.window);
+        self.set_net_wm_state(maximized_vert, maximize);
+        self.set_net_wm_state(maximized_horz, maximize);
+        if maximize {
+            return;
+        }
+
+        let (client_x, client_y) = self.client_position();
+        let (width, height) = self.client_size();
+        let Some(monitor) = self
+            .platform
+            .monitor_at(client_x + width as i32 / 2, client_y + height as i32 / 2)
+        else {
+            return;
+        };
+
+        let (x, y, width, height) = region.rect(monitor.work_area_position, monitor.work_area_size);
+        let [left, right, top, bottom] = self.frame_extents().unwrap_or_default();
+        let client_width = (width as i64 - left - right).max(1) as u32;
+        let client_height = (height as i64 - top - bottom).max(1) as u32;
+
+        unsafe {
+            (self.platform.xlib.XMoveResizeWindow)(
+                self.platform.display,
+                self.window,
+                x,
+                y,
+                client_width,
+                client_height,
+            );
+            (self.platform.xlib.XFlush)(self.platform.display);
+        }
+    }
+
+    fn set_size_anchored(&self, size: Resolution<u32>, anchor: Anchor) {
+        let (width, height) = size.physical_size(self.platform.scale_factor());
+        let (width, height) = (width.max(1), height.max(1));
+        let (dx, dy) = anchor.offset(self.client_size(), (width, height));
+
+        // XMoveResizeWindow places the frame (with the default north-west gravity), and the frame extents don't change with the size
+        let (client_x, client_y) = self.client_position();
+        let [left, _, top, _] = self.frame_extents().unwrap_or_default();
+        let (x, y) = (client_x - left as i32 + dx, client_y - top as i32 + dy);
+
+        unsafe {
+            (self.platform.xlib.XMoveResizeWindow)(
+                self.platform.display,
+                self.window,
+                x,
+                y,
+                width,
+                height,
+            );
+            (self.platform.xlib.XFlush)(self.platform.display);
+        }
+    }
+
+    fn set_client_size(&self, size: Resolution<u32>) {
+        let (width, height) = size.physical_size(self.platform.scale_factor());
+        let (width, height) = (width.max(1), height.max(1));
+
+        // on X11 the window *is* the client area (decorations live in the window manager's frame), so there is nothing to correct: sending the same size again wouldn't change the window manager's mind
+        let actual = self.resize_and_wait(width, height);
+        if actual != (width, height) {
+            debug!(
+                "Window {:?} has client size {:?} instead of {:?} (frame extents: {:?})",
+                self.id,
+                actual,
+                (width, height),
+                self.frame_extents()
+            );
+        }
+    }
+}
 
 impl X11Window {
     pub(super) fn new(
@@ -42,8 +612,7 @@ impl X11Window {
         id: WindowId,
     ) -> anyhow::Result<Self> {
         unsafe {
-            #[allow(invalid_value)]
-            let mut swa = MaybeUninit::<XSetWindowAttributes>::uninit().assume_init();
+            let mut swa: XSetWindowAttributes = std::mem::zeroed();
             swa.event_mask = KeyPressMask
                 | KeyReleaseMask
                 | ButtonPressMask
@@ -62,9 +631,12 @@ impl X11Window {
                 | ColormapChangeMask
                 | OwnerGrabButtonMask;
 
-            let cw_mask = CWEventMask;
+            let mut cw_mask = CWEventMask;
 
-            let (x, y) = window_attributes.position.map_or((0, 0), |p| (p.x, p.y));
+            // X11 has a single scale factor for all monitors
+            let (x, y) = window_attributes
+                .position
+                .map_or((0, 0), |p| p.physical_position(platform.scale_factor()));
 
             let (width, height) = window_attributes.size.map_or((800, 600), |s| match s {
                 Resolution::Physical { width, height } | Resolution::Logical { width, height } => {
@@ -72,13 +644,110 @@ impl X11Window {
                 }
             });
 
-            let depth = (platform.xlib.XDefaultDepth)(platform.display, platform.default_screen);
+            let saved_placement = window_attributes
+                .persistence_key
+                .as_deref()
+                .and_then(SavedPlacement::load)
+                .map(|placement| {
+                    let (center_x, center_y) = placement.center();
+                    match platform.monitor_at(center_x, center_y) {
+                        Some(monitor) => placement.clamp_to(&monitor),
+                        None => placement,
+                    }
+                });
+
+            let ((x, y), (width, height)) = saved_placement
+                .map_or(((x, y), (width, height)), |placement| {
+                    (placement.position, placement.size)
+                });
+
+            let min_size = window_attributes
+                .min_size
+                .map(|size| size.physical_size(platform.scale_factor()));
+            let max_size = window_attributes
+                .max_size
+                .map(|size| size.physical_size(platform.scale_factor()));
+            let (width, height) = if window_attributes.resizable {
+                clamp_size((width, height), min_size, max_size)
+            } else {
+                (width, height)
+            };
+
+            let screen = match window_attributes.platform_specific.x11.screen {
+                Some(screen)
+                    if (0..(platform.xlib.XScreenCount)(platform.display)).contains(&screen) =>
+                {
+                    screen
+                }
+                Some(screen) => {
+                    warn!(
+                        "X screen {} doesn't exist, creating the window on the default screen",
+                        screen
+                    );
+                    platform.default_screen
+                }
+                None => platform.default_screen,
+            };
+            let root = (platform.xlib.XRootWindow)(platform.display, screen);
+
+            let argb_visual = if window_attributes.transparent {
+                let argb_visual = find_argb_visual(&platform, screen);
+                if argb_visual.is_none() {
+                    warn!("No 32-bit TrueColor visual available, creating an opaque window");
+                }
+                argb_visual
+            } else {
+                None
+            };
+
+            let (visual, depth, colormap) = match argb_visual {
+                Some((visual, depth)) => {
+                    // a visual other than the parent's needs its own colormap, and the border and background pixels default to the parent's which don't exist in it
+                    let colormap = (platform.xlib.XCreateColormap)(
+                        platform.display,
+                        root,
+                        visual,
+                        xlib::AllocNone,
+                    );
+                    swa.colormap = colormap;
+                    swa.border_pixel = 0;
+                    swa.background_pixel = 0;
+                    cw_mask |= xlib::CWColormap | xlib::CWBorderPixel | xlib::CWBackPixel;
+                    (visual, depth, colormap)
+                }
+                None => {
+                    if let Some((red, green, blue)) = window_attributes.background_color {
+                        // X colors have 16 bits per channel, 257 maps 0xff to 0xffff
+                        let mut color = xlib::XColor {
+                            red: red as u16 * 257,
+                            green: green as u16 * 257,
+                            blue: blue as u16 * 257,
+                            ..std::mem::zeroed()
+                        };
+                        let colormap = (platform.xlib.XDefaultColormap)(platform.display, screen);
+                        if (platform.xlib.XAllocColor)(platform.display, colormap, &mut color) != 0
+                        {
+                            swa.background_pixel = color.pixel;
+                            cw_mask |= xlib::CWBackPixel;
+                        } else {
+                            warn!(
+                                "Failed to allocate the background color {:?}",
+                                (red, green, blue)
+                            );
+                        }
+                    }
 
-            let visual = (platform.xlib.XDefaultVisual)(platform.display, platform.default_screen);
+                    (
+                        (platform.xlib.XDefaultVisual)(platform.display, screen),
+                        (platform.xlib.XDefaultDepth)(platform.display, screen),
+                        0,
+                    )
+                }
+            };
 
             let window = (platform.xlib.XCreateWindow)(
                 platform.display,
-                platform.root_window,
+                root,
                 x,
                 y,
                 width,
@@ -91,50 +760,691 @@ impl X11Window {
                 &mut swa,
             );
 
-            let title = CString::new(window_attributes.title.unwrap_or_else(|| "Window".to_string()))?;
-
-            (platform.xlib.XStoreName)(platform.display, window, title.as_ptr());
+            set_title(
+                &platform,
+                window,
+                window_attributes.title.as_deref().unwrap_or("Window"),
+            )?;
 
-            #[allow(invalid_value)]
-            let mut size_hints = MaybeUninit::<XSizeHints>::uninit().assume_init();
+            let mut size_hints: XSizeHints = std::mem::zeroed();
             size_hints.flags = PSize | PPosition;
             size_hints.x = x;
-            size_hints.y = x;
+            size_hints.y = y;
             size_hints.width = width as i32;
             size_hints.height = height as i32;
 
+            // windows that aren't resizable have min == max
             if window_attributes.resizable {
-                size_hints.flags |= PMinSize | PMaxSize;
-                size_hints.min_width = width as i32;
-                size_hints.max_width = width as i32;
-                size_hints.min_height = height as i32;
-                size_hints.max_height = height as i32;
+                set_size_limits(&mut size_hints, min_size, max_size);
+            } else {
+                set_size_limits(
+                    &mut size_hints,
+                    Some((width, height)),
+                    Some((width, height)),
+                );
             }
 
             (platform.xlib.XSetWMNormalHints)(platform.display, window, &mut size_hints);
 
-            if window_attributes.initially_visible {
-                (platform.xlib.XMapWindow)(platform.display, window);
+            // window states of unmapped windows are set through the property directly, so this has to happen before mapping
+            let mut initial_states = Vec::new();
+            if window_attributes.skip_taskbar || !window_attributes.show_in_taskbar {
+                initial_states.push(platform.intern_atom(c"_NET_WM_STATE_SKIP_TASKBAR") as c_long);
+            }
+            if !window_attributes.show_in_switcher {
+                initial_states.push(platform.intern_atom(c"_NET_WM_STATE_SKIP_PAGER") as c_long);
+            }
+            if window_attributes.always_on_top {
+                platform.check_wm_supports(c"_NET_WM_STATE_ABOVE");
+                initial_states.push(platform.intern_atom(c"_NET_WM_STATE_ABOVE") as c_long);
             }
 
+            if !initial_states.is_empty() {
+                (platform.xlib.XChangeProperty)(
+                    platform.display,
+                    window,
+                    platform.intern_atom(c"_NET_WM_STATE"),
+                    xlib::XA_ATOM,
+                    32,
+                    xlib::PropModeReplace,
+                    initial_states.as_ptr() as *const c_uchar,
+                    initial_states.len() as c_int,
+                );
+            }
+
+            set_bypass_compositor(&platform, window, window_attributes.compositor_bypass);
+
+            if let Some(window_type) = window_attributes.platform_specific.x11.window_type {
+                let window_type = platform.intern_atom(match window_type {
+                    X11WindowType::Normal => c"_NET_WM_WINDOW_TYPE_NORMAL",
+                    X11WindowType::Dialog => c"_NET_WM_WINDOW_TYPE_DIALOG",
+                    X11WindowType::Utility => c"_NET_WM_WINDOW_TYPE_UTILITY",
+                    X11WindowType::Toolbar => c"_NET_WM_WINDOW_TYPE_TOOLBAR",
+                    X11WindowType::Menu => c"_NET_WM_WINDOW_TYPE_MENU",
+                    X11WindowType::Splash => c"_NET_WM_WINDOW_TYPE_SPLASH",
+                    X11WindowType::Dock => c"_NET_WM_WINDOW_TYPE_DOCK",
+                    X11WindowType::Desktop => c"_NET_WM_WINDOW_TYPE_DESKTOP",
+                }) as c_long;
+
+                (platform.xlib.XChangeProperty)(
+                    platform.display,
+                    window,
+                    platform.intern_atom(c"_NET_WM_WINDOW_TYPE"),
+                    xlib::XA_ATOM,
+                    32,
+                    xlib::PropModeReplace,
+                    &window_type as *const c_long as *const c_uchar,
+                    1,
+                );
+            }
+
+            if window_attributes.auto_hide_on_focus_loss {
+                platform.auto_hide_windows.lock().unwrap().insert(window);
+            }
+
+            if !window_attributes.initially_visible {
+                platform.hidden_windows.lock().unwrap().insert(window);
+            }
+
+            // not visible until the MapNotify arrives
+            platform.set_occluded(window, true, None);
+
+            platform.select_xinput_events(window);
+            platform.create_input_context(window);
+
             let visual_id = (platform.xlib.XVisualIDFromVisual)(visual);
 
-            let mut protocols = [platform.xa_wm_delete_window];
+            let protocols = [platform.xa_wm_delete_window];
 
-            (platform.xlib.XSetWMProtocols)(platform.display, window, protocols.as_ptr() as *mut c_ulong, 1);
+            (platform.xlib.XSetWMProtocols)(
+                platform.display,
+                window,
+                protocols.as_ptr() as *mut c_ulong,
+                1,
+            );
 
-            Ok(Self {
+            platform
+                .surface_sizes
+                .lock()
+                .unwrap()
+                .insert(window, (width, height));
+
+            let x11_window = Self {
                 window,
                 id,
                 visual_id,
-                platform,
-            })
+                screen,
+                root,
+                colormap,
+                platform: platform.clone(),
+                present_mode: Cell::new(None),
+                persistence_key: window_attributes.persistence_key.clone(),
+                resizable: Cell::new(window_attributes.resizable),
+                min_size: Cell::new(min_size),
+                max_size: Cell::new(max_size),
+                fullscreen: RefCell::new(None),
+                windowed_geometry: Cell::new(None),
+            };
+
+            if window_attributes.icon.is_some() {
+                x11_window.set_icon(window_attributes.icon.clone());
+            }
+
+            // still unmapped, so the window manager picks the state up when it maps the window
+            if window_attributes.fullscreen.is_some() {
+                x11_window.set_fullscreen(window_attributes.fullscreen.clone());
+            }
+
+            if window_attributes.initially_visible {
+                (platform.xlib.XMapWindow)(platform.display, window);
+
+                // window managers turn these into restacking requests, handled after the map request
+                match window_attributes.initial_z_order {
+                    InitialZOrder::Top => {
+                        (platform.xlib.XRaiseWindow)(platform.display, window);
+                    }
+                    InitialZOrder::Bottom => {
+                        (platform.xlib.XLowerWindow)(platform.display, window);
+                    }
+                    InitialZOrder::NoChange => (),
+                }
+            }
+
+            Ok(x11_window)
+        }
+    }
+}
+
+/// How long to wait for the window manager to apply a resize, see [`X11Window::resize_and_wait`].
+const RESIZE_TIMEOUT: Duration = Duration::from_millis(200);
+
+const NET_WM_STATE_REMOVE: c_long = 0;
+const NET_WM_STATE_ADD: c_long = 1;
+
+impl X11Window {
+    fn is_mapped(&self) -> bool {
+        unsafe {
+            let mut attributes: xlib::XWindowAttributes = std::mem::zeroed();
+            (self.platform.xlib.XGetWindowAttributes)(
+                self.platform.display,
+                self.window,
+                &mut attributes,
+            );
+            attributes.map_state != xlib::IsUnmapped
+        }
+    }
+
+    /// Add or remove an EWMH `_NET_WM_STATE_*` atom.
+    ///
+    /// Mapped windows have to ask the window manager with a client message to the root window, unmapped windows just get their `_NET_WM_STATE` property updated (the window manager reads it when the window is mapped).
+    /// Whether the window manager has the window minimized (ICCCM `WM_STATE` is `IconicState`).
+    fn is_iconic(&self) -> bool {
+        const ICONIC_STATE: c_long = 3;
+
+        let wm_state = self.platform.intern_atom(c"WM_STATE");
+        self.platform
+            .get_property_longs(self.window, wm_state, wm_state)
+            .is_some_and(|state| state.first() == Some(&ICONIC_STATE))
+    }
+
+    pub(super) fn set_net_wm_state(&self, state: xlib::Atom, enabled: bool) {
+        let net_wm_state = self.platform.intern_atom(c"_NET_WM_STATE");
+
+        unsafe {
+            if self.is_mapped() {
+                // the last one is the source indication: normal application
+                let action = if enabled {
+                    NET_WM_STATE_ADD
+                } else {
+                    NET_WM_STATE_REMOVE
+                };
+                self.send_to_window_manager(net_wm_state, [action, state as c_long, 0, 1, 0]);
+            } else {
+                let mut states = self
+                    .platform
+                    .get_property_longs(self.window, net_wm_state, xlib::XA_ATOM)
+                    .unwrap_or_default();
+
+                states.retain(|s| *s as xlib::Atom != state);
+                if enabled {
+                    states.push(state as c_long);
+                }
+
+                (self.platform.xlib.XChangeProperty)(
+                    self.platform.display,
+                    self.window,
+                    net_wm_state,
+                    xlib::XA_ATOM,
+                    32,
+                    xlib::PropModeReplace,
+                    states.as_ptr() as *const c_uchar,
+                    states.len() as c_int,
+                );
+            }
+
+            (self.platform.xlib.XFlush)(self.platform.display);
+        }
+    }
+
+    /// Send an EWMH client message about this window to the window manager (which listens on the root window).
+    fn send_to_window_manager(&self, message_type: xlib::Atom, longs: [c_long; 5]) {
+        let mut data = xlib::ClientMessageData::new();
+        for (i, value) in longs.into_iter().enumerate() {
+            data.set_long(i, value);
+        }
+
+        let mut event = xlib::XEvent {
+            client_message: xlib::XClientMessageEvent {
+                type_: xlib::ClientMessage,
+                serial: 0,
+                send_event: xlib::True,
+                display: self.platform.display,
+                window: self.window,
+                message_type,
+                format: 32,
+                data,
+            },
+        };
+
+        unsafe {
+            (self.platform.xlib.XSendEvent)(
+                self.platform.display,
+                self.root,
+                xlib::False,
+                xlib::SubstructureRedirectMask | xlib::SubstructureNotifyMask,
+                &mut event,
+            );
+        }
+    }
+
+    /// Put the size limits into the normal hints: the current size as both min and max for windows that aren't resizable, the configured limits otherwise. The other hints are kept.
+    fn update_size_hints(&self) {
+        let (min_size, max_size) = if self.resizable.get() {
+            (self.min_size.get(), self.max_size.get())
+        } else {
+            let size = self.client_size();
+            (Some(size), Some(size))
+        };
+
+        unsafe {
+            let mut size_hints: XSizeHints = std::mem::zeroed();
+            let mut supplied: c_long = 0;
+            (self.platform.xlib.XGetWMNormalHints)(
+                self.platform.display,
+                self.window,
+                &mut size_hints,
+                &mut supplied,
+            );
+
+            set_size_limits(&mut size_hints, min_size, max_size);
+
+            (self.platform.xlib.XSetWMNormalHints)(
+                self.platform.display,
+                self.window,
+                &mut size_hints,
+            );
+            (self.platform.xlib.XFlush)(self.platform.display);
+        }
+    }
+
+    /// Update the hints, and resize the window into the new limits if it doesn't fit anymore (window managers only apply them to later resizes).
+    fn size_limits_changed(&self) {
+        self.update_size_hints();
+
+        if self.resizable.get() {
+            let size = self.client_size();
+            let (width, height) = clamp_size(size, self.min_size.get(), self.max_size.get());
+            if (width, height) != size {
+                self.set_client_size(Resolution::Physical { width, height });
+            }
+        }
+    }
+
+    /// Resize the window and wait until the size was applied, or [`RESIZE_TIMEOUT`] passed. Returns the size the window ended up with.
+    ///
+    /// Window managers intercept resizes of mapped windows and apply them (or a constrained size) some time later, which `XSync` doesn't wait for.
+    fn resize_and_wait(&self, width: u32, height: u32) -> (u32, u32) {
+        unsafe {
+            (self.platform.xlib.XResizeWindow)(self.platform.display, self.window, width, height);
+        }
+
+        let deadline = Instant::now() + RESIZE_TIMEOUT;
+        loop {
+            // a round trip, so this also sends the request
+            let size = self.client_size();
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if size == (width, height) || remaining.is_zero() {
+                return size;
+            }
+
+            // the window manager's ConfigureNotify wakes this up
+            let mut fd = libc::pollfd {
+                fd: unsafe { (self.platform.xlib.XConnectionNumber)(self.platform.display) },
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            unsafe {
+                libc::poll(&mut fd, 1, remaining.as_millis() as c_int);
+            }
+        }
+    }
+
+    /// Current size of the client area.
+    fn client_size(&self) -> (u32, u32) {
+        let mut root: xlib::Window = 0;
+        let (mut x, mut y): (c_int, c_int) = (0, 0);
+        let (mut width, mut height, mut border, mut depth): (c_uint, c_uint, c_uint, c_uint) =
+            (0, 0, 0, 0);
+
+        unsafe {
+            (self.platform.xlib.XGetGeometry)(
+                self.platform.display,
+                self.window,
+                &mut root,
+                &mut x,
+                &mut y,
+                &mut width,
+                &mut height,
+                &mut border,
+                &mut depth,
+            );
+        }
+
+        (width, height)
+    }
+
+    /// Position of the client area relative to the root window.
+    fn client_position(&self) -> (i32, i32) {
+        let (mut x, mut y): (c_int, c_int) = (0, 0);
+        let mut child: xlib::Window = 0;
+
+        unsafe {
+            (self.platform.xlib.XTranslateCoordinates)(
+                self.platform.display,
+                self.window,
+                self.root,
+                0,
+                0,
+                &mut x,
+                &mut y,
+                &mut child,
+            );
+        }
+
+        (x, y)
+    }
+
+    /// Decoration sizes reported by the window manager through `_NET_FRAME_EXTENTS` (left, right, top, bottom).
+    fn frame_extents(&self) -> Option<[c_long; 4]> {
+        let atom = self.platform.intern_atom(c"_NET_FRAME_EXTENTS");
+        let values = self
+            .platform
+            .get_property_longs(self.window, atom, xlib::XA_CARDINAL)?;
+
+        values.get(..4).map(|v| [v[0], v[1], v[2], v[3]])
+    }
+}
+
+/// A 32-bit TrueColor visual with an alpha channel (bits not covered by the color masks), and its depth.
+unsafe fn find_argb_visual(
+    platform: &X11Platform,
+    screen: c_int,
+) -> Option<(*mut xlib::Visual, c_int)> {
+    unsafe {
+        let mut template: XVisualInfo = std::mem::zeroed();
+        template.screen = screen;
+        template.depth = 32;
+        template.class = xlib::TrueColor;
+
+        let mut count = 0;
+        let infos = (platform.xlib.XGetVisualInfo)(
+            platform.display,
+            xlib::VisualScreenMask | xlib::VisualDepthMask | xlib::VisualClassMask,
+            &mut template,
+            &mut count,
+        );
+        if infos.is_null() {
+            return None;
+        }
+
+        let visual = std::slice::from_raw_parts(infos, count as usize)
+            .iter()
+            .find(|info| (info.red_mask | info.green_mask | info.blue_mask) as u32 != u32::MAX)
+            .map(|info| (info.visual, info.depth));
+
+        (platform.xlib.XFree)(infos as *mut _);
+
+        visual
+    }
+}
+
+/// Set (or with `None` clear) the min and max size of `size_hints`.
+fn set_size_limits(
+    size_hints: &mut XSizeHints,
+    min_size: Option<(u32, u32)>,
+    max_size: Option<(u32, u32)>,
+) {
+    match min_size {
+        Some((width, height)) => {
+            size_hints.flags |= PMinSize;
+            size_hints.min_width = width as i32;
+            size_hints.min_height = height as i32;
+        }
+        None => size_hints.flags &= !PMinSize,
+    }
+
+    match max_size {
+        Some((width, height)) => {
+            size_hints.flags |= PMaxSize;
+            size_hints.max_width = width as i32;
+            size_hints.max_height = height as i32;
+        }
+        None => size_hints.flags &= !PMaxSize,
+    }
+}
+
+/// Set both the ICCCM `WM_NAME` (which older window managers read) and the UTF-8 `_NET_WM_NAME`.
+fn set_title(platform: &X11Platform, window: xlib::Window, title: &str) -> anyhow::Result<()> {
+    let c_title = CString::new(title)?;
+
+    unsafe {
+        (platform.xlib.XStoreName)(platform.display, window, c_title.as_ptr());
+        (platform.xlib.XChangeProperty)(
+            platform.display,
+            window,
+            platform.intern_atom(c"_NET_WM_NAME"),
+            platform.intern_atom(c"UTF8_STRING"),
+            8,
+            xlib::PropModeReplace,
+            title.as_ptr(),
+            title.len() as c_int,
+        );
+    }
+
+    Ok(())
+}
+
+/// Set or remove `_NET_WM_BYPASS_COMPOSITOR` (1 requests bypassing, 2 requests compositing).
+fn set_bypass_compositor(platform: &X11Platform, window: xlib::Window, bypass: CompositorBypass) {
+    let property = platform.intern_atom(c"_NET_WM_BYPASS_COMPOSITOR");
+
+    let value: c_long = match bypass {
+        CompositorBypass::Default => {
+            unsafe {
+                (platform.xlib.XDeleteProperty)(platform.display, window, property);
+            }
+            return;
         }
+        CompositorBypass::Bypass => 1,
+        CompositorBypass::NoBypass => 2,
+    };
+
+    unsafe {
+        (platform.xlib.XChangeProperty)(
+            platform.display,
+            window,
+            property,
+            xlib::XA_CARDINAL,
+            32,
+            xlib::PropModeReplace,
+            &value as *const c_long as *const c_uchar,
+            1,
+        );
     }
 }
 
 impl Drop for X11Window {
     fn drop(&mut self) {
+        // a maximized or minimized geometry isn't worth restoring, keep the previously saved one then
+        if let Some(key) = &self.persistence_key
+            && self.is_mapped()
+            && !self.platform.is_maximized(self.window)
+        {
+            let (client_x, client_y) = self.client_position();
+            let [left, _, top, _] = self.frame_extents().unwrap_or_default();
+
+            SavedPlacement {
+                position: (client_x - left as i32, client_y - top as i32),
+                size: self.client_size(),
+            }
+            .save(key);
+        }
+
+        if let Some(Fullscreen::Exclusive(mode)) = &*self.fullscreen.borrow() {
+            self.platform.restore_video_mode(&mode.monitor_name);
+        }
+
         self.platform.notify_window_destroy(self.window);
+
+        if self.colormap != 0 {
+            unsafe {
+                (self.platform.xlib.XFreeColormap)(self.platform.display, self.colormap);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Engine;
+    use std::ffi::CStr;
+
+    /// An engine on the X server from `DISPLAY`. The tests using it are ignored by default, run them with `cargo test -- --ignored` on a machine with an X server.
+    fn x11_engine() -> Engine {
+        let display = X11Platform::open_display().expect("no X server to test against");
+        unsafe { Engine::builder().x11_display(display, true) }
+            .build()
+            .unwrap()
+    }
+
+    fn x11_platform(engine: &Engine) -> &X11Platform {
+        engine
+            .platform()
+            .as_any()
+            .downcast_ref::<X11Platform>()
+            .unwrap()
+    }
+
+    fn xlib_window(window: &dyn Window) -> xlib::Window {
+        let RawWindowHandle::Xlib(handle) = window.window_handle().unwrap().as_raw() else {
+            panic!("not an Xlib window");
+        };
+        handle.window
+    }
+
+    /// Read back the `WM_NORMAL_HINTS` of `window`.
+    fn normal_hints(engine: &Engine, window: &dyn Window) -> XSizeHints {
+        let platform = x11_platform(engine);
+
+        unsafe {
+            let mut size_hints: XSizeHints = std::mem::zeroed();
+            let mut supplied: c_long = 0;
+            (platform.xlib.XGetWMNormalHints)(
+                platform.display,
+                xlib_window(window),
+                &mut size_hints,
+                &mut supplied,
+            );
+            size_hints
+        }
+    }
+
+    #[test]
+    #[ignore = "needs an X server"]
+    fn fixed_size_hints() {
+        let engine = x11_engine();
+        let size = Resolution::Physical {
+            width: 320,
+            height: 240,
+        };
+
+        let (_, window) = engine
+            .create_window(WindowAttributes {
+                size: Some(size),
+                resizable: false,
+                ..Default::default()
+            })
+            .unwrap();
+        let window = window.upgrade().unwrap();
+        let hints = normal_hints(&engine, window.as_ref());
+        assert_eq!(hints.flags & (PMinSize | PMaxSize), PMinSize | PMaxSize);
+        assert_eq!((hints.min_width, hints.min_height), (320, 240));
+        assert_eq!((hints.max_width, hints.max_height), (320, 240));
+
+        window.set_resizable(true);
+        let hints = normal_hints(&engine, window.as_ref());
+        assert_eq!(hints.flags & (PMinSize | PMaxSize), 0);
+
+        let (_, window) = engine
+            .create_window(WindowAttributes {
+                size: Some(size),
+                ..Default::default()
+            })
+            .unwrap();
+        let window = window.upgrade().unwrap();
+        let hints = normal_hints(&engine, window.as_ref());
+        assert_eq!(hints.flags & (PMinSize | PMaxSize), 0);
+
+        window.set_resizable(false);
+        let hints = normal_hints(&engine, window.as_ref());
+        assert_eq!(hints.flags & (PMinSize | PMaxSize), PMinSize | PMaxSize);
+        assert_eq!((hints.min_width, hints.min_height), (320, 240));
+        assert_eq!((hints.max_width, hints.max_height), (320, 240));
+    }
+
+    #[test]
+    #[ignore = "needs an X server"]
+    fn position_hints() {
+        let engine = x11_engine();
+
+        let (_, window) = engine
+            .create_window(WindowAttributes {
+                position: Some(Position::Physical { x: 40, y: 90 }),
+                ..Default::default()
+            })
+            .unwrap();
+        let window = window.upgrade().unwrap();
+        let hints = normal_hints(&engine, window.as_ref());
+        assert_eq!(hints.flags & PPosition, PPosition);
+        assert_eq!((hints.x, hints.y), (40, 90));
+    }
+
+    #[test]
+    #[ignore = "needs an X server"]
+    fn hidden_window_stays_active() {
+        let engine = x11_engine();
+
+        let (id, window) = engine
+            .create_window(WindowAttributes {
+                initially_visible: false,
+                ..Default::default()
+            })
+            .unwrap();
+
+        window.upgrade().unwrap().hide();
+        engine.process_all_pending();
+        assert!(engine.window_manager().is_window_active(id));
     }
-}
\ No newline at end of file
+
+    #[test]
+    #[ignore = "needs an X server"]
+    fn set_title_updates_wm_name() {
+        let engine = x11_engine();
+        let platform = x11_platform(&engine);
+
+        let (_, window) = engine.create_window(WindowAttributes::default()).unwrap();
+        let window = window.upgrade().unwrap();
+        window.set_title("updated").unwrap();
+        assert!(window.set_title("nul\0byte").is_err());
+
+        unsafe {
+            let mut name = std::ptr::null_mut();
+            (platform.xlib.XFetchName)(platform.display, xlib_window(window.as_ref()), &mut name);
+            assert!(!name.is_null());
+            assert_eq!(CStr::from_ptr(name), c"updated");
+            (platform.xlib.XFree)(name as *mut c_void);
+        }
+    }
+
+    #[test]
+    #[ignore = "needs an X server"]
+    fn inner_size_matches_requested() {
+        let engine = x11_engine();
+
+        let (_, window) = engine
+            .create_window(WindowAttributes {
+                size: Some(Resolution::Physical {
+                    width: 800,
+                    height: 600,
+                }),
+                ..Default::default()
+            })
+            .unwrap();
+        engine.process_all_pending();
+
+        // the window manager may adjust the size slightly
+        let size = window.upgrade().unwrap().inner_size();
+        assert!(size.width().abs_diff(800) <= 2 && size.height().abs_diff(600) <= 2);
+    }
+}
@@ -0,0 +1,500 @@
+use crate::event::{Event, KeyEvent, KeyState, PenState};
+use crate::os::OsLoopInputs;
+use crate::os::x11::X11Platform;
+use hashbrown::HashMap;
+use log::debug;
+use std::cell::{Cell, RefCell};
+use std::ffi::{c_int, c_uchar};
+use x11_dl::xinput2;
+use x11_dl::xinput2::XInput2;
+use x11_dl::xlib;
+use x11_dl::xlib::Xlib;
+
+/// XInput2 extension state. Touch events require version 2.2.
+pub(super) struct XInput {
+    pub(super) xinput2: XInput2,
+    pub(super) opcode: c_int,
+    tablets: RefCell<HashMap<c_int, Tablet>>,
+    /// Raw events selected on the root window: key events for the global keyboard hook, motion for locked cursors.
+    raw_keys: Cell<bool>,
+    raw_motion: Cell<bool>,
+}
+
+/// A slave pointer device with a pressure axis (a pen tablet).
+struct Tablet {
+    pressure: Axis,
+    tilt_x: Option<Axis>,
+    tilt_y: Option<Axis>,
+    /// Valuators are only sent when they change, so keep the last known state.
+    state: Cell<PenState>,
+}
+
+#[derive(Clone, Copy)]
+struct Axis {
+    number: c_int,
+    min: f64,
+    max: f64,
+}
+
+/// Bit mask with room for every XInput2 event type.
+type EventMask = [c_uchar; ((xinput2::XI_LASTEVENT >> 3) + 1) as usize];
+
+const EMPTY_MASK: EventMask = [0; ((xinput2::XI_LASTEVENT >> 3) + 1) as usize];
+
+/// Button number of the pen tip.
+const PEN_TIP_BUTTON: c_int = 1;
+
+/// Button numbers of the barrel buttons.
+const PEN_BARREL_BUTTONS: [c_int; 2] = [2, 3];
+
+impl XInput {
+    pub(super) fn new(
+        xlib: &Xlib,
+        display: *mut xlib::Display,
+        root_window: xlib::Window,
+    ) -> Option<XInput> {
+        let xinput2 = XInput2::open().ok()?;
+
+        let (mut opcode, mut event_base, mut error_base) = (0, 0, 0);
+        let (mut major, mut minor) = (2, 2);
+
+        unsafe {
+            if (xlib.XQueryExtension)(
+                display,
+                c"XInputExtension".as_ptr(),
+                &mut opcode,
+                &mut event_base,
+                &mut error_base,
+            ) == xlib::False
+            {
+                debug!("XInput extension is not available");
+                return None;
+            }
+
+            if (xinput2.XIQueryVersion)(display, &mut major, &mut minor) != xlib::Success as c_int
+                || (major, minor) < (2, 2)
+            {
+                debug!("XInput {}.{} is too old, 2.2 is required", major, minor);
+                return None;
+            }
+        }
+
+        let xinput = XInput {
+            xinput2,
+            opcode,
+            tablets: RefCell::new(HashMap::new()),
+            raw_keys: Cell::new(false),
+            raw_motion: Cell::new(false),
+        };
+
+        // hierarchy changes tell us when tablets are plugged in or removed
+        let mut mask = EMPTY_MASK;
+        xinput2::XISetMask(&mut mask, xinput2::XI_HierarchyChanged);
+        xinput.select(display, root_window, &mut [(xinput2::XIAllDevices, mask)]);
+
+        xinput.query_tablets(xlib, display);
+
+        Some(xinput)
+    }
+
+    fn select(
+        &self,
+        display: *mut xlib::Display,
+        window: xlib::Window,
+        masks: &mut [(c_int, EventMask)],
+    ) {
+        let mut event_masks: Vec<xinput2::XIEventMask> = masks
+            .iter_mut()
+            .map(|(deviceid, mask)| xinput2::XIEventMask {
+                deviceid: *deviceid,
+                mask_len: mask.len() as c_int,
+                mask: mask.as_mut_ptr(),
+            })
+            .collect();
+
+        unsafe {
+            (self.xinput2.XISelectEvents)(
+                display,
+                window,
+                event_masks.as_mut_ptr(),
+                event_masks.len() as c_int,
+            );
+        }
+    }
+
+    /// Rebuild the list of tablet devices.
+    fn query_tablets(&self, xlib: &Xlib, display: *mut xlib::Display) {
+        let intern = |name: &std::ffi::CStr| unsafe {
+            (xlib.XInternAtom)(display, name.as_ptr(), xlib::False)
+        };
+        let abs_pressure = intern(c"Abs Pressure");
+        let abs_tilt_x = intern(c"Abs Tilt X");
+        let abs_tilt_y = intern(c"Abs Tilt Y");
+
+        let mut tablets = self.tablets.borrow_mut();
+        tablets.clear();
+
+        unsafe {
+            let mut count: c_int = 0;
+            let devices = (self.xinput2.XIQueryDevice)(display, xinput2::XIAllDevices, &mut count);
+            if devices.is_null() {
+                return;
+            }
+
+            for device in std::slice::from_raw_parts(devices, count.max(0) as usize) {
+                if device._use != xinput2::XISlavePointer && device._use != xinput2::XIFloatingSlave
+                {
+                    continue;
+                }
+
+                let (mut pressure, mut tilt_x, mut tilt_y) = (None, None, None);
+
+                let classes =
+                    std::slice::from_raw_parts(device.classes, device.num_classes.max(0) as usize);
+                for &class in classes {
+                    if (*class)._type != xinput2::XIValuatorClass {
+                        continue;
+                    }
+
+                    let valuator = &*(class as *const xinput2::XIValuatorClassInfo);
+                    let axis = Some(Axis {
+                        number: valuator.number,
+                        min: valuator.min,
+                        max: valuator.max,
+                    });
+
+                    match valuator.label {
+                        label if label == abs_pressure => pressure = axis,
+                        label if label == abs_tilt_x => tilt_x = axis,
+                        label if label == abs_tilt_y => tilt_y = axis,
+                        _ => (),
+                    }
+                }
+
+                if let Some(pressure) = pressure {
+                    tablets.insert(
+                        device.deviceid,
+                        Tablet {
+                            pressure,
+                            tilt_x,
+                            tilt_y,
+                            state: Cell::new(PenState::default()),
+                        },
+                    );
+                }
+            }
+
+            (self.xinput2.XIFreeDeviceInfo)(devices);
+        }
+
+        debug!("Found {} tablet device(s)", tablets.len());
+    }
+}
+
+impl X11Platform {
+    /// Select the XInput2 events we handle for a window.
+    pub(super) fn select_xinput_events(&self, window: xlib::Window) {
+        let Some(xinput) = &self.xinput else {
+            return;
+        };
+
+        let mut touch_mask = EMPTY_MASK;
+        // touch events must be selected together, selecting only some of them is an error
+        xinput2::XISetMask(&mut touch_mask, xinput2::XI_TouchBegin);
+        xinput2::XISetMask(&mut touch_mask, xinput2::XI_TouchUpdate);
+        xinput2::XISetMask(&mut touch_mask, xinput2::XI_TouchEnd);
+
+        let mut masks = vec![(xinput2::XIAllMasterDevices, touch_mask)];
+
+        // pen events are selected on the tablet's slave device only: selecting pointer events on the master device would stop the core pointer events from being delivered
+        let mut pen_mask = EMPTY_MASK;
+        xinput2::XISetMask(&mut pen_mask, xinput2::XI_ButtonPress);
+        xinput2::XISetMask(&mut pen_mask, xinput2::XI_ButtonRelease);
+        xinput2::XISetMask(&mut pen_mask, xinput2::XI_Motion);
+
+        masks.extend(
+            xinput
+                .tablets
+                .borrow()
+                .keys()
+                .map(|&deviceid| (deviceid, pen_mask)),
+        );
+
+        xinput.select(self.display, window, &mut masks);
+    }
+
+    /// Start or stop receiving raw key events (sent to the root window, whichever window has the focus). Returns `false` without XInput2.
+    pub(super) fn select_raw_key_events(&self, enabled: bool) -> bool {
+        let Some(xinput) = &self.xinput else {
+            return false;
+        };
+
+        xinput.raw_keys.set(enabled);
+        self.select_raw_events(xinput);
+        true
+    }
+
+    /// Start or stop receiving raw pointer motion, the deltas of locked cursors. Returns `false` without XInput2.
+    pub(super) fn select_raw_motion_events(&self, enabled: bool) -> bool {
+        let Some(xinput) = &self.xinput else {
+            return false;
+        };
+
+        if xinput.raw_motion.replace(enabled) != enabled {
+            self.select_raw_events(xinput);
+        }
+        true
+    }
+
+    /// Selecting replaces the previous mask, so both kinds of raw events are selected together.
+    fn select_raw_events(&self, xinput: &XInput) {
+        let mut mask = EMPTY_MASK;
+        if xinput.raw_keys.get() {
+            xinput2::XISetMask(&mut mask, xinput2::XI_RawKeyPress);
+            xinput2::XISetMask(&mut mask, xinput2::XI_RawKeyRelease);
+        }
+        if xinput.raw_motion.get() {
+            xinput2::XISetMask(&mut mask, xinput2::XI_RawMotion);
+        }
+
+        // the master devices only, slave devices would report every event a second time
+        xinput.select(
+            self.display,
+            self.root_window,
+            &mut [(xinput2::XIAllMasterDevices, mask)],
+        );
+        unsafe {
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+
+    /// Report how far a locked pointer moved and put it back in the center. Raw motion is what the device reported, so it doesn't depend on where the pointer was warped to.
+    fn handle_raw_motion(
+        &self,
+        xinput: &XInput,
+        raw_event: &xinput2::XIRawEvent,
+        inputs: &OsLoopInputs,
+    ) {
+        // tablets report absolute positions on these axes
+        if xinput.tablets.borrow().contains_key(&raw_event.sourceid) {
+            return;
+        }
+        let Some(xwindow) = self.locking_window() else {
+            return;
+        };
+
+        // valuators 0 and 1 are the x and y movement of relative pointer devices, after acceleration
+        let dx = unsafe { valuator_value(&raw_event.valuators, 0) }.unwrap_or(0.0);
+        let dy = unsafe { valuator_value(&raw_event.valuators, 1) }.unwrap_or(0.0);
+        if dx == 0.0 && dy == 0.0 {
+            return;
+        }
+
+        if let Some(window) = self.window_id(xwindow) {
+            inputs.event_queue.push(Event::MouseMotion {
+                window,
+                delta: (dx, dy),
+            });
+        }
+        self.center_pointer(xwindow);
+    }
+
+    /// Handle a `GenericEvent`, which is how XInput2 events are delivered.
+    pub(super) fn handle_generic_event(
+        &self,
+        cookie: &mut xlib::XGenericEventCookie,
+        inputs: &OsLoopInputs,
+    ) {
+        let Some(xinput) = &self.xinput else {
+            return;
+        };
+
+        if cookie.extension != xinput.opcode {
+            return;
+        }
+
+        unsafe {
+            if (self.xlib.XGetEventData)(self.display, cookie) == xlib::False {
+                return;
+            }
+
+            match cookie.evtype {
+                xinput2::XI_TouchBegin | xinput2::XI_TouchUpdate | xinput2::XI_TouchEnd => {
+                    let device_event = &*(cookie.data as *const xinput2::XIDeviceEvent);
+
+                    if let Some(window) = self.window_id(device_event.event) {
+                        // the touch id (`detail`) stays unique while the touch is active
+                        let id = device_event.detail as u32 as u64;
+                        let position = (device_event.event_x, device_event.event_y);
+
+                        inputs.event_queue.push(match cookie.evtype {
+                            xinput2::XI_TouchBegin => Event::TouchBegin {
+                                window,
+                                id,
+                                position,
+                            },
+                            xinput2::XI_TouchUpdate => Event::TouchMove {
+                                window,
+                                id,
+                                position,
+                            },
+                            _ => Event::TouchEnd {
+                                window,
+                                id,
+                                position,
+                            },
+                        });
+                    }
+                }
+                xinput2::XI_ButtonPress | xinput2::XI_ButtonRelease | xinput2::XI_Motion => {
+                    let device_event = &*(cookie.data as *const xinput2::XIDeviceEvent);
+                    self.handle_pen_event(xinput, cookie.evtype, device_event, inputs);
+                }
+                xinput2::XI_RawMotion => {
+                    let raw_event = &*(cookie.data as *const xinput2::XIRawEvent);
+                    self.handle_raw_motion(xinput, raw_event, inputs);
+                }
+                xinput2::XI_RawKeyPress | xinput2::XI_RawKeyRelease => {
+                    let raw_event = &*(cookie.data as *const xinput2::XIRawEvent);
+                    let pressed = cookie.evtype == xinput2::XI_RawKeyPress;
+
+                    let hook = self.global_keyboard_hook.lock().unwrap().clone();
+                    if let Some(hook) = hook
+                        && let Ok(mut hook) = hook.try_borrow_mut()
+                    {
+                        (*hook)(KeyEvent {
+                            scancode: raw_event.detail as u32,
+                            state: if pressed {
+                                KeyState::Pressed
+                            } else {
+                                KeyState::Released
+                            },
+                            repeat: pressed && raw_event.flags & xinput2::XIKeyRepeat != 0,
+                            modifiers: self.global_modifiers(),
+                        });
+                    }
+                }
+                xinput2::XI_HierarchyChanged => {
+                    xinput.query_tablets(&self.xlib, self.display);
+
+                    let windows: Vec<xlib::Window> =
+                        self.window_map.lock().unwrap().keys().copied().collect();
+                    for window in windows {
+                        self.select_xinput_events(window);
+                    }
+                }
+                _ => (),
+            }
+
+            (self.xlib.XFreeEventData)(self.display, cookie);
+        }
+    }
+
+    fn handle_pen_event(
+        &self,
+        xinput: &XInput,
+        evtype: c_int,
+        device_event: &xinput2::XIDeviceEvent,
+        inputs: &OsLoopInputs,
+    ) {
+        let tablets = xinput.tablets.borrow();
+        let Some(tablet) = tablets.get(&device_event.deviceid) else {
+            return;
+        };
+        let Some(window) = self.window_id(device_event.event) else {
+            return;
+        };
+
+        let mut pen = tablet.state.get();
+        let valuator = |axis: Axis| unsafe { valuator_value(&device_event.valuators, axis.number) };
+
+        if let Some(value) = valuator(tablet.pressure) {
+            let range = tablet.pressure.max - tablet.pressure.min;
+            if range > 0.0 {
+                pen.pressure = ((value - tablet.pressure.min) / range).clamp(0.0, 1.0);
+            }
+        }
+
+        // tablet drivers report tilt in degrees already
+        if let Some(axis) = tablet.tilt_x
+            && let Some(value) = valuator(axis)
+        {
+            pen.tilt.0 = value.clamp(-90.0, 90.0);
+        }
+        if let Some(axis) = tablet.tilt_y
+            && let Some(value) = valuator(axis)
+        {
+            pen.tilt.1 = value.clamp(-90.0, 90.0);
+        }
+
+        let buttons =
+            unsafe { mask_slice(device_event.buttons.mask, device_event.buttons.mask_len) };
+        pen.barrel_button = PEN_BARREL_BUTTONS
+            .iter()
+            .any(|&button| mask_is_set(buttons, button));
+
+        // the button state doesn't include the button that is changing in this event
+        if PEN_BARREL_BUTTONS.contains(&device_event.detail) {
+            match evtype {
+                xinput2::XI_ButtonPress => pen.barrel_button = true,
+                xinput2::XI_ButtonRelease => {
+                    pen.barrel_button = PEN_BARREL_BUTTONS
+                        .iter()
+                        .filter(|&&button| button != device_event.detail)
+                        .any(|&button| mask_is_set(buttons, button))
+                }
+                _ => (),
+            }
+        }
+
+        tablet.state.set(pen);
+
+        let position = (device_event.event_x, device_event.event_y);
+
+        inputs.event_queue.push(match evtype {
+            xinput2::XI_ButtonPress if device_event.detail == PEN_TIP_BUTTON => Event::PenDown {
+                window,
+                position,
+                pen,
+            },
+            xinput2::XI_ButtonRelease if device_event.detail == PEN_TIP_BUTTON => Event::PenUp {
+                window,
+                position,
+                pen,
+            },
+            _ => Event::PenMove {
+                window,
+                position,
+                pen,
+            },
+        });
+    }
+}
+
+unsafe fn mask_slice<'a>(mask: *const c_uchar, len: c_int) -> &'a [c_uchar] {
+    if mask.is_null() || len <= 0 {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(mask, len as usize) }
+    }
+}
+
+/// Whether a bit is set in a mask sent by the server, which can be shorter than the bit (or empty).
+fn mask_is_set(mask: &[c_uchar], bit: c_int) -> bool {
+    bit >= 0 && ((bit >> 3) as usize) < mask.len() && xinput2::XIMaskIsSet(mask, bit)
+}
+
+/// Value of a valuator in an event, `None` if it didn't change.
+///
+/// Only the valuators set in the mask are sent, packed in order.
+unsafe fn valuator_value(state: &xinput2::XIValuatorState, number: c_int) -> Option<f64> {
+    let mask = unsafe { mask_slice(state.mask, state.mask_len) };
+
+    if !mask_is_set(mask, number) {
+        return None;
+    }
+
+    let index = (0..number).filter(|&n| mask_is_set(mask, n)).count();
+
+    Some(unsafe { *state.values.add(index) })
+}
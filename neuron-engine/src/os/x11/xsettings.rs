@@ -0,0 +1,116 @@
+//! XSETTINGS, the desktop settings (double-click time, drag threshold, ...) that the settings daemon shares with toolkits through the X server
+
+use crate::os::x11::X11Platform;
+use std::ffi::{CString, c_int, c_long, c_uchar, c_ulong, c_void};
+use x11_dl::xlib;
+
+/// `MSBFirst` in the settings' byte order field.
+const MSB_FIRST: u8 = 1;
+
+const SETTING_INTEGER: u8 = 0;
+const SETTING_STRING: u8 = 1;
+const SETTING_COLOR: u8 = 2;
+
+impl X11Platform {
+    /// Read an integer setting (such as `Net/DoubleClickTime`).
+    ///
+    /// `None` if no settings daemon is running (plain window managers usually don't start one) or it doesn't have the setting.
+    pub(super) fn xsetting_int(&self, name: &str) -> Option<i32> {
+        find_integer(&self.xsettings_data()?, name)
+    }
+
+    /// The raw `_XSETTINGS_SETTINGS` property of the settings manager's window.
+    fn xsettings_data(&self) -> Option<Vec<u8>> {
+        let selection = CString::new(format!("_XSETTINGS_S{}", self.default_screen)).ok()?;
+        let property = self.intern_atom(c"_XSETTINGS_SETTINGS");
+
+        let mut actual_type: xlib::Atom = 0;
+        let mut actual_format: c_int = 0;
+        let mut nitems: c_ulong = 0;
+        let mut bytes_after: c_ulong = 0;
+        let mut data: *mut c_uchar = std::ptr::null_mut();
+
+        unsafe {
+            let owner = (self.xlib.XGetSelectionOwner)(self.display, self.intern_atom(&selection));
+            if owner == 0 {
+                return None;
+            }
+
+            let status = (self.xlib.XGetWindowProperty)(
+                self.display,
+                owner,
+                property,
+                0,
+                c_long::MAX / 4,
+                xlib::False,
+                property,
+                &mut actual_type,
+                &mut actual_format,
+                &mut nitems,
+                &mut bytes_after,
+                &mut data,
+            );
+
+            if status != xlib::Success as c_int || data.is_null() {
+                return None;
+            }
+
+            let bytes = if actual_type == property && actual_format == 8 {
+                Some(std::slice::from_raw_parts(data, nitems as usize).to_vec())
+            } else {
+                None
+            };
+
+            (self.xlib.XFree)(data as *mut c_void);
+
+            bytes
+        }
+    }
+}
+
+/// Find an integer setting in the serialized settings.
+///
+/// Layout: byte order (1 byte, 3 padding), serial (4), setting count (4), then per setting: type (1, 1 padding), name length (2), name (padded to 4), last change serial (4) and the value.
+fn find_integer(data: &[u8], name: &str) -> Option<i32> {
+    let big_endian = *data.first()? == MSB_FIRST;
+
+    let read_u16 = |at: usize| -> Option<u16> {
+        let bytes = data.get(at..at + 2)?.try_into().ok()?;
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let read_u32 = |at: usize| -> Option<u32> {
+        let bytes = data.get(at..at + 4)?.try_into().ok()?;
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+
+    let count = read_u32(8)?;
+    let mut offset = 12;
+
+    for _ in 0..count {
+        let kind = *data.get(offset)?;
+        let name_length = read_u16(offset + 2)? as usize;
+        let setting_name = data.get(offset + 4..offset + 4 + name_length)?;
+        offset += 4 + name_length.next_multiple_of(4) + 4;
+
+        if kind == SETTING_INTEGER && setting_name == name.as_bytes() {
+            return Some(read_u32(offset)? as i32);
+        }
+
+        offset += match kind {
+            SETTING_INTEGER => 4,
+            SETTING_STRING => 4 + (read_u32(offset)? as usize).next_multiple_of(4),
+            SETTING_COLOR => 8,
+            _ => return None,
+        };
+    }
+
+    None
+}
@@ -1,9 +1,9 @@
-use anyhow::anyhow;
-use log::info;
+use log::{debug, info};
 use neuron_engine::os::window::WindowAttributes;
-use neuron_engine::{Engine, ExitState};
+use neuron_engine::{ControlFlow, Engine};
+use std::process::ExitCode;
 
-fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<ExitCode> {
     env_logger::init();
 
     let engine = Engine::new()?;
@@ -16,21 +16,15 @@ fn main() -> anyhow::Result<()> {
         engine.platform().supported_window_attributes()
     );
 
-    let (window_id, window) = engine.create_window(WindowAttributes {
+    let (window_id, _window) = engine.create_window(WindowAttributes {
         title: Some("Hello!".to_string()),
         ..Default::default()
     })?;
 
     info!("Window ID: {:?}", window_id);
 
-    while engine.window_manager().is_window_alive(window_id) {
-        match engine.process_events() {
-            ExitState::Running => (),
-            ExitState::ExitSuccess => return Ok(()),
-            ExitState::ExitError(e) => return Err(e),
-            ExitState::ExitErrorGeneric => return Err(anyhow!("Unknown error")),
-        }
-    }
-
-    Ok(())
+    Ok(engine.run(|event, _engine| {
+        debug!("Event: {:?}", event);
+        ControlFlow::Wait
+    }))
 }